@@ -0,0 +1,74 @@
+use crate::error::PayloadError;
+
+pub const ADDRESS_MAX_LENGTH     : usize = 64;
+pub const ENCODED_ADDRESS_LENGTH : usize = 1 + ADDRESS_MAX_LENGTH;   // Length byte + address
+
+/// Chain agnostic address. Encoded as a length byte followed by 64 bytes, with the address right-aligned
+/// and left-padded with zeros (i.e. an EVM address is encoded as 0x14 + 44 zero bytes + 20 address bytes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CatalystAddress {
+    length: u8,
+    bytes: [u8; ADDRESS_MAX_LENGTH]
+}
+
+impl CatalystAddress {
+
+    pub fn new(address: &[u8]) -> Result<Self, PayloadError> {
+        if address.len() > ADDRESS_MAX_LENGTH {
+            return Err(PayloadError::InvalidAddress);
+        }
+
+        let mut bytes = [0u8; ADDRESS_MAX_LENGTH];
+        bytes[ADDRESS_MAX_LENGTH - address.len()..].copy_from_slice(address);
+
+        Ok(CatalystAddress { length: address.len() as u8, bytes })
+    }
+
+    /// The address bytes (without padding)
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[ADDRESS_MAX_LENGTH - self.length as usize..]
+    }
+
+    pub fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub(crate) fn encode_into(&self, buffer: &mut Vec<u8>) {
+        buffer.push(self.length);
+        buffer.extend_from_slice(&self.bytes);
+    }
+
+    pub(crate) fn decode(encoded: &[u8]) -> Result<Self, PayloadError> {
+        let length = encoded[0] as usize;
+        if length > ADDRESS_MAX_LENGTH {
+            return Err(PayloadError::InvalidAddress);
+        }
+
+        // The padding must be zeroed, otherwise the same address could be encoded in more than one way
+        let padded = &encoded[1..ENCODED_ADDRESS_LENGTH];
+        if padded[..ADDRESS_MAX_LENGTH - length].iter().any(|byte| *byte != 0) {
+            return Err(PayloadError::InvalidAddress);
+        }
+
+        let mut bytes = [0u8; ADDRESS_MAX_LENGTH];
+        bytes.copy_from_slice(padded);
+
+        Ok(CatalystAddress { length: length as u8, bytes })
+    }
+}
+
+impl From<[u8; 32]> for CatalystAddress {
+    fn from(address: [u8; 32]) -> Self {
+        CatalystAddress::new(&address).unwrap()
+    }
+}
+
+impl From<[u8; 20]> for CatalystAddress {
+    fn from(address: [u8; 20]) -> Self {
+        CatalystAddress::new(&address).unwrap()
+    }
+}
@@ -0,0 +1,81 @@
+use shared_lib::u256::U256;
+
+use crate::*;
+
+/// CTX0 - Asset swap payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetSwapPayload {
+    pub from_vault: CatalystAddress,
+    pub to_vault: CatalystAddress,
+    pub to_account: CatalystAddress,
+    pub units: U256,
+    pub to_asset_index: u8,
+    pub min_out: U256,
+    pub from_amount: U256,
+    pub from_asset: CatalystAddress,
+    pub block_number: u32,              // Block number of the source chain (mod 2**32)
+    pub underwrite_incentive_x16: u16,
+    pub calldata: Vec<u8>
+}
+
+impl AssetSwapPayload {
+
+    pub fn encode(&self) -> Result<Vec<u8>, PayloadError> {
+        let mut buffer = Vec::with_capacity(CTX0_DATA_START + self.calldata.len());
+
+        buffer.push(encode_context(CTX0_ASSET_SWAP));
+        self.from_vault.encode_into(&mut buffer);
+        self.to_vault.encode_into(&mut buffer);
+        self.to_account.encode_into(&mut buffer);
+        encode_u256(&mut buffer, self.units);
+        buffer.push(self.to_asset_index);
+        encode_u256(&mut buffer, self.min_out);
+        encode_u256(&mut buffer, self.from_amount);
+        self.from_asset.encode_into(&mut buffer);
+        buffer.extend_from_slice(&self.block_number.to_be_bytes());
+        buffer.extend_from_slice(&self.underwrite_incentive_x16.to_be_bytes());
+        encode_calldata(&mut buffer, &self.calldata)?;
+
+        Ok(buffer)
+    }
+
+    pub fn decode(payload: &[u8]) -> Result<Self, PayloadError> {
+        let context = decode_context(payload)?;
+        if context != CTX0_ASSET_SWAP {
+            return Err(PayloadError::InvalidContext(context));
+        }
+
+        verify_payload_length(payload, CTX0_DATA_LENGTH_START, CTX0_DATA_START)?;
+
+        Ok(AssetSwapPayload {
+            from_vault               : CatalystAddress::decode(&payload[FROM_VAULT_LENGTH_POS..FROM_VAULT_END])?,
+            to_vault                 : CatalystAddress::decode(&payload[TO_VAULT_LENGTH_POS..TO_VAULT_END])?,
+            to_account               : CatalystAddress::decode(&payload[TO_ACCOUNT_LENGTH_POS..TO_ACCOUNT_END])?,
+            units                    : decode_u256(&payload[UNITS_START..UNITS_END]),
+            to_asset_index           : payload[CTX0_TO_ASSET_INDEX_POS],
+            min_out                  : decode_u256(&payload[CTX0_MIN_OUT_START..CTX0_MIN_OUT_END]),
+            from_amount              : decode_u256(&payload[CTX0_FROM_AMOUNT_START..CTX0_FROM_AMOUNT_END]),
+            from_asset               : CatalystAddress::decode(&payload[CTX0_FROM_ASSET_LENGTH_POS..CTX0_FROM_ASSET_END])?,
+            block_number             : decode_u32(&payload[CTX0_BLOCK_NUMBER_START..CTX0_BLOCK_NUMBER_END]),
+            underwrite_incentive_x16 : decode_u16(&payload[CTX0_UW_INCENTIVE_START..CTX0_UW_INCENTIVE_END]),
+            calldata                 : payload[CTX0_DATA_START..].to_vec()
+        })
+    }
+
+    /// Identifier of the swap, used by the source chain to match the swap acknowledgement/timeout with its escrow.
+    /// Equivalent to '_computeSendAssetHash' of CatalystVaultCommon.sol:
+    ///     keccak256(TO_ACCOUNT (encoded, 65 bytes) + UNITS + FROM_AMOUNT + FROM_ASSET (address bytes) + BLOCK_NUMBER)
+    /// i.e. for EVM assets FROM_ASSET is hashed as bytes20(fromAsset).
+    pub fn swap_id(&self) -> [u8; 32] {
+        let mut to_account = Vec::with_capacity(address::ENCODED_ADDRESS_LENGTH);
+        self.to_account.encode_into(&mut to_account);
+
+        keccak256(&[
+            &to_account,
+            &u256_to_be_bytes(self.units),
+            &u256_to_be_bytes(self.from_amount),
+            self.from_asset.as_bytes(),
+            &self.block_number.to_be_bytes()
+        ])
+    }
+}
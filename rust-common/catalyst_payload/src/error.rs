@@ -0,0 +1,32 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadError {
+    /// The payload is empty
+    EmptyPayload,
+    /// The payload version (upper nibble of the context byte) is not supported by this codec
+    UnsupportedVersion(u8),
+    /// The payload context (lower nibble of the context byte) is not recognised
+    InvalidContext(u8),
+    /// The payload length does not match the one expected for its context
+    InvalidLength { expected: usize, actual: usize },
+    /// An address field declares a length larger than 64 bytes, or is not zero-padded
+    InvalidAddress,
+    /// The calldata is longer than the 2 byte length field allows
+    CalldataTooLong(usize)
+}
+
+impl fmt::Display for PayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayloadError::EmptyPayload                      => write!(f, "empty payload"),
+            PayloadError::UnsupportedVersion(version)       => write!(f, "unsupported payload version {}", version),
+            PayloadError::InvalidContext(context)           => write!(f, "invalid payload context {:#04x}", context),
+            PayloadError::InvalidLength { expected, actual } => write!(f, "invalid payload length (expected {}, got {})", expected, actual),
+            PayloadError::InvalidAddress                    => write!(f, "invalid address encoding"),
+            PayloadError::CalldataTooLong(length)           => write!(f, "calldata too long ({} bytes)", length)
+        }
+    }
+}
+
+impl std::error::Error for PayloadError {}
@@ -0,0 +1,235 @@
+// Catalyst Messaging payload codec. The layout matches evm/src/CatalystPayload.sol:
+// Note: Addresses have 65 bytes reserved, however, the first byte should only be used for the address size.
+//
+// Common Payload (beginning)
+//    CONTEXT               0   (1 byte)
+//    + FROM_VAULT_LENGTH   1   (1 byte)
+//    + FROM_VAULT          2   (64 bytes)
+//    + TO_VAULT_LENGTH     66  (1 byte)
+//    + TO_VAULT            67  (64 bytes)
+//    + TO_ACCOUNT_LENGTH   131 (1 byte)
+//    + TO_ACCOUNT          132 (64 bytes)
+//    + UNITS               196 (32 bytes)
+//
+// Context-depending Payload
+//    CTX0 - 0x00 - Asset Swap Payload
+//       + TO_ASSET_INDEX   228 (1 byte)
+//       + MIN_OUT          229 (32 bytes)
+//       + FROM_AMOUNT      261 (32 bytes)
+//       + FROM_ASSET_LEN   293 (1 byte)
+//       + FROM_ASSET       294 (64 bytes)
+//       + BLOCK_NUMBER     358 (4 bytes)
+//      (Underwrite Logic)
+//       + UW_INCENTIVE     362 (2 bytes)
+//
+//    CTX1 - 0x01 - Liquidity Swap Payload
+//       + MIN_OUT          228 (32 bytes)
+//       + MIN_REFERENCE    260 (32 bytes)
+//       + FROM_AMOUNT      292 (32 bytes)
+//       + BLOCK_NUMBER     324 (4 bytes)
+//
+// Common Payload (end)
+//    + DATA_LENGTH         LENGTH-N-2 (2 bytes)
+//    + DATA                LENGTH-N   (N bytes)
+//
+// All numbers are encoded big endian.
+//
+// DATA is reserved for the calldata of the swap (see onCatalystCall), and is empty otherwise. The swaps are identified
+// by hashing some of the common fields (see 'AssetSwapPayload::swap_id' and 'LiquiditySwapPayload::swap_id').
+//
+// Versioning: the upper nibble of the CONTEXT byte holds the payload version, the lower nibble the context.
+// The layout above is version 0, which makes the CONTEXT byte identical to the one used by the EVM
+// implementation (CTX0 = 0x00, CTX1 = 0x01). Any other version is rejected by both implementations.
+
+pub mod address;
+pub mod asset_swap;
+pub mod error;
+pub mod liquidity_swap;
+
+#[cfg(test)]
+pub mod test {
+    pub mod test_payload;
+}
+
+pub use address::CatalystAddress;
+pub use asset_swap::AssetSwapPayload;
+pub use error::PayloadError;
+pub use liquidity_swap::LiquiditySwapPayload;
+
+use sha3::{Digest, Keccak256};
+use shared_lib::u256::U256;
+
+
+// Contexts *********************************************************************************************************************
+
+pub const PAYLOAD_VERSION     : u8 = 0;
+
+pub const CTX0_ASSET_SWAP     : u8 = 0x00;
+pub const CTX1_LIQUIDITY_SWAP : u8 = 0x01;
+
+
+// Common Payload ***************************************************************************************************************
+
+pub const CONTEXT_POS           : usize = 0;
+
+pub const FROM_VAULT_LENGTH_POS : usize = 1;
+pub const FROM_VAULT_END        : usize = 66;
+
+pub const TO_VAULT_LENGTH_POS   : usize = 66;
+pub const TO_VAULT_END          : usize = 131;
+
+pub const TO_ACCOUNT_LENGTH_POS : usize = 131;
+pub const TO_ACCOUNT_END        : usize = 196;
+
+pub const UNITS_START           : usize = 196;
+pub const UNITS_END             : usize = 228;
+
+
+// CTX0 Asset Swap Payload ******************************************************************************************************
+
+pub const CTX0_TO_ASSET_INDEX_POS    : usize = 228;
+
+pub const CTX0_MIN_OUT_START         : usize = 229;
+pub const CTX0_MIN_OUT_END           : usize = 261;
+
+pub const CTX0_FROM_AMOUNT_START     : usize = 261;
+pub const CTX0_FROM_AMOUNT_END       : usize = 293;
+
+pub const CTX0_FROM_ASSET_LENGTH_POS : usize = 293;
+pub const CTX0_FROM_ASSET_END        : usize = 358;
+
+pub const CTX0_BLOCK_NUMBER_START    : usize = 358;
+pub const CTX0_BLOCK_NUMBER_END      : usize = 362;
+
+pub const CTX0_UW_INCENTIVE_START    : usize = 362;
+pub const CTX0_UW_INCENTIVE_END      : usize = 364;
+
+pub const CTX0_DATA_LENGTH_START     : usize = 364;
+pub const CTX0_DATA_LENGTH_END       : usize = 366;
+
+pub const CTX0_DATA_START            : usize = 366;
+
+
+// CTX1 Liquidity Swap Payload **************************************************************************************************
+
+pub const CTX1_MIN_VAULT_TOKEN_START : usize = 228;
+pub const CTX1_MIN_VAULT_TOKEN_END   : usize = 260;
+
+pub const CTX1_MIN_REFERENCE_START   : usize = 260;
+pub const CTX1_MIN_REFERENCE_END     : usize = 292;
+
+pub const CTX1_FROM_AMOUNT_START     : usize = 292;
+pub const CTX1_FROM_AMOUNT_END       : usize = 324;
+
+pub const CTX1_BLOCK_NUMBER_START    : usize = 324;
+pub const CTX1_BLOCK_NUMBER_END      : usize = 328;
+
+pub const CTX1_DATA_LENGTH_START     : usize = 328;
+pub const CTX1_DATA_LENGTH_END       : usize = 330;
+
+pub const CTX1_DATA_START            : usize = 330;
+
+
+
+// Payload **********************************************************************************************************************
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatalystPayload {
+    AssetSwap(AssetSwapPayload),
+    LiquiditySwap(LiquiditySwapPayload)
+}
+
+impl CatalystPayload {
+
+    /// Decode a payload of any context.
+    pub fn decode(payload: &[u8]) -> Result<Self, PayloadError> {
+        match decode_context(payload)? {
+            CTX0_ASSET_SWAP     => Ok(CatalystPayload::AssetSwap(AssetSwapPayload::decode(payload)?)),
+            CTX1_LIQUIDITY_SWAP => Ok(CatalystPayload::LiquiditySwap(LiquiditySwapPayload::decode(payload)?)),
+            context             => Err(PayloadError::InvalidContext(context))
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, PayloadError> {
+        match self {
+            CatalystPayload::AssetSwap(payload)     => payload.encode(),
+            CatalystPayload::LiquiditySwap(payload) => payload.encode()
+        }
+    }
+}
+
+
+/// Verify the version of the payload and return its context.
+pub fn decode_context(payload: &[u8]) -> Result<u8, PayloadError> {
+    let context_byte = *payload.get(CONTEXT_POS).ok_or(PayloadError::EmptyPayload)?;
+
+    let version = context_byte >> 4;
+    if version != PAYLOAD_VERSION {
+        return Err(PayloadError::UnsupportedVersion(version));
+    }
+
+    Ok(context_byte & 0x0F)
+}
+
+pub(crate) fn encode_context(context: u8) -> u8 {
+    (PAYLOAD_VERSION << 4) | context
+}
+
+
+
+// Codec helpers ****************************************************************************************************************
+
+/// Check the payload length against the fixed part of its context, and return the declared calldata length.
+pub(crate) fn verify_payload_length(
+    payload: &[u8],
+    data_length_start: usize,
+    data_start: usize
+) -> Result<usize, PayloadError> {
+    if payload.len() < data_start {
+        return Err(PayloadError::InvalidLength { expected: data_start, actual: payload.len() });
+    }
+
+    let data_length = decode_u16(&payload[data_length_start..data_start]) as usize;
+    if payload.len() != data_start + data_length {
+        return Err(PayloadError::InvalidLength { expected: data_start + data_length, actual: payload.len() });
+    }
+
+    Ok(data_length)
+}
+
+pub(crate) fn encode_calldata(buffer: &mut Vec<u8>, calldata: &[u8]) -> Result<(), PayloadError> {
+    let length: u16 = calldata.len().try_into().map_err(|_| PayloadError::CalldataTooLong(calldata.len()))?;
+
+    buffer.extend_from_slice(&length.to_be_bytes());
+    buffer.extend_from_slice(calldata);
+
+    Ok(())
+}
+
+pub(crate) fn encode_u256(buffer: &mut Vec<u8>, value: U256) {
+    buffer.extend_from_slice(&u256_to_be_bytes(value));
+}
+
+pub(crate) fn decode_u256(bytes: &[u8]) -> U256 {
+    U256::from_big_endian(bytes)
+}
+
+pub(crate) fn decode_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes(bytes.try_into().unwrap())
+}
+
+pub(crate) fn decode_u16(bytes: &[u8]) -> u16 {
+    u16::from_be_bytes(bytes.try_into().unwrap())
+}
+
+pub(crate) fn u256_to_be_bytes(value: U256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    bytes
+}
+
+pub(crate) fn keccak256(chunks: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    chunks.iter().for_each(|chunk| hasher.update(chunk));
+    hasher.finalize().into()
+}
@@ -0,0 +1,73 @@
+use shared_lib::u256::U256;
+
+use crate::*;
+
+/// CTX1 - Liquidity swap payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiquiditySwapPayload {
+    pub from_vault: CatalystAddress,
+    pub to_vault: CatalystAddress,
+    pub to_account: CatalystAddress,
+    pub units: U256,
+    pub min_vault_tokens: U256,
+    pub min_reference_asset: U256,
+    pub from_amount: U256,
+    pub block_number: u32,              // Block number of the source chain (mod 2**32)
+    pub calldata: Vec<u8>
+}
+
+impl LiquiditySwapPayload {
+
+    pub fn encode(&self) -> Result<Vec<u8>, PayloadError> {
+        let mut buffer = Vec::with_capacity(CTX1_DATA_START + self.calldata.len());
+
+        buffer.push(encode_context(CTX1_LIQUIDITY_SWAP));
+        self.from_vault.encode_into(&mut buffer);
+        self.to_vault.encode_into(&mut buffer);
+        self.to_account.encode_into(&mut buffer);
+        encode_u256(&mut buffer, self.units);
+        encode_u256(&mut buffer, self.min_vault_tokens);
+        encode_u256(&mut buffer, self.min_reference_asset);
+        encode_u256(&mut buffer, self.from_amount);
+        buffer.extend_from_slice(&self.block_number.to_be_bytes());
+        encode_calldata(&mut buffer, &self.calldata)?;
+
+        Ok(buffer)
+    }
+
+    pub fn decode(payload: &[u8]) -> Result<Self, PayloadError> {
+        let context = decode_context(payload)?;
+        if context != CTX1_LIQUIDITY_SWAP {
+            return Err(PayloadError::InvalidContext(context));
+        }
+
+        verify_payload_length(payload, CTX1_DATA_LENGTH_START, CTX1_DATA_START)?;
+
+        Ok(LiquiditySwapPayload {
+            from_vault          : CatalystAddress::decode(&payload[FROM_VAULT_LENGTH_POS..FROM_VAULT_END])?,
+            to_vault            : CatalystAddress::decode(&payload[TO_VAULT_LENGTH_POS..TO_VAULT_END])?,
+            to_account          : CatalystAddress::decode(&payload[TO_ACCOUNT_LENGTH_POS..TO_ACCOUNT_END])?,
+            units               : decode_u256(&payload[UNITS_START..UNITS_END]),
+            min_vault_tokens    : decode_u256(&payload[CTX1_MIN_VAULT_TOKEN_START..CTX1_MIN_VAULT_TOKEN_END]),
+            min_reference_asset : decode_u256(&payload[CTX1_MIN_REFERENCE_START..CTX1_MIN_REFERENCE_END]),
+            from_amount         : decode_u256(&payload[CTX1_FROM_AMOUNT_START..CTX1_FROM_AMOUNT_END]),
+            block_number        : decode_u32(&payload[CTX1_BLOCK_NUMBER_START..CTX1_BLOCK_NUMBER_END]),
+            calldata            : payload[CTX1_DATA_START..].to_vec()
+        })
+    }
+
+    /// Identifier of the liquidity swap, used by the source chain to match the swap acknowledgement/timeout with its
+    /// escrow. Equivalent to '_computeSendLiquidityHash' of CatalystVaultCommon.sol:
+    ///     keccak256(TO_ACCOUNT (encoded, 65 bytes) + UNITS + FROM_AMOUNT + BLOCK_NUMBER)
+    pub fn swap_id(&self) -> [u8; 32] {
+        let mut to_account = Vec::with_capacity(address::ENCODED_ADDRESS_LENGTH);
+        self.to_account.encode_into(&mut to_account);
+
+        keccak256(&[
+            &to_account,
+            &u256_to_be_bytes(self.units),
+            &u256_to_be_bytes(self.from_amount),
+            &self.block_number.to_be_bytes()
+        ])
+    }
+}
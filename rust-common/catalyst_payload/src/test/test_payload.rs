@@ -0,0 +1,315 @@
+use shared_lib::u256::U256;
+
+use crate::*;
+
+
+
+// Helpers **********************************************************************************************************************
+
+const EVM_VAULT   : [u8; 20] = [0x11; 20];
+const EVM_ASSET   : [u8; 20] = [0x22; 20];
+const SOLANA_POOL : [u8; 32] = [0x33; 32];
+const SOLANA_USER : [u8; 32] = [0x44; 32];
+
+/// Replicates the bytes.concat encoding of an address used by CatalystChainInterface.sol (length byte + left padded address)
+fn evm_encoded_address(address: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![address.len() as u8];
+    encoded.extend(vec![0u8; 64 - address.len()]);
+    encoded.extend_from_slice(address);
+    encoded
+}
+
+fn u256_be(value: U256) -> Vec<u8> {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    bytes.to_vec()
+}
+
+fn asset_swap_payload() -> AssetSwapPayload {
+    AssetSwapPayload {
+        from_vault               : CatalystAddress::from(EVM_VAULT),
+        to_vault                 : CatalystAddress::from(SOLANA_POOL),
+        to_account               : CatalystAddress::from(SOLANA_USER),
+        units                    : U256([1, 2, 3, 4]),
+        to_asset_index           : 2,
+        min_out                  : U256::from(1000u64),
+        from_amount              : U256::MAX,
+        from_asset               : CatalystAddress::from(EVM_ASSET),
+        block_number             : 0x01020304,
+        underwrite_incentive_x16 : 0x0506,
+        calldata                 : vec![0xAA, 0xBB, 0xCC]
+    }
+}
+
+fn liquidity_swap_payload() -> LiquiditySwapPayload {
+    LiquiditySwapPayload {
+        from_vault          : CatalystAddress::from(EVM_VAULT),
+        to_vault            : CatalystAddress::from(SOLANA_POOL),
+        to_account          : CatalystAddress::from(SOLANA_USER),
+        units               : U256([5, 6, 7, 8]),
+        min_vault_tokens    : U256::from(10u64),
+        min_reference_asset : U256::from(20u64),
+        from_amount         : U256::from(30u64),
+        block_number        : 0xFFFFFFFF,
+        calldata            : vec![]
+    }
+}
+
+
+
+// Layout *********************************************************************************************************************
+
+/// The encoded asset swap must match byte by byte the one built by CatalystChainInterface.sol::sendCrossChainAsset
+#[test]
+fn test_asset_swap_evm_layout() {
+    let payload = asset_swap_payload();
+
+    let mut expected = vec![0x00];  // CTX0_ASSET_SWAP
+    expected.extend(evm_encoded_address(&EVM_VAULT));
+    expected.extend(evm_encoded_address(&SOLANA_POOL));
+    expected.extend(evm_encoded_address(&SOLANA_USER));
+    expected.extend(u256_be(payload.units));
+    expected.push(payload.to_asset_index);
+    expected.extend(u256_be(payload.min_out));
+    expected.extend(u256_be(payload.from_amount));
+    expected.extend(evm_encoded_address(&EVM_ASSET));
+    expected.extend([0x01, 0x02, 0x03, 0x04]);
+    expected.extend([0x05, 0x06]);
+    expected.extend([0x00, 0x03]);
+    expected.extend([0xAA, 0xBB, 0xCC]);
+
+    let encoded = payload.encode().unwrap();
+
+    assert_eq!(encoded, expected);
+    assert_eq!(encoded.len(), CTX0_DATA_START + 3);
+    assert_eq!(encoded[CTX0_TO_ASSET_INDEX_POS], 2);
+    assert_eq!(&encoded[CTX0_BLOCK_NUMBER_START..CTX0_BLOCK_NUMBER_END], &[0x01, 0x02, 0x03, 0x04]);
+}
+
+/// The encoded liquidity swap must match byte by byte the one built by CatalystChainInterface.sol::sendCrossChainLiquidity
+#[test]
+fn test_liquidity_swap_evm_layout() {
+    let payload = liquidity_swap_payload();
+
+    let mut expected = vec![0x01];  // CTX1_LIQUIDITY_SWAP
+    expected.extend(evm_encoded_address(&EVM_VAULT));
+    expected.extend(evm_encoded_address(&SOLANA_POOL));
+    expected.extend(evm_encoded_address(&SOLANA_USER));
+    expected.extend(u256_be(payload.units));
+    expected.extend(u256_be(payload.min_vault_tokens));
+    expected.extend(u256_be(payload.min_reference_asset));
+    expected.extend(u256_be(payload.from_amount));
+    expected.extend([0xFF, 0xFF, 0xFF, 0xFF]);
+    expected.extend([0x00, 0x00]);
+
+    let encoded = payload.encode().unwrap();
+
+    assert_eq!(encoded, expected);
+    assert_eq!(encoded.len(), CTX1_DATA_START);
+}
+
+#[test]
+fn test_roundtrip() {
+    let asset_swap = CatalystPayload::AssetSwap(asset_swap_payload());
+    assert_eq!(CatalystPayload::decode(&asset_swap.encode().unwrap()).unwrap(), asset_swap);
+
+    let liquidity_swap = CatalystPayload::LiquiditySwap(liquidity_swap_payload());
+    assert_eq!(CatalystPayload::decode(&liquidity_swap.encode().unwrap()).unwrap(), liquidity_swap);
+
+    let decoded = AssetSwapPayload::decode(&asset_swap.encode().unwrap()).unwrap();
+    assert_eq!(decoded.from_vault.as_bytes(), &EVM_VAULT);
+    assert_eq!(decoded.to_account.as_bytes(), &SOLANA_USER);
+}
+
+
+
+// Errors *********************************************************************************************************************
+
+#[test]
+fn test_invalid_version_and_context() {
+    assert_eq!(CatalystPayload::decode(&[]), Err(PayloadError::EmptyPayload));
+
+    let mut encoded = asset_swap_payload().encode().unwrap();
+
+    encoded[CONTEXT_POS] = 0x10;
+    assert_eq!(CatalystPayload::decode(&encoded), Err(PayloadError::UnsupportedVersion(1)));
+
+    encoded[CONTEXT_POS] = 0x05;
+    assert_eq!(CatalystPayload::decode(&encoded), Err(PayloadError::InvalidContext(0x05)));
+
+    // Context mismatch when decoding a specific payload type
+    let encoded = asset_swap_payload().encode().unwrap();
+    assert_eq!(LiquiditySwapPayload::decode(&encoded), Err(PayloadError::InvalidContext(CTX0_ASSET_SWAP)));
+}
+
+#[test]
+fn test_invalid_length() {
+    let encoded = asset_swap_payload().encode().unwrap();
+
+    // Truncated fixed section
+    for length in [1, UNITS_END, CTX0_DATA_START - 1] {
+        assert_eq!(
+            CatalystPayload::decode(&encoded[..length]),
+            Err(PayloadError::InvalidLength { expected: CTX0_DATA_START, actual: length })
+        );
+    }
+
+    // Truncated calldata
+    assert_eq!(
+        CatalystPayload::decode(&encoded[..encoded.len() - 1]),
+        Err(PayloadError::InvalidLength { expected: encoded.len(), actual: encoded.len() - 1 })
+    );
+
+    // Trailing bytes
+    let mut extended = encoded.clone();
+    extended.push(0);
+    assert_eq!(
+        CatalystPayload::decode(&extended),
+        Err(PayloadError::InvalidLength { expected: encoded.len(), actual: encoded.len() + 1 })
+    );
+}
+
+#[test]
+fn test_invalid_address() {
+    assert_eq!(CatalystAddress::new(&[0u8; 65]), Err(PayloadError::InvalidAddress));
+
+    let encoded = liquidity_swap_payload().encode().unwrap();
+
+    // Address length larger than 64 bytes
+    let mut invalid = encoded.clone();
+    invalid[TO_VAULT_LENGTH_POS] = 65;
+    assert_eq!(CatalystPayload::decode(&invalid), Err(PayloadError::InvalidAddress));
+
+    // Non-zero padding
+    let mut invalid = encoded.clone();
+    invalid[FROM_VAULT_LENGTH_POS + 1] = 1;
+    assert_eq!(CatalystPayload::decode(&invalid), Err(PayloadError::InvalidAddress));
+}
+
+#[test]
+fn test_calldata_too_long() {
+    let mut payload = asset_swap_payload();
+    payload.calldata = vec![0u8; u16::MAX as usize + 1];
+
+    assert_eq!(payload.encode(), Err(PayloadError::CalldataTooLong(u16::MAX as usize + 1)));
+}
+
+
+// Swap identifiers *********************************************************************************************************
+
+/// Asset swap from an EVM vault to a Solana pool, as built by CatalystChainInterface.sol::sendCrossChainAsset (empty calldata)
+const EVM_ASSET_SWAP_FIXTURE: &str = concat!(
+    "00",                                                                                                                                   // CTX0_ASSET_SWAP
+    "1400000000000000000000000000000000000000000000000000000000000000000000000000000000000000001111111111111111111111111111111111111111", // FROM_VAULT
+    "2000000000000000000000000000000000000000000000000000000000000000003333333333333333333333333333333333333333333333333333333333333333", // TO_VAULT
+    "2000000000000000000000000000000000000000000000000000000000000000004444444444444444444444444444444444444444444444444444444444444444", // TO_ACCOUNT
+    "000000000000000000000000000000000de0b6b3a76400000000000000000000",                                                                 // UNITS
+    "01",                                                                                                                                   // TO_ASSET_INDEX
+    "00000000000000000000000000000000000000000000000000000000000003e8",                                                                 // MIN_OUT
+    "00000000000000000000000000000000000000000000000000000000075bcd15",                                                                 // FROM_AMOUNT
+    "1400000000000000000000000000000000000000000000000000000000000000000000000000000000000000002222222222222222222222222222222222222222", // FROM_ASSET
+    "00bc614e",                                                                                                                             // BLOCK_NUMBER
+    "0000",                                                                                                                                 // UW_INCENTIVE
+    "0000"                                                                                                                                  // DATA_LENGTH
+);
+
+/// Liquidity swap from an EVM vault to a Solana pool, as built by CatalystChainInterface.sol::sendCrossChainLiquidity (empty calldata)
+const EVM_LIQUIDITY_SWAP_FIXTURE: &str = concat!(
+    "01",                                                                                                                                   // CTX1_LIQUIDITY_SWAP
+    "1400000000000000000000000000000000000000000000000000000000000000000000000000000000000000001111111111111111111111111111111111111111", // FROM_VAULT
+    "2000000000000000000000000000000000000000000000000000000000000000003333333333333333333333333333333333333333333333333333333333333333", // TO_VAULT
+    "2000000000000000000000000000000000000000000000000000000000000000004444444444444444444444444444444444444444444444444444444444444444", // TO_ACCOUNT
+    "000000000000000000000000000000000de0b6b3a76400000000000000000000",                                                                 // UNITS
+    "000000000000000000000000000000000000000000000000000000000000000a",                                                                 // MIN_VAULT_TOKENS
+    "0000000000000000000000000000000000000000000000000000000000000014",                                                                 // MIN_REFERENCE_ASSET
+    "00000000000000000000000000000000000000000000000000000000075bcd15",                                                                 // FROM_AMOUNT
+    "00bc614e",                                                                                                                             // BLOCK_NUMBER
+    "0000"                                                                                                                                  // DATA_LENGTH
+);
+
+// Swap identifiers of the fixtures (computed independently of this crate)
+const EVM_ASSET_SWAP_FIXTURE_ID     : &str = "9ebe2a3cfd04f943bce1f3ace01854c797efd750ed8e28ce7667dca967ad6d24";
+const EVM_LIQUIDITY_SWAP_FIXTURE_ID : &str = "48cfed4566b409ecfb5660617c29587387b3d004de4e9fd5fa71e65e3b4a4884";
+
+fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i+2], 16).unwrap()).collect()
+}
+
+/// Payloads sent by the EVM implementation (which carry no calldata) must be accepted, and their swap identifier must
+/// match the one computed by CatalystVaultCommon.sol (_computeSendAssetHash/_computeSendLiquidityHash) from the same payload.
+#[test]
+fn test_evm_payload_swap_id() {
+    let encoded = from_hex(EVM_ASSET_SWAP_FIXTURE);
+
+    let payload = match CatalystPayload::decode(&encoded).unwrap() {
+        CatalystPayload::AssetSwap(payload) => payload,
+        _ => panic!("expected an asset swap payload")
+    };
+
+    assert_eq!(payload.from_vault.as_bytes(), &EVM_VAULT);
+    assert_eq!(payload.to_account.as_bytes(), &SOLANA_USER);
+    assert_eq!(payload.units, U256::from(10u64).pow(U256::from(18u64)) << 64);
+    assert_eq!(payload.min_out, U256::from(1000u64));
+    assert_eq!(payload.from_amount, U256::from(123456789u64));
+    assert_eq!(payload.from_asset.as_bytes(), &EVM_ASSET);
+    assert_eq!(payload.block_number, 12345678);
+    assert!(payload.calldata.is_empty());
+
+    // keccak256(bytes.concat(toAccount, bytes32(U), bytes32(amount), bytes20(fromAsset), bytes4(blockNumberMod)))
+    let expected_swap_id = keccak256(&[
+        &encoded[TO_ACCOUNT_LENGTH_POS..TO_ACCOUNT_END],
+        &encoded[UNITS_START..UNITS_END],
+        &encoded[CTX0_FROM_AMOUNT_START..CTX0_FROM_AMOUNT_END],
+        &encoded[CTX0_FROM_ASSET_END - 20..CTX0_FROM_ASSET_END],
+        &encoded[CTX0_BLOCK_NUMBER_START..CTX0_BLOCK_NUMBER_END]
+    ]);
+    assert_eq!(payload.swap_id(), expected_swap_id);
+    assert_eq!(payload.swap_id().to_vec(), from_hex(EVM_ASSET_SWAP_FIXTURE_ID));
+
+    let encoded = from_hex(EVM_LIQUIDITY_SWAP_FIXTURE);
+
+    let payload = match CatalystPayload::decode(&encoded).unwrap() {
+        CatalystPayload::LiquiditySwap(payload) => payload,
+        _ => panic!("expected a liquidity swap payload")
+    };
+
+    assert_eq!(payload.from_amount, U256::from(123456789u64));
+    assert!(payload.calldata.is_empty());
+
+    // keccak256(bytes.concat(toAccount, bytes32(U), bytes32(amount), bytes4(blockNumberMod)))
+    let expected_swap_id = keccak256(&[
+        &encoded[TO_ACCOUNT_LENGTH_POS..TO_ACCOUNT_END],
+        &encoded[UNITS_START..UNITS_END],
+        &encoded[CTX1_FROM_AMOUNT_START..CTX1_FROM_AMOUNT_END],
+        &encoded[CTX1_BLOCK_NUMBER_START..CTX1_BLOCK_NUMBER_END]
+    ]);
+    assert_eq!(payload.swap_id(), expected_swap_id);
+    assert_eq!(payload.swap_id().to_vec(), from_hex(EVM_LIQUIDITY_SWAP_FIXTURE_ID));
+}
+
+/// The swap identifier depends only on the fields hashed by the EVM implementation
+#[test]
+fn test_swap_id_fields() {
+    let payload = asset_swap_payload();
+    let swap_id = payload.swap_id();
+
+    // Not affected by the encoding roundtrip, nor by the fields not included in the hash
+    assert_eq!(AssetSwapPayload::decode(&payload.encode().unwrap()).unwrap().swap_id(), swap_id);
+    assert_eq!(AssetSwapPayload { min_out: U256::zero(), calldata: vec![], ..payload.clone() }.swap_id(), swap_id);
+
+    assert_ne!(AssetSwapPayload { to_account: CatalystAddress::from(SOLANA_POOL), ..payload.clone() }.swap_id(), swap_id);
+    assert_ne!(AssetSwapPayload { units: U256([1, 2, 3, 5]), ..payload.clone() }.swap_id(), swap_id);
+    assert_ne!(AssetSwapPayload { from_amount: U256::zero(), ..payload.clone() }.swap_id(), swap_id);
+    assert_ne!(AssetSwapPayload { from_asset: CatalystAddress::from(EVM_VAULT), ..payload.clone() }.swap_id(), swap_id);
+    assert_ne!(AssetSwapPayload { block_number: 0, ..payload.clone() }.swap_id(), swap_id);
+
+    let payload = liquidity_swap_payload();
+    let swap_id = payload.swap_id();
+
+    assert_eq!(LiquiditySwapPayload::decode(&payload.encode().unwrap()).unwrap().swap_id(), swap_id);
+    assert_eq!(LiquiditySwapPayload { min_vault_tokens: U256::zero(), ..payload.clone() }.swap_id(), swap_id);
+
+    assert_ne!(LiquiditySwapPayload { units: U256([5, 6, 7, 9]), ..payload.clone() }.swap_id(), swap_id);
+    assert_ne!(LiquiditySwapPayload { from_amount: U256::zero(), ..payload.clone() }.swap_id(), swap_id);
+    assert_ne!(LiquiditySwapPayload { block_number: 0, ..payload.clone() }.swap_id(), swap_id);
+}
@@ -0,0 +1,46 @@
+use std::fmt;
+
+use shared_lib::math_error::MathError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteError {
+    /// The account data cannot be deserialized into a pool state
+    InvalidAccountData,
+    /// The asset index is not contained in the pool
+    InvalidAssetIndex(usize),
+    /// The count of wallet balances provided does not match the count of pool assets
+    InvalidBalancesCount { expected: usize, actual: usize },
+    /// The swap would exceed the units inflow security limit of the pool
+    SwapLimitExceeded,
+    /// The liquidity swap would exceed the liquidity inflow security limit of the pool
+    LiquiditySwapLimitExceeded,
+    /// The pool is paused
+    PoolPaused,
+    /// The pool is a local-only pool, cross chain swaps are disabled
+    LocalOnlyPool,
+    /// The swap cannot be computed (the on-chain transaction would fail)
+    Math(MathError)
+}
+
+impl From<MathError> for QuoteError {
+    fn from(err: MathError) -> Self {
+        QuoteError::Math(err)
+    }
+}
+
+impl fmt::Display for QuoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuoteError::InvalidAccountData                     => write!(f, "invalid pool state account data"),
+            QuoteError::InvalidAssetIndex(index)               => write!(f, "invalid asset index {}", index),
+            QuoteError::InvalidBalancesCount { expected, actual } => write!(f, "invalid balances count (expected {}, got {})", expected, actual),
+            QuoteError::SwapLimitExceeded                      => write!(f, "swap amount exceeds pool limit"),
+            QuoteError::LiquiditySwapLimitExceeded             => write!(f, "liquidity swap amount exceeds pool limit"),
+            QuoteError::PoolPaused                             => write!(f, "pool is paused"),
+            QuoteError::LocalOnlyPool                          => write!(f, "pool is local-only"),
+            QuoteError::Math(err)                              => write!(f, "math error ({})", err)
+        }
+    }
+}
+
+impl std::error::Error for QuoteError {}
@@ -0,0 +1,29 @@
+// Off-chain quoting of the Catalyst swap pools.
+//
+// The swap integrals are not reimplemented: the calculation helpers of the SwapPool and SwapPoolAmplified programs only depend
+// on shared_lib, and are compiled as part of this crate (see swap_pool::calculation_helpers and
+// swap_pool_amplified::calculation_helpers). The quotes hence match exactly the amounts computed on-chain.
+//
+// The pool state is deserialized from the raw account data into a mirror of the on-chain SwapPoolState account, so that this
+// crate does not depend on Anchor. The asset wallet balances and the pool token supply are not part of the pool state and must
+// be provided separately.
+
+pub mod error;
+pub mod quote;
+pub mod security_limit;
+pub mod swap_pool;
+pub mod swap_pool_amplified;
+
+pub use error::QuoteError;
+pub use quote::*;
+
+/// Solana public key bytes. Serialized identically to solana_program::pubkey::Pubkey.
+pub type Pubkey = [u8; 32];
+
+/// Length of the Anchor account discriminator which prefixes the account data.
+pub const ACCOUNT_DISCRIMINATOR_LENGTH: usize = 8;
+
+#[cfg(test)]
+pub mod test {
+    pub mod test_quote;
+}
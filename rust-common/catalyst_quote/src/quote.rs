@@ -0,0 +1,83 @@
+use shared_lib::u256::U256;
+
+use crate::error::QuoteError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fees {
+    /// Fee taken from the input amount (includes the governance fee)
+    pub pool_fee: u64,
+    /// Share of the pool fee sent to the governance fee destination
+    pub governance_fee: u64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalSwapQuote {
+    pub output: u64,
+    pub fees: Fees
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutSwapQuote {
+    pub units_x64: U256,
+    pub fees: Fees
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InSwapQuote {
+    pub output: u64,
+    /// Units that the pool will still accept once the swap is executed
+    pub units_inflow_headroom_x64: U256
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutLiquiditySwapQuote {
+    pub liquidity_units_x64: U256
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InLiquiditySwapQuote {
+    pub pool_tokens: u64,
+    /// Pool tokens that the pool will still accept once the liquidity swap is executed
+    pub liquidity_inflow_headroom: u64
+}
+
+
+pub(crate) fn verify_not_paused(paused: bool) -> Result<(), QuoteError> {
+    if paused {
+        return Err(QuoteError::PoolPaused);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn verify_not_local_only(local_only: bool) -> Result<(), QuoteError> {
+    if local_only {
+        return Err(QuoteError::LocalOnlyPool);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn verify_balances_count(asset_count: usize, asset_balances: &[u64]) -> Result<(), QuoteError> {
+    if asset_balances.len() != asset_count {
+        return Err(QuoteError::InvalidBalancesCount { expected: asset_count, actual: asset_balances.len() });
+    }
+
+    Ok(())
+}
+
+pub(crate) fn verify_asset_index(asset_count: usize, asset_index: usize) -> Result<(), QuoteError> {
+    if asset_index >= asset_count {
+        return Err(QuoteError::InvalidAssetIndex(asset_index));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn to_u64(value: U256) -> Result<u64, QuoteError> {
+    if value > U256::from(u64::MAX) {
+        return Err(QuoteError::Math(shared_lib::math_error::MathError::Overflow));
+    }
+
+    Ok(value.as_u64())
+}
@@ -0,0 +1,81 @@
+// Replicates the security limit checks of the pools (SwapPoolState::update_units_inflow and
+// SwapPoolState::update_liquidity_units_inflow), without updating the state.
+
+use shared_lib::math_error::MathError;
+use shared_lib::u256::U256;
+
+use crate::error::QuoteError;
+
+pub const DECAYRATE: u64 = 60*60*24;
+
+
+
+/// Verify that the given units inflow is accepted by the pool. Returns the units inflow headroom left after the swap.
+pub fn check_units_inflow(
+    max_units_inflow_x64: U256,
+    current_units_inflow_x64: U256,
+    current_units_inflow_timestamp: u64,
+    units_inflow_x64: U256,
+    current_timestamp: u64
+) -> Result<U256, QuoteError> {
+
+    // If more time has passed since the last update than DECAYRATE, the current inflow state does not matter (it has fully decayed)
+    let new_net_units_inflow_x64 = if current_timestamp > current_units_inflow_timestamp + DECAYRATE {
+        units_inflow_x64
+    }
+    else {
+        let decayed_inflow = max_units_inflow_x64.checked_mul(
+            U256::from(current_timestamp.checked_sub(current_units_inflow_timestamp).ok_or(MathError::Underflow)?)
+        ).ok_or(MathError::Overflow)? / DECAYRATE;
+
+        if current_units_inflow_x64 <= decayed_inflow {
+            units_inflow_x64
+        }
+        else {
+            (current_units_inflow_x64 - decayed_inflow).checked_add(units_inflow_x64).ok_or(MathError::Overflow)?
+        }
+    };
+
+    if new_net_units_inflow_x64 > max_units_inflow_x64 {
+        return Err(QuoteError::SwapLimitExceeded);
+    }
+
+    Ok(max_units_inflow_x64 - new_net_units_inflow_x64)
+}
+
+
+/// Verify that the given pool tokens inflow is accepted by the pool. Returns the pool tokens inflow headroom left after the
+/// liquidity swap.
+pub fn check_liquidity_inflow(
+    current_pool_token_supply: u64,
+    current_liquidity_inflow: u64,
+    current_liquidity_inflow_timestamp: u64,
+    pool_tokens_flow: u64,
+    current_timestamp: u64
+) -> Result<u64, QuoteError> {
+
+    let max_pool_tokens_flow = current_pool_token_supply / 2;
+
+    // If more time has passed since the last update than DECAYRATE, the current inflow state does not matter (it has fully decayed)
+    let new_net_liquidity_inflow = if current_timestamp > current_liquidity_inflow_timestamp + DECAYRATE {
+        pool_tokens_flow
+    }
+    else {
+        let decayed_inflow = max_pool_tokens_flow.checked_mul(
+            current_timestamp.checked_sub(current_liquidity_inflow_timestamp).ok_or(MathError::Underflow)?
+        ).ok_or(MathError::Overflow)? / DECAYRATE;
+
+        if current_liquidity_inflow <= decayed_inflow {
+            pool_tokens_flow
+        }
+        else {
+            (current_liquidity_inflow - decayed_inflow).checked_add(pool_tokens_flow).ok_or(MathError::Overflow)?
+        }
+    };
+
+    if new_net_liquidity_inflow > max_pool_tokens_flow {
+        return Err(QuoteError::LiquiditySwapLimitExceeded);
+    }
+
+    Ok(max_pool_tokens_flow - new_net_liquidity_inflow)
+}
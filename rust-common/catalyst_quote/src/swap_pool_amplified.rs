@@ -0,0 +1,303 @@
+// Quotes for the SwapPoolAmplified program.
+
+use std::ops::Shl;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use shared_lib::math_error::MathError;
+use shared_lib::u256::U256;
+use shared_lib::fixed_point_math_x64::pow_x64_up;
+
+use crate::error::QuoteError;
+use crate::quote::*;
+use crate::security_limit::{check_liquidity_inflow, check_units_inflow};
+use crate::{Pubkey, ACCOUNT_DISCRIMINATOR_LENGTH};
+
+const ONE_X64: U256 = U256([0, 1, 0, 0]);
+
+#[path = "../../../solana/programs/SwapPoolAmplified/src/calculation_helpers.rs"]
+pub mod calculation_helpers;
+
+
+
+// State ************************************************************************************************************************
+
+/// Mirror of the SwapPoolAmplified program SwapPoolState account. The field order must match the on-chain definition.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct SwapPoolState {
+    pub setup_master: Pubkey,
+    pub dao_authority: Pubkey,
+    pub pending_dao_authority: Pubkey,
+    pub paused: bool,
+    pub ibc_interface: Pubkey,
+    pub local_only: bool,
+    pub pool_assets_mints: Vec<Pubkey>,
+    pub pool_assets_weights: Vec<u64>,
+    pub pool_assets_eq_balances: Vec<u64>,
+    pub amplification_x64: [u64; 4],
+    pub target_amplification_x64: [u64; 4],
+    pub amplification_adjustment_target_timestamp: u64,
+    pub amplification_adjustment_last_timestamp: u64,
+
+    pub escrowed_assets: Vec<u64>,
+    pub escrowed_pool_tokens: u64,
+
+    pub fee_administrator: Pubkey,
+    pub pool_fee_x64: u64,
+    pub governance_fee_x64: u64,
+    pub governance_fee_destination: Pubkey,
+
+    pub max_units_inflow_x64: [u64; 4],
+    pub units_inflow_amplification_x64: [u64; 4],
+    pub current_units_inflow_x64: [u64; 4],
+    pub current_units_inflow_timestamp: u64,
+
+    pub current_liquidity_inflow: u64,
+    pub current_liquidity_inflow_timestamp: u64,
+
+    pub unit_tracker_x64: [u64; 4],     // I256 limbs (two's complement)
+
+    pub authority_bump: u8,
+    pub token_mint_bump: u8,
+    pub wallets_bumps: Vec<u8>
+}
+
+impl SwapPoolState {
+
+    /// Deserialize the state from the raw account data (including the Anchor discriminator).
+    /// NOTE: the discriminator is not verified, the caller must make sure that the account is owned by the SwapPoolAmplified program.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, QuoteError> {
+        let mut data = data.get(ACCOUNT_DISCRIMINATOR_LENGTH..).ok_or(QuoteError::InvalidAccountData)?;
+        Self::deserialize(&mut data).map_err(|_| QuoteError::InvalidAccountData)
+    }
+
+    pub fn asset_count(&self) -> usize {
+        self.pool_assets_mints.len()
+    }
+
+    pub fn get_asset_index(&self, asset: &Pubkey) -> Option<usize> {
+        self.pool_assets_mints.iter().position(|a| { a == asset })
+    }
+
+    /// Apply the ongoing amplification adjustment (if any) up to 'current_timestamp', as done on-chain before every swap of a
+    /// local-only pool. The stored amplification is only updated on-chain when the pool is used, so this should be called
+    /// before quoting.
+    /// NOTE: the amplification of cross chain pools is only moved by the 'update_amplification' instruction (which also
+    /// updates the unit tracker and the security limit), hence their stored amplification is left untouched.
+    pub fn update_amplification(&mut self, current_timestamp: u64) -> Result<(), QuoteError> {
+
+        if !self.local_only {
+            return Ok(());
+        }
+
+        let target_timestamp = self.amplification_adjustment_target_timestamp;
+        let last_timestamp   = self.amplification_adjustment_last_timestamp;
+
+        if target_timestamp == 0 || current_timestamp <= last_timestamp {
+            return Ok(());
+        }
+
+        let amplification_x64 = calculation_helpers::calc_amplification_adjustment(
+            U256(self.amplification_x64),
+            U256(self.target_amplification_x64),
+            current_timestamp - last_timestamp,
+            target_timestamp - last_timestamp
+        )?;
+
+        self.amplification_x64 = amplification_x64.0;
+
+        if current_timestamp >= target_timestamp {
+            self.amplification_adjustment_target_timestamp = 0;
+        }
+
+        self.amplification_adjustment_last_timestamp = current_timestamp;
+
+        Ok(())
+    }
+
+    fn compute_fees(&self, amount: u64) -> Fees {
+        let pool_fee = calculation_helpers::calc_fee_amount(amount, self.pool_fee_x64);
+        let governance_fee = calculation_helpers::calc_fee_amount(pool_fee, self.governance_fee_x64);
+
+        Fees { pool_fee, governance_fee }
+    }
+
+    // The escrowed assets cannot be used to pay out swaps
+    fn get_available_balance(&self, asset_balances: &[u64], asset_index: usize) -> Result<u64, QuoteError> {
+        Ok(asset_balances[asset_index].checked_sub(self.escrowed_assets[asset_index]).ok_or(MathError::Underflow)?)
+    }
+}
+
+
+
+// Asset swaps ******************************************************************************************************************
+// 'asset_balances' are the balances of the pool asset wallets, in the same order as 'pool_assets_mints'.
+
+/// Quote the output of local_swap.
+pub fn quote_local_swap(
+    state: &SwapPoolState,
+    asset_balances: &[u64],
+    from_asset_index: usize,
+    to_asset_index: usize,
+    amount: u64
+) -> Result<LocalSwapQuote, QuoteError> {
+    verify_not_paused(state.paused)?;
+    verify_balances_count(state.asset_count(), asset_balances)?;
+    verify_asset_index(state.asset_count(), from_asset_index)?;
+    verify_asset_index(state.asset_count(), to_asset_index)?;
+
+    let fees = state.compute_fees(amount);
+
+    let output = calculation_helpers::full_swap(
+        U256::from(amount - fees.pool_fee),      // Subtraction is safe, as pool_fee <= amount
+        U256::from(asset_balances[from_asset_index]),
+        U256::from(state.pool_assets_weights[from_asset_index]),
+        U256::from(state.get_available_balance(asset_balances, to_asset_index)?),
+        U256::from(state.pool_assets_weights[to_asset_index]),
+        U256(state.amplification_x64)
+    )?;
+
+    Ok(LocalSwapQuote { output: to_u64(output)?, fees })
+}
+
+/// Quote the units sent by out_swap.
+pub fn quote_out_swap(
+    state: &SwapPoolState,
+    asset_balances: &[u64],
+    from_asset_index: usize,
+    amount: u64
+) -> Result<OutSwapQuote, QuoteError> {
+    verify_not_paused(state.paused)?;
+    verify_not_local_only(state.local_only)?;
+    verify_balances_count(state.asset_count(), asset_balances)?;
+    verify_asset_index(state.asset_count(), from_asset_index)?;
+
+    let fees = state.compute_fees(amount);
+
+    let units_x64 = calculation_helpers::out_swap_x64(
+        U256::from(amount - fees.pool_fee),      // Subtraction is safe, as pool_fee <= amount
+        U256::from(asset_balances[from_asset_index]),
+        U256::from(state.pool_assets_weights[from_asset_index]),
+        U256(state.amplification_x64)
+    )?;
+
+    Ok(OutSwapQuote { units_x64, fees })
+}
+
+/// Quote the output of in_swap at the given timestamp.
+pub fn quote_in_swap(
+    state: &SwapPoolState,
+    asset_balances: &[u64],
+    to_asset_index: usize,
+    units_x64: U256,
+    current_timestamp: u64
+) -> Result<InSwapQuote, QuoteError> {
+    verify_not_paused(state.paused)?;
+    verify_not_local_only(state.local_only)?;
+    verify_balances_count(state.asset_count(), asset_balances)?;
+    verify_asset_index(state.asset_count(), to_asset_index)?;
+
+    let output = calculation_helpers::in_swap(
+        units_x64,
+        U256::from(state.get_available_balance(asset_balances, to_asset_index)?),
+        U256::from(state.pool_assets_weights[to_asset_index]),
+        U256(state.amplification_x64)
+    )?;
+
+    // NOTE: the pool also lowers the units inflow limit after the swap (proportionally to the swapped liquidity), which is not
+    // reflected in the returned headroom
+    let units_inflow_headroom_x64 = check_units_inflow(
+        U256(state.max_units_inflow_x64),
+        U256(state.current_units_inflow_x64),
+        state.current_units_inflow_timestamp,
+        units_x64,
+        current_timestamp
+    )?;
+
+    Ok(InSwapQuote { output: to_u64(output)?, units_inflow_headroom_x64 })
+}
+
+
+
+// Liquidity swaps **************************************************************************************************************
+
+/// Quote the liquidity units sent by out_liquidity_swap.
+pub fn quote_out_liquidity_swap(
+    state: &SwapPoolState,
+    pool_token_supply: u64,
+    pool_tokens_amount: u64
+) -> Result<OutLiquiditySwapQuote, QuoteError> {
+    verify_not_paused(state.paused)?;
+    verify_not_local_only(state.local_only)?;
+
+    let mut liquidity_units_x64 = U256::zero();
+
+    for asset_index in 0..state.asset_count() {
+
+        let asset_eq_balance = state.pool_assets_eq_balances[asset_index];
+
+        let pool_tokens_for_asset = pool_tokens_amount
+            .checked_mul(asset_eq_balance).ok_or(MathError::Overflow)?
+            .checked_div(pool_token_supply).ok_or(MathError::DivisionByZero)?;
+
+        let liquidity_for_asset_x64 = calculation_helpers::calc_out_liquidity_swap_x64(
+            U256::from(pool_tokens_for_asset),
+            U256::from(asset_eq_balance),
+            U256::from(state.pool_assets_weights[asset_index]),
+            U256(state.amplification_x64)
+        )?;
+
+        liquidity_units_x64 = liquidity_units_x64.checked_add(liquidity_for_asset_x64).ok_or(MathError::Overflow)?;
+    }
+
+    Ok(OutLiquiditySwapQuote { liquidity_units_x64 })
+}
+
+/// Quote the pool tokens minted by in_liquidity_swap at the given timestamp.
+pub fn quote_in_liquidity_swap(
+    state: &SwapPoolState,
+    pool_token_supply: u64,
+    liquidity_units_x64: U256,
+    current_timestamp: u64
+) -> Result<InLiquiditySwapQuote, QuoteError> {
+    verify_not_paused(state.paused)?;
+    verify_not_local_only(state.local_only)?;
+    verify_asset_index(state.asset_count(), 0)?;
+
+    let amplification_x64 = U256(state.amplification_x64);
+    let one_minus_amp_x64 = ONE_X64.checked_sub(amplification_x64).ok_or(MathError::Underflow)?;
+
+    // NOTE: rounded up, as on-chain
+    let mut aggregate_weight_x64 = U256::zero();
+    for asset_index in 0..state.asset_count() {
+        aggregate_weight_x64 = aggregate_weight_x64.checked_add(
+            U256::from(state.pool_assets_weights[asset_index]).checked_mul(
+                pow_x64_up(U256::from(state.pool_assets_eq_balances[asset_index]).shl(64u8), one_minus_amp_x64)?
+            ).ok_or(MathError::Overflow)?
+        ).ok_or(MathError::Overflow)?;
+    }
+
+    // The pool tokens are computed from the ones corresponding to the first asset of the pool
+    let asset_0_pool_tokens = calculation_helpers::calc_in_liquidity_swap(
+        liquidity_units_x64,
+        U256::from(state.pool_assets_eq_balances[0]),
+        aggregate_weight_x64,
+        amplification_x64
+    )?;
+
+    let pool_tokens = to_u64(
+        asset_0_pool_tokens
+            .checked_mul(U256::from(pool_token_supply)).ok_or(MathError::Overflow)?
+            .checked_div(U256::from(state.pool_assets_eq_balances[0])).ok_or(MathError::DivisionByZero)?
+    )?;
+
+    let liquidity_inflow_headroom = check_liquidity_inflow(
+        pool_token_supply,
+        state.current_liquidity_inflow,
+        state.current_liquidity_inflow_timestamp,
+        pool_tokens,
+        current_timestamp
+    )?;
+
+    Ok(InLiquiditySwapQuote { pool_tokens, liquidity_inflow_headroom })
+}
@@ -0,0 +1,366 @@
+use borsh::BorshSerialize;
+
+use shared_lib::math_error::MathError;
+use shared_lib::u256::U256;
+
+use crate::*;
+use crate::security_limit::DECAYRATE;
+
+
+
+// Helpers **********************************************************************************************************************
+
+const ONE_PERCENT_X64: u64 = 184467440737095516;            // 0.01 · 2^64
+const HALF_X64: u64 = 9223372036854775808;                  // 0.5 · 2^64
+
+const TIMESTAMP: u64 = 1_700_000_000;
+
+fn swap_pool_state() -> swap_pool::SwapPoolState {
+    swap_pool::SwapPoolState {
+        setup_master: [0x01; 32],
+        dao_authority: [0x02; 32],
+        pending_dao_authority: [0x00; 32],
+        paused: false,
+        ibc_interface: [0x03; 32],
+        local_only: false,
+        pool_assets_mints: vec![[0x11; 32], [0x12; 32], [0x13; 32]],
+        pool_assets_weights: vec![1, 1, 2],
+        pool_assets_target_weights: vec![1, 1, 2],
+        weights_adjustment_target_timestamp: 0,
+        weights_adjustment_last_timestamp: 0,
+        pool_assets_eq_balances: vec![1_000_000, 1_000_000, 1_000_000],
+        escrowed_assets: vec![0, 0, 0],
+        escrowed_pool_tokens: 0,
+        fee_administrator: [0x04; 32],
+        pool_fee_x64: ONE_PERCENT_X64,
+        governance_fee_x64: HALF_X64,
+        governance_fee_destination: [0x05; 32],
+        max_units_inflow_x64: (U256::from(1_000_000u64) << 64).0,
+        current_units_inflow_x64: [0, 0, 0, 0],
+        current_units_inflow_timestamp: 0,
+        current_liquidity_inflow: 0,
+        current_liquidity_inflow_timestamp: 0,
+        authority_bump: 255,
+        token_mint_bump: 254,
+        wallets_bumps: vec![253, 252, 251]
+    }
+}
+
+fn swap_pool_amplified_state() -> swap_pool_amplified::SwapPoolState {
+    swap_pool_amplified::SwapPoolState {
+        setup_master: [0x01; 32],
+        dao_authority: [0x02; 32],
+        pending_dao_authority: [0x00; 32],
+        paused: false,
+        ibc_interface: [0x03; 32],
+        local_only: false,
+        pool_assets_mints: vec![[0x11; 32], [0x12; 32]],
+        pool_assets_weights: vec![1, 1],
+        pool_assets_eq_balances: vec![1_000_000, 1_000_000],
+        amplification_x64: [HALF_X64, 0, 0, 0],
+        target_amplification_x64: [HALF_X64, 0, 0, 0],
+        amplification_adjustment_target_timestamp: 0,
+        amplification_adjustment_last_timestamp: 0,
+        escrowed_assets: vec![0, 0],
+        escrowed_pool_tokens: 0,
+        fee_administrator: [0x04; 32],
+        pool_fee_x64: 0,
+        governance_fee_x64: 0,
+        governance_fee_destination: [0x05; 32],
+        max_units_inflow_x64: (U256::from(1_000_000u64) << 64).0,
+        units_inflow_amplification_x64: [HALF_X64, 0, 0, 0],
+        current_units_inflow_x64: [0, 0, 0, 0],
+        current_units_inflow_timestamp: 0,
+        current_liquidity_inflow: 0,
+        current_liquidity_inflow_timestamp: 0,
+        unit_tracker_x64: [u64::MAX; 4],    // -1
+        authority_bump: 255,
+        token_mint_bump: 254,
+        wallets_bumps: vec![253, 252]
+    }
+}
+
+/// Replicates the account data of an Anchor account (discriminator + Borsh serialized state)
+fn account_data<T: BorshSerialize>(state: &T) -> Vec<u8> {
+    let mut data = vec![0xAA; ACCOUNT_DISCRIMINATOR_LENGTH];
+    data.extend(state.try_to_vec().unwrap());
+    data
+}
+
+
+
+// State ************************************************************************************************************************
+
+#[test]
+fn test_state_from_account_data() {
+    let state = swap_pool_state();
+    assert_eq!(swap_pool::SwapPoolState::try_from_account_data(&account_data(&state)), Ok(state));
+
+    let state = swap_pool_amplified_state();
+    assert_eq!(swap_pool_amplified::SwapPoolState::try_from_account_data(&account_data(&state)), Ok(state));
+}
+
+#[test]
+fn test_state_from_invalid_account_data() {
+    let data = account_data(&swap_pool_state());
+
+    assert_eq!(swap_pool::SwapPoolState::try_from_account_data(&data[..4]), Err(QuoteError::InvalidAccountData));
+    assert_eq!(swap_pool::SwapPoolState::try_from_account_data(&data[..data.len() - 1]), Err(QuoteError::InvalidAccountData));
+}
+
+
+
+// Asset swaps ******************************************************************************************************************
+
+#[test]
+fn test_local_swap_quote() {
+    let state = swap_pool_state();
+    let balances = [1_000_000, 1_000_000, 1_000_000];
+
+    let quote = swap_pool::quote_local_swap(&state, &balances, 0, 1, 1000, false).unwrap();
+
+    // 1% pool fee, of which 50% goes to governance
+    assert_eq!(quote.fees, Fees { pool_fee: 9, governance_fee: 4 });
+
+    // Equal weights: Bt·x/(At + x) = 10^6·991/(10^6 + 991) = 990.01.. (rounded down)
+    assert_eq!(quote.output, 990);
+
+    // Must match the on-chain calculation for any pair of assets
+    for approx in [false, true] {
+        let quote = swap_pool::quote_local_swap(&state, &balances, 2, 0, 5000, approx).unwrap();
+        let expected_output = swap_pool::calculation_helpers::full_swap(
+            U256::from(5000 - quote.fees.pool_fee),
+            U256::from(1_000_000u64),
+            U256::from(2u64),
+            U256::from(1_000_000u64),
+            U256::from(1u64),
+            approx
+        ).unwrap();
+
+        assert_eq!(U256::from(quote.output), expected_output);
+    }
+}
+
+#[test]
+fn test_local_swap_quote_escrowed_assets() {
+    let mut state = swap_pool_state();
+    let balances = [1_000_000, 1_000_000, 1_000_000];
+
+    // Escrowed assets are not available to pay out swaps
+    state.escrowed_assets[1] = 500_000;
+    let quote = swap_pool::quote_local_swap(&state, &balances, 0, 1, 1000, false).unwrap();
+    assert_eq!(quote.output, 495);      // 5·10^5·991/(10^6 + 991) = 495.00..
+
+    state.escrowed_assets[1] = 1_000_001;
+    assert_eq!(
+        swap_pool::quote_local_swap(&state, &balances, 0, 1, 1000, false),
+        Err(QuoteError::Math(MathError::Underflow))
+    );
+}
+
+#[test]
+fn test_amplified_swap_quotes() {
+    let state = swap_pool_amplified_state();
+    let balances = [1_000_000, 1_000_000];
+
+    let local_quote = swap_pool_amplified::quote_local_swap(&state, &balances, 0, 1, 1000).unwrap();
+    assert_eq!(local_quote.fees, Fees { pool_fee: 0, governance_fee: 0 });
+
+    // A local swap is equivalent to an out swap followed by an in swap
+    let out_quote = swap_pool_amplified::quote_out_swap(&state, &balances, 0, 1000).unwrap();
+    let in_quote = swap_pool_amplified::quote_in_swap(&state, &balances, 1, out_quote.units_x64, TIMESTAMP).unwrap();
+
+    assert_eq!(local_quote.output, in_quote.output);
+    assert!(local_quote.output < 1000);
+    assert_eq!(in_quote.units_inflow_headroom_x64, U256(state.max_units_inflow_x64) - out_quote.units_x64);
+}
+
+#[test]
+fn test_invalid_quote_parameters() {
+    let state = swap_pool_state();
+
+    assert_eq!(
+        swap_pool::quote_local_swap(&state, &[1_000_000, 1_000_000], 0, 1, 1000, false),
+        Err(QuoteError::InvalidBalancesCount { expected: 3, actual: 2 })
+    );
+
+    assert_eq!(
+        swap_pool::quote_out_swap(&state, &[1_000_000, 1_000_000, 1_000_000], 3, 1000, false),
+        Err(QuoteError::InvalidAssetIndex(3))
+    );
+}
+
+#[test]
+fn test_paused_pool_quotes() {
+    let mut state = swap_pool_state();
+    state.paused = true;
+
+    assert_eq!(
+        swap_pool::quote_local_swap(&state, &[1_000_000, 1_000_000, 1_000_000], 0, 1, 1000, false),
+        Err(QuoteError::PoolPaused)
+    );
+
+    let mut state = swap_pool_amplified_state();
+    state.paused = true;
+
+    assert_eq!(
+        swap_pool_amplified::quote_in_liquidity_swap(&state, 1_000_000, U256::zero(), TIMESTAMP),
+        Err(QuoteError::PoolPaused)
+    );
+}
+
+#[test]
+fn test_local_only_pool_quotes() {
+    let mut state = swap_pool_state();
+    state.local_only = true;
+
+    let balances = [1_000_000, 1_000_000, 1_000_000];
+
+    assert!(swap_pool::quote_local_swap(&state, &balances, 0, 1, 1000, false).is_ok());
+    assert_eq!(
+        swap_pool::quote_out_swap(&state, &balances, 0, 1000, false),
+        Err(QuoteError::LocalOnlyPool)
+    );
+    assert_eq!(
+        swap_pool::quote_in_swap(&state, &balances, 0, U256::from(1000u64) << 64, false, TIMESTAMP),
+        Err(QuoteError::LocalOnlyPool)
+    );
+}
+
+#[test]
+fn test_weights_adjustment() {
+    let mut state = swap_pool_state();
+    state.pool_assets_weights = vec![100, 100, 200];
+    state.pool_assets_target_weights = vec![200, 50, 200];
+    state.weights_adjustment_last_timestamp = TIMESTAMP;
+    state.weights_adjustment_target_timestamp = TIMESTAMP + 100;
+
+    // Halfway through the adjustment
+    state.update_weights(TIMESTAMP + 50).unwrap();
+    assert_eq!(state.pool_assets_weights, vec![150, 75, 200]);
+    // The security limit is scaled by the change of the weights sum (400 => 425)
+    assert_eq!(state.max_units_inflow_x64, (U256::from(1_062_500u64) << 64).0);
+    assert_eq!(state.weights_adjustment_last_timestamp, TIMESTAMP + 50);
+
+    // Past the target timestamp, the target weights are reached and the adjustment is finished
+    state.update_weights(TIMESTAMP + 1000).unwrap();
+    assert_eq!(state.pool_assets_weights, vec![200, 50, 200]);
+    assert_eq!(state.max_units_inflow_x64, (U256::from(1_125_000u64) << 64).0);
+    assert_eq!(state.weights_adjustment_target_timestamp, 0);
+
+    // No further changes once the adjustment is finished
+    let finished_state = state.clone();
+    state.update_weights(TIMESTAMP + 2000).unwrap();
+    assert_eq!(state, finished_state);
+}
+
+#[test]
+fn test_amplification_adjustment() {
+    let balances = [1_000_000, 1_000_000];
+
+    let mut state = swap_pool_amplified_state();
+    state.target_amplification_x64 = [HALF_X64 / 2, 0, 0, 0];
+    state.amplification_adjustment_last_timestamp = TIMESTAMP;
+    state.amplification_adjustment_target_timestamp = TIMESTAMP + 100;
+
+    // The amplification of cross chain pools is only moved by the 'update_amplification' instruction
+    let cross_chain_state = state.clone();
+    state.update_amplification(TIMESTAMP + 50).unwrap();
+    assert_eq!(state, cross_chain_state);
+
+    // The amplification of local-only pools is moved whenever the pool is used
+    state.local_only = true;
+
+    let initial_state = state.clone();
+    let initial_quote = swap_pool_amplified::quote_local_swap(&state, &balances, 0, 1, 1000).unwrap();
+
+    // Halfway through the adjustment
+    state.update_amplification(TIMESTAMP + 50).unwrap();
+    assert_eq!(state.amplification_x64, [HALF_X64 / 4 * 3, 0, 0, 0]);
+    assert_eq!(state.amplification_adjustment_last_timestamp, TIMESTAMP + 50);
+
+    // The security limit is not used by local-only pools, and is left untouched
+    assert_eq!(state.units_inflow_amplification_x64, initial_state.units_inflow_amplification_x64);
+    assert_eq!(state.max_units_inflow_x64, initial_state.max_units_inflow_x64);
+
+    // Past the target timestamp, the target amplification is reached and the adjustment is finished
+    state.update_amplification(TIMESTAMP + 1000).unwrap();
+    assert_eq!(state.amplification_x64, [HALF_X64 / 2, 0, 0, 0]);
+    assert_eq!(state.amplification_adjustment_target_timestamp, 0);
+
+    // Quotes use the updated amplification
+    assert_ne!(swap_pool_amplified::quote_local_swap(&state, &balances, 0, 1, 1000).unwrap(), initial_quote);
+}
+
+
+
+// Security limit ***************************************************************************************************************
+
+#[test]
+fn test_in_swap_quote_security_limit() {
+    let mut state = swap_pool_state();
+    let balances = [1_000_000, 1_000_000, 1_000_000];
+    let max_units_inflow_x64 = U256(state.max_units_inflow_x64);
+
+    // Full headroom
+    let quote = swap_pool::quote_in_swap(&state, &balances, 0, U256::zero(), false, TIMESTAMP).unwrap();
+    assert_eq!(quote.output, 0);
+    assert_eq!(quote.units_inflow_headroom_x64, max_units_inflow_x64);
+
+    assert_eq!(
+        swap_pool::quote_in_swap(&state, &balances, 0, max_units_inflow_x64 + 1, false, TIMESTAMP),
+        Err(QuoteError::SwapLimitExceeded)
+    );
+
+    // The limit is fully used, and half of it has decayed
+    state.current_units_inflow_x64 = max_units_inflow_x64.0;
+    state.current_units_inflow_timestamp = TIMESTAMP - DECAYRATE / 2;
+
+    let quote = swap_pool::quote_in_swap(&state, &balances, 0, U256::zero(), false, TIMESTAMP).unwrap();
+    assert_eq!(quote.units_inflow_headroom_x64, max_units_inflow_x64 / 2);
+
+    assert_eq!(
+        swap_pool::quote_in_swap(&state, &balances, 0, max_units_inflow_x64 / 2 + 1, false, TIMESTAMP),
+        Err(QuoteError::SwapLimitExceeded)
+    );
+
+    // The limit has fully decayed
+    let quote = swap_pool::quote_in_swap(&state, &balances, 0, U256::zero(), false, TIMESTAMP + DECAYRATE).unwrap();
+    assert_eq!(quote.units_inflow_headroom_x64, max_units_inflow_x64);
+}
+
+
+
+// Liquidity swaps **************************************************************************************************************
+
+#[test]
+fn test_liquidity_swap_quotes() {
+    let state = swap_pool_state();
+    let pool_token_supply = 1_000_000;
+
+    let out_quote = swap_pool::quote_out_liquidity_swap(&state, pool_token_supply, 1000).unwrap();
+    assert!(!out_quote.liquidity_units_x64.is_zero());
+
+    // Swapping the liquidity units back into the same pool must not yield more pool tokens
+    let in_quote = swap_pool::quote_in_liquidity_swap(&state, pool_token_supply, out_quote.liquidity_units_x64, TIMESTAMP).unwrap();
+    assert!(in_quote.pool_tokens <= 1000);
+    assert_eq!(in_quote.liquidity_inflow_headroom, pool_token_supply / 2 - in_quote.pool_tokens);
+
+    assert_eq!(
+        swap_pool::quote_out_liquidity_swap(&state, 0, 1000),
+        Err(QuoteError::Math(MathError::DivisionByZero))
+    );
+}
+
+#[test]
+fn test_in_liquidity_swap_quote_security_limit() {
+    let state = swap_pool_amplified_state();
+    let pool_token_supply = 1_000_000;
+
+    let out_quote = swap_pool_amplified::quote_out_liquidity_swap(&state, pool_token_supply, pool_token_supply / 2 + 10_000).unwrap();
+
+    assert_eq!(
+        swap_pool_amplified::quote_in_liquidity_swap(&state, pool_token_supply, out_quote.liquidity_units_x64, TIMESTAMP),
+        Err(QuoteError::LiquiditySwapLimitExceeded)
+    );
+}
@@ -1,4 +1,5 @@
-use crate::u256::U256;
+use crate::u256::{U256, U512};
+use crate::math_error::MathError;
 
 const P_XX      : u64  = 64;
 const P_XX_MAX  : U256 = U256([0xFFFFFFFFFFFFFFFFu64, 0, 0, 0]);
@@ -23,9 +24,7 @@ const INV_EXP_POS_LOOKUP    : [U256; 8]  = [U256([6786177901268885274, 0, 0, 0])
 
 const INV_EXP_NEG_LOOKUP    : [U256; 20] = [U256([11188515852577165299, 0, 0, 0]), U256([14366338729722795843, 0, 0, 0]), U256([16279194507819420732, 0, 0, 0]), U256([17329112349219823218, 0, 0, 0]), U256([17879197424118840458, 0, 0, 0]), U256([18160753814917686419, 0, 0, 0]), U256([18303190372430456779, 0, 0, 0]), U256([18374827034086858296, 0, 0, 0]), U256([18410750438167364677, 0, 0, 0]), U256([18428738468430479223, 0, 0, 0]), U256([18437739073120195921, 0, 0, 0]), U256([18442241023793258495, 0, 0, 0]), U256([18444492411329227605, 0, 0, 0]), U256([18445618208161748319, 0, 0, 0]), U256([18446181132345977515, 0, 0, 0]), U256([18446462600880313685, 0, 0, 0]), U256([18446603336758065834, 0, 0, 0]), U256([18446673705099591509, 0, 0, 0]), U256([18446708889371017194, 0, 0, 0]), U256([18446726481531895805, 0, 0, 0])];
 
-// TODO currently these functions return Result<>. would it be better to simply panic?
-
-pub fn mul_x64(a: U256, b: U256) -> Result<U256, ()> {
+pub fn mul_x64(a: U256, b: U256) -> Result<U256, MathError> {
     let (r0, _) = a.overflowing_mul(b);
     let mut r1 = mulmod(a, b, U256_MAX);
 
@@ -35,35 +34,52 @@ pub fn mul_x64(a: U256, b: U256) -> Result<U256, ()> {
     // We know r1 expand with shift(r1, HighBase-pXX)
     // r1 · 2^(256-64) = r1 · 2^192 < 2^256
     // r1 < 2^64 (or r1 <= 2^64 - 1, that is P_XX_MAX)
-    if r1 > P_XX_MAX { return Err(()); }
+    if r1 > P_XX_MAX { return Err(MathError::Overflow); }
 
     Ok((r1 << (256-P_XX)) + (r0 >> P_XX))
 }
 
 
-pub fn div_x64(a: U256, b: U256) -> Result<U256, ()> {
-    if b.is_zero() { return Err(()) };
+/// Computes a/b, rounded down.
+/// NOTE: computed with a 512 bit intermediate value, so it only fails if the result does not fit in 256 bits.
+pub fn div_x64(a: U256, b: U256) -> Result<U256, MathError> {
+    mul_div_x64(a, ONE_X64, b)
+}
+
 
-    let m = P_XX_MAX % b; // 2**p-1 % b
-    let r = P_XX_MAX / b; // 2**p-1 / b
+/// Computes a/b, rounded up.
+/// NOTE: computed with a 512 bit intermediate value, so it only fails if the result does not fit in 256 bits.
+pub fn div_x64_up(a: U256, b: U256) -> Result<U256, MathError> {
+    mul_div_x64_up(a, ONE_X64, b)
+}
 
-    // Ok(r * a + (m + P_XX_ONE) * a / b)
-    r.checked_mul(a).ok_or(())?
-        .checked_add(
-            (m.checked_add(P_XX_ONE).ok_or(())?).checked_mul(a / b).ok_or(())?
-        ).ok_or(())
 
-    //TODO fix overflow of (m + P_XX_ONE) * a
-    // let (partial, overflowed) = (m + P_XX_ONE).overflowing_mul(a);
-    // if overflowed {
-    //     return Err(());
-    // }
-    // Ok(r * a + partial / b) 
+/// Computes a*b/c, rounded down.
+/// The x64 scaling of b and c cancel out, hence the output has the same scaling as a (i.e. it is an x64 value if a is).
+/// NOTE: a*b is computed with 512 bits, so it only fails if the result does not fit in 256 bits.
+pub fn mul_div_x64(a: U256, b: U256, c: U256) -> Result<U256, MathError> {
+    if c.is_zero() { return Err(MathError::DivisionByZero) };
+
+    U256::try_from(U512::from(a) * U512::from(b) / U512::from(c))
 }
 
 
-pub fn log2_x64(x: U256) -> Result<U256, ()> {
-    if x.is_zero() { return Err(()) }
+/// Computes a*b/c, rounded up.
+/// The x64 scaling of b and c cancel out, hence the output has the same scaling as a (i.e. it is an x64 value if a is).
+/// NOTE: a*b is computed with 512 bits, so it only fails if the result does not fit in 256 bits.
+pub fn mul_div_x64_up(a: U256, b: U256, c: U256) -> Result<U256, MathError> {
+    if c.is_zero() { return Err(MathError::DivisionByZero) };
+
+    let (quotient, remainder) = (U512::from(a) * U512::from(b)).div_mod(U512::from(c));
+
+    // NOTE: quotient + 1 cannot overflow a U512, as quotient <= a*b < 2^512 - 1
+    if remainder.is_zero() { U256::try_from(quotient) }
+    else                   { U256::try_from(quotient + U512::one()) }
+}
+
+
+pub fn log2_x64(x: U256) -> Result<U256, MathError> {
+    if x.is_zero() { return Err(MathError::DomainError) }
 
     let mut x_i = x.clone();
     let mut log2_intermediate = ZERO_X64;
@@ -100,7 +116,7 @@ pub fn log2_x64(x: U256) -> Result<U256, ()> {
         // x_i = x_i >> 1u32;
         log2_intermediate += U256([1, 0, 0, 0]);
     }
-    log2_intermediate = log2_intermediate.checked_sub(U256([P_XX, 0, 0, 0])).ok_or(())?;
+    log2_intermediate = log2_intermediate.checked_sub(U256([P_XX, 0, 0, 0])).ok_or(MathError::Underflow)?;  // x < 1 (negative output)
     // TODO: Is it cheaper to get the major and then run this on the major
     // instead of running it on the whole and then removing 64.
 
@@ -119,14 +135,14 @@ pub fn log2_x64(x: U256) -> Result<U256, ()> {
 }
 
 
-pub fn ln_x64(x: U256) -> Result<U256, ()> {
+pub fn ln_x64(x: U256) -> Result<U256, MathError> {
     Ok(mul_x64(log2_x64(x)?, LN2_X64)?)
 }
 
 
-pub fn pow2_x64(x: U256) -> Result<U256, ()> {
+pub fn pow2_x64(x: U256) -> Result<U256, MathError> {
     let major_x = x >> P_XX;
-    if major_x >= U256([192, 0, 0, 0]) { return Err(()) };
+    if major_x >= U256([192, 0, 0, 0]) { return Err(MathError::Overflow) };
 
     let mut intermediate = ONE_X64; // 2**64
     for i in 1..20 {
@@ -143,11 +159,11 @@ pub fn pow2_x64(x: U256) -> Result<U256, ()> {
 }
 
 
-pub fn inv_pow2_x64(x: U256) -> Result<U256, ()> {
+pub fn inv_pow2_x64(x: U256) -> Result<U256, MathError> {
     let major_x = x >> P_XX;
 
     // dev: Major larger than fixed points. Reserve a few (64-41=23) bits for accuracy
-    if major_x >=  U256([41, 0, 0, 0]) { return Err(()) };
+    if major_x >=  U256([41, 0, 0, 0]) { return Err(MathError::Underflow) };
 
     let mut intermediate = ONE_X64; // 2**64
     for i in 1..(20-1) {
@@ -163,20 +179,20 @@ pub fn inv_pow2_x64(x: U256) -> Result<U256, ()> {
     Ok(intermediate >> major_x)
 }
     
-pub fn pow_x64(x: U256, p: U256) -> Result<U256, ()> {
+pub fn pow_x64(x: U256, p: U256) -> Result<U256, MathError> {
     Ok(pow2_x64(
       mul_x64(p, log2_x64(x)?)? 
     )?)
 }
     
-pub fn inv_pow_x64(x: U256, p: U256) -> Result<U256, ()> {
+pub fn inv_pow_x64(x: U256, p: U256) -> Result<U256, MathError> {
     Ok(inv_pow2_x64(
       mul_x64(log2_x64(x)?, p)? 
     )?)
 }
     
-pub fn exp_x64(x: U256) -> Result<U256, ()> {
-    if (x >> U256([64, 0, 0, 0])) > U256([134, 0, 0, 0]) { return Err(()) };
+pub fn exp_x64(x: U256) -> Result<U256, MathError> {
+    if (x >> U256([64, 0, 0, 0])) > U256([134, 0, 0, 0]) { return Err(MathError::Overflow) };
 
     let mut exp_intermediate = ONE_X64; // 2**64
     for i in 0..8 {
@@ -196,8 +212,8 @@ pub fn exp_x64(x: U256) -> Result<U256, ()> {
     Ok(exp_intermediate)
 }
     
-pub fn inv_exp_x64(x: U256) -> Result<U256, ()> {
-    if x > (P_XX_ONE << ((P_XX as usize) + 4)) { return Err(()) };
+pub fn inv_exp_x64(x: U256) -> Result<U256, MathError> {
+    if x > (P_XX_ONE << ((P_XX as usize) + 4)) { return Err(MathError::Underflow) };
 
     let mut exp_intermediate = ONE_X64; // 2**64
     for i in 0..8 {
@@ -217,7 +233,7 @@ pub fn inv_exp_x64(x: U256) -> Result<U256, ()> {
     Ok(exp_intermediate)
 }
     
-pub fn safe_pow_x64(a: U256, b: U256, p: U256) -> Result<U256, ()> {
+pub fn safe_pow_x64(a: U256, b: U256, p: U256) -> Result<U256, MathError> {
     if a < b {
         return Ok(inv_pow_x64(div_x64(b, a)?, p)?);
     }
@@ -225,6 +241,109 @@ pub fn safe_pow_x64(a: U256, b: U256, p: U256) -> Result<U256, ()> {
 }
 
 
+// Directed rounding ************************************************************************************************************
+// mul_x64, div_x64 and mul_div_x64 round down, and have _up variants which round up. log2_x64, pow2_x64 and inv_pow2_x64 are
+// approximations which are not guaranteed to round in any given direction, hence their _down/_up variants widen the
+// approximation by a margin larger than its maximum error. The pow variants combine the former so that every step rounds
+// in the same direction.
+// NOTE: these are meant for computations which must never round against a given party (e.g. the pool on swaps).
+
+// log2_x64 computes the first 31 fractional bits of the result: absolute error < 2^-31. Margin: 2^-30
+const LOG2_ERROR_MARGIN_X64     : U256 = U256([1 << 34, 0, 0, 0]);
+
+// pow2_x64 ignores the input bits below 2^-19: relative error < ln(2)·2^-19 ≈ 2^-19.5. Margin: 2^-19 (relative)
+const POW2_ERROR_MARGIN_SHIFT     : u32 = 19;
+
+// inv_pow2_x64 ignores the input bits below 2^-18: relative error < ln(2)·2^-18 ≈ 2^-18.5. Margin: 2^-18 (relative)
+const INV_POW2_ERROR_MARGIN_SHIFT : u32 = 18;
+
+
+/// Computes a*b, rounded up.
+pub fn mul_x64_up(a: U256, b: U256) -> Result<U256, MathError> {
+    mul_div_x64_up(a, b, ONE_X64)
+}
+
+
+/// Computes a lower bound of log2(x).
+pub fn log2_x64_down(x: U256) -> Result<U256, MathError> {
+    Ok(log2_x64(x)?.saturating_sub(LOG2_ERROR_MARGIN_X64))
+}
+
+
+/// Computes an upper bound of log2(x).
+pub fn log2_x64_up(x: U256) -> Result<U256, MathError> {
+    log2_x64(x)?.checked_add(LOG2_ERROR_MARGIN_X64).ok_or(MathError::Overflow)
+}
+
+
+/// Computes a lower bound of 2^x.
+pub fn pow2_x64_down(x: U256) -> Result<U256, MathError> {
+    let pow2 = pow2_x64(x)?;
+
+    // The extra 1 accounts for the rounding of the margin itself
+    Ok(pow2.saturating_sub((pow2 >> POW2_ERROR_MARGIN_SHIFT) + 1))
+}
+
+
+/// Computes an upper bound of 2^x.
+pub fn pow2_x64_up(x: U256) -> Result<U256, MathError> {
+    let pow2 = pow2_x64(x)?;
+
+    // The extra 1 accounts for the rounding of the margin itself
+    pow2.checked_add((pow2 >> POW2_ERROR_MARGIN_SHIFT) + 1).ok_or(MathError::Overflow)
+}
+
+
+/// Computes a lower bound of 2^(-x).
+pub fn inv_pow2_x64_down(x: U256) -> Result<U256, MathError> {
+    let inv_pow2 = inv_pow2_x64(x)?;
+
+    // The extra 1 accounts for the rounding of the margin and of the result (inv_pow2_x64 truncates its output)
+    Ok(inv_pow2.saturating_sub((inv_pow2 >> INV_POW2_ERROR_MARGIN_SHIFT) + 1))
+}
+
+
+/// Computes an upper bound of 2^(-x).
+pub fn inv_pow2_x64_up(x: U256) -> Result<U256, MathError> {
+    let inv_pow2 = inv_pow2_x64(x)?;
+
+    // The extra 1 accounts for the rounding of the margin and of the result (inv_pow2_x64 truncates its output)
+    Ok(inv_pow2 + (inv_pow2 >> INV_POW2_ERROR_MARGIN_SHIFT) + 1)      // inv_pow2 <= 2^64, cannot overflow
+}
+
+
+/// Computes a lower bound of x^p, for x >= 1.
+pub fn pow_x64_down(x: U256, p: U256) -> Result<U256, MathError> {
+    pow2_x64_down(
+        mul_x64(p, log2_x64_down(x)?)?
+    )
+}
+
+
+/// Computes an upper bound of x^p, for x >= 1.
+pub fn pow_x64_up(x: U256, p: U256) -> Result<U256, MathError> {
+    pow2_x64_up(
+        mul_x64_up(p, log2_x64_up(x)?)?
+    )
+}
+
+
+/// Computes a lower bound of x^(-p), for x >= 1.
+pub fn inv_pow_x64_down(x: U256, p: U256) -> Result<U256, MathError> {
+    inv_pow2_x64_down(
+        mul_x64_up(log2_x64_up(x)?, p)?
+    )
+}
+
+
+/// Computes an upper bound of x^(-p), for x >= 1.
+pub fn inv_pow_x64_up(x: U256, p: U256) -> Result<U256, MathError> {
+    inv_pow2_x64_up(
+        mul_x64(log2_x64_down(x)?, p)?
+    )
+}
+
+
 // https://stackoverflow.com/questions/12168348/ways-to-do-modulo-multiplication-with-primitive-types
 fn mulmod(a: U256, b: U256, m: U256) -> U256 {
     let mut res = ZERO_X64;
@@ -0,0 +1,148 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+use crate::u256::U256;
+use crate::math_error::MathError;
+
+const SIGN_BIT: U256 = U256([0, 0, 0, 0x8000000000000000u64]);
+
+/// Signed 256 bit integer, stored in two's complement (same representation as Solidity's int256).
+/// Used as the signed counterpart of U256 for the signed x64 math (see signed_fixed_point_math_x64).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct I256(pub U256);
+
+impl I256 {
+
+    pub const ZERO : I256 = I256(U256([0, 0, 0, 0]));
+    pub const MAX  : I256 = I256(U256([0xFFFFFFFFFFFFFFFFu64, 0xFFFFFFFFFFFFFFFFu64, 0xFFFFFFFFFFFFFFFFu64, 0x7FFFFFFFFFFFFFFFu64]));
+    pub const MIN  : I256 = I256(SIGN_BIT);
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        !(self.0 & SIGN_BIT).is_zero()
+    }
+
+    /// Convert an unsigned value. Fails if the value is larger than I256::MAX.
+    pub fn from_unsigned(value: U256) -> Result<I256, MathError> {
+        if value >= SIGN_BIT { return Err(MathError::Overflow) }
+        Ok(I256(value))
+    }
+
+    /// Convert into an unsigned value. Fails for negative values.
+    pub fn to_unsigned(self) -> Result<U256, MathError> {
+        if self.is_negative() { return Err(MathError::Underflow) }
+        Ok(self.0)
+    }
+
+    /// Build a signed value from its sign and absolute value. Fails if the result does not fit into an I256.
+    pub fn from_sign_and_abs(negative: bool, abs: U256) -> Result<I256, MathError> {
+        if negative {
+            if abs > SIGN_BIT { return Err(MathError::Underflow) }
+            return Ok(I256((!abs).overflowing_add(U256::one()).0));
+        }
+        I256::from_unsigned(abs)
+    }
+
+    /// Absolute value. Never overflows, as it is returned as an unsigned value (I256::MIN => 2**255).
+    pub fn unsigned_abs(self) -> U256 {
+        if self.is_negative() { (!self.0).overflowing_add(U256::one()).0 }
+        else                  { self.0 }
+    }
+
+    pub fn checked_neg(self) -> Result<I256, MathError> {
+        if self == I256::MIN { return Err(MathError::Overflow) }
+        Ok(I256((!self.0).overflowing_add(U256::one()).0))
+    }
+
+    pub fn checked_add(self, other: I256) -> Result<I256, MathError> {
+        let result = I256(self.0.overflowing_add(other.0).0);
+
+        // Overflow only if both operands have the same sign, and the result has a different one
+        if self.is_negative() == other.is_negative() && result.is_negative() != self.is_negative() {
+            return Err(if self.is_negative() { MathError::Underflow } else { MathError::Overflow });
+        }
+
+        Ok(result)
+    }
+
+    pub fn checked_sub(self, other: I256) -> Result<I256, MathError> {
+        let result = I256(self.0.overflowing_sub(other.0).0);
+
+        // Overflow only if the operands have different signs, and the result sign differs from the minuend one
+        if self.is_negative() != other.is_negative() && result.is_negative() != self.is_negative() {
+            return Err(if self.is_negative() { MathError::Underflow } else { MathError::Overflow });
+        }
+
+        Ok(result)
+    }
+}
+
+impl Ord for I256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Flipping the sign bit maps the two's complement range onto the unsigned range preserving the order
+        (self.0 ^ SIGN_BIT).cmp(&(other.0 ^ SIGN_BIT))
+    }
+}
+
+impl PartialOrd for I256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<i128> for I256 {
+    fn from(value: i128) -> Self {
+        let extension = if value < 0 { 0xFFFFFFFFFFFFFFFFu64 } else { 0 };
+        let value = value as u128;
+
+        I256(U256([value as u64, (value >> 64) as u64, extension, extension]))
+    }
+}
+
+impl From<i64> for I256 {
+    fn from(value: i64) -> Self {
+        I256::from(value as i128)
+    }
+}
+
+impl TryFrom<I256> for i128 {
+    type Error = MathError;
+
+    fn try_from(value: I256) -> Result<Self, Self::Error> {
+        let limbs = (value.0).0;
+        let result = ((limbs[1] as u128) << 64 | limbs[0] as u128) as i128;
+
+        // The upper limbs must be the sign extension of the lower 128 bits
+        let extension = if result < 0 { 0xFFFFFFFFFFFFFFFFu64 } else { 0 };
+        if limbs[2] != extension || limbs[3] != extension {
+            return Err(if value.is_negative() { MathError::Underflow } else { MathError::Overflow });
+        }
+
+        Ok(result)
+    }
+}
+
+// Borsh serialization. Serialized as the 4 little-endian u64 limbs of its two's complement representation (see U256).
+#[cfg(feature = "borsh")]
+mod borsh_serialization {
+    use std::io;
+    use borsh::{BorshDeserialize, BorshSerialize};
+
+    use super::I256;
+    use crate::u256::U256;
+
+    impl BorshSerialize for I256 {
+        fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+            self.0.serialize(writer)
+        }
+    }
+
+    impl BorshDeserialize for I256 {
+        fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+            Ok(I256(U256::deserialize(buf)?))
+        }
+    }
+}
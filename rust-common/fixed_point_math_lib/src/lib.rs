@@ -1,7 +1,10 @@
 pub mod fixed_point_math_x64;
+pub mod math_error;
+pub mod signed_fixed_point_math_x64;
 pub mod u256;
+pub mod i256;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-utils"))]
 pub mod test {
     pub mod test_common;
 
@@ -11,5 +14,15 @@ pub mod test {
 
     pub mod test_mul;
     pub mod test_div;
+    pub mod test_mul_div;
+    pub mod test_rounding;
     pub mod test_pow;
+
+    pub mod test_math_error;
+
+    pub mod test_i256;
+    pub mod test_signed;
+
+    #[cfg(feature = "borsh")]
+    pub mod test_u256_borsh;
 }
\ No newline at end of file
@@ -0,0 +1,25 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathError {
+    /// The result is too large to be represented
+    Overflow,
+    /// The result is too small (or negative) to be represented, or too small to be computed accurately
+    Underflow,
+    DivisionByZero,
+    /// The input is outside the domain of the function (e.g. log2_x64(0))
+    DomainError
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MathError::Overflow       => write!(f, "overflow"),
+            MathError::Underflow      => write!(f, "underflow"),
+            MathError::DivisionByZero => write!(f, "division by zero"),
+            MathError::DomainError    => write!(f, "input outside of the function domain")
+        }
+    }
+}
+
+impl std::error::Error for MathError {}
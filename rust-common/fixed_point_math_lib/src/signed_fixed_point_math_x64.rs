@@ -0,0 +1,62 @@
+use crate::u256::U256;
+use crate::i256::I256;
+use crate::math_error::MathError;
+use crate::fixed_point_math_x64 as unsigned;
+
+// Signed variants of the x64 fixed point math functions. The computation is delegated to the unsigned implementations
+// (fixed_point_math_x64), hence the same accuracy and input bounds apply to the absolute values.
+// NOTE: mul_x64 and div_x64 round towards zero.
+
+pub const ZERO_X64_SIGNED : I256 = I256(U256([0, 0, 0, 0]));
+pub const ONE_X64_SIGNED  : I256 = I256(U256([0, 1, 0, 0]));
+
+const P_XX_SIGNED : I256 = I256(U256([0, 64, 0, 0]));   // 64 (x64)
+
+
+pub fn mul_x64(a: I256, b: I256) -> Result<I256, MathError> {
+    I256::from_sign_and_abs(
+        a.is_negative() != b.is_negative(),
+        unsigned::mul_x64(a.unsigned_abs(), b.unsigned_abs())?
+    )
+}
+
+
+pub fn div_x64(a: I256, b: I256) -> Result<I256, MathError> {
+    I256::from_sign_and_abs(
+        a.is_negative() != b.is_negative(),
+        unsigned::div_x64(a.unsigned_abs(), b.unsigned_abs())?
+    )
+}
+
+
+pub fn log2_x64(x: I256) -> Result<I256, MathError> {
+    if x.is_negative() || x.is_zero() { return Err(MathError::DomainError) }
+
+    if x >= ONE_X64_SIGNED {
+        return I256::from_unsigned(unsigned::log2_x64(x.0)?);
+    }
+
+    // For x < 1: log2(x) = log2(x · 2**64) - 64. As x < 2**64 (x64), the shift cannot overflow.
+    I256::from_unsigned(unsigned::log2_x64(x.0 << 64)?)?.checked_sub(P_XX_SIGNED)
+}
+
+
+pub fn pow2_x64(x: I256) -> Result<I256, MathError> {
+    if x.is_negative() {
+        return I256::from_unsigned(unsigned::inv_pow2_x64(x.unsigned_abs())?);
+    }
+    I256::from_unsigned(unsigned::pow2_x64(x.0)?)
+}
+
+
+pub fn exp_x64(x: I256) -> Result<I256, MathError> {
+    if x.is_negative() {
+        return I256::from_unsigned(unsigned::inv_exp_x64(x.unsigned_abs())?);
+    }
+    I256::from_unsigned(unsigned::exp_x64(x.0)?)
+}
+
+
+pub fn pow_x64(x: I256, p: I256) -> Result<I256, MathError> {
+    pow2_x64(mul_x64(p, log2_x64(x)?)?)
+}
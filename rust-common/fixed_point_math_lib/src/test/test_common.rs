@@ -1,19 +1,16 @@
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-utils"))]
 pub mod test_common {
     use std::ops::Shr;
     use crate::u256::U256;
+    pub use crate::u256::U512;
+    use crate::i256::I256;
 
     use std::{ops::{Shl, Sub}, f64::{INFINITY, NEG_INFINITY}, fmt};
 
     use cached::proc_macro::cached;
 
     use rug::{Float, integer::Order};
-    use uint::construct_uint;
-
-    construct_uint! {
-        pub struct U512(8);
-    }
 
     pub fn u256_to_u512(val: U256) -> U512 {
         U512([val.0[0], val.0[1], val.0[2], val.0[3], 0, 0, 0, 0])
@@ -117,6 +114,18 @@ pub mod test_common {
         ]))
     }
 
+    pub fn int_x64_to_high_precision_float(val: &I256) -> Float {
+        let out = uint_x64_to_high_precision_float(&val.unsigned_abs());
+        if val.is_negative() { -out } else { out }
+    }
+
+    pub fn high_precision_float_to_int_x64(val: Float) -> Result<I256, String> {
+        let negative = val.is_sign_negative();
+        let abs = high_precision_float_to_uint_x64(val.abs())?;
+
+        I256::from_sign_and_abs(negative, abs).map_err(|_| "Overflow".to_owned())
+    }
+
     pub fn uint_x64_to_float(val: &U256) -> f64 {
         let val_arr = val.0;
 
@@ -202,6 +211,11 @@ pub mod test_common {
         ]))
     }
 
+    pub fn int_x64_to_float(val: &I256) -> f64 {
+        let out = uint_x64_to_float(&val.unsigned_abs());
+        if val.is_negative() { -out } else { out }
+    }
+
     pub fn rand_range(start: U256, end: U256) -> U256 {
         // TODO better implementation? Use U512
     
@@ -277,6 +291,30 @@ pub mod test_common {
         2.*(value_f - target_f)/(value_f + target_f)
     }
 
+    pub fn get_signed_rel_error(value: I256, target: I256) -> f64 {
+        if value.is_zero() && target.is_zero() {
+            return 0.;
+        }
+
+        let value_f  = int_x64_to_float(&value);
+        let target_f = int_x64_to_float(&target);
+
+        2.*(value_f - target_f)/(value_f.abs() + target_f.abs())
+    }
+
+    /// Output types which can be evaluated with evaluate_impl
+    pub trait EvalOutput: Copy {
+        fn rel_error(value: Self, target: Self) -> f64;
+    }
+
+    impl EvalOutput for U256 {
+        fn rel_error(value: Self, target: Self) -> f64 { get_rel_error(value, target) }
+    }
+
+    impl EvalOutput for I256 {
+        fn rel_error(value: Self, target: Self) -> f64 { get_signed_rel_error(value, target) }
+    }
+
     pub type EvalRelError = Option<f64>;
 
     #[derive(Debug)]
@@ -285,7 +323,7 @@ pub mod test_common {
         CalcForInvalidTarget
     }
 
-    pub struct EvaluateImplResult<T> {
+    pub struct EvaluateImplResult<T, O = U256> {
         pub low_error       : f64,
         pub high_error      : f64,
         pub avg_error       : f64,
@@ -293,8 +331,8 @@ pub mod test_common {
         pub max_abs_error   : f64,
         pub avg_abs_error   : f64,
         pub eval_points     : Vec<T>,
-        pub calc_points     : Vec<Result<U256, ()>>,
-        pub target_points   : Vec<Result<U256, ()>>,
+        pub calc_points     : Vec<Result<O, ()>>,
+        pub target_points   : Vec<Result<O, ()>>,
         pub relative_errors : Vec<Result<EvalRelError, EvalError>>,
         pub valid_count     : u64,
         pub invalid_count_expected_none: u64,
@@ -303,7 +341,7 @@ pub mod test_common {
         pub expected_none_count : u64,
     }
 
-    impl<T> fmt::Display for EvaluateImplResult<T> {
+    impl<T, O> fmt::Display for EvaluateImplResult<T, O> {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             writeln!(f, "Out of {} samples (of which {} are expected to fail)", self.relative_errors.len(), self.target_points.iter().filter(|target_result| target_result.is_err()).count())?;
             writeln!(f, "")?;
@@ -322,14 +360,14 @@ pub mod test_common {
         }
     }
 
-    pub fn evaluate_impl<T> (
-        impl_fn: fn(&T) -> Result<U256, ()>,
-        target_fn: fn(&T) -> Result<U256, ()>,
+    pub fn evaluate_impl<T, O: EvalOutput, E> (
+        impl_fn: fn(&T) -> Result<O, E>,
+        target_fn: fn(&T) -> Result<O, ()>,
         eval_points: Vec<T>
-    ) -> EvaluateImplResult<T> {
+    ) -> EvaluateImplResult<T, O> {
 
-        let calc_points   : Vec<Result<U256, ()>> = eval_points.iter().map(|p_x64| impl_fn(p_x64)).collect();
-        let target_points : Vec<Result<U256, ()>> = eval_points.iter().map(|p_x64| target_fn(p_x64)).collect();
+        let calc_points   : Vec<Result<O, ()>> = eval_points.iter().map(|p_x64| impl_fn(p_x64).map_err(|_err| ())).collect();
+        let target_points : Vec<Result<O, ()>> = eval_points.iter().map(|p_x64| target_fn(p_x64)).collect();
 
         let mut high_error: f64 = NEG_INFINITY;
         let mut low_error: f64 = INFINITY;
@@ -349,7 +387,7 @@ pub mod test_common {
                 },
                 (Ok(e), Ok(t)) => {
 
-                    let rel_error = get_rel_error(*e, *t);
+                    let rel_error = O::rel_error(*e, *t);
 
                     if rel_error > high_error { high_error = rel_error }
                     if rel_error < low_error  { low_error = rel_error }
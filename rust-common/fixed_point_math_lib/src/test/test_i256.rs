@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod test_i256 {
+    use std::convert::TryFrom;
+
+    use crate::u256::U256;
+    use crate::i256::I256;
+    use crate::fixed_point_math_x64::U256_MAX;
+    use crate::math_error::MathError;
+
+    #[test]
+    fn test_i256_conversions() {
+        for value in [0i128, 1, -1, i128::MAX, i128::MIN, 123456789, -987654321] {
+            let signed = I256::from(value);
+
+            assert_eq!(signed.is_negative(), value < 0);
+            assert_eq!(i128::try_from(signed), Ok(value));
+            assert_eq!(signed.unsigned_abs(), U256::from(value.unsigned_abs()));
+        }
+
+        assert_eq!(I256::from(-1i64).0, U256_MAX);
+        assert_eq!(i128::try_from(I256::MAX), Err(MathError::Overflow));
+        assert_eq!(i128::try_from(I256::MIN), Err(MathError::Underflow));
+
+        assert_eq!(I256::from_unsigned(I256::MAX.0), Ok(I256::MAX));
+        assert_eq!(I256::from_unsigned(I256::MAX.0 + 1), Err(MathError::Overflow));
+        assert_eq!(I256::from(-5i64).to_unsigned(), Err(MathError::Underflow));
+
+        // The absolute value of MIN is only representable as a negative number
+        assert_eq!(I256::MIN.unsigned_abs(), U256::one() << 255);
+        assert_eq!(I256::from_sign_and_abs(true, U256::one() << 255), Ok(I256::MIN));
+        assert_eq!(I256::from_sign_and_abs(false, U256::one() << 255), Err(MathError::Overflow));
+        assert_eq!(I256::from_sign_and_abs(true, (U256::one() << 255) + 1), Err(MathError::Underflow));
+        assert_eq!(I256::from_sign_and_abs(true, U256::zero()), Ok(I256::ZERO));
+    }
+
+    #[test]
+    fn test_i256_ordering() {
+        let mut values = vec![I256::MAX, I256::from(1i64), I256::MIN, I256::ZERO, I256::from(-1i64), I256::from(i128::MIN)];
+        values.sort();
+
+        assert_eq!(values, vec![I256::MIN, I256::from(i128::MIN), I256::from(-1i64), I256::ZERO, I256::from(1i64), I256::MAX]);
+    }
+
+    #[test]
+    fn test_i256_checked_arithmetic() {
+        assert_eq!(I256::from(5i64).checked_add(I256::from(-7i64)), Ok(I256::from(-2i64)));
+        assert_eq!(I256::from(-5i64).checked_sub(I256::from(-7i64)), Ok(I256::from(2i64)));
+        assert_eq!(I256::from(5i64).checked_neg(), Ok(I256::from(-5i64)));
+
+        assert_eq!(I256::MAX.checked_add(I256::from(1i64)), Err(MathError::Overflow));
+        assert_eq!(I256::MIN.checked_add(I256::from(-1i64)), Err(MathError::Underflow));
+        assert_eq!(I256::MIN.checked_sub(I256::from(1i64)), Err(MathError::Underflow));
+        assert_eq!(I256::MAX.checked_sub(I256::from(-1i64)), Err(MathError::Overflow));
+        assert_eq!(I256::MIN.checked_neg(), Err(MathError::Overflow));
+
+        assert_eq!(I256::MAX.checked_add(I256::MIN), Ok(I256::from(-1i64)));
+        assert_eq!(I256::MIN.checked_sub(I256::MIN), Ok(I256::ZERO));
+    }
+}
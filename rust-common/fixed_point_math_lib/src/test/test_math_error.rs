@@ -0,0 +1,22 @@
+#[cfg(test)]
+mod test_math_error {
+    use crate::u256::U256;
+    use crate::math_error::MathError;
+    use crate::fixed_point_math_x64::*;
+
+    /// The functions must report the reason of the failure
+    #[test]
+    fn test_math_error_kinds() {
+        assert_eq!(mul_x64(U256_MAX, U256_MAX), Err(MathError::Overflow));
+        assert_eq!(div_x64(ONE_X64, ZERO_X64), Err(MathError::DivisionByZero));
+
+        assert_eq!(log2_x64(ZERO_X64), Err(MathError::DomainError));
+        assert_eq!(log2_x64(ONE_X64 - 1), Err(MathError::Underflow));
+
+        assert_eq!(pow2_x64(U256::from(192) * ONE_X64), Err(MathError::Overflow));
+        assert_eq!(inv_pow2_x64(U256::from(41) * ONE_X64), Err(MathError::Underflow));
+
+        assert_eq!(exp_x64(U256::from(135) * ONE_X64), Err(MathError::Overflow));
+        assert_eq!(inv_exp_x64(U256::from(17) * ONE_X64), Err(MathError::Underflow));
+    }
+}
@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod test_mul_div {
+    use itertools::{Itertools, izip};
+
+    use crate::u256::{U256, U512};
+    use crate::math_error::MathError;
+    use crate::test::test_common::test_common::*;
+    use crate::fixed_point_math_x64::*;
+
+
+
+    // Test mul_div_x64 ********************************************************************************************************
+
+    // Set test bounds
+    const MUL_DIV_MAX_ABS_ERROR_BOUND: f64 = 0_f64;
+    const MUL_DIV_AVG_ABS_ERROR_BOUND: f64 = 0_f64;
+
+    /// Compute accurately a*b/c using U512 numbers, rounding either down or up
+    pub fn target_mul_div_x64(a: U256, b: U256, c: U256, round_up: bool) -> Result<U256, String> {
+
+        if c.is_zero() {
+            return Err("Can't divide by 0.".to_string())
+        }
+
+        let a = U512([a.0[0], a.0[1], a.0[2], a.0[3], 0, 0, 0, 0]);
+        let b = U512([b.0[0], b.0[1], b.0[2], b.0[3], 0, 0, 0, 0]);
+        let c = U512([c.0[0], c.0[1], c.0[2], c.0[3], 0, 0, 0, 0]);
+
+        let mut d = (a * b) / c;
+        if round_up && !((a * b) % c).is_zero() { d = d + 1 }
+
+        if d >= U512([0, 0, 0, 0, 1, 0, 0, 0]) { return Err("Mul div overflow".to_owned()) }
+
+        Ok(U256([d.0[0], d.0[1], d.0[2], d.0[3]]))
+    }
+
+    fn get_mul_div_points_of_interest() -> Vec<(U256, U256, U256)> {
+        let mut points_of_interest_x64 = vec![
+            ZERO_X64,
+            U256::one(),
+            ONE_X64,
+            U256_MAX
+        ];
+        points_of_interest_x64.append(&mut get_powers_of_2_x64(-64, 256 - 64, 16));
+        points_of_interest_x64.append(&mut get_powers_of_2_minus_1_x64(-64, 256 - 64 + 1, 16));
+
+        let points_of_interest_x64 = remove_duplicates_and_sort(points_of_interest_x64);
+
+        // Get all combinations (with repetition) of 3 elements of points_of_interest_x64
+        (0..3).map(|_| points_of_interest_x64.iter())
+            .multi_cartesian_product()
+            .map(|values| (*values[0], *values[1], *values[2]))
+            .collect()
+    }
+
+    /// Test mul_div_x64 and mul_div_x64_up for a set of interest points
+    #[test]
+    fn test_mul_div_poi() -> Result<(), ()> {
+
+        let points_of_interest_x64 = get_mul_div_points_of_interest();
+
+        let result = evaluate_impl(
+            |(a, b, c): &(U256, U256, U256)| mul_div_x64(*a, *b, *c),
+            |(a, b, c): &(U256, U256, U256)| target_mul_div_x64(*a, *b, *c, false).map_err(|_err| ()),
+            points_of_interest_x64.clone()
+        );
+
+        println!("\nmul_div_x64 - Points of interest\n{}", result);
+
+        assert!(result.max_abs_error <= MUL_DIV_MAX_ABS_ERROR_BOUND);
+        assert!(result.avg_abs_error <= MUL_DIV_AVG_ABS_ERROR_BOUND);
+        assert!(result.invalid_count_expected_none == 0);
+        assert!(result.invalid_count_expected_some == 0);
+
+        let result = evaluate_impl(
+            |(a, b, c): &(U256, U256, U256)| mul_div_x64_up(*a, *b, *c),
+            |(a, b, c): &(U256, U256, U256)| target_mul_div_x64(*a, *b, *c, true).map_err(|_err| ()),
+            points_of_interest_x64
+        );
+
+        println!("\nmul_div_x64_up - Points of interest\n{}", result);
+
+        assert!(result.max_abs_error <= MUL_DIV_MAX_ABS_ERROR_BOUND);
+        assert!(result.avg_abs_error <= MUL_DIV_AVG_ABS_ERROR_BOUND);
+        assert!(result.invalid_count_expected_none == 0);
+        assert!(result.invalid_count_expected_some == 0);
+
+        Ok(())
+    }
+
+    /// Test mul_div_x64 and mul_div_x64_up for a random set of numbers whose intermediate product overflows 256 bits,
+    /// but whose result does not
+    #[test]
+    fn test_mul_div_randrange_intermediate_overflow() -> Result<(), ()> {
+
+        // a, b in [2^128, 2^192), c in [2^192, 2^256) => a*b overflows 256 bits, but a*b/c < 2^384/2^192 = 2^192
+        let sample_count = 20000_usize;
+        let a_samples = sample_space(U256::one() << 128, U256::one() << 192, sample_count);
+        let b_samples = sample_space(U256::one() << 128, U256::one() << 192, sample_count);
+        let c_samples = sample_space(U256::one() << 192, U256_MAX, sample_count);
+
+        let points_of_interest_x64: Vec<(U256, U256, U256)> = izip!(a_samples, b_samples, c_samples).collect();
+
+        let result = evaluate_impl(
+            |(a, b, c): &(U256, U256, U256)| mul_div_x64(*a, *b, *c),
+            |(a, b, c): &(U256, U256, U256)| target_mul_div_x64(*a, *b, *c, false).map_err(|_err| ()),
+            points_of_interest_x64.clone()
+        );
+
+        println!("\nmul_div_x64 - Randrange a, b: [2^128, 2^192), c: [2^192, MAX)\n{}", result);
+
+        assert!(result.max_abs_error <= MUL_DIV_MAX_ABS_ERROR_BOUND);
+        assert!(result.avg_abs_error <= MUL_DIV_AVG_ABS_ERROR_BOUND);
+        assert!(result.invalid_count_expected_none == 0);
+        assert!(result.invalid_count_expected_some == 0);
+
+        assert!(result.expected_none_count == 0);
+
+        let result = evaluate_impl(
+            |(a, b, c): &(U256, U256, U256)| mul_div_x64_up(*a, *b, *c),
+            |(a, b, c): &(U256, U256, U256)| target_mul_div_x64(*a, *b, *c, true).map_err(|_err| ()),
+            points_of_interest_x64
+        );
+
+        println!("\nmul_div_x64_up - Randrange a, b: [2^128, 2^192), c: [2^192, MAX)\n{}", result);
+
+        assert!(result.max_abs_error <= MUL_DIV_MAX_ABS_ERROR_BOUND);
+        assert!(result.avg_abs_error <= MUL_DIV_AVG_ABS_ERROR_BOUND);
+        assert!(result.invalid_count_expected_none == 0);
+        assert!(result.invalid_count_expected_some == 0);
+
+        assert!(result.expected_none_count == 0);
+
+        Ok(())
+    }
+
+    /// Test the rounding direction and the error cases of the full precision operations
+    #[test]
+    fn test_mul_div_rounding() {
+
+        // 7/2 = 3.5
+        assert_eq!(mul_div_x64(U256::from(7), U256::one(), U256::from(2)), Ok(U256::from(3)));
+        assert_eq!(mul_div_x64_up(U256::from(7), U256::one(), U256::from(2)), Ok(U256::from(4)));
+
+        // Exact results are not rounded
+        assert_eq!(mul_div_x64(U256::from(8), U256::one(), U256::from(2)), Ok(U256::from(4)));
+        assert_eq!(mul_div_x64_up(U256::from(8), U256::one(), U256::from(2)), Ok(U256::from(4)));
+
+        // 1/3 in x64 form
+        assert_eq!(div_x64(ONE_X64, U256::from(3) << 64), Ok(U256::from(0x5555555555555555u64)));
+        assert_eq!(div_x64_up(ONE_X64, U256::from(3) << 64), Ok(U256::from(0x5555555555555556u64)));
+
+        // The intermediate product does not fit in 256 bits, but the result does
+        assert_eq!(mul_div_x64(U256_MAX, U256_MAX, U256_MAX), Ok(U256_MAX));
+        assert_eq!(mul_div_x64_up(U256_MAX, U256_MAX, U256_MAX), Ok(U256_MAX));
+        assert_eq!(div_x64(U256_MAX, U256_MAX), Ok(ONE_X64));
+
+        // Results that do not fit in 256 bits fail
+        assert_eq!(mul_div_x64(U256_MAX, U256::from(3), U256::from(3)), Ok(U256_MAX));
+        assert_eq!(mul_div_x64_up(U256_MAX, U256_MAX, U256_MAX - 1), Err(MathError::Overflow));
+
+        assert_eq!(mul_div_x64(U256::one(), U256::one(), U256::zero()), Err(MathError::DivisionByZero));
+        assert_eq!(mul_div_x64_up(U256::one(), U256::one(), U256::zero()), Err(MathError::DivisionByZero));
+        assert_eq!(div_x64_up(U256::one(), U256::zero()), Err(MathError::DivisionByZero));
+        assert_eq!(div_x64(U256_MAX, U256::one()), Err(MathError::Overflow));
+    }
+
+}
@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod test_rounding {
+    use rug::Float;
+    use rug::ops::Pow;
+
+    use crate::u256::U256;
+    use crate::test::test_common::test_common::*;
+    use crate::fixed_point_math_x64::*;
+
+
+
+    // Test directed rounding ***************************************************************************************************
+    // The _down/_up variants must bound the exact result (computed with high precision floats) from below/above.
+
+    /// Verify that down <= target <= up for every sample. Returns the number of samples which break the bounds.
+    fn count_bound_violations<T>(
+        samples: &[T],
+        down_fn: impl Fn(&T) -> Option<U256>,
+        up_fn: impl Fn(&T) -> Option<U256>,
+        target_fn: impl Fn(&T) -> Float
+    ) -> usize {
+        samples.iter().filter(|sample| {
+            let target = target_fn(sample);
+
+            let below = down_fn(sample).map_or(false, |down| uint_x64_to_high_precision_float(&down) > target);
+            let above = up_fn(sample).map_or(false, |up| uint_x64_to_high_precision_float(&up) < target);
+
+            below || above
+        }).count()
+    }
+
+    #[test]
+    fn test_mul_x64_rounding() {
+
+        let samples: Vec<(U256, U256)> = sample_space(U256::zero(), U256::one() << 160, 20000_usize).into_iter()
+            .zip(sample_space(U256::zero(), U256::one() << 128, 20000_usize))
+            .collect();
+
+        let violations = count_bound_violations(
+            &samples,
+            |(a, b)| mul_x64(*a, *b).ok(),
+            |(a, b)| mul_x64_up(*a, *b).ok(),
+            |(a, b)| uint_x64_to_high_precision_float(a) * uint_x64_to_high_precision_float(b)
+        );
+
+        assert!(violations == 0);
+
+        // The bounds must be tight
+        samples.iter().for_each(|(a, b)| {
+            let down = mul_x64(*a, *b).unwrap();
+            let up = mul_x64_up(*a, *b).unwrap();
+            assert!(up == down || up == down + 1);
+        });
+    }
+
+    #[test]
+    fn test_log2_x64_rounding() {
+
+        let mut samples = sample_space(ONE_X64, U256::one() << 70, 20000_usize);
+        samples.append(&mut sample_space(ONE_X64, U256_MAX, 20000_usize));
+        samples.append(&mut get_powers_of_2_x64(0, 256 - 64, 1));
+        samples.append(&mut get_powers_of_2_minus_1_x64(1, 256 - 64, 1));
+
+        let violations = count_bound_violations(
+            &samples,
+            |x| log2_x64_down(*x).ok(),
+            |x| log2_x64_up(*x).ok(),
+            |x| uint_x64_to_high_precision_float(x).log2()
+        );
+
+        assert!(violations == 0);
+    }
+
+    #[test]
+    fn test_pow2_x64_rounding() {
+
+        let mut samples = sample_space(U256::zero(), ONE_X64 << 4, 20000_usize);
+        samples.append(&mut sample_space(U256::zero(), U256::from(191) * ONE_X64, 20000_usize));
+        samples.append(&mut get_powers_of_2_x64(-64, 7, 1));
+
+        let violations = count_bound_violations(
+            &samples,
+            |x| pow2_x64_down(*x).ok(),
+            |x| pow2_x64_up(*x).ok(),
+            |x| Float::with_val(256, 2).pow(uint_x64_to_high_precision_float(x))
+        );
+
+        assert!(violations == 0);
+    }
+
+    #[test]
+    fn test_inv_pow2_x64_rounding() {
+
+        let mut samples = sample_space(U256::zero(), ONE_X64 << 2, 20000_usize);
+        samples.append(&mut sample_space(U256::zero(), U256::from(41) * ONE_X64, 20000_usize));
+        samples.append(&mut get_powers_of_2_x64(-64, 5, 1));
+
+        let violations = count_bound_violations(
+            &samples,
+            |x| inv_pow2_x64_down(*x).ok(),
+            |x| inv_pow2_x64_up(*x).ok(),
+            |x| Float::with_val(256, 2).pow(-uint_x64_to_high_precision_float(x))
+        );
+
+        assert!(violations == 0);
+    }
+
+    #[test]
+    fn test_pow_x64_rounding() {
+
+        let samples: Vec<(U256, U256)> = sample_space(ONE_X64, ONE_X64 << 64, 20000_usize).into_iter()
+            .zip(sample_space(U256::zero(), ONE_X64 << 2, 20000_usize))
+            .collect();
+
+        let violations = count_bound_violations(
+            &samples,
+            |(x, p)| pow_x64_down(*x, *p).ok(),
+            |(x, p)| pow_x64_up(*x, *p).ok(),
+            |(x, p)| uint_x64_to_high_precision_float(x).pow(uint_x64_to_high_precision_float(p))
+        );
+
+        assert!(violations == 0);
+
+        let violations = count_bound_violations(
+            &samples,
+            |(x, p)| inv_pow_x64_down(*x, *p).ok(),
+            |(x, p)| inv_pow_x64_up(*x, *p).ok(),
+            |(x, p)| uint_x64_to_high_precision_float(x).pow(-uint_x64_to_high_precision_float(p))
+        );
+
+        assert!(violations == 0);
+    }
+
+}
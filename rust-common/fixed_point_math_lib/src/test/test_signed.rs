@@ -0,0 +1,362 @@
+#[cfg(test)]
+mod test_signed {
+    use std::ops::Mul;
+
+    use rug::Float;
+    use rug::ops::Pow;
+
+    use crate::u256::U256;
+    use crate::i256::I256;
+    use crate::test::test_common::test_common::*;
+    use crate::fixed_point_math_x64::{ONE_X64, U256_MAX};
+    use crate::math_error::MathError;
+    use crate::signed_fixed_point_math_x64::*;
+
+
+
+    // Helpers ******************************************************************************************************************
+
+    /// Random sample of values with absolute value within [start, end) and random sign
+    fn sample_signed_space(start: U256, end: U256, sample_count: usize) -> Vec<I256> {
+        sample_space(start, end, sample_count).into_iter().map(|abs| {
+            I256::from_sign_and_abs(rand::random::<bool>(), abs).unwrap()
+        }).collect()
+    }
+
+    /// Points of interest of both signs
+    fn signed_points_of_interest(points: Vec<U256>) -> Vec<I256> {
+        points.into_iter().flat_map(|abs| [
+            I256::from_sign_and_abs(false, abs),
+            I256::from_sign_and_abs(true, abs)
+        ]).filter_map(|value| value.ok()).collect()
+    }
+
+    fn x64(value: i64) -> I256 {
+        mul_x64(I256::from(value), I256(U256([0, 0, 1, 0]))).unwrap()   // value * 2**64 (x64)
+    }
+
+    /// Compute the signed result of an operation on the absolute values
+    fn signed_target(negative: bool, abs_result: Result<U256, ()>) -> Result<I256, ()> {
+        I256::from_sign_and_abs(negative, abs_result?).map_err(|_err| ())
+    }
+
+
+
+    // Test mul_x64 *************************************************************************************************************
+
+    const MUL_MAX_ABS_ERROR_BOUND: f64 = 0_f64;
+    const MUL_AVG_ABS_ERROR_BOUND: f64 = 0_f64;
+
+    /// Compute accurately the product of two numbers using U512 numbers (rounded towards zero)
+    pub fn target_mul_x64(a: I256, b: I256) -> Result<I256, ()> {
+        signed_target(
+            a.is_negative() != b.is_negative(),
+            safe_u256_mul_x64(a.unsigned_abs(), b.unsigned_abs())
+        )
+    }
+
+    #[test]
+    fn test_signed_mul_poi() -> Result<(), ()> {
+
+        let points = signed_points_of_interest(vec![
+            U256::zero(), U256::one(), ONE_X64, ONE_X64 * 2, I256::MAX.0, I256::MIN.unsigned_abs()
+        ]);
+        let points: Vec<(I256, I256)> = points.iter().flat_map(|a| points.iter().map(move |b| (*a, *b))).collect();
+
+        let result = evaluate_impl(
+            |(a, b): &(I256, I256)| mul_x64(*a, *b),
+            |(a, b): &(I256, I256)| target_mul_x64(*a, *b),
+            points
+        );
+
+        println!("\nsigned mul_x64 - Points of interest\n{}", result);
+
+        assert!(result.max_abs_error <= MUL_MAX_ABS_ERROR_BOUND);
+        assert!(result.avg_abs_error <= MUL_AVG_ABS_ERROR_BOUND);
+        assert!(result.invalid_count_expected_none == 0);
+        assert!(result.invalid_count_expected_some == 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_signed_mul_randrange() -> Result<(), ()> {
+
+        let a = sample_signed_space(U256::zero(), ONE_X64 << 60, 200000_usize);
+        let b = sample_signed_space(U256::zero(), ONE_X64 << 60, 200000_usize);
+
+        let result = evaluate_impl(
+            |(a, b): &(I256, I256)| mul_x64(*a, *b),
+            |(a, b): &(I256, I256)| target_mul_x64(*a, *b),
+            a.into_iter().zip(b).collect()
+        );
+
+        println!("\nsigned mul_x64 - Randrange ( -2**60_x64, 2**60_x64 )\n{}", result);
+
+        assert!(result.max_abs_error <= MUL_MAX_ABS_ERROR_BOUND);
+        assert!(result.avg_abs_error <= MUL_AVG_ABS_ERROR_BOUND);
+        assert!(result.invalid_count_expected_none == 0);
+        assert!(result.invalid_count_expected_some == 0);
+
+        Ok(())
+    }
+
+
+
+    // Test div_x64 *************************************************************************************************************
+
+    const DIV_MAX_ABS_ERROR_BOUND: f64 = 0_f64;
+    const DIV_AVG_ABS_ERROR_BOUND: f64 = 0_f64;
+
+    /// Compute accurately the division of two numbers using U512 numbers (rounded towards zero)
+    pub fn target_div_x64(a: I256, b: I256) -> Result<I256, ()> {
+        if b.is_zero() { return Err(()) }
+
+        signed_target(
+            a.is_negative() != b.is_negative(),
+            safe_u256_div_x64(a.unsigned_abs(), b.unsigned_abs())
+        )
+    }
+
+    #[test]
+    fn test_signed_div_randrange() -> Result<(), ()> {
+
+        // Ranges within which the unsigned div_x64 implementation is exact (see test_div)
+        let a = sample_signed_space(U256::zero(), ONE_X64 << 64, 200000_usize);
+        let b = sample_signed_space(U256::one() << 32, ONE_X64 << 64, 200000_usize);
+
+        let result = evaluate_impl(
+            |(a, b): &(I256, I256)| div_x64(*a, *b),
+            |(a, b): &(I256, I256)| target_div_x64(*a, *b),
+            a.into_iter().zip(b).collect()
+        );
+
+        println!("\nsigned div_x64 - Randrange ( -2**64_x64, 2**64_x64 )\n{}", result);
+
+        assert!(result.max_abs_error <= DIV_MAX_ABS_ERROR_BOUND);
+        assert!(result.avg_abs_error <= DIV_AVG_ABS_ERROR_BOUND);
+        assert!(result.invalid_count_expected_none == 0);
+        assert!(result.invalid_count_expected_some == 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_signed_div_by_zero() {
+        assert_eq!(div_x64(x64(1), I256::ZERO), Err(MathError::DivisionByZero));
+        assert_eq!(div_x64(x64(-1), I256::ZERO), Err(MathError::DivisionByZero));
+    }
+
+
+
+    // Test log2_x64 ************************************************************************************************************
+
+    const LOG2_MAX_ABS_ERROR_BOUND: f64 = 1e-6;
+    const LOG2_AVG_ABS_ERROR_BOUND: f64 = 1e-6;
+
+    /// Compute accurately the log2 of a number using floating point numbers
+    pub fn target_log2_x64(a: I256) -> Result<I256, ()> {
+        if a.is_negative() || a.is_zero() { return Err(()) }
+
+        let a = int_x64_to_high_precision_float(&a);
+        high_precision_float_to_int_x64(a.log2()).map_err(|_err| ())
+    }
+
+    #[test]
+    fn test_signed_log2_poi() -> Result<(), ()> {
+
+        let mut points_of_interest_x64: Vec<I256> = vec![
+            I256::MIN,          // Must fail
+            I256::from(-1i64),  // Must fail
+            I256::ZERO,         // Must fail
+            I256::from(1i64),
+            x64(1),
+            I256::MAX
+        ];
+        points_of_interest_x64.append(&mut get_powers_of_2_x64(-64_i64, 191, 1).into_iter().map(I256).collect());
+
+        let result = evaluate_impl(
+            |a: &I256| log2_x64(*a),
+            |a: &I256| target_log2_x64(*a),
+            points_of_interest_x64
+        );
+
+        println!("\nsigned log2_x64 - Points of interest\n{}", result);
+
+        assert!(result.max_abs_error <= LOG2_MAX_ABS_ERROR_BOUND);
+        assert!(result.avg_abs_error <= LOG2_AVG_ABS_ERROR_BOUND);
+        assert!(result.invalid_count_expected_none == 0);
+        assert!(result.invalid_count_expected_some == 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_signed_log2_randrange_lower_than_one() -> Result<(), ()> {
+
+        // Values smaller than 0.5, to avoid evaluating the relative error around log2(1) = 0
+        let rand_sample_x64: Vec<I256> = sample_space(U256::one(), ONE_X64 / 2, 200000_usize).into_iter().map(I256).collect();
+
+        let result = evaluate_impl(
+            |a: &I256| log2_x64(*a),
+            |a: &I256| target_log2_x64(*a),
+            rand_sample_x64
+        );
+
+        println!("\nsigned log2_x64 - Randrange [ 2**-64, 0.5 )\n{}", result);
+
+        assert!(result.max_abs_error <= LOG2_MAX_ABS_ERROR_BOUND);
+        assert!(result.avg_abs_error <= LOG2_AVG_ABS_ERROR_BOUND);
+        assert!(result.invalid_count_expected_none == 0);
+        assert!(result.invalid_count_expected_some == 0);
+
+        Ok(())
+    }
+
+
+
+    // Test pow2_x64 ************************************************************************************************************
+
+    const POW2_MAX_ABS_ERROR_BOUND: f64 = 1e-5;
+    const POW2_AVG_ABS_ERROR_BOUND: f64 = 1e-5;
+
+    /// Compute accurately the power of 2 of a number using floating point numbers
+    pub fn target_pow2_x64(a: I256) -> Result<I256, ()> {
+
+        // Fail for negative powers smaller than -41_x64 // ! This is specific to the inv_pow2_x64 implementation used
+        if a <= x64(-41) { return Err(()) }
+
+        // Output will overflow for a >= (255 - 64)*2**64
+        if a >= x64(255 - 64) { return Err(()) }
+
+        let a = int_x64_to_high_precision_float(&a);
+        high_precision_float_to_int_x64(Float::with_val(256, 2).pow(a)).map_err(|_err| ())
+    }
+
+    #[test]
+    fn test_signed_pow2_poi() -> Result<(), ()> {
+
+        let points_of_interest_x64 = vec![
+            I256::MIN,                              // Must fail
+            x64(-41),                               // Must fail
+            x64(-41).checked_add(I256::from(1i64)).unwrap(),
+            x64(-1),
+            I256::ZERO,
+            x64(1),
+            x64(255 - 64).checked_sub(I256::from(1i64)).unwrap(),
+            x64(255 - 64),                          // Must fail
+            I256::MAX                               // Must fail
+        ];
+
+        let result = evaluate_impl(
+            |a: &I256| pow2_x64(*a),
+            |a: &I256| target_pow2_x64(*a),
+            points_of_interest_x64
+        );
+
+        println!("\nsigned pow2_x64 - Points of interest\n{}", result);
+
+        assert!(result.max_abs_error <= POW2_MAX_ABS_ERROR_BOUND);
+        assert!(result.avg_abs_error <= POW2_AVG_ABS_ERROR_BOUND);
+        assert!(result.invalid_count_expected_none == 0);
+        assert!(result.invalid_count_expected_some == 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_signed_pow2_randrange() -> Result<(), ()> {
+
+        let rand_sample_x64 = sample_signed_space(ZERO_X64_SIGNED.0, ONE_X64.mul(U256::from(40)), 200000_usize);
+
+        let result = evaluate_impl(
+            |a: &I256| pow2_x64(*a),
+            |a: &I256| target_pow2_x64(*a),
+            rand_sample_x64
+        );
+
+        println!("\nsigned pow2_x64 - Randrange ( -40_x64, 40_x64 )\n{}", result);
+
+        assert!(result.max_abs_error <= POW2_MAX_ABS_ERROR_BOUND);
+        assert!(result.avg_abs_error <= POW2_AVG_ABS_ERROR_BOUND);
+        assert!(result.invalid_count_expected_none == 0);
+        assert!(result.invalid_count_expected_some == 0);
+
+        Ok(())
+    }
+
+
+
+    // Test exp_x64 *************************************************************************************************************
+
+    const EXP_MAX_ABS_ERROR_BOUND: f64 = 1e-6;
+    const EXP_AVG_ABS_ERROR_BOUND: f64 = 1e-6;
+
+    /// Compute accurately the exponent of a number using floating point numbers
+    pub fn target_exp_x64(a: I256) -> Result<I256, ()> {
+
+        // Fail for negative exponents smaller than -16_x64 // ! This is specific to the inv_exp_x64 implementation used
+        if a < x64(-16) { return Err(()) }
+
+        let a = int_x64_to_high_precision_float(&a);
+        high_precision_float_to_int_x64(a.exp()).map_err(|_err| ())
+    }
+
+    #[test]
+    fn test_signed_exp_poi() -> Result<(), ()> {
+
+        let points_of_interest_x64 = vec![
+            I256::MIN,                                          // Must fail
+            x64(-16).checked_sub(I256::from(1i64)).unwrap(),    // Must fail
+            x64(-16),
+            x64(-1),
+            I256::ZERO,
+            x64(1),
+            x64(100)
+        ];
+
+        let result = evaluate_impl(
+            |a: &I256| exp_x64(*a),
+            |a: &I256| target_exp_x64(*a),
+            points_of_interest_x64
+        );
+
+        println!("\nsigned exp_x64 - Points of interest\n{}", result);
+
+        assert!(result.max_abs_error <= EXP_MAX_ABS_ERROR_BOUND);
+        assert!(result.avg_abs_error <= EXP_AVG_ABS_ERROR_BOUND);
+        assert!(result.invalid_count_expected_none == 0);
+        assert!(result.invalid_count_expected_some == 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_signed_exp_randrange() -> Result<(), ()> {
+
+        let rand_sample_x64 = sample_signed_space(ZERO_X64_SIGNED.0, ONE_X64.mul(U256::from(16)), 200000_usize);
+
+        let result = evaluate_impl(
+            |a: &I256| exp_x64(*a),
+            |a: &I256| target_exp_x64(*a),
+            rand_sample_x64
+        );
+
+        println!("\nsigned exp_x64 - Randrange ( -16_x64, 16_x64 )\n{}", result);
+
+        assert!(result.max_abs_error <= EXP_MAX_ABS_ERROR_BOUND);
+        assert!(result.avg_abs_error <= EXP_AVG_ABS_ERROR_BOUND);
+        assert!(result.invalid_count_expected_none == 0);
+        assert!(result.invalid_count_expected_some == 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_signed_invalid_input() {
+        assert_eq!(exp_x64(I256::MIN), Err(MathError::Underflow));
+        assert_eq!(exp_x64(I256(U256_MAX >> 1)), Err(MathError::Overflow));
+        assert_eq!(log2_x64(I256::ZERO), Err(MathError::DomainError));
+        assert_eq!(log2_x64(I256::from(-1i64)), Err(MathError::DomainError));
+    }
+}
@@ -0,0 +1,27 @@
+#[cfg(test)]
+mod test_u256_borsh {
+    use borsh::{BorshDeserialize, BorshSerialize};
+
+    use crate::u256::U256;
+    use crate::fixed_point_math_x64::*;
+
+    /// U256 must serialize exactly as the [u64; 4] limb arrays used by the programs
+    #[test]
+    fn test_u256_borsh_layout() {
+        for value in [ZERO_X64, ONE_X64, LN2_X64, U256_MAX, U256([1, 2, 3, 4])] {
+            let serialized = value.try_to_vec().unwrap();
+
+            assert_eq!(serialized, value.0.try_to_vec().unwrap());
+            assert_eq!(U256::try_from_slice(&serialized).unwrap(), value);
+            assert_eq!(<[u64; 4]>::try_from_slice(&serialized).unwrap(), value.0);
+        }
+    }
+
+    #[test]
+    fn test_u256_borsh_invalid_length() {
+        let serialized = U256_MAX.try_to_vec().unwrap();
+
+        assert!(U256::try_from_slice(&serialized[..31]).is_err());
+        assert!(U256::try_from_slice(&[serialized.clone(), vec![0]].concat()).is_err());
+    }
+}
@@ -1,5 +1,52 @@
 use uint::construct_uint;
 
+use crate::math_error::MathError;
+
 construct_uint! {
     pub struct U256(4);
-}
\ No newline at end of file
+}
+
+construct_uint! {
+    pub struct U512(8);
+}
+
+// U512 is used to hold the full width intermediate results of U256 operations (e.g. the product of two U256 numbers).
+impl From<U256> for U512 {
+    fn from(value: U256) -> Self {
+        U512([value.0[0], value.0[1], value.0[2], value.0[3], 0, 0, 0, 0])
+    }
+}
+
+impl TryFrom<U512> for U256 {
+    type Error = MathError;
+
+    fn try_from(value: U512) -> Result<Self, Self::Error> {
+        if value.0[4..].iter().any(|limb| *limb != 0) { return Err(MathError::Overflow) }
+
+        Ok(U256([value.0[0], value.0[1], value.0[2], value.0[3]]))
+    }
+}
+
+// Borsh serialization. U256 is serialized as its 4 little-endian u64 limbs (least significant limb first), i.e. with
+// exactly the same layout as the [u64; 4] arrays used across the programs, so both representations are interchangeable.
+// NOTE: Anchor's AnchorSerialize/AnchorDeserialize are the Borsh traits, hence this enables U256 in Anchor accounts and
+// instruction arguments.
+#[cfg(feature = "borsh")]
+mod borsh_serialization {
+    use std::io;
+    use borsh::{BorshDeserialize, BorshSerialize};
+
+    use super::U256;
+
+    impl BorshSerialize for U256 {
+        fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+            self.0.serialize(writer)
+        }
+    }
+
+    impl BorshDeserialize for U256 {
+        fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+            Ok(U256(<[u64; 4]>::deserialize(buf)?))
+        }
+    }
+}
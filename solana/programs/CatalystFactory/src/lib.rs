@@ -0,0 +1,499 @@
+use anchor_lang::prelude::*;
+use anchor_lang::prelude::borsh;
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke_signed};
+use anchor_lang::solana_program::hash::hashv;
+use borsh::BorshSerialize;
+
+//TODO! change id
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsFAC");
+
+// CPI sighashs
+const SIGHASH_INITIALIZE : [u8; 8] = [175, 175, 109, 31, 13, 152, 155, 237];
+
+const MAX_TEMPLATES: usize = 16;
+
+const MAX_GOVERNANCE_FEE_X64: u64 = 13835058055282163712;  // 75% (0.75 · 2^64)
+
+pub const POOL_SEED              : &[u8] = b"factoryPool";
+pub const POOL_REGISTRATION_SEED : &[u8] = b"factoryPoolReg";
+
+#[program]
+pub mod catalyst_factory {
+
+    use super::*;
+
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        default_governance_fee_x64: u64,
+        governance_fee_destination: Pubkey
+    ) -> Result<()> {
+
+        if default_governance_fee_x64 > MAX_GOVERNANCE_FEE_X64 {
+            return Err(error!(ErrorCode::InvalidGovernanceFee));
+        }
+
+        if governance_fee_destination == Pubkey::default() {
+            return Err(error!(ErrorCode::InvalidGovernanceFeeDestination));
+        }
+
+        let factory_state = &mut ctx.accounts.factory_state_account;
+
+        factory_state.owner                      = ctx.accounts.owner.key();
+        factory_state.default_governance_fee_x64 = default_governance_fee_x64;
+        factory_state.governance_fee_destination = governance_fee_destination;
+        factory_state.pool_count                 = 0;
+        factory_state.templates                  = vec![];
+
+        Ok(())
+    }
+
+    /// Registers a pool program which can then be used to deploy pools. The template id is the index of the template in
+    /// the order in which the templates are added.
+    /// NOTE: the pool program must expose the SwapPool 'initialize' interface.
+    pub fn add_template(
+        ctx: Context<FactoryOwnerOnly>,
+        template_program: Pubkey
+    ) -> Result<()> {
+
+        let factory_state = &mut ctx.accounts.factory_state_account;
+
+        if factory_state.templates.len() >= MAX_TEMPLATES {
+            return Err(error!(ErrorCode::TemplatesLimitReached));
+        }
+
+        factory_state.templates.push(template_program);
+
+        emit!(AddTemplateEvent {
+            factory: factory_state.key(),
+            template_id: (factory_state.templates.len() - 1) as u8,
+            template_program
+        });
+
+        Ok(())
+    }
+
+    /// Sets the governance fee of the pools deployed from now on. Already deployed pools are not affected.
+    pub fn set_default_governance_fee(
+        ctx: Context<FactoryOwnerOnly>,
+        default_governance_fee_x64: u64
+    ) -> Result<()> {
+
+        if default_governance_fee_x64 > MAX_GOVERNANCE_FEE_X64 {
+            return Err(error!(ErrorCode::InvalidGovernanceFee));
+        }
+
+        ctx.accounts.factory_state_account.default_governance_fee_x64 = default_governance_fee_x64;
+
+        emit!(SetDefaultGovernanceFeeEvent {
+            factory: ctx.accounts.factory_state_account.key(),
+            default_governance_fee_x64
+        });
+
+        Ok(())
+    }
+
+    /// Sets the governance fee destination of the pools deployed from now on. Already deployed pools are not affected.
+    pub fn set_governance_fee_destination(
+        ctx: Context<FactoryOwnerOnly>,
+        governance_fee_destination: Pubkey
+    ) -> Result<()> {
+
+        if governance_fee_destination == Pubkey::default() {
+            return Err(error!(ErrorCode::InvalidGovernanceFeeDestination));
+        }
+
+        ctx.accounts.factory_state_account.governance_fee_destination = governance_fee_destination;
+
+        emit!(SetGovernanceFeeDestinationEvent {
+            factory: ctx.accounts.factory_state_account.key(),
+            governance_fee_destination
+        });
+
+        Ok(())
+    }
+
+    /// Deploys a pool using the given template at the address derived from the factory, the template id, the pool assets,
+    /// the deployer (setup master) and the given salt (see `derive_pool_address`), and registers it on the factory.
+    /// The deployer and the salt are part of the address so that the address of a pool cannot be taken by someone else.
+    /// The governance fee, the fee administrator (factory owner) and the governance fee destination are set by the factory.
+    ///
+    /// The remaining accounts are forwarded to the pool 'initialize' instruction (3 accounts per asset: asset mint,
+    /// swap pool asset wallet and depositor asset wallet).
+    pub fn deploy_pool<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, DeployPool<'info>>,
+        template_id: u8,
+        salt: [u8; 32],
+        k: u64,
+        initial_asset_balances: Vec<u64>,
+        asset_weights: Vec<u64>,
+        pool_fee_x64: u64
+    ) -> Result<()> {
+
+        // Verify the template
+        let template_program = ctx.accounts.factory_state_account.templates
+            .get(template_id as usize)
+            .copied()
+            .ok_or(error!(ErrorCode::InvalidTemplate))?;
+
+        if ctx.accounts.template_program.key() != template_program {
+            return Err(error!(ErrorCode::InvalidTemplate));
+        }
+
+        // Verify the pool address
+        if ctx.remaining_accounts.len() != 3*initial_asset_balances.len() {
+            return Err(error!(ErrorCode::InvalidRemainingAccountsCount));
+        }
+
+        let factory = ctx.accounts.factory_state_account.key();
+        let assets: Vec<Pubkey> = ctx.remaining_accounts.iter().step_by(3).map(|asset_mint| asset_mint.key()).collect();
+        let assets_hash = compute_assets_hash(&assets);
+
+        let deployer = ctx.accounts.setup_master.key();
+
+        let (expected_pool, pool_bump) = derive_pool_address(&factory, template_id, &assets, &deployer, &salt);
+        if ctx.accounts.swap_pool_state_account.key() != expected_pool {
+            return Err(error!(ErrorCode::InvalidSwapPoolStateAccount));
+        }
+
+        // Build CPI data (sighash + arguments)
+        let mut data_vec = SIGHASH_INITIALIZE.try_to_vec().unwrap();
+        data_vec.append(&mut InitializeArgs {
+            k,
+            initial_asset_balances,
+            asset_weights,
+            pool_fee_x64,
+            governance_fee_x64: ctx.accounts.factory_state_account.default_governance_fee_x64,
+            fee_administrator: ctx.accounts.factory_state_account.owner,
+            governance_fee_destination: ctx.accounts.factory_state_account.governance_fee_destination
+        }.try_to_vec().unwrap());
+
+        let mut accounts = vec![
+            AccountMeta::new(ctx.accounts.setup_master.key(), true),
+            AccountMeta::new_readonly(ctx.accounts.dao_authority.key(), true),
+            AccountMeta::new(ctx.accounts.swap_pool_state_account.key(), true),     // Signed by the factory
+            AccountMeta::new(ctx.accounts.swap_pool_token_mint.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.depositor_pool_token_wallet_authority.key(), false),
+            AccountMeta::new(ctx.accounts.depositor_pool_token_wallet.key(), true),
+            AccountMeta::new_readonly(ctx.accounts.swap_pool_authority.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.rent.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+            AccountMeta::new_readonly(ctx.accounts.system_program.key(), false)
+        ];
+
+        let mut account_infos = vec![
+            ctx.accounts.setup_master.to_account_info(),
+            ctx.accounts.dao_authority.to_account_info(),
+            ctx.accounts.swap_pool_state_account.to_account_info(),
+            ctx.accounts.swap_pool_token_mint.to_account_info(),
+            ctx.accounts.depositor_pool_token_wallet_authority.to_account_info(),
+            ctx.accounts.depositor_pool_token_wallet.to_account_info(),
+            ctx.accounts.swap_pool_authority.to_account_info(),
+            ctx.accounts.rent.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info()
+        ];
+
+        for account in ctx.remaining_accounts.iter() {
+            accounts.push(AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable
+            });
+            account_infos.push(account.to_account_info());
+        }
+
+        account_infos.push(ctx.accounts.template_program.to_account_info());
+
+        invoke_signed(
+            &Instruction {
+                program_id: template_program,
+                accounts,
+                data: data_vec
+            },
+            &account_infos,
+            &[&[
+                &factory.to_bytes(),
+                &[template_id],
+                &assets_hash,
+                &deployer.to_bytes(),
+                &salt,
+                POOL_SEED,
+                &[pool_bump]
+            ]]
+        )?;
+
+        // Register the pool
+        let pool_registration = &mut ctx.accounts.pool_registration;
+
+        pool_registration.factory          = factory;
+        pool_registration.pool             = expected_pool;
+        pool_registration.template_id      = template_id;
+        pool_registration.template_program = template_program;
+        pool_registration.deployer         = deployer;
+        pool_registration.salt             = salt;
+        pool_registration.bump             = ctx.bumps.get("pool_registration").unwrap().to_owned();
+
+        ctx.accounts.factory_state_account.pool_count += 1;
+
+        emit!(DeployPoolEvent {
+            factory,
+            pool: expected_pool,
+            template_id,
+            template_program,
+            deployer,
+            salt,
+            assets
+        });
+
+        Ok(())
+    }
+
+    pub fn transfer_ownership(
+        ctx: Context<FactoryOwnerOnly>,
+        new_owner: Pubkey
+    ) -> Result<()> {
+        ctx.accounts.factory_state_account.owner = new_owner;
+
+        emit!(TransferOwnershipEvent {
+            factory: ctx.accounts.factory_state_account.key(),
+            new_owner
+        });
+
+        Ok(())
+    }
+
+}
+
+
+
+// Address derivation ***********************************************************************************************************
+
+/// Hash of the (ordered) pool assets, used to derive the pool address (the asset count may exceed the seeds count limit).
+pub fn compute_assets_hash(assets: &[Pubkey]) -> [u8; 32] {
+    let assets_bytes: Vec<&[u8]> = assets.iter().map(|asset| asset.as_ref()).collect();
+
+    hashv(&assets_bytes).to_bytes()
+}
+
+/// Address of the pool deployed by `factory` using the template `template_id` for the given (ordered) assets, by the given
+/// deployer and with the given salt.
+pub fn derive_pool_address(
+    factory: &Pubkey,
+    template_id: u8,
+    assets: &[Pubkey],
+    deployer: &Pubkey,
+    salt: &[u8; 32]
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            &factory.to_bytes(),
+            &[template_id],
+            &compute_assets_hash(assets),
+            &deployer.to_bytes(),
+            salt,
+            POOL_SEED
+        ],
+        &ID
+    )
+}
+
+/// Address of the registration account of a pool deployed by `factory`. The pool was created by the factory if and only if
+/// this account exists (i.e. is owned by the factory program).
+pub fn derive_pool_registration_address(factory: &Pubkey, pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            &factory.to_bytes(),
+            &pool.to_bytes(),
+            POOL_REGISTRATION_SEED
+        ],
+        &ID
+    )
+}
+
+
+
+// Contexts *********************************************************************************************************************
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(init, payer = owner, space = FactoryState::LEN)]
+    pub factory_state_account: Account<'info, FactoryState>,
+
+    pub system_program: Program<'info, System>
+}
+
+
+#[derive(Accounts)]
+pub struct FactoryOwnerOnly<'info> {
+    pub owner: Signer<'info>,
+    #[account(mut, has_one = owner @ ErrorCode::InvalidOwner)]
+    pub factory_state_account: Account<'info, FactoryState>
+}
+
+
+#[derive(Accounts)]
+pub struct DeployPool<'info> {
+    #[account(mut)]
+    pub factory_state_account: Box<Account<'info, FactoryState>>,
+
+    /// CHECK: Safe, as it must match the template saved on the factory state
+    pub template_program: UncheckedAccount<'info>,
+
+    // Pool 'initialize' accounts
+    #[account(mut)]
+    pub setup_master: Signer<'info>,
+    pub dao_authority: Signer<'info>,
+    #[account(mut)]
+    /// CHECK: Safe, as it is verified against the derived pool address and initialized by the template program
+    pub swap_pool_state_account: UncheckedAccount<'info>,
+    #[account(mut)]
+    /// CHECK: Safe, as we are passing it directly to the template program
+    pub swap_pool_token_mint: UncheckedAccount<'info>,
+    /// CHECK: Safe, as we are passing it directly to the template program
+    pub depositor_pool_token_wallet_authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub depositor_pool_token_wallet: Signer<'info>,
+    /// CHECK: Safe, as we are passing it directly to the template program
+    pub swap_pool_authority: UncheckedAccount<'info>,
+
+    // Registry
+    #[account(
+        init,
+        payer = setup_master,
+        space = PoolRegistration::LEN,
+        seeds = [
+            &factory_state_account.key().to_bytes(),
+            &swap_pool_state_account.key().to_bytes(),
+            POOL_REGISTRATION_SEED
+        ],
+        bump
+    )]
+    pub pool_registration: Account<'info, PoolRegistration>,
+
+    // Sys and generics
+    pub rent: Sysvar<'info, Rent>,
+    /// CHECK: Safe, as we are passing it directly to the template program
+    pub token_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>
+}
+
+
+
+// State ************************************************************************************************************************
+
+#[account]
+pub struct FactoryState {
+    pub owner: Pubkey,
+    pub default_governance_fee_x64: u64,
+    pub governance_fee_destination: Pubkey,
+    pub pool_count: u64,
+    pub templates: Vec<Pubkey>
+}
+
+impl FactoryState {
+    pub const LEN: usize =
+        8                           // discriminator
+        + 32                        // owner
+        + 8                         // default_governance_fee_x64
+        + 32                        // governance_fee_destination
+        + 8                         // pool_count
+        + 4 + 32*MAX_TEMPLATES;     // templates
+}
+
+#[account]
+pub struct PoolRegistration {
+    pub factory: Pubkey,
+    pub pool: Pubkey,
+    pub template_id: u8,
+    pub template_program: Pubkey,
+    pub deployer: Pubkey,
+    pub salt: [u8; 32],
+    pub bump: u8
+}
+
+impl PoolRegistration {
+    pub const LEN: usize =
+        8       // discriminator
+        + 32    // factory
+        + 32    // pool
+        + 1     // template_id
+        + 32    // template_program
+        + 32    // deployer
+        + 32    // salt
+        + 1;    // bump
+}
+
+#[derive(PartialEq, Debug, Clone, BorshSerialize)]
+pub struct InitializeArgs {
+    k: u64,
+    initial_asset_balances: Vec<u64>,
+    asset_weights: Vec<u64>,
+    pool_fee_x64: u64,
+    governance_fee_x64: u64,
+    fee_administrator: Pubkey,
+    governance_fee_destination: Pubkey
+}
+
+
+
+// Events ***********************************************************************************************************************
+
+#[event]
+pub struct AddTemplateEvent {
+    factory: Pubkey,
+    template_id: u8,
+    template_program: Pubkey
+}
+
+#[event]
+pub struct SetDefaultGovernanceFeeEvent {
+    factory: Pubkey,
+    default_governance_fee_x64: u64
+}
+
+#[event]
+pub struct SetGovernanceFeeDestinationEvent {
+    factory: Pubkey,
+    governance_fee_destination: Pubkey
+}
+
+#[event]
+pub struct DeployPoolEvent {
+    factory: Pubkey,
+    pool: Pubkey,
+    template_id: u8,
+    template_program: Pubkey,
+    deployer: Pubkey,
+    salt: [u8; 32],
+    assets: Vec<Pubkey>
+}
+
+#[event]
+pub struct TransferOwnershipEvent {
+    factory: Pubkey,
+    new_owner: Pubkey
+}
+
+
+
+// Errors ***********************************************************************************************************************
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("The signer is not the factory owner.")]
+    InvalidOwner,
+    #[msg("The governance fee must be at most 75%.")]
+    InvalidGovernanceFee,
+    #[msg("The governance fee destination cannot be the default pubkey.")]
+    InvalidGovernanceFeeDestination,
+    #[msg("The maximum number of templates has been reached.")]
+    TemplatesLimitReached,
+    #[msg("Invalid template id or template program.")]
+    InvalidTemplate,
+    #[msg("Invalid remaining accounts count.")]
+    InvalidRemainingAccountsCount,
+    #[msg("The swap pool state account does not match the derived pool address.")]
+    InvalidSwapPoolStateAccount
+}
@@ -0,0 +1,596 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::system_instruction;
+use anchor_spl::token::{Token, TokenAccount};
+
+//TODO! change id
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsRTR");
+
+// Amount sentinel: use the full balance of the input wallet at the time the command is executed (i.e. the output of the
+// previous commands plus any pre-existing balance). Mirrors CONTRACT_BALANCE of the EVM router.
+pub const WALLET_BALANCE: u64 = u64::MAX;
+
+// Accounts consumed from the remaining accounts by each command (including the pool program account where applicable)
+const LOCAL_SWAP_ACCOUNTS_COUNT : usize = 10;
+const OUT_SWAP_ACCOUNTS_COUNT   : usize = 13;
+const LIQUIDITY_ACCOUNTS_COUNT  : usize = 5;     // Deposit/Withdraw. 3 more accounts are required per pool asset
+const WRAPPED_SOL_ACCOUNTS_COUNT: usize = 1;
+
+#[program]
+pub mod catalyst_router {
+
+    use super::*;
+
+    /// Executes the given commands atomically, and verifies that the balance of `output_account` has increased by at least
+    /// `min_output`.
+    ///
+    /// The accounts required by each command are taken in order from the remaining accounts. The pool commands take the
+    /// pool program (SwapPool or SwapPoolAmplified) as the first account, followed by the accounts of the respective pool
+    /// instruction, excluding the ones provided by the router (user signer, token program and system program).
+    pub fn execute<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, Execute<'info>>,
+        commands: Vec<Command>,
+        min_output: u64
+    ) -> Result<()> {
+
+        let output_balance_before = get_balance(&ctx.accounts.output_account)?;
+
+        let mut remaining_accounts: &'c [AccountInfo<'info>] = ctx.remaining_accounts;
+
+        for command in commands.iter() {
+            match command {
+                Command::LocalSwap { amount, min_yield, approx } => {
+                    let accounts = take_accounts(&mut remaining_accounts, LOCAL_SWAP_ACCOUNTS_COUNT)?;
+                    ctx.accounts.local_swap(accounts, *amount, *min_yield, *approx)?;
+                },
+                Command::OutSwap(args) => {
+                    let accounts = take_accounts(&mut remaining_accounts, OUT_SWAP_ACCOUNTS_COUNT)?;
+                    ctx.accounts.out_swap(accounts, args)?;
+                },
+                Command::Deposit { pool_tokens_amount, asset_count } => {
+                    let accounts = take_accounts(&mut remaining_accounts, LIQUIDITY_ACCOUNTS_COUNT + 3*(*asset_count as usize))?;
+                    ctx.accounts.deposit(accounts, *pool_tokens_amount)?;
+                },
+                Command::Withdraw { pool_tokens_amount, asset_count } => {
+                    let accounts = take_accounts(&mut remaining_accounts, LIQUIDITY_ACCOUNTS_COUNT + 3*(*asset_count as usize))?;
+                    ctx.accounts.withdraw(accounts, *pool_tokens_amount)?;
+                },
+                Command::WrapSol { amount } => {
+                    let accounts = take_accounts(&mut remaining_accounts, WRAPPED_SOL_ACCOUNTS_COUNT)?;
+                    ctx.accounts.wrap_sol(&accounts[0], *amount)?;
+                },
+                Command::UnwrapSol => {
+                    let accounts = take_accounts(&mut remaining_accounts, WRAPPED_SOL_ACCOUNTS_COUNT)?;
+                    ctx.accounts.unwrap_sol(&accounts[0])?;
+                }
+            }
+        }
+
+        // All the provided accounts must have been consumed by the commands
+        if !remaining_accounts.is_empty() {
+            return Err(error!(ErrorCode::InvalidRemainingAccountsCount));
+        }
+
+        // Verify the route output
+        let output = get_balance(&ctx.accounts.output_account)?.saturating_sub(output_balance_before);
+        if output < min_output {
+            return Err(error!(ErrorCode::MinOutputNotFulfilled));
+        }
+
+        emit!(RouteEvent {
+            user: ctx.accounts.user.key(),
+            output_account: ctx.accounts.output_account.key(),
+            commands_count: commands.len() as u32,
+            output
+        });
+
+        Ok(())
+    }
+
+}
+
+
+
+// Commands *********************************************************************************************************************
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+
+    /// Accounts: pool program, swap_pool_state_account, input_asset_mint, input_asset_wallet, swap_pool_input_asset_wallet,
+    /// output_asset_mint, output_asset_wallet, swap_pool_output_asset_wallet, governance_fee_wallet, swap_pool_authority
+    ///
+    /// `amount` may be set to WALLET_BALANCE. `approx` is ignored by SwapPoolAmplified.
+    LocalSwap {
+        amount: u64,
+        min_yield: u64,
+        approx: bool
+    },
+
+    /// Accounts: pool program, swap_pool_state_account, input_asset_mint, input_asset_wallet, swap_pool_input_asset_wallet,
+    /// governance_fee_wallet, swap_escrow, ibc_interface_program, swap_pool_authority, interface_state_account,
+    /// connection_state_account, polymerase_sender_program, polymerase_ibc_data (signer)
+    ///
+    /// The user pays for the rent of the swap escrow and of the polymerase ibc data account.
+    OutSwap(OutSwapArgs),
+
+    /// Accounts: pool program, swap_pool_state_account, depositor_pool_token_wallet, swap_pool_token_mint, swap_pool_authority,
+    /// and for every pool asset: asset mint, swap pool asset wallet, depositor asset wallet
+    Deposit {
+        pool_tokens_amount: u64,
+        asset_count: u8
+    },
+
+    /// Accounts: pool program, swap_pool_state_account, withdrawer_pool_token_wallet, swap_pool_token_mint, swap_pool_authority,
+    /// and for every pool asset: asset mint, swap pool asset wallet, withdrawer asset wallet
+    ///
+    /// `pool_tokens_amount` may be set to WALLET_BALANCE.
+    Withdraw {
+        pool_tokens_amount: u64,
+        asset_count: u8
+    },
+
+    /// Transfers `amount` lamports from the user to the given wrapped SOL wallet.
+    /// Accounts: wrapped SOL wallet
+    WrapSol {
+        amount: u64
+    },
+
+    /// Closes the given wrapped SOL wallet of the user, returning all its lamports to the user.
+    /// Accounts: wrapped SOL wallet
+    UnwrapSol
+}
+
+/// Arguments of the SwapPool/SwapPoolAmplified out_swap instruction. `approx_from` is ignored by SwapPoolAmplified.
+/// NOTE: `amount` must be exact (WALLET_BALANCE is not supported), as it is committed to by `swap_hash`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OutSwapArgs {
+    pub chain: u64,
+    pub target_pool: Pubkey,
+    pub to_asset_index: u8,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub min_output: [u64; 4],
+    pub swap_hash: [u8; 32],
+    pub fallback_wallet: Pubkey,
+    pub timeout_slot: u64,
+    pub timeout_timestamp: u64,
+    pub approx_from: bool
+}
+
+enum PoolProgram {
+    SwapPool,
+    SwapPoolAmplified
+}
+
+fn get_pool_program(pool_program: &AccountInfo) -> Result<PoolProgram> {
+    if pool_program.key() == swap_pool::ID {
+        Ok(PoolProgram::SwapPool)
+    }
+    else if pool_program.key() == swap_pool_amplified::ID {
+        Ok(PoolProgram::SwapPoolAmplified)
+    }
+    else {
+        Err(error!(ErrorCode::InvalidPoolProgram))
+    }
+}
+
+fn take_accounts<'c, 'info>(
+    remaining_accounts: &mut &'c [AccountInfo<'info>],
+    count: usize
+) -> Result<&'c [AccountInfo<'info>]> {
+
+    if remaining_accounts.len() < count {
+        return Err(error!(ErrorCode::InvalidRemainingAccountsCount));
+    }
+
+    let (accounts, rest) = remaining_accounts.split_at(count);
+    *remaining_accounts = rest;
+
+    Ok(accounts)
+}
+
+/// Returns the token balance of the account if it is a token account, or its lamports otherwise.
+fn get_balance(account: &AccountInfo) -> Result<u64> {
+    if account.owner == &spl_token::ID && !account.data_is_empty() {
+        Ok(TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])?.amount)
+    }
+    else {
+        Ok(account.lamports())
+    }
+}
+
+fn resolve_amount(amount: u64, wallet: &AccountInfo) -> Result<u64> {
+    if amount == WALLET_BALANCE {
+        return Ok(TokenAccount::try_deserialize(&mut &wallet.try_borrow_data()?[..])?.amount);
+    }
+
+    Ok(amount)
+}
+
+
+
+// Contexts *********************************************************************************************************************
+
+#[derive(Accounts)]
+pub struct Execute<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,                    // Owner of all the user wallets used by the commands
+
+    /// CHECK: Safe, as only its balance is read (token balance if it is a token account, lamports otherwise)
+    pub output_account: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> Execute<'info> {
+
+    pub fn local_swap(
+        &self,
+        accounts: &[AccountInfo<'info>],
+        amount: u64,
+        min_yield: u64,
+        approx: bool
+    ) -> Result<()> {
+
+        let pool_program = accounts[0].clone();
+        let amount = resolve_amount(amount, &accounts[3])?;
+
+        // The pool pulls the input assets using its authority as delegate
+        self.approve(&accounts[3], &accounts[9], amount)?;
+
+        match get_pool_program(&pool_program)? {
+            PoolProgram::SwapPool => swap_pool::cpi::local_swap(
+                CpiContext::new(
+                    pool_program,
+                    swap_pool::cpi::accounts::LocalSwap {
+                        swap_pool_state_account: accounts[1].clone(),
+                        input_asset_mint: accounts[2].clone(),
+                        input_asset_wallet: accounts[3].clone(),
+                        swap_pool_input_asset_wallet: accounts[4].clone(),
+                        output_asset_mint: accounts[5].clone(),
+                        output_asset_wallet: accounts[6].clone(),
+                        swap_pool_output_asset_wallet: accounts[7].clone(),
+                        governance_fee_wallet: accounts[8].clone(),
+                        swap_pool_authority: accounts[9].clone(),
+                        token_program: self.token_program.to_account_info()
+                    }
+                ),
+                amount,
+                min_yield,
+                approx
+            ),
+            PoolProgram::SwapPoolAmplified => swap_pool_amplified::cpi::local_swap(
+                CpiContext::new(
+                    pool_program,
+                    swap_pool_amplified::cpi::accounts::LocalSwap {
+                        swap_pool_state_account: accounts[1].clone(),
+                        input_asset_mint: accounts[2].clone(),
+                        input_asset_wallet: accounts[3].clone(),
+                        swap_pool_input_asset_wallet: accounts[4].clone(),
+                        output_asset_mint: accounts[5].clone(),
+                        output_asset_wallet: accounts[6].clone(),
+                        swap_pool_output_asset_wallet: accounts[7].clone(),
+                        governance_fee_wallet: accounts[8].clone(),
+                        swap_pool_authority: accounts[9].clone(),
+                        token_program: self.token_program.to_account_info()
+                    }
+                ),
+                amount,
+                min_yield
+            )
+        }
+    }
+
+    pub fn out_swap(
+        &self,
+        accounts: &[AccountInfo<'info>],
+        args: &OutSwapArgs
+    ) -> Result<()> {
+
+        let pool_program = accounts[0].clone();
+
+        self.approve(&accounts[3], &accounts[8], args.amount)?;
+
+        match get_pool_program(&pool_program)? {
+            PoolProgram::SwapPool => swap_pool::cpi::out_swap(
+                CpiContext::new(
+                    pool_program,
+                    swap_pool::cpi::accounts::OutSwap {
+                        swap_pool_state_account: accounts[1].clone(),
+                        input_asset_mint: accounts[2].clone(),
+                        input_asset_wallet: accounts[3].clone(),
+                        swap_pool_input_asset_wallet: accounts[4].clone(),
+                        governance_fee_wallet: accounts[5].clone(),
+                        token_program: self.token_program.to_account_info(),
+                        swap_escrow: accounts[6].clone(),
+                        swap_escrow_rent_payer: self.user.to_account_info(),
+                        ibc_interface_program: accounts[7].clone(),
+                        swap_pool_authority: accounts[8].clone(),
+                        interface_state_account: accounts[9].clone(),
+                        connection_state_account: accounts[10].clone(),
+                        polymerase_sender_program: accounts[11].clone(),
+                        polymerase_ibc_data: accounts[12].clone(),
+                        polymerase_ibc_data_account_payer: self.user.to_account_info(),
+                        system_program: self.system_program.to_account_info()
+                    }
+                ),
+                args.chain,
+                args.target_pool,
+                args.to_asset_index,
+                args.destination,
+                args.amount,
+                args.min_output,
+                args.swap_hash,
+                args.fallback_wallet,
+                args.timeout_slot,
+                args.timeout_timestamp,
+                args.approx_from
+            ),
+            PoolProgram::SwapPoolAmplified => swap_pool_amplified::cpi::out_swap(
+                CpiContext::new(
+                    pool_program,
+                    swap_pool_amplified::cpi::accounts::OutSwap {
+                        swap_pool_state_account: accounts[1].clone(),
+                        input_asset_mint: accounts[2].clone(),
+                        input_asset_wallet: accounts[3].clone(),
+                        swap_pool_input_asset_wallet: accounts[4].clone(),
+                        governance_fee_wallet: accounts[5].clone(),
+                        token_program: self.token_program.to_account_info(),
+                        swap_escrow: accounts[6].clone(),
+                        swap_escrow_rent_payer: self.user.to_account_info(),
+                        ibc_interface_program: accounts[7].clone(),
+                        swap_pool_authority: accounts[8].clone(),
+                        interface_state_account: accounts[9].clone(),
+                        connection_state_account: accounts[10].clone(),
+                        polymerase_sender_program: accounts[11].clone(),
+                        polymerase_ibc_data: accounts[12].clone(),
+                        polymerase_ibc_data_account_payer: self.user.to_account_info(),
+                        system_program: self.system_program.to_account_info()
+                    }
+                ),
+                args.chain,
+                args.target_pool,
+                args.to_asset_index,
+                args.destination,
+                args.amount,
+                args.min_output,
+                args.swap_hash,
+                args.fallback_wallet,
+                args.timeout_slot,
+                args.timeout_timestamp
+            )
+        }
+    }
+
+    pub fn deposit(
+        &self,
+        accounts: &[AccountInfo<'info>],
+        pool_tokens_amount: u64
+    ) -> Result<()> {
+
+        let pool_program = accounts[0].clone();
+        let swap_pool_authority = &accounts[4];
+        let asset_accounts = &accounts[LIQUIDITY_ACCOUNTS_COUNT..];
+
+        // The deposited amounts are computed by the pool: allow it to pull any amount from the depositor wallets, and
+        // revoke the allowances once the deposit is done.
+        for depositor_asset_wallet in asset_accounts.iter().skip(2).step_by(3) {
+            self.approve(depositor_asset_wallet, swap_pool_authority, u64::MAX)?;
+        }
+
+        match get_pool_program(&pool_program)? {
+            PoolProgram::SwapPool => swap_pool::cpi::deposit(
+                CpiContext::new(
+                    pool_program,
+                    swap_pool::cpi::accounts::Deposit {
+                        swap_pool_state_account: accounts[1].clone(),
+                        depositor_pool_token_wallet: accounts[2].clone(),
+                        swap_pool_token_mint: accounts[3].clone(),
+                        swap_pool_authority: accounts[4].clone(),
+                        token_program: self.token_program.to_account_info()
+                    }
+                ).with_remaining_accounts(asset_accounts.to_vec()),
+                pool_tokens_amount
+            ),
+            PoolProgram::SwapPoolAmplified => swap_pool_amplified::cpi::deposit(
+                CpiContext::new(
+                    pool_program,
+                    swap_pool_amplified::cpi::accounts::Deposit {
+                        swap_pool_state_account: accounts[1].clone(),
+                        depositor_pool_token_wallet: accounts[2].clone(),
+                        swap_pool_token_mint: accounts[3].clone(),
+                        swap_pool_authority: accounts[4].clone(),
+                        token_program: self.token_program.to_account_info()
+                    }
+                ).with_remaining_accounts(asset_accounts.to_vec()),
+                pool_tokens_amount
+            )
+        }?;
+
+        for depositor_asset_wallet in asset_accounts.iter().skip(2).step_by(3) {
+            self.revoke(depositor_asset_wallet)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn withdraw(
+        &self,
+        accounts: &[AccountInfo<'info>],
+        pool_tokens_amount: u64
+    ) -> Result<()> {
+
+        let pool_program = accounts[0].clone();
+        let pool_tokens_amount = resolve_amount(pool_tokens_amount, &accounts[2])?;
+        let asset_accounts = &accounts[LIQUIDITY_ACCOUNTS_COUNT..];
+
+        // The pool burns the pool tokens using its authority as delegate
+        self.approve(&accounts[2], &accounts[4], pool_tokens_amount)?;
+
+        match get_pool_program(&pool_program)? {
+            PoolProgram::SwapPool => swap_pool::cpi::withdraw(
+                CpiContext::new(
+                    pool_program,
+                    swap_pool::cpi::accounts::Withdraw {
+                        swap_pool_state_account: accounts[1].clone(),
+                        withdrawer_pool_token_wallet: accounts[2].clone(),
+                        swap_pool_token_mint: accounts[3].clone(),
+                        swap_pool_authority: accounts[4].clone(),
+                        token_program: self.token_program.to_account_info()
+                    }
+                ).with_remaining_accounts(asset_accounts.to_vec()),
+                pool_tokens_amount
+            ),
+            PoolProgram::SwapPoolAmplified => swap_pool_amplified::cpi::withdraw(
+                CpiContext::new(
+                    pool_program,
+                    swap_pool_amplified::cpi::accounts::Withdraw {
+                        swap_pool_state_account: accounts[1].clone(),
+                        withdrawer_pool_token_wallet: accounts[2].clone(),
+                        swap_pool_token_mint: accounts[3].clone(),
+                        swap_pool_authority: accounts[4].clone(),
+                        token_program: self.token_program.to_account_info()
+                    }
+                ).with_remaining_accounts(asset_accounts.to_vec()),
+                pool_tokens_amount
+            )
+        }
+    }
+
+    pub fn wrap_sol(
+        &self,
+        wrapped_sol_wallet: &AccountInfo<'info>,
+        amount: u64
+    ) -> Result<()> {
+
+        self.verify_wrapped_sol_wallet(wrapped_sol_wallet)?;
+
+        invoke(
+            &system_instruction::transfer(&self.user.key(), &wrapped_sol_wallet.key(), amount),
+            &[
+                self.user.to_account_info(),
+                wrapped_sol_wallet.to_owned(),
+                self.system_program.to_account_info()
+            ]
+        )?;
+
+        // Update the token balance of the wallet with the transferred lamports
+        invoke(
+            &spl_token::instruction::sync_native(&self.token_program.key(), &wrapped_sol_wallet.key())?,
+            &[
+                wrapped_sol_wallet.to_owned(),
+                self.token_program.to_account_info()
+            ]
+        )?;
+
+        Ok(())
+    }
+
+    pub fn unwrap_sol(
+        &self,
+        wrapped_sol_wallet: &AccountInfo<'info>
+    ) -> Result<()> {
+
+        self.verify_wrapped_sol_wallet(wrapped_sol_wallet)?;
+
+        invoke(
+            &spl_token::instruction::close_account(
+                &self.token_program.key(),
+                &wrapped_sol_wallet.key(),
+                &self.user.key(),
+                &self.user.key(),
+                &[]
+            )?,
+            &[
+                wrapped_sol_wallet.to_owned(),
+                self.user.to_account_info(),
+                self.token_program.to_account_info()
+            ]
+        )?;
+
+        Ok(())
+    }
+
+    fn verify_wrapped_sol_wallet(&self, wrapped_sol_wallet: &AccountInfo<'info>) -> Result<()> {
+        let wallet = Account::<TokenAccount>::try_from(wrapped_sol_wallet)?;
+
+        if wallet.mint != spl_token::native_mint::ID {
+            return Err(error!(ErrorCode::InvalidWrappedSolWallet));
+        }
+
+        Ok(())
+    }
+
+    fn approve(
+        &self,
+        wallet: &AccountInfo<'info>,
+        delegate: &AccountInfo<'info>,
+        amount: u64
+    ) -> Result<()> {
+        invoke(
+            &spl_token::instruction::approve(
+                &self.token_program.key(),
+                &wallet.key(),
+                &delegate.key(),
+                &self.user.key(),
+                &[],
+                amount
+            )?,
+            &[
+                wallet.to_owned(),
+                delegate.to_owned(),
+                self.user.to_account_info(),
+                self.token_program.to_account_info()
+            ]
+        )?;
+
+        Ok(())
+    }
+
+    fn revoke(&self, wallet: &AccountInfo<'info>) -> Result<()> {
+        invoke(
+            &spl_token::instruction::revoke(
+                &self.token_program.key(),
+                &wallet.key(),
+                &self.user.key(),
+                &[]
+            )?,
+            &[
+                wallet.to_owned(),
+                self.user.to_account_info(),
+                self.token_program.to_account_info()
+            ]
+        )?;
+
+        Ok(())
+    }
+}
+
+
+
+// Events ***********************************************************************************************************************
+
+#[event]
+pub struct RouteEvent {
+    user: Pubkey,
+    output_account: Pubkey,
+    commands_count: u32,
+    output: u64
+}
+
+
+
+// Errors ***********************************************************************************************************************
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Invalid remaining accounts count for the given commands.")]
+    InvalidRemainingAccountsCount,
+    #[msg("The pool program must be either SwapPool or SwapPoolAmplified.")]
+    InvalidPoolProgram,
+    #[msg("The wallet is not a wrapped SOL token account.")]
+    InvalidWrappedSolWallet,
+    #[msg("The route output is less than the minimum output.")]
+    MinOutputNotFulfilled
+}
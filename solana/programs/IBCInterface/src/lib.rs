@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
 use anchor_lang::prelude::borsh;
 use anchor_lang::solana_program::entrypoint::ProgramResult;
-use anchor_lang::solana_program::{instruction::Instruction, program::invoke_signed};
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke, program::invoke_signed, program::get_return_data, program::set_return_data};
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::program_pack::Pack;
 use borsh::BorshSerialize;
 use std::convert::TryInto;
 
@@ -11,6 +13,10 @@ use polymerase_register::cpi::accounts::RegisterPort;
 use polymerase_register::{program::PolymeraseRegister};
 use polymerase_sender::cpi::accounts::SendIbcPacket;
 use polymerase_sender::program::PolymeraseSender;
+use polymerase_sender::POLYMERASE_AUTHORITY_SEED;
+
+use catalyst_payload::{AssetSwapPayload, CatalystAddress, CatalystPayload, LiquiditySwapPayload, PayloadError};
+use shared_lib::u256::U256;
 
 //TODO! change id
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLCC");
@@ -21,12 +27,24 @@ const SIGHASH_IN_SWAP           : [u8; 8] = [78, 13, 161, 247, 110, 249, 148, 65
 const SIGHASH_IN_LIQUIDITY_SWAP : [u8; 8] = [148, 187, 47, 168, 127, 65, 48, 95];
 const SIGHASH_OUT_SWAP_ACK      : [u8; 8] = [25, 202, 103, 138, 82, 99, 175, 108];
 const SIGHASH_OUT_SWAP_TIMEOUT  : [u8; 8] = [221, 78, 207, 141, 128, 224, 34, 43];
+const SIGHASH_OUT_LIQUIDITY_SWAP_ACK     : [u8; 8] = [84, 16, 127, 31, 153, 106, 53, 181];
+const SIGHASH_OUT_LIQUIDITY_SWAP_TIMEOUT : [u8; 8] = [109, 225, 107, 164, 105, 148, 41, 7];
+const SIGHASH_UNDERWRITE_ASSET           : [u8; 8] = [17, 133, 147, 113, 40, 210, 74, 105];
+const SIGHASH_RELEASE_UNDERWRITE_ASSET   : [u8; 8] = [123, 37, 46, 61, 115, 139, 189, 152];
+const SIGHASH_DELETE_UNDERWRITE_ASSET    : [u8; 8] = [63, 89, 76, 205, 198, 119, 61, 63];
+
+// The messages are encoded with the catalyst_payload codec. The (32 byte) Solana pool ids are right-aligned within the 64 byte
+// address fields, hence these are read directly from the message by the account contexts.
+const PAYLOAD_FROM_VAULT_START  : usize = catalyst_payload::FROM_VAULT_END - 32;
+const PAYLOAD_FROM_VAULT_END    : usize = catalyst_payload::FROM_VAULT_END;
+const PAYLOAD_TO_VAULT_START    : usize = catalyst_payload::TO_VAULT_END - 32;
+const PAYLOAD_TO_VAULT_END      : usize = catalyst_payload::TO_VAULT_END;
 
-// Define swap contexts
-const CTX_ASSET_SWAP            : u8 = 0x00;
-const CTX_ASSET_APPROX_SWAP     : u8 = 0x01;
-const CTX_LIQUIDITY_SWAP        : u8 = 0x02;
-const CTX_LIQUIDITY_APPROX_SWAP : u8 = 0x03;
+const UNDERWRITE_STATE_SEED     : &[u8] = b"underwrite";
+
+// NOTE: the underwrite expiry is slot based rather than timestamp based, so that underwriters are not at risk of being expired
+// should the chain halt. This is the default value, it can be changed by the configurator (see 'set_underwrite_expiry_slots').
+const UNDERWRITE_EXPIRY_SLOTS   : u64 = 216000;    // ~24 hours (at 400ms per slot)
 
 #[program]
 pub mod ibc_interface {
@@ -43,6 +61,7 @@ pub mod ibc_interface {
         ctx.accounts.interface_state_account.swap_pool = swap_pool_state;
         ctx.accounts.interface_state_account.swap_pool_authority = swap_pool_authority;
         ctx.accounts.interface_state_account.polymerase_endpoint_program = polymerase_endpoint_program;
+        ctx.accounts.interface_state_account.underwrite_expiry_slots = UNDERWRITE_EXPIRY_SLOTS;
         ctx.accounts.interface_state_account.interface_state_account_bump = ctx.bumps.get("interface_state_account").unwrap().to_owned();
         Ok(())
     }
@@ -52,6 +71,12 @@ pub mod ibc_interface {
         Ok(())
     }
 
+    // NOTE: only affects underwrites performed after the change, as the expiry slot is saved on each underwrite state.
+    pub fn set_underwrite_expiry_slots(ctx: Context<SetUnderwriteExpirySlots>, underwrite_expiry_slots: u64) -> Result<()> {
+        ctx.accounts.interface_state_account.underwrite_expiry_slots = underwrite_expiry_slots;
+        Ok(())
+    }
+
     // ! TODO must be required before completing setup
     pub fn register_receiver(
         ctx: Context<RegisterReceiver>
@@ -111,78 +136,82 @@ pub mod ibc_interface {
         ctx: Context<CreateConnection>,
         chain: u64,                     // Used in the CreateConnection context
         pool: Pubkey,                   // Used in the CreateConnection context
-        interface_program: Pubkey       // target crosschain program
+        interface_program: Pubkey,      // target crosschain program
+        channel_id: [u8; 32]            // channel on which packets to the target pool are routed
     ) -> Result<()> {
         ctx.accounts.connection_state_account.connected_interface_program   = interface_program;
+        ctx.accounts.connection_state_account.connected_chain               = chain;
+        ctx.accounts.connection_state_account.connected_pool                = pool;
+        ctx.accounts.connection_state_account.channel_id                    = channel_id;
+        ctx.accounts.connection_state_account.enabled                       = true;
         ctx.accounts.connection_state_account.connection_state_account_bump = ctx.bumps.get("connection_state_account").unwrap().to_owned();
+
+        emit!(CreateConnectionEvent {
+            interface_state: ctx.accounts.interface_state_account.key(),
+            chain,
+            pool,
+            interface_program,
+            channel_id
+        });
+
         Ok(())
     }
 
+    pub fn set_connection_enabled(
+        ctx: Context<SetConnectionEnabled>,
+        chain: u64,                     // Used in the SetConnectionEnabled context
+        pool: Pubkey,                   // Used in the SetConnectionEnabled context
+        enabled: bool
+    ) -> Result<()> {
+        ctx.accounts.connection_state_account.enabled = enabled;
+
+        emit!(SetConnectionEnabledEvent {
+            interface_state: ctx.accounts.interface_state_account.key(),
+            chain,
+            pool,
+            enabled
+        });
+
+        Ok(())
+    }
+
+    // Send an asset swap to the target pool. The swap id of the message (see 'AssetSwapPayload::swap_id') is set as the
+    // return data, so that the swap pool can match the swap acknowledgement/timeout with its escrow.
     pub fn cross_chain_swap(
         ctx: Context<CrossChainSwap>,
         chain: u64,
-        approx: bool,
         source_pool: Pubkey,    // Used to derive the ibc_interface_state account
         target_pool: Pubkey,
         target_asset_index: u8,
         destination: Pubkey,
         transferred_units_x64: [u64; 4],
         min_output: [u64; 4],
-        source_swap_id: u32
+        escrowed_amount: u64,
+        escrowed_asset: Pubkey,
+        timeout_slot: u64,          // Slot at which the packet times out (0 for no timeout)
+        timeout_timestamp: u64      // Unix timestamp at which the packet times out (0 for no timeout)
     ) -> Result<()> {
-        /*
-            Message Format
-
-            0       context            : u8
-            1-32    source_pool        : Pubkey
-            33-64   target_pool        : Pubkey
-            65-96   destination        : Pubkey
-            97-128  units              : u256 (as 4 u64)
-            129     target_asset_index : u8
-            130-161 min_output         : u256 (as 4 u64)
-            162-165 source_swap_id     : u32
-        */
-
         // ! Swap pool authority verification in CrossChainSwap context
         // !    ==> Only allow the authorized swap pool authority holder to execute this function
 
         // ! The target pool connection state (chain + pool) is checked in the context
 
-        let mut message = vec![0; 166];
-
-        match approx {
-            false => message[0] = CTX_ASSET_SWAP,
-            true =>  message[0] = CTX_ASSET_APPROX_SWAP
-        }
-        
-        message[1..33]      // Context
-            .copy_from_slice(&ctx.accounts.interface_state_account.key().to_bytes());
-        
-        message[33..65]     // Source pool
-            .copy_from_slice(&target_pool.to_bytes());
-        
-        message[65..97]     // Target pool
-            .copy_from_slice(&destination.to_bytes());
-        
-        message[97..129]    // Destination
-            .copy_from_slice(&transferred_units_x64
-                .iter()
-                .flat_map(|el| {el.to_be_bytes()})
-                .collect::<Vec<u8>>()[0..32]
-            ); //TODO! verify + efficiency + use big_endian?
-
-        message[129..130]   // Units
-            .copy_from_slice(&[target_asset_index]);
-
-        message[130..162]   // Minimum output
-            .copy_from_slice(&min_output
-                .iter()
-                .flat_map(|el| {el.to_be_bytes()})
-                .collect::<Vec<u8>>()[0..32]
-            ); //TODO! verify + efficiency + use big_endian?
-        
-        message[162..166]   // Source swap id
-            .copy_from_slice(&source_swap_id.to_be_bytes());
+        // NOTE: as on EVM, the escrowed amount and asset are part of the swap id, which ties the acknowledgement/timeout of the
+        // swap to the escrow it releases
+        let payload = AssetSwapPayload {
+            from_vault               : CatalystAddress::from(ctx.accounts.interface_state_account.key().to_bytes()),
+            to_vault                 : CatalystAddress::from(target_pool.to_bytes()),
+            to_account               : CatalystAddress::from(destination.to_bytes()),
+            units                    : U256(transferred_units_x64),
+            to_asset_index           : target_asset_index,
+            min_out                  : U256(min_output),
+            from_amount              : U256::from(escrowed_amount),
+            from_asset               : CatalystAddress::from(escrowed_asset.to_bytes()),
+            block_number             : Clock::get()?.slot as u32,   // mod 2**32
+            underwrite_incentive_x16 : 0,
+            calldata                 : vec![]
+        };
+        let message = payload.encode().map_err(ErrorCode::from)?;
 
         // Invoke call_multichain
         //TODO create external function for the following code
@@ -202,60 +231,46 @@ pub mod ibc_interface {
             },
             signer
         );
-        let channel_id: [u8; 32] = [1; 32]; // ! TODO channel_id from connection_state_account, linked to 'chain' and 'connection_state_account.connected_interface_program'
+        let channel_id: [u8; 32] = ctx.accounts.connection_state_account.channel_id;
+
+        polymerase_sender::cpi::send_ibc_packet(cpi_ctx, channel_id, message, timeout_slot, timeout_timestamp)?;    // ! TODO must add dynamic accounts
 
-        let timeout_block_height: u64 = 0;
-        polymerase_sender::cpi::send_ibc_packet(cpi_ctx, channel_id, message, timeout_block_height)    // ! TODO must add dynamic accounts
+        set_return_data(&payload.swap_id());
 
+        Ok(())
     }
 
 
+    // Send a liquidity swap to the target pool. As for 'cross_chain_swap', the swap id of the message is set as the return data.
     pub fn cross_chain_liquidity_swap(
         ctx: Context<CrossChainSwap>,
         chain: u64,
-        approx: bool,
         source_pool: Pubkey,    // Used to derive the ibc_interface_state account
         target_pool: Pubkey,
         destination: Pubkey,
-        transferred_liquidity_units_x64: [u64; 4]
+        transferred_liquidity_units_x64: [u64; 4],
+        escrowed_pool_token_amount: u64,
+        timeout_slot: u64,          // Slot at which the packet times out (0 for no timeout)
+        timeout_timestamp: u64      // Unix timestamp at which the packet times out (0 for no timeout)
     ) -> Result<()> {
-        /*
-            Message Format
-
-            0       context            : u8
-            1-32    source_pool        : Pubkey
-            33-64   target_pool        : Pubkey
-            65-96   destination        : Pubkey
-            97-128  units              : u256 (as 4 u64)
-            129-XX  TBD
-        */
-
         // ! Swap pool authority verification in CrossChainSwap context
         // !    ==> Only allow the authorized swap pool authority holder to execute this function
 
         // ! The target pool connection state (chain + pool) is checked in the context
 
-        assert!(!approx, "Cross chain liquidity swap does not support approx mode.");
-
-        let mut message = vec![0; 130];
-
-        message[0] = CTX_LIQUIDITY_SWAP;     // Context
-
-        message[1..33]                  // Source pool
-            .copy_from_slice(&ctx.accounts.interface_state_account.key().to_bytes());
-
-        message[33..65]                 // Target pool
-            .copy_from_slice(&target_pool.to_bytes());
-
-        message[65..97]                 // Destination
-            .copy_from_slice(&destination.to_bytes());
-
-        message[97..129]                // Units
-            .copy_from_slice(&transferred_liquidity_units_x64
-            .iter()
-            .flat_map(|el| {el.to_be_bytes()})
-            .collect::<Vec<u8>>()[0..32]
-        ); //TODO! verify + efficiency + use big_endian?
+        // NOTE: the minimum outputs are not forwarded by the swap pools, they are only used by the EVM implementation
+        let payload = LiquiditySwapPayload {
+            from_vault          : CatalystAddress::from(ctx.accounts.interface_state_account.key().to_bytes()),
+            to_vault            : CatalystAddress::from(target_pool.to_bytes()),
+            to_account          : CatalystAddress::from(destination.to_bytes()),
+            units               : U256(transferred_liquidity_units_x64),
+            min_vault_tokens    : U256::zero(),
+            min_reference_asset : U256::zero(),
+            from_amount         : U256::from(escrowed_pool_token_amount),
+            block_number        : Clock::get()?.slot as u32,   // mod 2**32
+            calldata            : vec![]
+        };
+        let message = payload.encode().map_err(ErrorCode::from)?;
 
 
         // Invoke call_multichain
@@ -276,28 +291,24 @@ pub mod ibc_interface {
             },
             signer
         );
-        let channel_id: [u8; 32] = [1; 32]; // ! TODO channel_id from connection_state_account, linked to 'chain' and 'connection_state_account.connected_interface_program'
+        let channel_id: [u8; 32] = ctx.accounts.connection_state_account.channel_id;
+
+        polymerase_sender::cpi::send_ibc_packet(cpi_ctx, channel_id, message, timeout_slot, timeout_timestamp)?;    // ! TODO must add dynamic accounts
 
-        let timeout_block_height: u64 = 0;
-        polymerase_sender::cpi::send_ibc_packet(cpi_ctx, channel_id, message, timeout_block_height)    // ! TODO must add dynamic accounts
+        set_return_data(&payload.swap_id());
 
+        Ok(())
     }
 
 
     pub fn on_recv_packet<'a, 'b, 'c, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, OnIBCInvocation<'info>>,
-        message: Vec<u8>
+        message: Vec<u8>,
+        channel_id: [u8; 32]    // channel on which the packet was received
     ) -> Result<()> {
 
-        // ! TODO:
-        // ! PolymeraseInterface authority verification in OnRecvPacket context
-        // !    ==> Only allow the authorized PolymeraseInterface authority holder to execute this function
-
-        // let chain //TODO
-
-        let context: u8 = u8::from_be_bytes(message[..1].try_into().unwrap());
-        let source_pool: Pubkey = Pubkey::new(&message[1..33]);
-        let target_pool: Pubkey = Pubkey::new(&message[33..65]);
+        // NOTE: The polymerase_authority is verified in the OnIBCInvocation context (must be the authority PDA of the registered polymerase endpoint)
+        // NOTE: The channel on which the packet was received is verified against the connection in the OnIBCInvocation context
 
         //TODO move to context?
         // Check the provided SwapPool program is the expected one
@@ -305,101 +316,122 @@ pub mod ibc_interface {
             return Err(error!(ErrorCode::InvalidSwapPoolProgram));
         }
 
-        //TODO!
-        // //TODO move to context?
-        // // Check the source chain + pool is valid, i.e. it is a connected and enabled pool connection
-        // if !ctx.accounts.connection_state_account.state {
-        //     return Err(error!(ErrorCode::SourcePoolNotConnected));
-        // }
+        // ! The source chain + pool is verified to be a connected and enabled pool connection in the OnIBCInvocation context
 
-        match context {
+        match CatalystPayload::decode(&message).map_err(ErrorCode::from)? {
 
-            CTX_ASSET_SWAP | CTX_ASSET_APPROX_SWAP => {
+            CatalystPayload::AssetSwap(payload) => {
                 // Invoke in_swap of the target pool
-                /*
-                    Message Format
-                    0       context            : u8
-                    1-32    source_pool        : Pubkey
-                    33-64   target_pool        : Pubkey
-                    65-96   destination        : Pubkey
-                    97-128  units              : u256 (as 4 u64)
-                    129     target_asset_index : u8
-                    130-161 min_output         : u256 (as 4 u64)
-                    162-165 source_swap_id     : u32
-                */
-    
-                let units_x64: [u64; 4] = [                                         // TODO create helper function
-                    u64::from_be_bytes(message[97..105].try_into().unwrap()),
-                    u64::from_be_bytes(message[105..113].try_into().unwrap()),
-                    u64::from_be_bytes(message[113..121].try_into().unwrap()),
-                    u64::from_be_bytes(message[121..129].try_into().unwrap())
-                ];
-
+                // NOTE: expired packets are rejected by the polymerase endpoint
 
                 // Min output: for Solana, the maximum value allowed is u64
-                let min_output: u64 = u64::from_be_bytes(message[130..138].try_into().unwrap());
-
-                // Make sure that the rest of the 'min_output' value is zero
-                if !message[138..162].iter().all(|slice| slice == &0) {
+                if payload.min_out > U256::from(u64::MAX) {
                     return Err(error!(ErrorCode::MinimumOutputExceedsMaxAllowed));
                 }
+                let min_output: u64 = payload.min_out.as_u64();
+
+                let units_x64: [u64; 4] = payload.units.0;
+                let destination: Pubkey = decode_pubkey(&payload.to_account)?;
 
 
                 // ! VERY IMPORTANT verify provided output_asset_wallet matches the one specified in the message
-                if Pubkey::new(&message[65..97]).ne(&ctx.remaining_accounts[1].key()) {
+                if destination.ne(&ctx.remaining_accounts[1].key()) {
                     return Err(error!(ErrorCode::InvalidAssetWalletAccount));
                 }
 
 
-                ctx.accounts.invoke_in_swap(
-                    message[129],
-                    units_x64,
+                // Check whether the swap has been underwritten. NOTE: the underwrite state account must always be provided (even
+                // if the swap has not been underwritten), as otherwise an underwritten swap could be paid out twice.
+                let underwrite_id = UnderwriteState::compute_underwrite_id(
+                    &ctx.accounts.swap_pool.key(),
+                    payload.to_asset_index,
+                    &units_x64,
                     min_output,
-                    context == CTX_ASSET_APPROX_SWAP,
+                    &destination,
+                    &payload.swap_id()
+                );
+
+                let underwrite_state_account_info = &ctx.remaining_accounts[4];
+                let (expected_underwrite_state_account, _) = Pubkey::find_program_address(
+                    &[
+                        &ctx.accounts.interface_state_account.key().to_bytes(),
+                        &underwrite_id,
+                        UNDERWRITE_STATE_SEED
+                    ],
+                    ctx.program_id
+                );
+
+                if underwrite_state_account_info.key().ne(&expected_underwrite_state_account) {
+                    return Err(error!(ErrorCode::InvalidUnderwriteStateAccount));
+                }
+
+                if underwrite_state_account_info.data_is_empty() {
+
+                    // NOTE: incoming swaps are always computed exactly, as the payload does not carry an approximation mode (as on EVM)
+                    ctx.accounts.invoke_in_swap(
+                        payload.to_asset_index,
+                        units_x64,
+                        min_output,
+                        false,
+                        ctx.remaining_accounts[0].to_account_info(),
+                        ctx.remaining_accounts[1].to_account_info(),
+                        ctx.remaining_accounts[2].to_account_info(),
+                        ctx.remaining_accounts[3].to_account_info(),
+                    ).unwrap();
+
+                    return Ok(());
+                }
+
+                // The swap has been underwritten: repay the underwriter with the assets escrowed on the pool instead
+                let underwrite_state_account: Account<UnderwriteState> = Account::try_from(underwrite_state_account_info)?;
+
+                if underwrite_state_account.refund_wallet.ne(&ctx.remaining_accounts[5].key()) {
+                    return Err(error!(ErrorCode::InvalidRefundWalletAccount));
+                }
+
+                if underwrite_state_account.underwriter.ne(&ctx.remaining_accounts[6].key()) {
+                    return Err(error!(ErrorCode::InvalidUnderwriterAccount));
+                }
+
+                ctx.accounts.invoke_release_underwrite_asset(
+                    underwrite_id,
+                    underwrite_state_account.output_asset_index,
+                    underwrite_state_account.amount,
                     ctx.remaining_accounts[0].to_account_info(),
-                    ctx.remaining_accounts[1].to_account_info(),
+                    ctx.remaining_accounts[5].to_account_info(),
                     ctx.remaining_accounts[2].to_account_info(),
                     ctx.remaining_accounts[3].to_account_info(),
                 ).unwrap();
-    
-                Ok(())
+
+                emit!(FulfillUnderwriteEvent {
+                    underwrite_id,
+                    underwriter: underwrite_state_account.underwriter,
+                    amount: underwrite_state_account.amount
+                });
+
+                // Close the underwrite state account, returning the rent to the underwriter
+                underwrite_state_account.close(ctx.remaining_accounts[6].to_account_info())
 
             },
 
-            CTX_LIQUIDITY_SWAP => {
+            CatalystPayload::LiquiditySwap(payload) => {
                 // Invoke in_liquidity_swap of the target pool
-                /*
-                    Message Format
-                    0       context            : u8
-                    1-32    source_pool        : Pubkey
-                    33-64   target_pool        : Pubkey
-                    65-96   destination        : Pubkey
-                    97-128  units              : u256 (as 4 u64)
-                */
-    
-                // CPI - Arguments 
-                let liquidity_units_x64: [u64; 4] = [
-                    u64::from_be_bytes(message[97..105].try_into().unwrap()),
-                    u64::from_be_bytes(message[105..113].try_into().unwrap()),
-                    u64::from_be_bytes(message[113..121].try_into().unwrap()),
-                    u64::from_be_bytes(message[121..129].try_into().unwrap())
-                ];
+                // NOTE: expired packets are rejected by the polymerase endpoint
 
                 // ! VERY IMPORTANT verify provided destination_pool_token_wallet matches the one specified in the message
-                if Pubkey::new(&message[65..97]).ne(&ctx.remaining_accounts[1].key()) {
+                if decode_pubkey(&payload.to_account)?.ne(&ctx.remaining_accounts[1].key()) {
                     return Err(error!(ErrorCode::InvalidPoolTokenWalletAccount));
                 }
 
                 ctx.accounts.invoke_liquidity_in_swap(
-                    liquidity_units_x64,
+                    payload.units.0,
                     ctx.remaining_accounts[0].to_account_info(),
                     ctx.remaining_accounts[1].to_account_info(),
                     ctx.remaining_accounts[2].to_account_info()
                 ).unwrap();
 
                 Ok(())
-            },
-            _ => Err(error!(ErrorCode::InvalidContext))
+            }
         }
 
     }
@@ -410,12 +442,7 @@ pub mod ibc_interface {
         message: Vec<u8>
     ) -> Result<()> {
 
-        // ! TODO:
-        // ! PolymeraseInterface authority verification in OnRecvPacket context
-        // !    ==> Only allow the authorized PolymeraseInterface authority holder to execute this function
-
-        let context: u8 = u8::from_be_bytes(message[..1].try_into().unwrap());
-        let source_swap_id: u32 = u32::from_be_bytes(message[162..166].try_into().unwrap());
+        // NOTE: The polymerase_authority is verified in the OnIBCResult context (must be the authority PDA of the registered polymerase endpoint)
 
         //TODO move to context?
         // Check the provided SwapPool program is the expected one
@@ -423,24 +450,13 @@ pub mod ibc_interface {
             return Err(error!(ErrorCode::InvalidSwapPoolProgram));
         }
 
-        match context {
+        match CatalystPayload::decode(&message).map_err(ErrorCode::from)? {
 
-            CTX_ASSET_SWAP | CTX_ASSET_APPROX_SWAP => {
-                // Invoke in_swap of the target pool
-                /*
-                    Message Format
-                    0       context            : u8
-                    1-32    source_pool        : Pubkey
-                    33-64   target_pool        : Pubkey
-                    65-96   destination        : Pubkey
-                    97-128  units              : u256 (as 4 u64)
-                    129     target_asset_index : u8
-                    130-161 min_output         : u256 (as 4 u64)
-                    162-165 source_swap_id     : u32
-                */
+            CatalystPayload::AssetSwap(payload) => {
+                // Release the escrow of the swap on the source pool
 
                 ctx.accounts.invoke_out_swap_ack(
-                    source_swap_id,
+                    payload.swap_id(),
                     ctx.remaining_accounts[3].to_owned(),
                     ctx.remaining_accounts[4].to_owned(),
                 ).unwrap();
@@ -448,20 +464,17 @@ pub mod ibc_interface {
                 Ok(())
             },
 
-            CTX_LIQUIDITY_SWAP => {
-                // Invoke in_liquidity_swap of the target pool
-                /*
-                    Message Format
-                    0       context            : u8
-                    1-32    source_pool        : Pubkey
-                    33-64   target_pool        : Pubkey
-                    65-96   destination        : Pubkey
-                    97-128  units              : u256 (as 4 u64)
-                */
-
-                Ok(())  //TODO do something?
-            },
-            _ => Err(error!(ErrorCode::InvalidContext))
+            CatalystPayload::LiquiditySwap(payload) => {
+                // Release the escrow of the liquidity swap on the source pool
+
+                ctx.accounts.invoke_out_liquidity_swap_ack(
+                    payload.swap_id(),
+                    ctx.remaining_accounts[2].to_owned(),
+                    ctx.remaining_accounts[3].to_owned(),
+                ).unwrap();
+
+                Ok(())
+            }
         }
 
     }
@@ -472,12 +485,7 @@ pub mod ibc_interface {
         message: Vec<u8>
     ) -> Result<()> {
 
-        // ! TODO:
-        // ! PolymeraseInterface authority verification in OnRecvPacket context
-        // !    ==> Only allow the authorized PolymeraseInterface authority holder to execute this function
-
-        let context: u8 = u8::from_be_bytes(message[..1].try_into().unwrap());
-        let source_swap_id: u32 = u32::from_be_bytes(message[162..166].try_into().unwrap());
+        // NOTE: The polymerase_authority is verified in the OnIBCResult context (must be the authority PDA of the registered polymerase endpoint)
 
         //TODO move to context?
         // Check the provided SwapPool program is the expected one
@@ -485,24 +493,13 @@ pub mod ibc_interface {
             return Err(error!(ErrorCode::InvalidSwapPoolProgram));
         }
 
-        match context {
+        match CatalystPayload::decode(&message).map_err(ErrorCode::from)? {
 
-            CTX_ASSET_SWAP | CTX_ASSET_APPROX_SWAP => {
-                // Invoke in_swap of the target pool
-                /*
-                    Message Format
-                    0       context            : u8
-                    1-32    source_pool        : Pubkey
-                    33-64   target_pool        : Pubkey
-                    65-96   destination        : Pubkey
-                    97-128  units              : u256 (as 4 u64)
-                    129     target_asset_index : u8
-                    130-161 min_output         : u256 (as 4 u64)
-                    162-165 source_swap_id     : u32
-                */
+            CatalystPayload::AssetSwap(payload) => {
+                // Return the escrowed assets of the swap on the source pool
 
                 ctx.accounts.invoke_out_swap_timeout(
-                    source_swap_id,
+                    payload.swap_id(),
                     ctx.remaining_accounts[0].to_owned(),
                     ctx.remaining_accounts[1].to_owned(),
                     ctx.remaining_accounts[2].to_owned(),
@@ -513,24 +510,130 @@ pub mod ibc_interface {
                 Ok(())
             },
 
-            CTX_LIQUIDITY_SWAP => {
-                // Invoke in_liquidity_swap of the target pool
-                /*
-                    Message Format
-                    0       context            : u8
-                    1-32    source_pool        : Pubkey
-                    33-64   target_pool        : Pubkey
-                    65-96   destination        : Pubkey
-                    97-128  units              : u256 (as 4 u64)
-                */
-
-                Ok(())  //TODO do something?
+            CatalystPayload::LiquiditySwap(payload) => {
+                // Return the escrowed pool tokens of the liquidity swap on the source pool
+
+                ctx.accounts.invoke_out_liquidity_swap_timeout(
+                    payload.swap_id(),
+                    ctx.remaining_accounts[0].to_owned(),
+                    ctx.remaining_accounts[1].to_owned(),
+                    ctx.remaining_accounts[2].to_owned(),
+                    ctx.remaining_accounts[3].to_owned(),
+                ).unwrap();
+
+                Ok(())
+            }
+        }
+
+    }
+
+
+    pub fn underwrite(
+        ctx: Context<Underwrite>,
+        underwrite_id: [u8; 32],        // Used in context to derive the underwrite_state_account
+        output_asset_index: u8,
+        units_x64: [u64; 4],
+        min_output: u64,
+        destination: Pubkey,
+        swap_id: [u8; 32]               // Id of the swap payload (see 'AssetSwapPayload::swap_id')
+    ) -> Result<()> {
+
+        // The underwrite id must match the one derived from the swap payload once the swap arrives
+        if underwrite_id != UnderwriteState::compute_underwrite_id(
+            &ctx.accounts.swap_pool.key(),
+            output_asset_index,
+            &units_x64,
+            min_output,
+            &destination,
+            &swap_id
+        ) {
+            return Err(error!(ErrorCode::InvalidUnderwriteId));
+        }
+
+        // ! VERY IMPORTANT verify provided destination_asset_wallet matches the destination of the swap
+        if destination.ne(&ctx.accounts.destination_asset_wallet.key()) {
+            return Err(error!(ErrorCode::InvalidAssetWalletAccount));
+        }
+
+        // Reserve the purchased assets on the swap pool. NOTE: computed exactly, as the swap itself (see 'on_recv_packet')
+        ctx.accounts.invoke_underwrite_asset(
+            underwrite_id,
+            output_asset_index,
+            units_x64,
+            min_output,
+            false
+        ).unwrap();
+
+        let amount = match get_return_data() {
+            Some((program_id, data)) if program_id.eq(&ctx.accounts.swap_pool_program.key()) && data.len() == 8 => {
+                u64::from_le_bytes(data[..].try_into().unwrap())
             },
-            _ => Err(error!(ErrorCode::InvalidContext))
+            _ => return Err(error!(ErrorCode::InvalidSwapPoolReturnData))
+        };
+
+        // Front the purchased assets to the destination on behalf of the underwriter
+        ctx.accounts.transfer_assets_from_underwriter(amount).unwrap();
+
+        let expiry = Clock::get()?.slot.checked_add(ctx.accounts.interface_state_account.underwrite_expiry_slots).unwrap();
+
+        let underwrite_state_account = &mut ctx.accounts.underwrite_state_account;
+        underwrite_state_account.underwriter                   = ctx.accounts.underwriter.key();
+        underwrite_state_account.refund_wallet                 = ctx.accounts.underwriter_asset_wallet.key();
+        underwrite_state_account.output_asset_index            = output_asset_index;
+        underwrite_state_account.units_x64                     = units_x64;
+        underwrite_state_account.amount                        = amount;
+        underwrite_state_account.expiry                        = expiry;
+        underwrite_state_account.underwrite_state_account_bump = ctx.bumps.get("underwrite_state_account").unwrap().to_owned();
+
+        emit!(SwapUnderwrittenEvent {
+            underwrite_id,
+            underwriter: ctx.accounts.underwriter.key(),
+            swap_pool: ctx.accounts.swap_pool.key(),
+            output_asset_mint: ctx.accounts.output_asset_mint.key(),
+            destination,
+            units_x64,
+            amount,
+            expiry
+        });
+
+        Ok(())
+    }
+
+
+    pub fn expire_underwrite(
+        ctx: Context<ExpireUnderwrite>,
+        underwrite_id: [u8; 32]         // Used in context to derive the underwrite_state_account
+    ) -> Result<()> {
+
+        // The underwriter may expire the underwrite at any time, anyone else only once the expiry slot has passed
+        if ctx.accounts.caller.key().ne(&ctx.accounts.underwrite_state_account.underwriter) &&
+            Clock::get()?.slot <= ctx.accounts.underwrite_state_account.expiry {
+            return Err(error!(ErrorCode::UnderwriteNotExpired));
         }
 
+        // Free the assets reserved on the swap pool. NOTE: the underwriter is not repaid.
+        ctx.accounts.invoke_delete_underwrite_asset(underwrite_id).unwrap();
+
+        // NOTE: the underwrite state account is closed once the instruction completes (see the 'ExpireUnderwrite' context), with
+        // its rent returned to the underwriter. Should the swap arrive afterwards, it is executed as an ordinary swap.
+        emit!(ExpireUnderwriteEvent {
+            underwrite_id,
+            expirer: ctx.accounts.caller.key(),
+            amount: ctx.accounts.underwrite_state_account.amount
+        });
+
+        Ok(())
     }
+}
 
+
+// Decodes a Solana address of a message. Only 32 byte addresses are valid.
+fn decode_pubkey(address: &CatalystAddress) -> Result<Pubkey> {
+    let bytes: [u8; 32] = address.as_bytes()
+        .try_into()
+        .map_err(|_| error!(ErrorCode::InvalidPayloadAddress))?;
+
+    Ok(Pubkey::new_from_array(bytes))
 }
 
 
@@ -559,6 +662,14 @@ pub struct SetConfigurator<'info> {
 }
 
 
+#[derive(Accounts)]
+pub struct SetUnderwriteExpirySlots<'info> {
+    #[account(mut, has_one = configurator @ ErrorCode::InvalidConfigurator)]
+    pub interface_state_account: Account<'info, CrossChainSwapInterfaceState>,
+    pub configurator: Signer<'info>
+}
+
+
 #[derive(Accounts)]
 pub struct RegisterReceiver<'info> {
     #[account(mut)]
@@ -606,7 +717,25 @@ pub struct CreateConnection<'info> {
 }
 
 #[derive(Accounts)]
-#[instruction(chain: u64, approx: bool, source_pool: Pubkey, target_pool: Pubkey)]
+#[instruction(chain: u64, pool: Pubkey)]
+pub struct SetConnectionEnabled<'info> {
+    pub swap_pool_authority: Signer<'info>,
+    #[account(has_one = swap_pool_authority @ ErrorCode::InvalidSwapPoolAuthority)]
+    pub interface_state_account: Account<'info, CrossChainSwapInterfaceState>,
+    #[account(
+        mut,
+        seeds = [
+            &interface_state_account.key().to_bytes(),
+            chain.to_le_bytes().as_ref(),
+            &pool.to_bytes()
+        ],
+        bump = connection_state_account.connection_state_account_bump
+    )]
+    pub connection_state_account: Account<'info, ConnectionState>
+}
+
+#[derive(Accounts)]
+#[instruction(chain: u64, source_pool: Pubkey, target_pool: Pubkey)]
 pub struct CrossChainSwap<'info> {
     pub swap_pool_authority: Signer<'info>,
     #[account(
@@ -617,6 +746,7 @@ pub struct CrossChainSwap<'info> {
     pub interface_state_account: Account<'info, CrossChainSwapInterfaceState>,    //TODO! must verify that it has been created using swap_pool
     #[account(
         constraint = !(connection_state_account.connected_interface_program.eq(&Pubkey::default())) @ ErrorCode::PoolNotConnected,  // The target pool must be connected
+        constraint = connection_state_account.enabled @ ErrorCode::PoolNotConnected,                                                // The connection must be enabled
         seeds = [
             &interface_state_account.key().to_bytes(),
             chain.to_le_bytes().as_ref(),
@@ -637,14 +767,19 @@ pub struct CrossChainSwap<'info> {
 }
 
 #[derive(Accounts)]
-#[instruction(message: Vec<u8>)]
+#[instruction(message: Vec<u8>, channel_id: [u8; 32])]
 pub struct OnIBCInvocation<'info> {
     /// CHECK: unused
     pub port_registration: UncheckedAccount<'info>,
-    #[account()]    // ! TODO add seeds => polymerase receiver set to pda with no seeds?
+    #[account(
+        seeds = [POLYMERASE_AUTHORITY_SEED],
+        bump,
+        seeds::program = interface_state_account.polymerase_endpoint_program                                               // ! Only the registered polymerase endpoint can deliver packets
+    )]
     pub polymerase_authority: Signer<'info>,
     #[account(
-        constraint = interface_state_account.key().eq(&Pubkey::new(&message[33..65])) @ ErrorCode::InvalidInterfaceAccount, // ! The interface_state_account, derived from the provided swap_pool account, must match the target cross chain pool id present in the payload (i.e. verify the provided swap pool) 
+        constraint = message.len() > PAYLOAD_TO_VAULT_END @ ErrorCode::InvalidPayload,                                       // ! Make sure the pool ids can be read from the message
+        constraint = interface_state_account.key().eq(&Pubkey::new(&message[PAYLOAD_TO_VAULT_START..PAYLOAD_TO_VAULT_END])) @ ErrorCode::InvalidInterfaceAccount, // ! The interface_state_account, derived from the provided swap_pool account, must match the target cross chain pool id present in the payload (i.e. verify the provided swap pool) 
         constraint = interface_state_account.swap_pool == swap_pool.key() @ ErrorCode::InvalidSwapPoolAccount               // ! The swap_pool must match the one saved
     )]
     pub interface_state_account: Box<Account<'info, CrossChainSwapInterfaceState>>,    //TODO! must verify that it has been created using swap_pool
@@ -661,16 +796,34 @@ pub struct OnIBCInvocation<'info> {
     pub token_program: UncheckedAccount<'info>,
 
     // Changing accounts
+    // NOTE: the source chain is not forwarded by the polymerase endpoint, hence the one saved on the connection state is used to
+    // derive the account. As the chain is bound to the channel on which the packet was received (which is forwarded by the
+    // endpoint), the connection is only accepted if its channel matches the packet one. The source pool is taken from the payload.
     #[account(
-        constraint=!connection_state_account.connected_interface_program.eq(&Pubkey::default()), //TODO delete account to remove connection ? => avoid check
-        // seeds = [        // ! TODO - Disabled until source chain and sender is known
-        //     &interface_state_account.key().to_bytes(),
-        //     &header[0..8],  // ! The source chain
-        //     &header[8..40]  // ! The source pool id (transaction signer)
-        // ],
-        // bump
+        constraint = connection_state_account.enabled @ ErrorCode::SourcePoolNotConnected,     // ! The connection with the source pool must be enabled
+        constraint = connection_state_account.channel_id == channel_id @ ErrorCode::InvalidChannel,  // ! The packet must have been received on the channel of the connection
+        seeds = [
+            &interface_state_account.key().to_bytes(),
+            connection_state_account.connected_chain.to_le_bytes().as_ref(),
+            &message[PAYLOAD_FROM_VAULT_START..PAYLOAD_FROM_VAULT_END]     // ! The source pool id
+        ],
+        bump = connection_state_account.connection_state_account_bump
     )]
     pub connection_state_account: Account<'info, ConnectionState>
+
+    // Remaining accounts used for dynamic accounts
+    // Asset swaps:
+    // 0 -     - output_asset_mint
+    // 1 - mut - output_asset_wallet
+    // 2 - mut - swap_pool_output_asset_wallet
+    // 3 -     - swap_pool_authority
+    // 4 - mut - underwrite_state_account (must always be provided)
+    // 5 - mut - underwriter refund_wallet (only if underwritten)
+    // 6 - mut - underwriter (only if underwritten)
+    // Liquidity swaps:
+    // 0 - mut - swap_pool_token_mint
+    // 1 - mut - destination_pool_token_wallet
+    // 2 -     - swap_pool_authority
 }
 
 impl<'info> OnIBCInvocation<'info> {
@@ -758,6 +911,87 @@ impl<'info> OnIBCInvocation<'info> {
     }
 
 
+    pub fn invoke_release_underwrite_asset(
+        &self,
+        underwrite_id: [u8; 32],
+        output_asset_index: u8,
+        amount: u64,
+        output_asset_mint: AccountInfo<'info>,
+        refund_wallet: AccountInfo<'info>,
+        swap_pool_asset_wallet: AccountInfo<'info>,
+        swap_authority: AccountInfo<'info>
+    ) -> ProgramResult {
+
+        // Build CPI data (sighash + arguments)
+        let mut data_vec = SIGHASH_RELEASE_UNDERWRITE_ASSET.try_to_vec().unwrap();
+        data_vec.append(&mut ReleaseUnderwriteAssetArgs {
+            underwrite_id,
+            output_asset_index,
+            amount
+        }.try_to_vec().unwrap()); //TODO beter way to do this?
+
+        // Build instruction
+        let instruction = Instruction {
+            data: data_vec,
+            program_id: self.swap_pool_program.key(),
+            accounts: vec![
+                AccountMeta {
+                    pubkey: self.swap_pool.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: output_asset_mint.key(),        // Output asset mint
+                    is_signer: false,
+                    is_writable: false,
+                },
+                AccountMeta {
+                    pubkey: refund_wallet.key(),            // Underwriter refund wallet // ! Must match the one saved on the underwrite state
+                    is_signer: false,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: swap_pool_asset_wallet.key(),   // Swap pool output asset wallet
+                    is_signer: false,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: swap_authority.key(),           // Swap pool authority
+                    is_signer: false,
+                    is_writable: false,
+                },
+                AccountMeta {
+                    pubkey: self.interface_state_account.key(),
+                    is_signer: true,
+                    is_writable: false,
+                },
+                AccountMeta {
+                    pubkey: self.token_program.key(),
+                    is_signer: false,
+                    is_writable: false,
+                }
+            ]
+        };
+
+        invoke_signed(
+            &instruction,
+            &[
+                self.swap_pool.to_account_info(),
+                output_asset_mint,
+                refund_wallet,
+                swap_pool_asset_wallet,
+                swap_authority,
+                self.interface_state_account.to_account_info(),
+                self.token_program.to_account_info()
+            ],
+            &[&[
+                &self.swap_pool.key().to_bytes(),
+                &[self.interface_state_account.interface_state_account_bump]
+            ]]
+        )
+    }
+
+
     pub fn invoke_liquidity_in_swap(
         &self,
         liquidity_units_x64: [u64; 4],
@@ -835,11 +1069,16 @@ impl<'info> OnIBCInvocation<'info> {
 pub struct OnIBCResult<'info> {
     /// CHECK: unused
     pub port_registration: UncheckedAccount<'info>,
-    // #[account()]    // ! TODO add seeds => polymerase receiver set to pda with no seeds?
-    // pub polymerase_authority: Signer<'info>, // TODO disabled for now (until Polymer contract is implemented)
+    #[account(
+        seeds = [POLYMERASE_AUTHORITY_SEED],
+        bump,
+        seeds::program = interface_state_account.polymerase_endpoint_program                                               // ! Only the registered polymerase endpoint can deliver packet results
+    )]
+    pub polymerase_authority: Signer<'info>,
 
     #[account(
-        constraint = interface_state_account.key().eq(&Pubkey::new(&message[1..33])) @ ErrorCode::InvalidInterfaceAccount, // ! The interface_state_account, derived from the provided swap_pool account, must match the source cross chain pool id present in the payload (i.e. verify the provided swap pool) 
+        constraint = message.len() > PAYLOAD_FROM_VAULT_END @ ErrorCode::InvalidPayload,                                     // ! Make sure the pool id can be read from the message
+        constraint = interface_state_account.key().eq(&Pubkey::new(&message[PAYLOAD_FROM_VAULT_START..PAYLOAD_FROM_VAULT_END])) @ ErrorCode::InvalidInterfaceAccount, // ! The interface_state_account, derived from the provided swap_pool account, must match the source cross chain pool id present in the payload (i.e. verify the provided swap pool) 
         constraint = interface_state_account.swap_pool == swap_pool.key() @ ErrorCode::InvalidSwapPoolAccount              // ! The swap_pool must match the one saved
     )]
     pub interface_state_account: Box<Account<'info, CrossChainSwapInterfaceState>>,    //TODO! must verify that it has been created using swap_pool
@@ -863,11 +1102,17 @@ pub struct OnIBCResult<'info> {
     pub system_program: UncheckedAccount<'info>,
 
     // Remaining accounts used for dynamic accounts
+    // Asset swaps:
     // 0 -     - asset_mint
     // 1 - mut - fallback_wallet
     // 2 - mut - swap_pool_asset_wallet
     // 3 - mut - swap_escrow
     // 4 - mut - swap_escrow_rent_payer
+    // Liquidity swaps:
+    // 0 - mut - swap_pool_token_mint
+    // 1 - mut - fallback_wallet (pool token wallet)
+    // 2 - mut - liquidity_escrow
+    // 3 - mut - liquidity_escrow_rent_payer
 }
 
 
@@ -875,14 +1120,14 @@ impl<'info> OnIBCResult<'info> {
 
     pub fn invoke_out_swap_ack(
         &self,
-        source_swap_id: u32,
+        swap_id: [u8; 32],
         swap_escrow: AccountInfo<'info>,
         swap_escrow_rent_payer: AccountInfo<'info>
     ) -> ProgramResult {
 
         // Build CPI data (sighash + arguments)
         let mut data_vec = SIGHASH_OUT_SWAP_ACK.try_to_vec().unwrap();
-        data_vec.append(&mut OutSwapAckArgs {escrow_nonce: source_swap_id}.try_to_vec().unwrap()); //TODO beter way to do this?
+        data_vec.append(&mut OutSwapAckArgs {swap_id}.try_to_vec().unwrap()); //TODO beter way to do this?
 
         // Build instruction
         let instruction = Instruction {
@@ -937,7 +1182,7 @@ impl<'info> OnIBCResult<'info> {
 
     pub fn invoke_out_swap_timeout(
         &self,
-        source_swap_id: u32,
+        swap_id: [u8; 32],
         asset_mint: AccountInfo<'info>,
         fallback_wallet: AccountInfo<'info>,
         swap_pool_asset_wallet: AccountInfo<'info>,
@@ -948,7 +1193,7 @@ impl<'info> OnIBCResult<'info> {
         // Build CPI data (sighash + arguments)
         let mut data_vec = SIGHASH_OUT_SWAP_TIMEOUT.try_to_vec().unwrap();
         data_vec.append(&mut OutSwapTimeoutArgs {
-            escrow_nonce: source_swap_id
+            swap_id
         }.try_to_vec().unwrap()); //TODO beter way to do this?
 
         // Build instruction
@@ -1030,55 +1275,483 @@ impl<'info> OnIBCResult<'info> {
         )
 
     }
-}
-
-
-
-// Accounts *********************************************************************************************************************
-
-#[account]
-pub struct CrossChainSwapInterfaceState {
-    pub configurator: Pubkey,
-    pub swap_pool: Pubkey,
-    pub swap_pool_authority: Pubkey,
-    pub polymerase_endpoint_program: Pubkey,    //TODO Hardcode?
-    pub interface_state_account_bump: u8
-}
-
-impl CrossChainSwapInterfaceState {
-    pub const LEN: usize = 8 + 32*4 + 1;  // NOTE: includes discriminator
-}
-
-
-#[account]
-pub struct ConnectionState {
-    pub connected_interface_program: Pubkey,
-    pub connection_state_account_bump: u8
-}
-
-impl ConnectionState {
-    pub const LEN: usize = 8 + 32*1 + 1;  // NOTE: includes discriminator
-}
 
 
+    pub fn invoke_out_liquidity_swap_ack(
+        &self,
+        swap_id: [u8; 32],
+        liquidity_escrow: AccountInfo<'info>,
+        liquidity_escrow_rent_payer: AccountInfo<'info>
+    ) -> ProgramResult {
 
-// CPI Argument Structs *********************************************************************************************************
+        // Build CPI data (sighash + arguments)
+        let mut data_vec = SIGHASH_OUT_LIQUIDITY_SWAP_ACK.try_to_vec().unwrap();
+        data_vec.append(&mut OutLiquiditySwapAckArgs {swap_id}.try_to_vec().unwrap()); //TODO beter way to do this?
 
-#[derive(PartialEq, Debug, Clone, BorshSerialize)]
-pub struct CallMultichainArgs {
-    target_chain: u64,
-    target_program: Pubkey,
-    payload: Vec<u8>
+        // Build instruction
+        let instruction = Instruction {
+            data: data_vec,
+            program_id: self.swap_pool_program.key(),
+            accounts: vec![
+                AccountMeta {
+                    pubkey: self.swap_pool.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: liquidity_escrow.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: liquidity_escrow_rent_payer.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: self.interface_state_account.key(),
+                    is_signer: true,
+                    is_writable: false,
+                },
+                AccountMeta {
+                    pubkey: self.system_program.key(),
+                    is_signer: false,
+                    is_writable: false,
+                }
+            ]
+        };
+
+        invoke_signed(
+            &instruction,
+            &[
+                self.swap_pool.to_account_info(),
+                liquidity_escrow,
+                liquidity_escrow_rent_payer,
+                self.interface_state_account.to_account_info(),
+                self.system_program.to_account_info()
+            ],
+            &[&[
+                &self.swap_pool.key().to_bytes(),
+                &[self.interface_state_account.interface_state_account_bump]
+            ]]
+        )
+
+    }
+
+
+    pub fn invoke_out_liquidity_swap_timeout(
+        &self,
+        swap_id: [u8; 32],
+        swap_pool_token_mint: AccountInfo<'info>,
+        fallback_wallet: AccountInfo<'info>,
+        liquidity_escrow: AccountInfo<'info>,
+        liquidity_escrow_rent_payer: AccountInfo<'info>
+    ) -> ProgramResult {
+
+        // Build CPI data (sighash + arguments)
+        let mut data_vec = SIGHASH_OUT_LIQUIDITY_SWAP_TIMEOUT.try_to_vec().unwrap();
+        data_vec.append(&mut OutLiquiditySwapTimeoutArgs {
+            swap_id
+        }.try_to_vec().unwrap()); //TODO beter way to do this?
+
+        // Build instruction
+        let instruction = Instruction {
+            data: data_vec,
+            program_id: self.swap_pool_program.key(),
+            accounts: vec![
+                AccountMeta {
+                    pubkey: self.swap_pool.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: swap_pool_token_mint.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: fallback_wallet.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: self.swap_pool_authority.key(),
+                    is_signer: false,
+                    is_writable: false,
+                },
+                AccountMeta {
+                    pubkey: self.token_program.key(),
+                    is_signer: false,
+                    is_writable: false,
+                },
+                AccountMeta {
+                    pubkey: liquidity_escrow.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: liquidity_escrow_rent_payer.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: self.interface_state_account.key(),
+                    is_signer: true,
+                    is_writable: false,
+                },
+                AccountMeta {
+                    pubkey: self.system_program.key(),
+                    is_signer: false,
+                    is_writable: false,
+                }
+            ]
+        };
+
+        invoke_signed(
+            &instruction,
+            &[
+                self.swap_pool.to_account_info(),
+                swap_pool_token_mint,
+                fallback_wallet,
+                self.swap_pool_authority.to_account_info(),
+                self.token_program.to_account_info(),
+                liquidity_escrow,
+                liquidity_escrow_rent_payer,
+                self.interface_state_account.to_account_info(),
+                self.system_program.to_account_info(),
+            ],
+            &[&[
+                &self.swap_pool.key().to_bytes(),
+                &[self.interface_state_account.interface_state_account_bump]
+            ]]
+        )
+
+    }
+}
+
+
+
+
+#[derive(Accounts)]
+#[instruction(underwrite_id: [u8; 32])]
+pub struct Underwrite<'info> {
+    #[account(mut)]
+    pub underwriter: Signer<'info>,
+    #[account(
+        constraint = interface_state_account.swap_pool == swap_pool.key() @ ErrorCode::InvalidSwapPoolAccount   // ! The swap_pool must match the one saved
+    )]
+    pub interface_state_account: Box<Account<'info, CrossChainSwapInterfaceState>>,
+    #[account(mut)]
+    /// CHECK: Safe, as it must match the one saved on interface_state_account
+    pub swap_pool: UncheckedAccount<'info>,
+    #[account(
+        constraint = swap_pool_program.key().eq(swap_pool.owner) @ ErrorCode::InvalidSwapPoolProgram    // ! Make sure the provided swap pool program matches the expected one
+    )]
+    /// CHECK: Safe, as it must be the owner of swap_pool
+    pub swap_pool_program: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = underwriter,
+        space = UnderwriteState::LEN,  // NOTE: includes discriminator
+        seeds = [
+            &interface_state_account.key().to_bytes(),
+            underwrite_id.as_ref(),
+            UNDERWRITE_STATE_SEED
+        ],
+        bump
+    )]
+    pub underwrite_state_account: Account<'info, UnderwriteState>,
+
+    // Output asset
+    /// CHECK: Verified by the swap pool
+    pub output_asset_mint: UncheckedAccount<'info>,
+    /// CHECK: Verified by the swap pool
+    pub swap_pool_output_asset_wallet: UncheckedAccount<'info>,
+    #[account(mut)]
+    /// CHECK: Verified by the token program. Funds the underwrite, and gets repaid once the swap arrives
+    pub underwriter_asset_wallet: UncheckedAccount<'info>,
+    #[account(mut)]
+    /// CHECK: Must match the destination of the swap (verified by the instruction handler)
+    pub destination_asset_wallet: UncheckedAccount<'info>,
+
+    #[account(address = TOKEN_PROGRAM_ID)]
+    /// CHECK: Safe, as the address is verified
+    pub token_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> Underwrite<'info> {
+
+    pub fn invoke_underwrite_asset(
+        &self,
+        underwrite_id: [u8; 32],
+        output_asset_index: u8,
+        units_x64: [u64; 4],
+        min_output: u64,
+        approx: bool
+    ) -> ProgramResult {
+
+        // Build CPI data (sighash + arguments)
+        let mut data_vec = SIGHASH_UNDERWRITE_ASSET.try_to_vec().unwrap();
+        data_vec.append(&mut UnderwriteAssetArgs {
+            underwrite_id,
+            output_asset_index,
+            units_x64,
+            min_output,
+            approx
+        }.try_to_vec().unwrap()); //TODO beter way to do this?
+
+        // Build instruction
+        let instruction = Instruction {
+            data: data_vec,
+            program_id: self.swap_pool_program.key(),
+            accounts: vec![
+                AccountMeta {
+                    pubkey: self.swap_pool.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: self.output_asset_mint.key(),
+                    is_signer: false,
+                    is_writable: false,
+                },
+                AccountMeta {
+                    pubkey: self.swap_pool_output_asset_wallet.key(),
+                    is_signer: false,
+                    is_writable: false,
+                },
+                AccountMeta {
+                    pubkey: self.interface_state_account.key(),
+                    is_signer: true,
+                    is_writable: false,
+                }
+            ]
+        };
+
+        invoke_signed(
+            &instruction,
+            &[
+                self.swap_pool.to_account_info(),
+                self.output_asset_mint.to_account_info(),
+                self.swap_pool_output_asset_wallet.to_account_info(),
+                self.interface_state_account.to_account_info()
+            ],
+            &[&[
+                &self.swap_pool.key().to_bytes(),
+                &[self.interface_state_account.interface_state_account_bump]
+            ]]
+        )
+    }
+
+
+    pub fn transfer_assets_from_underwriter(
+        &self,
+        amount: u64
+    ) -> ProgramResult {
+
+        let decimals = spl_token::state::Mint::unpack(&self.output_asset_mint.data.borrow())?.decimals;
+
+        // NOTE: transfer_checked guarantees that both wallets hold the output asset
+        invoke(
+            &spl_token::instruction::transfer_checked(
+                &TOKEN_PROGRAM_ID,
+                &self.underwriter_asset_wallet.key(),
+                &self.output_asset_mint.key(),
+                &self.destination_asset_wallet.key(),
+                &self.underwriter.key(),
+                &[],
+                amount,
+                decimals
+            )?,
+            &[
+                self.underwriter_asset_wallet.to_account_info(),
+                self.output_asset_mint.to_account_info(),
+                self.destination_asset_wallet.to_account_info(),
+                self.underwriter.to_account_info(),
+                self.token_program.to_account_info()
+            ]
+        )
+    }
+}
+
+
+#[derive(Accounts)]
+#[instruction(underwrite_id: [u8; 32])]
+pub struct ExpireUnderwrite<'info> {
+    pub caller: Signer<'info>,
+    #[account(
+        constraint = interface_state_account.swap_pool == swap_pool.key() @ ErrorCode::InvalidSwapPoolAccount   // ! The swap_pool must match the one saved
+    )]
+    pub interface_state_account: Box<Account<'info, CrossChainSwapInterfaceState>>,
+    #[account(mut)]
+    /// CHECK: Safe, as it must match the one saved on interface_state_account
+    pub swap_pool: UncheckedAccount<'info>,
+    #[account(
+        constraint = swap_pool_program.key().eq(swap_pool.owner) @ ErrorCode::InvalidSwapPoolProgram    // ! Make sure the provided swap pool program matches the expected one
+    )]
+    /// CHECK: Safe, as it must be the owner of swap_pool
+    pub swap_pool_program: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = underwriter,
+        has_one = underwriter @ ErrorCode::InvalidUnderwriterAccount,
+        seeds = [
+            &interface_state_account.key().to_bytes(),
+            underwrite_id.as_ref(),
+            UNDERWRITE_STATE_SEED
+        ],
+        bump = underwrite_state_account.underwrite_state_account_bump
+    )]
+    pub underwrite_state_account: Account<'info, UnderwriteState>,
+    #[account(mut)]
+    /// CHECK: Must match the one saved in underwrite_state_account
+    pub underwriter: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> ExpireUnderwrite<'info> {
+
+    pub fn invoke_delete_underwrite_asset(
+        &self,
+        underwrite_id: [u8; 32]
+    ) -> ProgramResult {
+
+        // Build CPI data (sighash + arguments)
+        let mut data_vec = SIGHASH_DELETE_UNDERWRITE_ASSET.try_to_vec().unwrap();
+        data_vec.append(&mut DeleteUnderwriteAssetArgs {
+            underwrite_id,
+            output_asset_index: self.underwrite_state_account.output_asset_index,
+            amount: self.underwrite_state_account.amount,
+            units_x64: self.underwrite_state_account.units_x64
+        }.try_to_vec().unwrap()); //TODO beter way to do this?
+
+        // Build instruction
+        let instruction = Instruction {
+            data: data_vec,
+            program_id: self.swap_pool_program.key(),
+            accounts: vec![
+                AccountMeta {
+                    pubkey: self.swap_pool.key(),
+                    is_signer: false,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: self.interface_state_account.key(),
+                    is_signer: true,
+                    is_writable: false,
+                }
+            ]
+        };
+
+        invoke_signed(
+            &instruction,
+            &[
+                self.swap_pool.to_account_info(),
+                self.interface_state_account.to_account_info()
+            ],
+            &[&[
+                &self.swap_pool.key().to_bytes(),
+                &[self.interface_state_account.interface_state_account_bump]
+            ]]
+        )
+    }
+}
+
+// Accounts *********************************************************************************************************************
+
+#[account]
+pub struct CrossChainSwapInterfaceState {
+    pub configurator: Pubkey,
+    pub swap_pool: Pubkey,
+    pub swap_pool_authority: Pubkey,
+    pub polymerase_endpoint_program: Pubkey,    //TODO Hardcode?
+    pub underwrite_expiry_slots: u64,
+    pub interface_state_account_bump: u8
+}
+
+impl CrossChainSwapInterfaceState {
+    pub const LEN: usize = 8 + 32*4 + 8 + 1;  // NOTE: includes discriminator
+}
+
+
+#[account]
+pub struct ConnectionState {
+    pub connected_interface_program: Pubkey,    // Interface of the remote chain
+    pub connected_chain: u64,                   // Identifier of the remote chain
+    pub connected_pool: Pubkey,                 // Identifier of the remote pool
+    pub channel_id: [u8; 32],                   // Channel on which the packets to the remote chain are routed
+    pub enabled: bool,
+    pub connection_state_account_bump: u8
+}
+
+impl ConnectionState {
+    pub const LEN: usize = 8 + 32*1 + 8 + 32 + 32 + 1 + 1;  // NOTE: includes discriminator
+}
+
+
+#[account]
+pub struct UnderwriteState {
+    pub underwriter: Pubkey,                // Rent payer of the account
+    pub refund_wallet: Pubkey,
+    pub output_asset_index: u8,
+    pub units_x64: [u64; 4],
+    pub amount: u64,
+    pub expiry: u64,                        // Slot after which anyone may expire the underwrite
+    pub underwrite_state_account_bump: u8
+}
+
+impl UnderwriteState {
+    pub const LEN: usize = 8 + 32*2 + 1 + 8*4 + 8 + 8 + 1;  // NOTE: includes discriminator
+
+    pub fn compute_underwrite_id(
+        swap_pool: &Pubkey,
+        output_asset_index: u8,
+        units_x64: &[u64; 4],
+        min_output: u64,
+        destination: &Pubkey,
+        swap_id: &[u8; 32]
+    ) -> [u8; 32] {
+        hashv(&[
+            &swap_pool.to_bytes(),
+            &[output_asset_index],
+            &units_x64.iter().flat_map(|el| el.to_be_bytes()).collect::<Vec<u8>>(),
+            &min_output.to_be_bytes(),
+            &destination.to_bytes(),
+            swap_id
+        ]).to_bytes()
+    }
+}
+
+
+
+// CPI Argument Structs *********************************************************************************************************
+
+#[derive(PartialEq, Debug, Clone, BorshSerialize)]
+pub struct CallMultichainArgs {
+    target_chain: u64,
+    target_program: Pubkey,
+    payload: Vec<u8>
 }
 
 #[derive(PartialEq, Debug, Clone, BorshSerialize)]
 pub struct OutSwapAckArgs {
-    escrow_nonce: u32
+    swap_id: [u8; 32]
 }
 
 #[derive(PartialEq, Debug, Clone, BorshSerialize)]
 pub struct OutSwapTimeoutArgs {
-    escrow_nonce: u32
+    swap_id: [u8; 32]
+}
+
+#[derive(PartialEq, Debug, Clone, BorshSerialize)]
+pub struct OutLiquiditySwapAckArgs {
+    swap_id: [u8; 32]
+}
+
+#[derive(PartialEq, Debug, Clone, BorshSerialize)]
+pub struct OutLiquiditySwapTimeoutArgs {
+    swap_id: [u8; 32]
 }
 
 #[derive(PartialEq, Debug, Clone, BorshSerialize)]
@@ -1094,11 +1767,76 @@ pub struct InLiquiditySwapArgs {
     liquidity_units_x64: [u64; 4]
 }
 
+#[derive(PartialEq, Debug, Clone, BorshSerialize)]
+pub struct UnderwriteAssetArgs {
+    underwrite_id: [u8; 32],
+    output_asset_index: u8,
+    units_x64: [u64; 4],
+    min_output: u64,
+    approx: bool
+}
+
+#[derive(PartialEq, Debug, Clone, BorshSerialize)]
+pub struct ReleaseUnderwriteAssetArgs {
+    underwrite_id: [u8; 32],
+    output_asset_index: u8,
+    amount: u64
+}
+
+#[derive(PartialEq, Debug, Clone, BorshSerialize)]
+pub struct DeleteUnderwriteAssetArgs {
+    underwrite_id: [u8; 32],
+    output_asset_index: u8,
+    amount: u64,
+    units_x64: [u64; 4]     // NOTE: must be last, as it is only read by the amplified pool
+}
+
 
 
 // Events ***********************************************************************************************************************
 
-//TODO
+#[event]
+pub struct CreateConnectionEvent {
+    interface_state: Pubkey,
+    chain: u64,
+    pool: Pubkey,
+    interface_program: Pubkey,
+    channel_id: [u8; 32]
+}
+
+#[event]
+pub struct SetConnectionEnabledEvent {
+    interface_state: Pubkey,
+    chain: u64,
+    pool: Pubkey,
+    enabled: bool
+}
+
+#[event]
+pub struct SwapUnderwrittenEvent {
+    underwrite_id: [u8; 32],
+    underwriter: Pubkey,
+    swap_pool: Pubkey,
+    output_asset_mint: Pubkey,
+    destination: Pubkey,
+    units_x64: [u64; 4],
+    amount: u64,
+    expiry: u64
+}
+
+#[event]
+pub struct FulfillUnderwriteEvent {
+    underwrite_id: [u8; 32],
+    underwriter: Pubkey,
+    amount: u64
+}
+
+#[event]
+pub struct ExpireUnderwriteEvent {
+    underwrite_id: [u8; 32],
+    expirer: Pubkey,
+    amount: u64
+}
 
 
 // Errors ***********************************************************************************************************************
@@ -1127,4 +1865,37 @@ pub enum ErrorCode {
     InvalidPoolTokenWalletAccount,
     #[msg("The specified minimum output exceeds the allowed one by the chain implementation.")]
     MinimumOutputExceedsMaxAllowed,
+    #[msg("The provided underwrite id does not match with the one derived from the swap parameters.")]
+    InvalidUnderwriteId,
+    #[msg("The provided underwrite state account does not match with the one derived from the swap payload.")]
+    InvalidUnderwriteStateAccount,
+    #[msg("The provided refund wallet does not match with the one saved on the underwrite state.")]
+    InvalidRefundWalletAccount,
+    #[msg("The provided underwriter does not match with the one saved on the underwrite state.")]
+    InvalidUnderwriterAccount,
+    #[msg("The underwrite has not expired yet.")]
+    UnderwriteNotExpired,
+    #[msg("The swap pool did not return the underwritten amount.")]
+    InvalidSwapPoolReturnData,
+    #[msg("The message payload is malformed.")]
+    InvalidPayload,
+    #[msg("The message payload version is not supported.")]
+    UnsupportedPayloadVersion,
+    #[msg("The message payload contains an invalid address.")]
+    InvalidPayloadAddress,
+    #[msg("The packet was not received on the channel of the connection with the source pool.")]
+    InvalidChannel,
+}
+
+impl From<PayloadError> for ErrorCode {
+    fn from(err: PayloadError) -> Self {
+        match err {
+            PayloadError::UnsupportedVersion(_) => ErrorCode::UnsupportedPayloadVersion,
+            PayloadError::InvalidContext(_)     => ErrorCode::InvalidContext,
+            PayloadError::InvalidAddress        => ErrorCode::InvalidPayloadAddress,
+            PayloadError::EmptyPayload
+            | PayloadError::InvalidLength { .. }
+            | PayloadError::CalldataTooLong(_)  => ErrorCode::InvalidPayload,
+        }
+    }
 }
\ No newline at end of file
@@ -1,8 +1,10 @@
 use std::ops::{Shr, Shl};
 
-use anchor_lang::prelude::*;
+use shared_lib::math_error::MathError;
 use shared_lib::u256::U256;
-use shared_lib::fixed_point_math_x64::{log2_x64, div_x64, inv_pow_x64, inv_pow2_x64, pow2_x64, LN2_X64, mul_x64};
+use shared_lib::fixed_point_math_x64::{
+    div_x64, mul_div_x64, mul_div_x64_up, mul_x64, log2_x64_down, pow2_x64_down, inv_pow2_x64_up, inv_pow_x64_up, LN2_X64
+};
 
 const ONE_X64: U256 = U256([0, 1, 0, 0]);
 
@@ -10,42 +12,92 @@ const ONE_X64: U256 = U256([0, 1, 0, 0]);
 
 // Deposits and Withdrawals *****************************************************************************************************
 
-pub fn calc_asset_amount_for_pool_tokens(pool_token_balance: u64, asset_balance: u64, asset_eq_balance: u64) -> Result<u64> {
+pub fn calc_asset_amount_for_pool_tokens(pool_token_balance: u64, asset_balance: u64, asset_eq_balance: u64) -> Result<u64, MathError> {
     if asset_eq_balance == asset_balance {
         return Ok(pool_token_balance)
     }
 
-    Ok(asset_balance.checked_mul(pool_token_balance).unwrap().checked_div(asset_eq_balance).unwrap())
+    // asset_balance * pool_token_balance / asset_eq_balance, rounded down
+    u64::try_from(
+        mul_div_x64(U256::from(asset_balance), U256::from(pool_token_balance), U256::from(asset_eq_balance))?
+    ).map_err(|_| MathError::Overflow)
+}
+
+pub fn calc_asset_amount_for_pool_tokens_up(pool_token_balance: u64, asset_balance: u64, asset_eq_balance: u64) -> Result<u64, MathError> {
+    if asset_eq_balance == asset_balance {
+        return Ok(pool_token_balance)
+    }
+
+    // asset_balance * pool_token_balance / asset_eq_balance, rounded up (used for deposits, to round in favour of the pool)
+    u64::try_from(
+        mul_div_x64_up(U256::from(asset_balance), U256::from(pool_token_balance), U256::from(asset_eq_balance))?
+    ).map_err(|_| MathError::Overflow)
+}
+
+
+
+// Fees *************************************************************************************************************************
+
+pub fn calc_fee_amount(amount: u64, fee_x64: u64) -> u64 {
+    // amount * fee, rounded down. Since fee_x64 < 2^64, the result is guaranteed to be <= amount
+
+    ((amount as u128 * fee_x64 as u128) >> 64) as u64     // No overflow guaranteed, u64 * u64 fits in u128
+}
+
+
+
+// Weights **********************************************************************************************************************
+
+/// Moves 'current_weight' linearly towards 'target_weight': current + (target - current)·elapsed/duration, where 'elapsed' is
+/// the time since the last update and 'duration' the time from the last update to the adjustment target. The weight change
+/// is rounded towards zero.
+pub fn calc_weight_adjustment(current_weight: u64, target_weight: u64, elapsed: u64, duration: u64) -> Result<u64, MathError> {
+    if duration == 0 {
+        return Err(MathError::DivisionByZero);
+    }
+
+    if elapsed >= duration {
+        return Ok(target_weight);
+    }
+
+    // No overflow guaranteed, u64 * u64 fits in u128, and the result is smaller than the weight difference
+    if target_weight >= current_weight {
+        Ok(current_weight + ((target_weight - current_weight) as u128 * elapsed as u128 / duration as u128) as u64)
+    }
+    else {
+        Ok(current_weight - ((current_weight - target_weight) as u128 * elapsed as u128 / duration as u128) as u64)
+    }
 }
 
 
 
 // Asset swaps ******************************************************************************************************************
+// NOTE: every rounding must favour the pool: the units computed for the assets sent to the pool are rounded down, and the
+// assets computed for the units received by the pool are rounded down. Every intermediate step is rounded accordingly.
 
 pub fn out_swap_x64(
     input: U256,                    // x
     source_asset_balance: U256,     // At
     source_asset_weight: U256,      // WA
     approx: bool
-) -> Result<U256> {
+) -> Result<U256, MathError> {
     // Computes the integral
     // int_{At}^{At+x} WA/w dw
 
     if approx {
-        return Ok(
-            div_x64(
-                source_asset_weight.checked_mul(input).unwrap().shl(64),
-                source_asset_balance.checked_add(input).unwrap().checked_mul(LN2_X64).unwrap()
-            ).unwrap()
+        // NOTE: LN2_X64 is rounded up
+        return div_x64(
+            source_asset_weight.checked_mul(input).ok_or(MathError::Overflow)?.shl(64),
+            source_asset_balance.checked_add(input).ok_or(MathError::Overflow)?.checked_mul(LN2_X64).ok_or(MathError::Overflow)?
         )
     }
 
     // WA * ln((At + x)/At)
-    Ok(source_asset_weight.checked_mul(
-        log2_x64(
-            div_x64(source_asset_balance.checked_add(input).unwrap(), source_asset_balance).unwrap()
-        ).unwrap()
-    ).unwrap())
+    source_asset_weight.checked_mul(
+        log2_x64_down(
+            div_x64(source_asset_balance.checked_add(input).ok_or(MathError::Overflow)?, source_asset_balance)?
+        )?
+    ).ok_or(MathError::Overflow)
 }
 
 
@@ -54,27 +106,29 @@ pub fn in_swap(
     target_asset_balance: U256,     // Bt
     target_asset_weight: U256,      // WB
     approx: bool
-) -> Result<U256> {
+) -> Result<U256, MathError> {
     // Solves the following integral for 'y'
     // int_{Bt-y}^{Bt} WB/w dW
 
     if approx {
-        let units_times_ln2_x64 = mul_x64(units_x64, LN2_X64).unwrap();
-
-        return Ok(
-            div_x64(
-                target_asset_balance.checked_mul(units_times_ln2_x64).unwrap(),
-                target_asset_weight.shl(64).checked_add(units_times_ln2_x64).unwrap()
-            ).unwrap()
+        // NOTE: LN2_X64 is rounded up, use its rounded down value instead
+        let units_times_ln2_x64 = mul_x64(units_x64, LN2_X64 - 1)?;
+
+        // NOTE: mul_div_x64 is used rather than div_x64, as the output must not be in x64 form
+        return mul_div_x64(
+            target_asset_balance,
+            units_times_ln2_x64,
+            target_asset_weight.shl(64).checked_add(units_times_ln2_x64).ok_or(MathError::Overflow)?
         )
     }
 
     // Bt * (1 - exp(-U/WB))
+    // NOTE: 1 - 2^(-U/WB) may be negative if U/WB is very small, as the upper bound of 2^(-U/WB) may be larger than 1
     Ok(target_asset_balance.checked_mul(
-        ONE_X64.checked_sub(
-            inv_pow2_x64(units_x64.checked_div(target_asset_weight).unwrap()).unwrap()
-        ).unwrap()
-    ).unwrap().shr(64u8))
+        ONE_X64.saturating_sub(
+            inv_pow2_x64_up(units_x64.checked_div(target_asset_weight).ok_or(MathError::DivisionByZero)?)?
+        )
+    ).ok_or(MathError::Overflow)?.shr(64u8))
 }
 
 pub fn full_swap(
@@ -84,44 +138,37 @@ pub fn full_swap(
     target_asset_balance: U256,
     target_asset_weight: U256,
     approx: bool
-) -> Result<U256> {
+) -> Result<U256, MathError> {
 
     // Bt * (1 - (At + input) / At) ^ (-WA/WB))       NOTE: (At + input) / At >= 1 as input > 0
 
     if source_asset_weight == target_asset_weight {
-        return Ok(target_asset_balance.checked_mul(input).unwrap().checked_div(source_asset_balance.checked_add(input).unwrap()).unwrap())
+        return mul_div_x64(target_asset_balance, input, source_asset_balance.checked_add(input).ok_or(MathError::Overflow)?)
     }
 
     if approx {
-        return Ok(
-            target_asset_balance.checked_mul(source_asset_weight).unwrap().checked_mul(input).unwrap().checked_div(
-                target_asset_weight.checked_mul(source_asset_balance).unwrap().checked_add(
-                    source_asset_weight.checked_add(target_asset_weight).unwrap().checked_mul(input).unwrap()
-                ).unwrap()
-            ).unwrap()
-        )
+        return target_asset_balance.checked_mul(source_asset_weight).ok_or(MathError::Overflow)?.checked_mul(input).ok_or(MathError::Overflow)?.checked_div(
+            target_asset_weight.checked_mul(source_asset_balance).ok_or(MathError::Overflow)?.checked_add(
+                source_asset_weight.checked_add(target_asset_weight).ok_or(MathError::Overflow)?.checked_mul(input).ok_or(MathError::Overflow)?
+            ).ok_or(MathError::Overflow)?
+        ).ok_or(MathError::DivisionByZero)
     }
 
+    // NOTE: both the base and the exponent are rounded down, hence the upper bound of the power is larger than the exact one
     Ok(target_asset_balance.checked_mul( 
-        ONE_X64.checked_sub(
-            inv_pow_x64(
-                div_x64(source_asset_balance.checked_add(input).unwrap(), source_asset_balance).unwrap(),
+        ONE_X64.saturating_sub(
+            inv_pow_x64_up(
+                div_x64(source_asset_balance.checked_add(input).ok_or(MathError::Overflow)?, source_asset_balance)?,
                 div_x64(
                     source_asset_weight,
                     target_asset_weight
-                ).unwrap()
-            ).unwrap()
-        ).unwrap()
-    ).unwrap().shr(64u8))
+                )?
+            )?
+        )
+    ).ok_or(MathError::Overflow)?.shr(64u8))
 }
 
 
-#[error_code]
-pub enum IntegralCalculationErrorCode {
-    #[msg("Arithmetic Error. Possible overflow/underflow.")]
-    ArithmeticError,
-}
-
 
 
 // Liquidity swaps **************************************************************************************************************
@@ -130,16 +177,16 @@ pub fn calc_out_liquidity_swap_x64(
     input_liquidity: U256,          // x
     source_asset_eq_balance: U256,  // A0
     source_asset_weight: U256       // WA
-) -> Result<U256> {
+) -> Result<U256, MathError> {
     // Computes the integral
     // int_{At}^{At+x} WA/w dw
 
     // WA * ln((At + x)/At)
-    Ok(source_asset_weight.checked_mul(
-        log2_x64(
-            div_x64(source_asset_eq_balance, source_asset_eq_balance.checked_sub(input_liquidity).unwrap()).unwrap()
-        ).unwrap()
-    ).unwrap())
+    source_asset_weight.checked_mul(
+        log2_x64_down(
+            div_x64(source_asset_eq_balance, source_asset_eq_balance.checked_sub(input_liquidity).ok_or(MathError::Underflow)?)?
+        )?
+    ).ok_or(MathError::Overflow)
 }
 
 
@@ -147,14 +194,15 @@ pub fn calc_in_liquidity_swap(
     liquidity_units_x64: U256,       // U
     target_asset_eq_balance: U256,   // B0
     target_assets_aggr_weight: U256  // W_SUM
-) -> Result<U256> {
+) -> Result<U256, MathError> {
     // Solves the following integral for 'y'
     // int_{Bt-y}^{Bt} W_SUM/w dW
 
     // Bt * (2^(U/W_SUM)-1)
+    // NOTE: 2^(U/W_SUM) - 1 may be negative if U/W_SUM is very small, as the lower bound of 2^(U/W_SUM) may be smaller than 1
     Ok(target_asset_eq_balance.checked_mul(
-        pow2_x64(
-            liquidity_units_x64.checked_div(target_assets_aggr_weight).unwrap()     // Weight is an integer for this case, can safely >> 64
-        ).unwrap().checked_sub(ONE_X64).unwrap()
-    ).unwrap().shr(64u8))
+        pow2_x64_down(
+            liquidity_units_x64.checked_div(target_assets_aggr_weight).ok_or(MathError::DivisionByZero)?     // Weight is an integer for this case, can safely >> 64
+        )?.saturating_sub(ONE_X64)
+    ).ok_or(MathError::Overflow)?.shr(64u8))
 }
@@ -0,0 +1,295 @@
+#[cfg(test)]
+mod test_calculation_helpers {
+    use std::ops::Shl;
+
+    use rug::Float;
+    use rug::ops::Pow;
+
+    use shared_lib::u256::U256;
+    use shared_lib::test::test_common::test_common::*;
+
+    use crate::calculation_helpers::*;
+
+    const SAMPLE_COUNT: usize = 20000;
+
+    // Maximum number of samples that may be skipped because the computation fails (i.e. too many units for the target pool,
+    // ~0.1% of the samples)
+    const MAX_SKIPPED_SAMPLES: usize = SAMPLE_COUNT / 100;
+
+    const PRECISION: u32 = 256;
+
+    // Samples are taken from [start, end)
+    fn rand_range(start: u64, end: u64) -> u64 {
+        start + rand::random::<u64>() % (end - start)
+    }
+
+    // Samples a balance, a weight and an input amount. The input share (input/balance) is sampled log-uniformly
+    // from [10^-6, 1), so that both small and large swaps are covered
+    fn sample_asset() -> (u64, u64, u64) {
+        let balance = rand_range(1_000, 1 << 60);
+        let weight  = rand_range(1, 1 << 16);
+        let input   = ((balance as f64) * 10_f64.powf(-6. * rand::random::<f64>())) as u64;
+
+        (balance, weight, input.max(1))
+    }
+
+    fn to_float(val: u64) -> Float {
+        Float::with_val(PRECISION, val)
+    }
+
+    fn uint_to_high_precision_float(val: U256) -> Float {
+        uint_x64_to_high_precision_float(&val.shl(64u8))
+    }
+
+    // Exact integrals **********************************************************************************************************
+
+    /// WA * log2((At + x)/At)
+    fn target_out_swap(input: u64, source_balance: u64, source_weight: u64) -> Float {
+        to_float(source_weight) * ((to_float(source_balance) + input) / source_balance).log2()
+    }
+
+    /// Bt * (1 - 2^(-U/WB))
+    fn target_in_swap(units: &Float, target_balance: u64, target_weight: u64) -> Float {
+        to_float(target_balance) * (1 - Float::with_val(PRECISION, 2).pow(-units.clone() / target_weight))
+    }
+
+    /// Bt * (1 - ((At + x)/At)^(-WA/WB))
+    fn target_full_swap(input: u64, source_balance: u64, source_weight: u64, target_balance: u64, target_weight: u64) -> Float {
+        let base = (to_float(source_balance) + input) / source_balance;
+        let exponent = to_float(source_weight) / target_weight;
+
+        to_float(target_balance) * (1 - base.pow(-exponent))
+    }
+
+    /// Error bound of the exact (i.e. non approximated) swaps: Bt * (2^-16 + WA/WB * 2^-28) + 1
+    /// NOTE: the error of the units computed by out_swap_x64 (~WA * 2^-29) is amplified by WB when computing the output
+    fn swap_error_bound(source_weight: u64, target_balance: u64, target_weight: u64) -> Float {
+        let weight_ratio = to_float(source_weight) / target_weight;
+
+        to_float(target_balance) * (Float::with_val(PRECISION, 2).pow(-16) + weight_ratio * Float::with_val(PRECISION, 2).pow(-28)) + 1
+    }
+
+
+
+    // Exact swaps **************************************************************************************************************
+    // The swaps must never return more than the exact integrals (i.e. round in favour of the pool), and must be accurate.
+
+    #[test]
+    fn test_out_swap() {
+
+        for _ in 0..SAMPLE_COUNT {
+            let (balance, weight, input) = sample_asset();
+
+            let units = uint_x64_to_high_precision_float(
+                &out_swap_x64(U256::from(input), U256::from(balance), U256::from(weight), false).unwrap()
+            );
+            let target = target_out_swap(input, balance, weight);
+
+            assert!(units <= target, "balance: {}, weight: {}, input: {}", balance, weight, input);
+            assert!(
+                target - units <= to_float(weight) * Float::with_val(PRECISION, 2).pow(-28),
+                "balance: {}, weight: {}, input: {}", balance, weight, input
+            );
+        }
+    }
+
+    #[test]
+    fn test_in_swap() {
+
+        let mut skipped = 0;
+        for _ in 0..SAMPLE_COUNT {
+            let (source_balance, source_weight, input) = sample_asset();
+            let (target_balance, target_weight, _) = sample_asset();
+
+            let units_x64 = out_swap_x64(U256::from(input), U256::from(source_balance), U256::from(source_weight), false).unwrap();
+
+            // NOTE: skip the swaps which cannot be computed (i.e. too many units for the target pool)
+            let output = match in_swap(units_x64, U256::from(target_balance), U256::from(target_weight), false) {
+                Ok(output) => uint_to_high_precision_float(output),
+                Err(_) => { skipped += 1; continue }
+            };
+            let target = target_in_swap(&uint_x64_to_high_precision_float(&units_x64), target_balance, target_weight);
+
+            assert!(output <= target, "units_x64: {}, balance: {}, weight: {}", units_x64, target_balance, target_weight);
+            assert!(
+                target - output <= to_float(target_balance) * Float::with_val(PRECISION, 2).pow(-16) + 1,
+                "units_x64: {}, balance: {}, weight: {}", units_x64, target_balance, target_weight
+            );
+        }
+
+        assert!(skipped <= MAX_SKIPPED_SAMPLES, "skipped samples: {}", skipped);
+    }
+
+    #[test]
+    fn test_full_swap() {
+
+        let mut skipped = 0;
+        for _ in 0..SAMPLE_COUNT {
+            let (source_balance, source_weight, input) = sample_asset();
+            let (target_balance, target_weight, _) = sample_asset();
+
+            let output = match full_swap(
+                U256::from(input),
+                U256::from(source_balance),
+                U256::from(source_weight),
+                U256::from(target_balance),
+                U256::from(target_weight),
+                false
+            ) {
+                Ok(output) => output,
+                Err(_) => { skipped += 1; continue }
+            };
+
+            let output_float = uint_to_high_precision_float(output);
+            let target = target_full_swap(input, source_balance, source_weight, target_balance, target_weight);
+            let error_bound = swap_error_bound(source_weight, target_balance, target_weight);
+
+            assert!(
+                output_float <= target,
+                "source balance: {}, source weight: {}, target balance: {}, target weight: {}, input: {}",
+                source_balance, source_weight, target_balance, target_weight, input
+            );
+            assert!(
+                target - &output_float <= error_bound,
+                "source balance: {}, source weight: {}, target balance: {}, target weight: {}, input: {}",
+                source_balance, source_weight, target_balance, target_weight, input
+            );
+
+            // full_swap must be consistent with the cross chain swaps (i.e. out_swap_x64 followed by in_swap)
+            let units_x64 = out_swap_x64(U256::from(input), U256::from(source_balance), U256::from(source_weight), false).unwrap();
+            let composed_output = match in_swap(units_x64, U256::from(target_balance), U256::from(target_weight), false) {
+                Ok(composed_output) => uint_to_high_precision_float(composed_output),
+                Err(_) => { skipped += 1; continue }
+            };
+
+            assert!(
+                (composed_output - output_float).abs() <= error_bound,
+                "source balance: {}, source weight: {}, target balance: {}, target weight: {}, input: {}",
+                source_balance, source_weight, target_balance, target_weight, input
+            );
+        }
+
+        assert!(skipped <= MAX_SKIPPED_SAMPLES, "skipped samples: {}", skipped);
+    }
+
+
+
+    // Approximated swaps *******************************************************************************************************
+    // The approximations must never return more than the exact integrals, and their relative error must be bounded by the
+    // size of the swap.
+
+    #[test]
+    fn test_out_swap_approx() {
+
+        for _ in 0..SAMPLE_COUNT {
+            let (balance, weight, input) = sample_asset();
+
+            let units = uint_x64_to_high_precision_float(
+                &out_swap_x64(U256::from(input), U256::from(balance), U256::from(weight), true).unwrap()
+            );
+            let target = target_out_swap(input, balance, weight);
+
+            // Relative error bounded by x/At
+            let error_bound = target.clone() * input / balance;
+
+            assert!(units <= target, "balance: {}, weight: {}, input: {}", balance, weight, input);
+            assert!(target - units <= error_bound, "balance: {}, weight: {}, input: {}", balance, weight, input);
+        }
+    }
+
+    #[test]
+    fn test_in_swap_approx() {
+
+        let mut skipped = 0;
+        for _ in 0..SAMPLE_COUNT {
+            let (source_balance, source_weight, input) = sample_asset();
+            let (target_balance, target_weight, _) = sample_asset();
+
+            let units_x64 = out_swap_x64(U256::from(input), U256::from(source_balance), U256::from(source_weight), false).unwrap();
+
+            let output = match in_swap(units_x64, U256::from(target_balance), U256::from(target_weight), true) {
+                Ok(output) => uint_to_high_precision_float(output),
+                Err(_) => { skipped += 1; continue }
+            };
+            let units = uint_x64_to_high_precision_float(&units_x64);
+            let target = target_in_swap(&units, target_balance, target_weight);
+
+            // Relative error bounded by U*ln(2)/WB, plus the rounding error
+            let error_bound = target.clone() * units * Float::with_val(PRECISION, 2).ln() / target_weight
+                + to_float(target_balance) * Float::with_val(PRECISION, 2).pow(-16) + 1;
+
+            assert!(output <= target, "units_x64: {}, balance: {}, weight: {}", units_x64, target_balance, target_weight);
+            assert!(target - output <= error_bound, "units_x64: {}, balance: {}, weight: {}", units_x64, target_balance, target_weight);
+        }
+
+        assert!(skipped <= MAX_SKIPPED_SAMPLES, "skipped samples: {}", skipped);
+    }
+
+    #[test]
+    fn test_full_swap_approx() {
+
+        let mut skipped = 0;
+        for _ in 0..SAMPLE_COUNT {
+            let (source_balance, source_weight, input) = sample_asset();
+            let (target_balance, target_weight, _) = sample_asset();
+
+            let output = match full_swap(
+                U256::from(input),
+                U256::from(source_balance),
+                U256::from(source_weight),
+                U256::from(target_balance),
+                U256::from(target_weight),
+                true
+            ) {
+                Ok(output) => uint_to_high_precision_float(output),
+                Err(_) => { skipped += 1; continue }
+            };
+            let target = target_full_swap(input, source_balance, source_weight, target_balance, target_weight);
+
+            // Relative error bounded by (1 + WA/WB) * x/At, plus the rounding error
+            let error_bound = target.clone() * (1 + to_float(source_weight) / target_weight) * input / source_balance + 2;
+
+            assert!(
+                output <= target,
+                "source balance: {}, source weight: {}, target balance: {}, target weight: {}, input: {}",
+                source_balance, source_weight, target_balance, target_weight, input
+            );
+            assert!(
+                target - output <= error_bound,
+                "source balance: {}, source weight: {}, target balance: {}, target weight: {}, input: {}",
+                source_balance, source_weight, target_balance, target_weight, input
+            );
+        }
+
+        assert!(skipped <= MAX_SKIPPED_SAMPLES, "skipped samples: {}", skipped);
+    }
+
+
+
+    // Liquidity swaps **********************************************************************************************************
+
+    #[test]
+    fn test_liquidity_swaps() {
+
+        for _ in 0..SAMPLE_COUNT {
+            let (eq_balance, weight, liquidity) = sample_asset();
+            if liquidity >= eq_balance { continue }
+
+            // WA * log2(A0/(A0 - x))
+            let units_x64 = calc_out_liquidity_swap_x64(U256::from(liquidity), U256::from(eq_balance), U256::from(weight)).unwrap();
+            let target_units = to_float(weight) * (to_float(eq_balance) / (eq_balance - liquidity)).log2();
+
+            let units = uint_x64_to_high_precision_float(&units_x64);
+            assert!(units <= target_units, "eq balance: {}, weight: {}, liquidity: {}", eq_balance, weight, liquidity);
+
+            // B0 * (2^(U/WB) - 1)
+            let output = uint_to_high_precision_float(
+                calc_in_liquidity_swap(units_x64, U256::from(eq_balance), U256::from(weight)).unwrap()
+            );
+            let target_output = to_float(eq_balance) * (Float::with_val(PRECISION, 2).pow(units / weight) - 1);
+
+            assert!(output <= target_output, "eq balance: {}, weight: {}, units_x64: {}", eq_balance, weight, units_x64);
+        }
+    }
+
+}
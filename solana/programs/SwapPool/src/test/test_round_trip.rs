@@ -0,0 +1,152 @@
+#[cfg(test)]
+mod test_round_trip {
+    use shared_lib::u256::U256;
+
+    use crate::calculation_helpers::*;
+
+    const SAMPLE_COUNT: usize = 20000;
+
+    // Samples are taken from [start, end)
+    fn rand_range(start: u64, end: u64) -> u64 {
+        start + rand::random::<u64>() % (end - start)
+    }
+
+    // Samples a balance, a weight and an input amount (of up to 'max_input_share' times the balance)
+    fn sample_asset(max_input_share: u64) -> (u64, u64, u64) {
+        let balance = rand_range(1_000, 1 << 60);
+        let weight  = rand_range(1, 1 << 16);
+        let input   = rand_range(1, balance / max_input_share + 2);
+
+        (balance, weight, input)
+    }
+
+
+
+    // Local round trips ********************************************************************************************************
+    // Swapping assets in and immediately swapping them back out must never return more than what was put in.
+
+    #[test]
+    fn test_out_in_swap_round_trip() {
+
+        for approx in [false, true] {
+            for _ in 0..SAMPLE_COUNT {
+                let (balance, weight, input) = sample_asset(1);
+
+                let units_x64 = out_swap_x64(U256::from(input), U256::from(balance), U256::from(weight), approx).unwrap();
+
+                let output = in_swap(units_x64, U256::from(balance) + input, U256::from(weight), approx).unwrap();
+
+                assert!(output <= U256::from(input), "approx: {}, balance: {}, weight: {}, input: {}", approx, balance, weight, input);
+            }
+        }
+    }
+
+    #[test]
+    fn test_full_swap_round_trip() {
+
+        for approx in [false, true] {
+            for _ in 0..SAMPLE_COUNT {
+                let (source_balance, source_weight, input) = sample_asset(1);
+                let (target_balance, target_weight, _) = sample_asset(1);
+
+                // NOTE: skip the swaps which cannot be computed (e.g. too large input compared to the pool balance)
+                let output = match full_swap(
+                    U256::from(input),
+                    U256::from(source_balance),
+                    U256::from(source_weight),
+                    U256::from(target_balance),
+                    U256::from(target_weight),
+                    approx
+                ) {
+                    Ok(output) => output,
+                    Err(_) => continue
+                };
+
+                if output.is_zero() { continue }
+
+                let round_trip_output = match full_swap(
+                    output,
+                    U256::from(target_balance) - output,
+                    U256::from(target_weight),
+                    U256::from(source_balance) + input,
+                    U256::from(source_weight),
+                    approx
+                ) {
+                    Ok(round_trip_output) => round_trip_output,
+                    Err(_) => continue
+                };
+
+                assert!(
+                    round_trip_output <= U256::from(input),
+                    "approx: {}, source balance: {}, source weight: {}, target balance: {}, target weight: {}, input: {}",
+                    approx, source_balance, source_weight, target_balance, target_weight, input
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_liquidity_swap_round_trip() {
+
+        for _ in 0..SAMPLE_COUNT {
+            let (eq_balance, weight, liquidity) = sample_asset(2);
+            if liquidity >= eq_balance { continue }
+
+            let units_x64 = calc_out_liquidity_swap_x64(U256::from(liquidity), U256::from(eq_balance), U256::from(weight)).unwrap();
+
+            let output = calc_in_liquidity_swap(units_x64, U256::from(eq_balance - liquidity), U256::from(weight)).unwrap();
+
+            assert!(output <= U256::from(liquidity), "eq balance: {}, weight: {}, liquidity: {}", eq_balance, weight, liquidity);
+        }
+    }
+
+
+    // Deposit round trips ******************************************************************************************************
+    // Depositing and immediately withdrawing the same pool tokens must never return more than what was put in.
+    // NOTE: the computations mirror the ones of the 'deposit' and 'withdraw' instructions.
+
+    #[test]
+    fn test_deposit_withdraw_round_trip() {
+
+        for _ in 0..SAMPLE_COUNT {
+            let pool_tokens_supply = rand_range(1_000, 1 << 40);
+            let asset_eq_balance   = rand_range(1_000, 1 << 40);
+            let asset_balance      = rand_range(asset_eq_balance / 2 + 1, asset_eq_balance * 2);
+            let pool_tokens        = rand_range(1, pool_tokens_supply);
+
+            // Deposit
+            let deposit_pool_tokens_for_asset = pool_tokens * asset_eq_balance / pool_tokens_supply;
+            let deposited_amount = calc_asset_amount_for_pool_tokens_up(
+                deposit_pool_tokens_for_asset,
+                asset_balance,
+                asset_eq_balance
+            ).unwrap();
+
+            // The deposited amount must cover the exact (non rounded) value of the pool tokens
+            assert!(
+                deposited_amount as u128 * asset_eq_balance as u128 >= asset_balance as u128 * deposit_pool_tokens_for_asset as u128,
+                "pool tokens supply: {}, eq balance: {}, balance: {}, pool tokens: {}",
+                pool_tokens_supply, asset_eq_balance, asset_balance, pool_tokens
+            );
+
+            let new_asset_eq_balance   = asset_eq_balance + deposit_pool_tokens_for_asset;
+            let new_asset_balance      = asset_balance + deposited_amount;
+            let new_pool_tokens_supply = pool_tokens_supply + pool_tokens;
+
+            // Withdraw
+            let withdraw_pool_tokens_for_asset = pool_tokens * new_asset_eq_balance / new_pool_tokens_supply;
+            let withdrawn_amount = calc_asset_amount_for_pool_tokens(
+                withdraw_pool_tokens_for_asset,
+                new_asset_balance,
+                new_asset_eq_balance
+            ).unwrap();
+
+            assert!(
+                withdrawn_amount <= deposited_amount,
+                "pool tokens supply: {}, eq balance: {}, balance: {}, pool tokens: {}",
+                pool_tokens_supply, asset_eq_balance, asset_balance, pool_tokens
+            );
+        }
+    }
+
+}
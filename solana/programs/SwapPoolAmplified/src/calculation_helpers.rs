@@ -1,50 +1,134 @@
 use std::ops::{Shr, Shl};
 
-use anchor_lang::prelude::*;
+use shared_lib::math_error::MathError;
 use shared_lib::u256::U256;
-use shared_lib::fixed_point_math_x64::{div_x64, pow_x64, inv_pow_x64};
+use shared_lib::i256::I256;
+use shared_lib::fixed_point_math_x64::{div_x64, mul_x64, mul_div_x64, mul_div_x64_up, pow_x64, pow_x64_down, pow_x64_up, inv_pow_x64_up, inv_pow2_x64};
 
 const ONE_X64: U256 = U256([0, 1, 0, 0]);
-const ONE_X128: U256 = U256([0, 0, 1, 0]);
 
 
 
 // Deposits and Withdrawals *****************************************************************************************************
 
-pub fn calc_asset_amount_for_pool_tokens(pool_token_balance: u64, asset_balance: u64, asset_eq_balance: u64) -> Result<u64> {
+pub fn calc_asset_amount_for_pool_tokens(pool_token_balance: u64, asset_balance: u64, asset_eq_balance: u64) -> Result<u64, MathError> {
     if asset_eq_balance == asset_balance {
         return Ok(pool_token_balance)
     }
 
-    Ok(asset_balance.checked_mul(pool_token_balance).unwrap().checked_div(asset_eq_balance).unwrap())
+    // asset_balance * pool_token_balance / asset_eq_balance, rounded down
+    u64::try_from(
+        mul_div_x64(U256::from(asset_balance), U256::from(pool_token_balance), U256::from(asset_eq_balance))?
+    ).map_err(|_| MathError::Overflow)
+}
+
+pub fn calc_asset_amount_for_pool_tokens_up(pool_token_balance: u64, asset_balance: u64, asset_eq_balance: u64) -> Result<u64, MathError> {
+    if asset_eq_balance == asset_balance {
+        return Ok(pool_token_balance)
+    }
+
+    // asset_balance * pool_token_balance / asset_eq_balance, rounded up (used for deposits, to round in favour of the pool)
+    u64::try_from(
+        mul_div_x64_up(U256::from(asset_balance), U256::from(pool_token_balance), U256::from(asset_eq_balance))?
+    ).map_err(|_| MathError::Overflow)
+}
+
+
+
+// Fees *************************************************************************************************************************
+
+pub fn calc_fee_amount(amount: u64, fee_x64: u64) -> u64 {
+    // amount * fee, rounded down. Since fee_x64 < 2^64, the result is guaranteed to be <= amount
+
+    ((amount as u128 * fee_x64 as u128) >> 64) as u64     // No overflow guaranteed, u64 * u64 fits in u128
+}
+
+
+
+// Amplification ****************************************************************************************************************
+
+/// Moves 'current_amplification_x64' linearly towards 'target_amplification_x64': current + (target - current)·elapsed/duration,
+/// where 'elapsed' is the time since the last update and 'duration' the time from the last update to the adjustment target.
+/// The amplification change is rounded towards zero.
+pub fn calc_amplification_adjustment(
+    current_amplification_x64: U256,
+    target_amplification_x64: U256,
+    elapsed: u64,
+    duration: u64
+) -> Result<U256, MathError> {
+    if duration == 0 {
+        return Err(MathError::DivisionByZero);
+    }
+
+    if elapsed >= duration {
+        return Ok(target_amplification_x64);
+    }
+
+    // No overflow guaranteed, the amplification is < 2^64 and 'elapsed' < 2^64
+    if target_amplification_x64 >= current_amplification_x64 {
+        Ok(current_amplification_x64 + (target_amplification_x64 - current_amplification_x64) * U256::from(elapsed) / U256::from(duration))
+    }
+    else {
+        Ok(current_amplification_x64 - (current_amplification_x64 - target_amplification_x64) * U256::from(elapsed) / U256::from(duration))
+    }
+}
+
+/// Computes the units inflow amplification (1 - 2^(amp - 1)) and the units inflow limit of a pool with the given asset weights
+/// and balances: units_inflow_amplification · Σ weight·balance^(1-amp).
+pub fn calc_max_units_inflow_x64(
+    asset_weights: &[u64],
+    asset_balances: &[u64],
+    amplification_x64: U256
+) -> Result<(U256, U256), MathError> {
+    let one_minus_amp_x64 = ONE_X64.checked_sub(amplification_x64).ok_or(MathError::Underflow)?;
+
+    let mut max_units_inflow_x64 = U256::zero();
+    for (asset_weight, asset_balance) in asset_weights.iter().zip(asset_balances.iter()) {
+        max_units_inflow_x64 = max_units_inflow_x64.checked_add(
+            U256::from(*asset_weight).checked_mul(
+                pow_x64(U256::from(*asset_balance).shl(64), one_minus_amp_x64)?
+            ).ok_or(MathError::Overflow)?
+        ).ok_or(MathError::Overflow)?;
+    }
+
+    let units_inflow_amplification_x64 = ONE_X64.checked_sub(inv_pow2_x64(one_minus_amp_x64)?).ok_or(MathError::Underflow)?;
+
+    Ok((
+        units_inflow_amplification_x64,
+        mul_x64(units_inflow_amplification_x64, max_units_inflow_x64)?
+    ))
 }
 
 
 
 // Asset swaps ******************************************************************************************************************
+// NOTE: every rounding must favour the pool: the units computed for the assets sent to the pool are rounded down, and the
+// assets computed for the units received by the pool are rounded down. Every intermediate step is rounded accordingly.
 
 pub fn out_swap_x64(
     input: U256,                    // x
     source_asset_balance: U256,     // At
     source_asset_weight: U256,      // WA
     amplification_x64: U256         // k
-) -> Result<U256> {
+) -> Result<U256, MathError> {
     // Computes the integral
     // int_{At}^{At+x} WA/w dw
 
     let input_x64 = input.shl(64u8);
     let source_asset_balance_x64 = source_asset_balance.shl(64u8);
 
-    let one_minus_amp_x64 = ONE_X64.checked_sub(amplification_x64).unwrap();
+    let one_minus_amp_x64 = ONE_X64.checked_sub(amplification_x64).ok_or(MathError::Underflow)?;
     
-    Ok(source_asset_weight.checked_mul(
-        pow_x64(
-            source_asset_balance_x64.checked_add(input_x64).unwrap(), 
+    // WA * ((At + x)^(1-k) - At^(1-k))
+    // NOTE: the difference may be negative for very small inputs, as the bounds of both powers may overlap
+    source_asset_weight.checked_mul(
+        pow_x64_down(
+            source_asset_balance_x64.checked_add(input_x64).ok_or(MathError::Overflow)?, 
             one_minus_amp_x64
-        ).unwrap().checked_sub(
-            pow_x64(source_asset_balance_x64, one_minus_amp_x64).unwrap()
-        ).unwrap()
-    ).unwrap())
+        )?.saturating_sub(
+            pow_x64_up(source_asset_balance_x64, one_minus_amp_x64)?
+        )
+    ).ok_or(MathError::Overflow)
 
 }
 
@@ -54,22 +138,25 @@ pub fn in_swap(
     target_asset_balance: U256,     // Bt
     target_asset_weight: U256,      // WB
     amplification_x64: U256         // k
-) -> Result<U256> {
+) -> Result<U256, MathError> {
     // Solves the following integral for 'y'
     // int_{Bt-y}^{Bt} WB/w dW
 
-    let one_minus_amp_x64 = ONE_X64.checked_sub(amplification_x64).unwrap();
+    let one_minus_amp_x64 = ONE_X64.checked_sub(amplification_x64).ok_or(MathError::Underflow)?;
 
+    // NOTE: intermediate_x64/(intermediate_x64 - U) decreases with intermediate_x64, hence it is rounded up
     let intermediate_x64 = target_asset_weight.checked_mul(
-        pow_x64(target_asset_balance.shl(64u8), one_minus_amp_x64).unwrap()
-    ).unwrap();
+        pow_x64_up(target_asset_balance.shl(64u8), one_minus_amp_x64)?
+    ).ok_or(MathError::Overflow)?;
 
+    // Bt * (1 - (intermediate_x64/(intermediate_x64 - U))^(-1/(1-k)))
+    // NOTE: both the base and the exponent are rounded down, hence the upper bound of the power is larger than the exact one
     Ok(target_asset_balance.checked_mul(
-        ONE_X64.checked_sub(inv_pow_x64(
-            div_x64(intermediate_x64, intermediate_x64.checked_sub(units_x64).unwrap()).unwrap(),
-            div_x64(ONE_X64, one_minus_amp_x64).unwrap()
-        ).unwrap()).unwrap()
-    ).unwrap().shr(64u8))
+        ONE_X64.saturating_sub(inv_pow_x64_up(
+            div_x64(intermediate_x64, intermediate_x64.checked_sub(units_x64).ok_or(MathError::Underflow)?)?,
+            div_x64(ONE_X64, one_minus_amp_x64)?
+        )?)
+    ).ok_or(MathError::Overflow)?.shr(64u8))
 }
 
 pub fn full_swap(
@@ -79,42 +166,19 @@ pub fn full_swap(
     target_asset_balance: U256,
     target_asset_weight: U256,
     amplification_x64: U256
-) -> Result<U256> {
-
-    let input_x64 = input.shl(64u8);
-    let source_asset_balance_x64 = source_asset_balance.shl(64u8);
-
-    let one_minus_amp_x64 = ONE_X64 - amplification_x64;
-
-    let intermediate_x64 = target_asset_weight.checked_mul(
-        pow_x64(target_asset_balance.shl(64u8), one_minus_amp_x64).unwrap()
-    ).unwrap();
-    
-    Ok(target_asset_balance.checked_mul(
-        ONE_X64.checked_sub(inv_pow_x64(
-            div_x64(
-                intermediate_x64,
-                intermediate_x64.checked_sub(source_asset_weight.checked_mul(
-                    pow_x64(
-                        source_asset_balance_x64.checked_add(input_x64).unwrap(), 
-                        one_minus_amp_x64
-                    ).unwrap().checked_sub(
-                        pow_x64(source_asset_balance_x64, one_minus_amp_x64).unwrap()
-                    ).unwrap()
-                ).unwrap()).unwrap()
-            ).unwrap(),
-            div_x64(ONE_X64, one_minus_amp_x64).unwrap()
-        ).unwrap()).unwrap()
-    ).unwrap().shr(64u8))
+) -> Result<U256, MathError> {
+
+    // NOTE: computed as the composition of out_swap_x64 and in_swap, so that the rounding is exactly the same as for
+    // cross chain swaps
+    in_swap(
+        out_swap_x64(input, source_asset_balance, source_asset_weight, amplification_x64)?,
+        target_asset_balance,
+        target_asset_weight,
+        amplification_x64
+    )
 }
 
 
-#[error_code]
-pub enum IntegralCalculationErrorCode {
-    #[msg("Arithmetic Error. Possible overflow/underflow.")]
-    ArithmeticError,
-}
-
 
 
 // Liquidity swaps **************************************************************************************************************
@@ -124,26 +188,28 @@ pub fn calc_out_liquidity_swap_x64(
     source_asset_eq_balance: U256,  // A0
     source_asset_weight: U256,      // WA
     amplification_x64: U256         // k
-) -> Result<U256> {
+) -> Result<U256, MathError> {
     // Computes the integral
     // int_{At}^{At+x} WA/w dw
 
     let input_liquidity_x64 = input_liquidity.shl(64u8);                    // Safe, as input_liquidity comes from a u64 number
     let source_asset_eq_balance_x64 = source_asset_eq_balance.shl(64u8);    // Safe, as source_asset_eq_balance comes from a u64 number
 
-    let one_minus_amp_x64 = ONE_X64.checked_sub(amplification_x64).unwrap();
+    let one_minus_amp_x64 = ONE_X64.checked_sub(amplification_x64).ok_or(MathError::Underflow)?;
     
-    Ok(source_asset_weight.checked_mul(
-        pow_x64(
+    // WA * (A0^(1-k) - (A0 - x)^(1-k))
+    // NOTE: the difference may be negative for very small inputs, as the bounds of both powers may overlap
+    source_asset_weight.checked_mul(
+        pow_x64_down(
             source_asset_eq_balance_x64, 
             one_minus_amp_x64
-        ).unwrap().checked_sub(
-            pow_x64(
-                source_asset_eq_balance_x64.checked_sub(input_liquidity_x64).unwrap(), 
+        )?.saturating_sub(
+            pow_x64_up(
+                source_asset_eq_balance_x64.checked_sub(input_liquidity_x64).ok_or(MathError::Underflow)?, 
                 one_minus_amp_x64
-            ).unwrap()
-        ).unwrap()
-    ).unwrap())
+            )?
+        )
+    ).ok_or(MathError::Overflow)
 
 }
 
@@ -153,20 +219,116 @@ pub fn calc_in_liquidity_swap(
     target_asset_eq_balance: U256,       // B0
     target_assets_aggr_weight_x64: U256, // W_SUM
     amplification_x64: U256              // k
-) -> Result<U256> {
+) -> Result<U256, MathError> {
     // Solves the following integral for 'y'
     // int_{Bt-y}^{Bt} W_SUM/w dW
 
+    // B0 * (((W_SUM + U)/W_SUM)^(1/(1-k)) - 1)
+    // NOTE: both the base and the exponent are rounded down. The result may be negative for very small inputs, as the lower
+    // bound of the power may be smaller than 1
     Ok(target_asset_eq_balance.checked_mul(
-        pow_x64(
+        pow_x64_down(
             div_x64(
-                target_assets_aggr_weight_x64.checked_add(liquidity_units_x64).unwrap(),
+                target_assets_aggr_weight_x64.checked_add(liquidity_units_x64).ok_or(MathError::Overflow)?,
                 target_assets_aggr_weight_x64
-            ).unwrap(),
+            )?,
             div_x64(
                 ONE_X64, 
-                ONE_X64.checked_sub(amplification_x64).unwrap()
-            ).unwrap()
-        ).unwrap().checked_sub(ONE_X64).unwrap()
-    ).unwrap().shr(64u8))
+                ONE_X64.checked_sub(amplification_x64).ok_or(MathError::Underflow)?
+            )?
+        )?.saturating_sub(ONE_X64)
+    ).ok_or(MathError::Overflow)?.shr(64u8))
+}
+
+
+
+
+// Fee distribution *************************************************************************************************************
+
+/// Computes the units implied by the pool balances with respect to the eq balances, i.e. sum of WA * (At^(1-k) - A0^(1-k)).
+/// Every term is computed as a liquidity swap from the larger of both balances, and is thus rounded down.
+pub fn calc_outstanding_units_x64(
+    asset_balances: &[u64],
+    asset_eq_balances: &[u64],
+    asset_weights: &[u64],
+    amplification_x64: U256
+) -> Result<I256, MathError> {
+
+    let mut outstanding_units_x64 = I256::ZERO;
+
+    for ((&asset_balance, &asset_eq_balance), &asset_weight) in asset_balances.iter().zip(asset_eq_balances).zip(asset_weights) {
+
+        if asset_balance > asset_eq_balance {
+            outstanding_units_x64 = outstanding_units_x64.checked_add(I256::from_unsigned(calc_out_liquidity_swap_x64(
+                U256::from(asset_balance - asset_eq_balance),
+                U256::from(asset_balance),
+                U256::from(asset_weight),
+                amplification_x64
+            )?)?)?;
+        }
+        else {
+            outstanding_units_x64 = outstanding_units_x64.checked_sub(I256::from_unsigned(calc_out_liquidity_swap_x64(
+                U256::from(asset_eq_balance - asset_balance),
+                U256::from(asset_eq_balance),
+                U256::from(asset_weight),
+                amplification_x64
+            )?)?)?;
+        }
+    }
+
+    Ok(outstanding_units_x64)
+}
+
+/// Computes the eq balances after distributing the pool fees to the liquidity providers. The fees are the units implied by
+/// the pool balances (see calc_outstanding_units_x64) that are not accounted for by the unit tracker. They are valued in
+/// pool tokens (as an 'in liquidity swap'), and every eq balance is increased by its share of these pool tokens.
+/// NOTE: fails if the balances do not hold any fees (i.e. the unaccounted units are negative).
+pub fn calc_fee_distribution_eq_balances(
+    unit_tracker_x64: I256,
+    asset_balances: &[u64],
+    asset_eq_balances: &[u64],
+    asset_weights: &[u64],
+    pool_tokens_supply: u64,
+    amplification_x64: U256
+) -> Result<Vec<u64>, MathError> {
+
+    let one_minus_amp_x64 = ONE_X64.checked_sub(amplification_x64).ok_or(MathError::Underflow)?;
+
+    let outstanding_units_x64 = calc_outstanding_units_x64(asset_balances, asset_eq_balances, asset_weights, amplification_x64)?;
+
+    let mut aggregate_weight_x64 = U256::zero();
+    for (&asset_eq_balance, &asset_weight) in asset_eq_balances.iter().zip(asset_weights) {
+
+        // NOTE: rounded up, as a larger aggregate weight yields less pool tokens
+        aggregate_weight_x64 = aggregate_weight_x64.checked_add(
+            U256::from(asset_weight).checked_mul(
+                pow_x64_up(U256::from(asset_eq_balance).shl(64u8), one_minus_amp_x64)?    // No overflow guaranteed, going from u64 to u256
+            ).ok_or(MathError::Overflow)?
+        ).ok_or(MathError::Overflow)?;
+    }
+
+    let fee_units_x64 = outstanding_units_x64.checked_sub(unit_tracker_x64)?.to_unsigned()?;
+
+    // Pool tokens corresponding to the first asset of the pool
+    let asset_0_pool_tokens = calc_in_liquidity_swap(
+        fee_units_x64,
+        U256::from(asset_eq_balances[0]),
+        aggregate_weight_x64,
+        amplification_x64
+    )?;
+
+    // Total pool tokens, derived from the ones of the first asset
+    let total_pool_tokens = asset_0_pool_tokens
+        .checked_mul(U256::from(pool_tokens_supply)).ok_or(MathError::Overflow)?
+        .checked_div(U256::from(asset_eq_balances[0])).ok_or(MathError::DivisionByZero)?;
+
+    // A0 + total_pool_tokens * A0 / pool_tokens_supply (rounded down)
+    asset_eq_balances.iter().map(|&asset_eq_balance| {
+        let increase = total_pool_tokens
+            .checked_mul(U256::from(asset_eq_balance)).ok_or(MathError::Overflow)?
+            .checked_div(U256::from(pool_tokens_supply)).ok_or(MathError::DivisionByZero)?;
+
+        u64::try_from(U256::from(asset_eq_balance).checked_add(increase).ok_or(MathError::Overflow)?)
+            .map_err(|_| MathError::Overflow)
+    }).collect()
 }
@@ -1,32 +1,48 @@
 use std::convert::TryInto;
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::program::{get_return_data, set_return_data};
 use anchor_spl::token::{Mint, Token, TokenAccount};
 
 use ibc_interface;
 use ibc_interface::cpi::accounts as interface_accounts;
 use ibc_interface::program::IbcInterface;
 
+use shared_lib::math_error::MathError;
 use shared_lib::u256::U256;
-use shared_lib::fixed_point_math_x64::{mul_x64, pow_x64, inv_pow2_x64};
+use shared_lib::i256::I256;
+use shared_lib::fixed_point_math_x64::{mul_x64, pow_x64, pow_x64_up};
 use token_utils::create_pda_token_account;
 
 use std::ops::Shl;
 
 pub mod calculation_helpers;
 
+#[cfg(test)]
+pub mod test {
+    pub mod test_round_trip;
+    pub mod test_calculation_helpers;
+}
+
 const ONE_X64: U256 = U256([0, 1, 0, 0]);
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnA");
 
-const NUMASSETS: usize = 3;
+const MAX_ASSETS: usize = 16;    // NOTE: initialize/deposit/withdraw take 3 accounts per asset, the transaction size limit may further restrict this
 
 const DECAYRATE: u64 = 60*60*24;
 
+const MIN_ADJUSTMENT_TIME: u64 = 60*60*24*7;     // Amplification adjustments must take at least 7 days
+const MAX_ADJUSTMENT_TIME: u64 = 60*60*24*365;   // and at most 365 days
+
+const MAX_GOVERNANCE_FEE_X64: u64 = 13835058055282163712;  // 75% (0.75 · 2^64)
+
 const POOL_ASSET_WALLET_SEED : &[u8] = b"poolAsset";
 const POOL_TOKEN_MINT_SEED   : &[u8] = b"poolMint";
 const POOL_AUTHORITY_SEED    : &[u8] = b"poolAuth";
 const POOL_ESCROW_SEED       : &[u8] = b"poolEscrow";
+const POOL_LIQUIDITY_ESCROW_SEED : &[u8] = b"poolLiqEscrow";
 
 #[program]
 pub mod swap_pool_amplified {
@@ -36,26 +52,51 @@ pub mod swap_pool_amplified {
     pub fn initialize<'a, 'b, 'c, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, Initialize<'info>>,
         k: u64,
-        initial_asset_balances: [u64; NUMASSETS],
-        asset_weights: [u64; NUMASSETS]
+        initial_asset_balances: Vec<u64>,
+        asset_weights: Vec<u64>,
+        pool_fee_x64: u64,
+        governance_fee_x64: u64,
+        fee_administrator: Pubkey,
+        governance_fee_destination: Pubkey
     ) -> Result<()> {
 
+        if governance_fee_x64 > MAX_GOVERNANCE_FEE_X64 {
+            return Err(error!(ErrorCode::InvalidGovernanceFee));
+        }
+
         // ! Save the pubkey of setup_master to verify setup instructions (only allow setup_master to call setup instructions)
         ctx.accounts.swap_pool_state_account.setup_master = ctx.accounts.setup_master.key();
         ctx.accounts.swap_pool_state_account.dao_authority = ctx.accounts.dao_authority.key();
         ctx.accounts.swap_pool_state_account.amplification_x64 = (ONE_X64 / k).0;   // TODO Add serialisation/desarialisation to U256 struct, avoid having to explicitly extract U256 internal array
+        ctx.accounts.swap_pool_state_account.target_amplification_x64 = ctx.accounts.swap_pool_state_account.amplification_x64;
         ctx.accounts.swap_pool_state_account.authority_bump = ctx.bumps.get("swap_pool_authority").unwrap().to_owned();
         ctx.accounts.swap_pool_state_account.token_mint_bump = ctx.bumps.get("swap_pool_token_mint").unwrap().to_owned();
 
-        let asset_count = match initial_asset_balances.iter().position(|balance| balance == &0u64) {
-            Some(count) => count,
-            None => NUMASSETS
-        };
+        // Fees
+        ctx.accounts.swap_pool_state_account.fee_administrator          = fee_administrator;
+        ctx.accounts.swap_pool_state_account.pool_fee_x64               = pool_fee_x64;
+        ctx.accounts.swap_pool_state_account.governance_fee_x64         = governance_fee_x64;
+        ctx.accounts.swap_pool_state_account.governance_fee_destination = governance_fee_destination;
+
+        let asset_count = initial_asset_balances.len();
 
         if asset_count == 0 {
             return Err(error!(ErrorCode::NoAssetsProvided));
         }
 
+        if asset_count > MAX_ASSETS || asset_weights.len() != asset_count {
+            return Err(error!(ErrorCode::InvalidAssetCount));
+        }
+
+        // Zero balances or weights would leave the pool with zero denominators in the swap and deposit math
+        if initial_asset_balances.iter().any(|balance| *balance == 0) {
+            return Err(error!(ErrorCode::InvalidAssetBalance));
+        }
+
+        if asset_weights.iter().any(|weight| *weight == 0) {
+            return Err(error!(ErrorCode::InvalidAssetWeight));
+        }
+
         // TODO is the following check required?
         // Verify the count of passed remaining accounts
         // 3 Accounts given per added asset:
@@ -66,9 +107,6 @@ pub mod swap_pool_amplified {
             return Err(error!(ErrorCode::InvalidRemainingAccountsCount));
         }
 
-        let mut max_units_inflow_x64 = U256([0, 0, 0, 0]);
-        let one_minus_amp = ONE_X64 - U256(ctx.accounts.swap_pool_state_account.amplification_x64);
-
         for asset_index in 0..asset_count {
 
             // Get accounts from 'remaining accounts'
@@ -78,7 +116,7 @@ pub mod swap_pool_amplified {
             let depositor_asset_wallet = &ctx.remaining_accounts[asset_index*3+2];  // No need to verify, assets taken from here
             
             // Create the wallet to hold the pool assets
-            ctx.accounts.swap_pool_state_account.wallets_bumps[asset_index] = ctx.accounts.create_swap_pool_asset_wallet(
+            let wallet_bump = ctx.accounts.create_swap_pool_asset_wallet(
                 asset_mint.to_owned(),
                 swap_pool_asset_wallet.to_owned()
             ).unwrap();
@@ -92,27 +130,24 @@ pub mod swap_pool_amplified {
 
             // Save asset details
             let asset_weight = asset_weights[asset_index];
-            if asset_weight == 0 {
-                return Err(error!(ErrorCode::InvalidAssetWeight));
-            }
 
-            max_units_inflow_x64 += U256::from(asset_weight).checked_mul(
-                pow_x64(U256::from(initial_asset_balances[asset_index]).shl(64), one_minus_amp).unwrap()
-            ).unwrap();
-
-            ctx.accounts.swap_pool_state_account.pool_assets_mints[asset_index]       = asset_mint.key();
-            ctx.accounts.swap_pool_state_account.pool_assets_weights[asset_index]     = asset_weight;
-            ctx.accounts.swap_pool_state_account.pool_assets_eq_balances[asset_index] = initial_asset_balances[asset_index];
+            // NOTE: the asset vectors are empty on account initialization, and are filled here in asset order
+            ctx.accounts.swap_pool_state_account.pool_assets_mints.push(asset_mint.key());
+            ctx.accounts.swap_pool_state_account.pool_assets_weights.push(asset_weight);
+            ctx.accounts.swap_pool_state_account.pool_assets_eq_balances.push(initial_asset_balances[asset_index]);
+            ctx.accounts.swap_pool_state_account.escrowed_assets.push(0);
+            ctx.accounts.swap_pool_state_account.wallets_bumps.push(wallet_bump);
 
         }
 
-        let units_inflow_amplification_x64 = ONE_X64.checked_sub(inv_pow2_x64(one_minus_amp).unwrap()).unwrap();
+        let (units_inflow_amplification_x64, max_units_inflow_x64) = calculation_helpers::calc_max_units_inflow_x64(
+            &asset_weights,
+            &initial_asset_balances,
+            U256(ctx.accounts.swap_pool_state_account.amplification_x64)
+        ).map_err(ErrorCode::from)?;
 
-        ctx.accounts.swap_pool_state_account.units_inflow_amplification_x64 = units_inflow_amplification_x64.0;
-        ctx.accounts.swap_pool_state_account.max_units_inflow_x64 = mul_x64(
-            units_inflow_amplification_x64, 
-            max_units_inflow_x64
-        ).unwrap().0;
+        ctx.accounts.swap_pool_state_account.units_inflow_amplification_x64 = units_inflow_amplification_x64.into();
+        ctx.accounts.swap_pool_state_account.max_units_inflow_x64 = max_units_inflow_x64.into();
 
         // Mint pool tokens for the depositor
         ctx.accounts.mint_pool_tokens_for_depositor(
@@ -126,7 +161,8 @@ pub mod swap_pool_amplified {
         ctx: Context<CreateConnection>,
         chain: u64,                     // Used in the CreateConnection context
         pool: Pubkey,                   // Used in the CreateConnection context
-        interface_program: Pubkey       // target interface program
+        interface_program: Pubkey,      // target interface program
+        channel_id: [u8; 32]            // channel on which packets to the target pool are routed
     ) -> Result<()> {
 
         let ccsi_program = ctx.accounts.ibc_interface_program.to_account_info();
@@ -155,7 +191,45 @@ pub mod swap_pool_amplified {
             ccsi_swap_ctx,
             chain,
             pool,
-            interface_program
+            interface_program,
+            channel_id
+        ).unwrap();
+
+        Ok(())
+    }
+
+    pub fn set_connection_enabled(
+        ctx: Context<SetConnectionEnabled>,
+        chain: u64,
+        pool: Pubkey,
+        enabled: bool
+    ) -> Result<()> {
+
+        let ccsi_program = ctx.accounts.ibc_interface_program.to_account_info();
+
+        let ccsi_swap_accounts = interface_accounts::SetConnectionEnabled {
+            swap_pool_authority: ctx.accounts.swap_pool_authority.to_account_info(),
+            interface_state_account: ctx.accounts.interface_state_account.to_account_info(),
+            connection_state_account: ctx.accounts.connection_state_account.to_account_info()
+        };
+
+        let signer: &[&[&[u8]]] = &[&[
+            ctx.accounts.swap_pool_state_account.to_account_info().key.as_ref(),
+            &POOL_AUTHORITY_SEED[..],
+            &[ctx.accounts.swap_pool_state_account.authority_bump]   // PDA bump
+        ]];
+
+        let ccsi_swap_ctx = CpiContext::new_with_signer(
+            ccsi_program,
+            ccsi_swap_accounts,
+            signer
+        );
+
+        ibc_interface::cpi::set_connection_enabled(
+            ccsi_swap_ctx,
+            chain,
+            pool,
+            enabled
         ).unwrap();
 
         Ok(())
@@ -169,10 +243,17 @@ pub mod swap_pool_amplified {
         Ok(())
     }
 
-    pub fn finish_setup(ctx: Context<FinishSetup>) -> Result<()> {
+    /// Finishes the pool setup. If 'local_only' is set, the pool is set up as a local-only pool: it has no IBC interface, the
+    /// cross chain instructions are disabled, and no security limit is maintained.
+    pub fn finish_setup(ctx: Context<FinishSetup>, local_only: bool) -> Result<()> {
 
+        if local_only {
+            // Unlink the swap interface (if any), as it is not used
+            ctx.accounts.swap_pool_state_account.ibc_interface = Pubkey::default();
+            ctx.accounts.swap_pool_state_account.local_only = true;
+        }
         // Make sure the swap interface is linked
-        if ctx.accounts.swap_pool_state_account.ibc_interface.eq(&Pubkey::default()) {
+        else if ctx.accounts.swap_pool_state_account.ibc_interface.eq(&Pubkey::default()) {
             return Err(error!(ErrorCode::SwapInterfaceNotLinked));
         }
 
@@ -181,7 +262,113 @@ pub mod swap_pool_amplified {
         Ok(())
     }
 
-    //TODO add function to cancel SwapPool setup and recover rent?
+    /// Cancels the pool setup (before finish_setup): the pool tokens minted on initialize are burnt, the pool assets are
+    /// returned, and the pool asset wallets and state account are closed (their rent is refunded to the setup_master).
+    /// The depositor pool token wallet is closed as well (its rent is refunded to its authority).
+    /// NOTE: the pool token mint cannot be closed with the SPL Token program (it would require the Token-2022 mint close
+    /// authority extension), hence its rent is not recovered.
+    /// 2 Accounts must be given per pool asset as remaining accounts, in asset order:
+    ///      - Swap pool asset wallet
+    ///      - Destination asset wallet (where to return the assets)
+    pub fn cancel_setup<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CancelSetup<'info>>
+    ) -> Result<()> {
+
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+        let asset_count = swap_pool_state_account.pool_assets_mints.len();
+
+        if ctx.remaining_accounts.len() != 2*asset_count {
+            return Err(error!(ErrorCode::InvalidRemainingAccountsCount));
+        }
+
+        // The assets can only be returned if no swaps (nor liquidity swaps) are pending
+        let pending_swaps = swap_pool_state_account.escrowed_assets.iter().any(|escrowed_amount| *escrowed_amount != 0);
+        if pending_swaps || swap_pool_state_account.escrowed_pool_tokens != 0 {
+            return Err(error!(ErrorCode::PendingSwapEscrows));
+        }
+
+        // Make sure that nobody else holds pool tokens (i.e. no deposits have been made), and burn them
+        let pool_tokens_supply = ctx.accounts.swap_pool_token_mint.supply;
+        if ctx.accounts.depositor_pool_token_wallet.amount != pool_tokens_supply {
+            return Err(error!(ErrorCode::PoolTokensOutstanding));
+        }
+
+        token_utils::burn_tokens(
+            pool_tokens_supply,
+            ctx.accounts.depositor_pool_token_wallet.to_account_info(),
+            ctx.accounts.swap_pool_token_mint.to_account_info(),
+            ctx.accounts.depositor_pool_token_wallet_authority.to_account_info(),
+            ctx.accounts.token_program.to_account_info()
+        );
+
+        // Close the (now empty) depositor pool token wallet, as the pool tokens cannot be minted anymore
+        token_utils::close_token_account(
+            ctx.accounts.depositor_pool_token_wallet.to_account_info(),
+            ctx.accounts.depositor_pool_token_wallet_authority.to_account_info(),
+            ctx.accounts.depositor_pool_token_wallet_authority.to_account_info(),
+            ctx.accounts.token_program.to_account_info()
+        );
+
+        let mut returned_asset_amounts: Vec<u64> = vec![0; asset_count];
+
+        for asset_index in 0..asset_count {
+
+            let swap_pool_asset_wallet   = &ctx.remaining_accounts[asset_index*2];
+            let destination_asset_wallet = &ctx.remaining_accounts[asset_index*2+1];  // No need to verify, assets sent here
+
+            // Make sure the swap_pool_asset_wallet provided matches the expected pda
+            let seeds: &[&[u8]] = &[
+                &swap_pool_state_account.key().to_bytes(),
+                &swap_pool_state_account.pool_assets_mints[asset_index].to_bytes(),
+                POOL_ASSET_WALLET_SEED,
+                &[swap_pool_state_account.wallets_bumps[asset_index]]
+            ];
+
+            let expected_swap_pool_asset_wallet = Pubkey::create_program_address(seeds, &ID).unwrap();
+
+            if swap_pool_asset_wallet.key() != expected_swap_pool_asset_wallet {
+                return Err(error!(ErrorCode::InvalidSwapPoolAssetWalletAccount));
+            }
+
+            let swap_pool_asset_wallet_balance = TokenAccount::try_deserialize(&mut &swap_pool_asset_wallet.try_borrow_data()?[..])?.amount;
+
+            let authority_seeds: &[&[u8]] = &[
+                &swap_pool_state_account.key().to_bytes(), // SwapPool state account
+                &POOL_AUTHORITY_SEED[..],                  // Pool authority seed
+                &[swap_pool_state_account.authority_bump]  // PDA bump
+            ];
+
+            // Return the whole wallet balance, and close the (now empty) wallet
+            token_utils::transfer_tokens_using_pda_authority(
+                swap_pool_asset_wallet_balance,
+                swap_pool_asset_wallet.to_account_info(),
+                destination_asset_wallet.to_account_info(),
+                ctx.accounts.swap_pool_authority.to_account_info(),
+                authority_seeds,
+                ctx.accounts.token_program.to_account_info()
+            );
+
+            token_utils::close_token_account_using_pda_authority(
+                swap_pool_asset_wallet.to_account_info(),
+                ctx.accounts.setup_master.to_account_info(),
+                ctx.accounts.swap_pool_authority.to_account_info(),
+                authority_seeds,
+                ctx.accounts.token_program.to_account_info()
+            );
+
+            returned_asset_amounts[asset_index] = swap_pool_asset_wallet_balance;
+        }
+
+        // NOTE: the state account is closed by Anchor (see the CancelSetup 'close' constraint)
+
+        emit!(CancelSetupEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            returned_asset_amounts,
+            burnt_pool_token_amount: pool_tokens_supply
+        });
+
+        Ok(())
+    }
 
     pub fn deposit<'a, 'b, 'c, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, Deposit<'info>>,
@@ -197,24 +384,24 @@ pub mod swap_pool_amplified {
             current_timestamp
         )?;
 
-        let mut deposited_amounts: [u64; NUMASSETS] = [0; NUMASSETS];
-
         let swap_pool_state_account = &mut ctx.accounts.swap_pool_state_account;
 
-        let pool_assets_mints: [Pubkey; NUMASSETS] = swap_pool_state_account.pool_assets_mints.clone();
+        let pool_assets_mints: Vec<Pubkey> = swap_pool_state_account.pool_assets_mints.clone();
+        let asset_count = pool_assets_mints.len();
 
-        let mut depositor_asset_wallets: [Pubkey; NUMASSETS] = [Pubkey::default(); NUMASSETS];
+        let mut deposited_amounts: Vec<u64> = vec![0; asset_count];
+        let mut depositor_asset_wallets: Vec<Pubkey> = vec![Pubkey::default(); asset_count];
 
         // Verify the count of passed remaining accounts
         // 3 Accounts given per deposited asset:
         //      - Asset mint
         //      - Swap pool asset wallet
         //      - Depositor asset wallet
-        for asset_index in 0..NUMASSETS {
+        if ctx.remaining_accounts.len() != 3*asset_count {
+            return Err(error!(ErrorCode::InvalidRemainingAccountsCount));
+        }
 
-            if swap_pool_state_account.pool_assets_mints[asset_index].eq(&Pubkey::default()) {
-                break;
-            }
+        for asset_index in 0..asset_count {
 
             // Verify the depositor_asset_wallet and the swap_pool_asset_wallet accounts
             // ! TODO VERY IMPORTANT, THIS VERIFICATION HAS TO BE REVISED
@@ -249,11 +436,12 @@ pub mod swap_pool_amplified {
                 .checked_mul(asset_eq_balance).unwrap()
                 .checked_div(ctx.accounts.swap_pool_token_mint.supply).unwrap();
 
-            let asset_deposit_amount = calculation_helpers::calc_asset_amount_for_pool_tokens(
+            // NOTE: rounded up, as the depositor must not be able to withdraw more than what was deposited
+            let asset_deposit_amount = calculation_helpers::calc_asset_amount_for_pool_tokens_up(
                 pool_tokens_for_asset,
                 swap_pool_asset_wallet_balance,     // Escrowed tokens are NOT subtracted from the total balance => deposits should return less
                 asset_eq_balance
-            )?;
+            ).map_err(ErrorCode::from)?;
 
             // Update asset eq balance
             swap_pool_state_account.pool_assets_eq_balances[asset_index] = asset_eq_balance.checked_add(pool_tokens_for_asset).unwrap();
@@ -320,13 +508,13 @@ pub mod swap_pool_amplified {
         // Burn pool tokens from the withdrawer wallet.
         ctx.accounts.burn_pool_tokens_of_withdrawer(pool_tokens_amount)?;
 
-        let mut withdrawn_amounts: [u64; NUMASSETS] = [0; NUMASSETS];
-
         let swap_pool_state_account = &mut ctx.accounts.swap_pool_state_account;
 
-        let pool_assets_mints: [Pubkey; NUMASSETS] = swap_pool_state_account.pool_assets_mints.clone();
+        let pool_assets_mints: Vec<Pubkey> = swap_pool_state_account.pool_assets_mints.clone();
+        let asset_count = pool_assets_mints.len();
 
-        let mut withdrawer_asset_wallets: [Pubkey; NUMASSETS] = [Pubkey::default(); NUMASSETS];
+        let mut withdrawn_amounts: Vec<u64> = vec![0; asset_count];
+        let mut withdrawer_asset_wallets: Vec<Pubkey> = vec![Pubkey::default(); asset_count];
 
         let initial_pool_tokens_supply = ctx.accounts.swap_pool_token_mint.supply;    // Theoretically, this is not needed, as the 'burn' operation does not update the data that has been loaded from the account, but this is here for clarity
         
@@ -334,11 +522,11 @@ pub mod swap_pool_amplified {
         //      - Asset mint
         //      - Swap pool asset wallet
         //      - Depositor asset wallet
-        for asset_index in 0..NUMASSETS {
+        if ctx.remaining_accounts.len() != 3*asset_count {
+            return Err(error!(ErrorCode::InvalidRemainingAccountsCount));
+        }
 
-            if swap_pool_state_account.pool_assets_mints[asset_index].eq(&Pubkey::default()) {
-                break;
-            }
+        for asset_index in 0..asset_count {
 
             // Verify the withdrawer_asset_wallet and the swap_pool_asset_wallet accounts
             // ! TODO VERY IMPORTANT, THIS VERIFICATION HAS TO BE REVISED
@@ -378,7 +566,7 @@ pub mod swap_pool_amplified {
                 swap_pool_asset_wallet_balance
                     .checked_sub(swap_pool_state_account.escrowed_assets[asset_index]).unwrap(),         // Escrowed tokens ARE subtracted from the total balance => withdrawals should return less
                 asset_eq_balance
-            )?;
+            ).map_err(ErrorCode::from)?;
 
             // Update asset eq balance
             swap_pool_state_account.pool_assets_eq_balances[asset_index] = asset_eq_balance.checked_sub(pool_tokens_for_asset).unwrap();
@@ -416,6 +604,9 @@ pub mod swap_pool_amplified {
 
     pub fn local_swap(ctx: Context<LocalSwap>, amount: u64, min_yield: u64) -> Result<()> {
 
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+        ctx.accounts.swap_pool_state_account.update_amplification(current_timestamp)?;
+
         let from_asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(
             &ctx.accounts.input_asset_mint.key()
         ).unwrap();
@@ -423,9 +614,13 @@ pub mod swap_pool_amplified {
         let to_asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(
             &ctx.accounts.output_asset_mint.key()
         ).unwrap();
+
+        // The pool fee is taken from the input amount. Of it, the governance share is sent to the governance fee destination.
+        let pool_fee = calculation_helpers::calc_fee_amount(amount, ctx.accounts.swap_pool_state_account.pool_fee_x64);
+        let governance_fee = calculation_helpers::calc_fee_amount(pool_fee, ctx.accounts.swap_pool_state_account.governance_fee_x64);
         
         let out: u64 = calculation_helpers::full_swap(
-            U256::from(amount),
+            U256::from(amount - pool_fee),      // Subtraction is safe, as pool_fee <= amount
             U256::from(ctx.accounts.swap_pool_input_asset_wallet.amount),
             U256::from(ctx.accounts.swap_pool_state_account.pool_assets_weights[from_asset_index]),
             U256::from(
@@ -435,7 +630,7 @@ pub mod swap_pool_amplified {
             ),
             U256::from(ctx.accounts.swap_pool_state_account.pool_assets_weights[to_asset_index]),
             U256(ctx.accounts.swap_pool_state_account.amplification_x64)
-        )?.as_u64();      // U256 to u64 will panic if overflow
+        ).map_err(ErrorCode::from)?.as_u64();      // U256 to u64 will panic if overflow
 
         if out < min_yield { return Err(error!(ErrorCode::SwapMinYieldNotFulfilled))}
 
@@ -467,25 +662,44 @@ pub mod swap_pool_amplified {
             ctx.accounts.token_program.to_account_info()
         );
 
+        // Collect the governance fee
+        if governance_fee != 0 {
+            token_utils::transfer_tokens_using_pda_authority(  //TODO use transfer_checked?
+                governance_fee,
+                ctx.accounts.swap_pool_input_asset_wallet.to_account_info(),
+                ctx.accounts.governance_fee_wallet.to_account_info(),
+                ctx.accounts.swap_pool_authority.to_account_info(),
+                &[
+                    &ctx.accounts.swap_pool_state_account.key().to_bytes(),  // SwapPool state account
+                    &POOL_AUTHORITY_SEED[..],                                // Pool authority seed
+                    &[ctx.accounts.swap_pool_state_account.authority_bump]   // PDA bump
+                ],
+                ctx.accounts.token_program.to_account_info()
+            );
+        }
+
 
-        ctx.accounts.swap_pool_state_account.max_units_inflow_x64 = 
-            U256(ctx.accounts.swap_pool_state_account.max_units_inflow_x64)
-                .checked_add(mul_x64(
-                    U256(ctx.accounts.swap_pool_state_account.units_inflow_amplification_x64),
-                    ctx.accounts.swap_pool_state_account.get_units_inflow_capacity(
-                        ctx.accounts.swap_pool_input_asset_wallet.amount,           // NOTE: this amount is from BEFORE the asset transfer
-                        ctx.accounts.swap_pool_input_asset_wallet.amount + amount,
-                        from_asset_index
-                    )?
-                ).unwrap()).unwrap()
-                .checked_sub(mul_x64(
-                    U256(ctx.accounts.swap_pool_state_account.units_inflow_amplification_x64),
-                    ctx.accounts.swap_pool_state_account.get_units_inflow_capacity(
-                        ctx.accounts.swap_pool_output_asset_wallet.amount,           // NOTE: this amount is from BEFORE the asset transfer
-                        ctx.accounts.swap_pool_output_asset_wallet.amount - out,
-                        to_asset_index
-                    )?
-                ).unwrap()).unwrap().0;
+        // No security limit is maintained for local-only pools
+        if !ctx.accounts.swap_pool_state_account.local_only {
+            ctx.accounts.swap_pool_state_account.max_units_inflow_x64 = 
+                U256::from(ctx.accounts.swap_pool_state_account.max_units_inflow_x64)
+                    .checked_add(mul_x64(
+                        U256::from(ctx.accounts.swap_pool_state_account.units_inflow_amplification_x64),
+                        ctx.accounts.swap_pool_state_account.get_units_inflow_capacity(
+                            ctx.accounts.swap_pool_input_asset_wallet.amount,           // NOTE: this amount is from BEFORE the asset transfer
+                            ctx.accounts.swap_pool_input_asset_wallet.amount + amount - governance_fee,
+                            from_asset_index
+                        )?
+                    ).map_err(ErrorCode::from)?).unwrap()
+                    .checked_sub(mul_x64(
+                        U256::from(ctx.accounts.swap_pool_state_account.units_inflow_amplification_x64),
+                        ctx.accounts.swap_pool_state_account.get_units_inflow_capacity(
+                            ctx.accounts.swap_pool_output_asset_wallet.amount,           // NOTE: this amount is from BEFORE the asset transfer
+                            ctx.accounts.swap_pool_output_asset_wallet.amount - out,
+                            to_asset_index
+                        )?
+                    ).map_err(ErrorCode::from)?).unwrap().into();
+        }
 
 
         emit!(LocalSwapEvent {
@@ -499,7 +713,7 @@ pub mod swap_pool_amplified {
         
             deposited_asset_amount: amount,
             withdrawn_asset_amount: out,
-            fees: 0
+            fees: pool_fee
         });
 
         Ok(())
@@ -513,20 +727,48 @@ pub mod swap_pool_amplified {
         destination: Pubkey,
         amount: u64,
         min_output: [u64; 4],
-        escrow_nonce: u32,
-        fallback_wallet: Pubkey
+        swap_hash: [u8; 32],        // Used in context
+        fallback_wallet: Pubkey,
+        timeout_slot: u64,          // Slot at which the swap times out (0 for no timeout)
+        timeout_timestamp: u64      // Unix timestamp at which the swap times out (0 for no timeout)
     ) -> Result<()> {
 
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+        ctx.accounts.swap_pool_state_account.update_amplification(current_timestamp)?;
+
+        // Verify the swap hash used to derive the swap escrow matches the swap parameters
+        let expected_swap_hash = SwapEscrow::compute_swap_hash(
+            &ctx.accounts.swap_pool_state_account.key(),
+            chain,
+            &target_pool,
+            to_asset_index,
+            &destination,
+            amount,
+            &min_output,
+            &ctx.accounts.input_asset_mint.key(),
+            &fallback_wallet,
+            &ctx.accounts.polymerase_ibc_data.key()
+        );
+
+        if swap_hash != expected_swap_hash {
+            return Err(error!(ErrorCode::InvalidSwapHash));
+        }
+
         let from_asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(
             &ctx.accounts.input_asset_mint.key()
         ).unwrap();
 
+        // The pool fee is taken from the input amount. Of it, the governance share is sent to the governance fee destination.
+        let pool_fee = calculation_helpers::calc_fee_amount(amount, ctx.accounts.swap_pool_state_account.pool_fee_x64);
+        let governance_fee = calculation_helpers::calc_fee_amount(pool_fee, ctx.accounts.swap_pool_state_account.governance_fee_x64);
+        let escrowed_amount = amount - pool_fee;    // Subtraction is safe, as pool_fee <= amount
+
         let units_x64 = calculation_helpers::out_swap_x64(
-            U256::from(amount),
+            U256::from(escrowed_amount),
             U256::from(ctx.accounts.swap_pool_input_asset_wallet.amount),
             U256::from(ctx.accounts.swap_pool_state_account.pool_assets_weights[from_asset_index]),
             U256(ctx.accounts.swap_pool_state_account.amplification_x64)
-        )?;
+        ).map_err(ErrorCode::from)?;
 
         //TODO! unitsDiff       line 902
 
@@ -556,16 +798,20 @@ pub mod swap_pool_amplified {
         ibc_interface::cpi::cross_chain_swap(
             ccsi_swap_ctx,
             chain,
-            false,
             ctx.accounts.swap_pool_state_account.key(),
             target_pool,
             to_asset_index,
             destination,
             units_x64.0,
             min_output,
-            escrow_nonce
+            escrowed_amount,
+            ctx.accounts.input_asset_mint.key(),
+            timeout_slot,
+            timeout_timestamp
         ).unwrap();
 
+        // The swap id identifies the swap acknowledgement/timeout (derived from the message, see 'AssetSwapPayload::swap_id')
+        let swap_id = get_interface_swap_id(&ctx.accounts.ibc_interface_program.key())?;
 
         // Transfer input assets to the pool
         token_utils::transfer_tokens_using_pda_authority(  //TODO use transfer_checked?
@@ -581,32 +827,50 @@ pub mod swap_pool_amplified {
             ctx.accounts.token_program.to_account_info()
         );
 
+        // Collect the governance fee
+        if governance_fee != 0 {
+            token_utils::transfer_tokens_using_pda_authority(  //TODO use transfer_checked?
+                governance_fee,
+                ctx.accounts.swap_pool_input_asset_wallet.to_account_info(),
+                ctx.accounts.governance_fee_wallet.to_account_info(),
+                ctx.accounts.swap_pool_authority.to_account_info(),
+                &[
+                    &ctx.accounts.swap_pool_state_account.key().to_bytes(),  // SwapPool state account
+                    &POOL_AUTHORITY_SEED[..],                                // Pool authority seed
+                    &[ctx.accounts.swap_pool_state_account.authority_bump]   // PDA bump
+                ],
+                ctx.accounts.token_program.to_account_info()
+            );
+        }
+
         // Escrow the tokens
+        // NOTE: the pool fee is not escrowed, otherwise the escrow could be used as a cheap denial of service vector (as on EVM)
         ctx.accounts.swap_pool_state_account.escrowed_assets[from_asset_index] =
-            ctx.accounts.swap_pool_state_account.escrowed_assets[from_asset_index].checked_add(amount).unwrap();
+            ctx.accounts.swap_pool_state_account.escrowed_assets[from_asset_index].checked_add(escrowed_amount).unwrap();
 
-        ctx.accounts.swap_escrow.amount                 = amount;
+        ctx.accounts.swap_escrow.swap_hash              = swap_hash;
+        ctx.accounts.swap_escrow.swap_id                = swap_id;
+        ctx.accounts.swap_escrow.amount                 = escrowed_amount;
         ctx.accounts.swap_escrow.asset_index            = from_asset_index as u8;
+        ctx.accounts.swap_escrow.units_x64              = units_x64.into();
         ctx.accounts.swap_escrow.fallback_wallet        = fallback_wallet;
         ctx.accounts.swap_escrow.swap_escrow_rent_payer = ctx.accounts.swap_escrow_rent_payer.key();
         ctx.accounts.swap_escrow.bump                   = ctx.bumps.get("swap_escrow").unwrap().to_owned();
 
 
-        // TODO verify
-        ctx.accounts.swap_pool_state_account.unit_tracker_x64 = ctx.accounts.swap_pool_state_account.unit_tracker_x64
-            .checked_add(units_x64.as_u128().try_into().unwrap()).unwrap();
+        ctx.accounts.swap_pool_state_account.update_unit_tracker(units_x64, true)?;
 
         
         ctx.accounts.swap_pool_state_account.max_units_inflow_x64 = 
-            U256(ctx.accounts.swap_pool_state_account.max_units_inflow_x64)
+            U256::from(ctx.accounts.swap_pool_state_account.max_units_inflow_x64)
                 .checked_add(mul_x64(
-                    U256(ctx.accounts.swap_pool_state_account.units_inflow_amplification_x64),
+                    U256::from(ctx.accounts.swap_pool_state_account.units_inflow_amplification_x64),
                     ctx.accounts.swap_pool_state_account.get_units_inflow_capacity(
                         ctx.accounts.swap_pool_input_asset_wallet.amount,           // NOTE: this amount is from BEFORE the asset transfer
-                        ctx.accounts.swap_pool_input_asset_wallet.amount + amount,
+                        ctx.accounts.swap_pool_input_asset_wallet.amount + amount - governance_fee,
                         from_asset_index
                     )?
-                ).unwrap()).unwrap().0;
+                ).map_err(ErrorCode::from)?).unwrap().into();
 
 
 
@@ -614,12 +878,12 @@ pub mod swap_pool_amplified {
         // no one will execute a trade. Hence, if people swap into the pool, it is expected that there is exactly that 
         // 'inswapped' amount of trust in the pool. Otherwise there would be effectively a maximum allowed daily cross 
         // chain volume, which is bad for liquidity providers.
-        let current_units_inflow_x64 = U256(ctx.accounts.swap_pool_state_account.current_units_inflow_x64);
+        let current_units_inflow_x64 = U256::from(ctx.accounts.swap_pool_state_account.current_units_inflow_x64);
         if current_units_inflow_x64 > units_x64 {
-            ctx.accounts.swap_pool_state_account.current_units_inflow_x64 = (current_units_inflow_x64 - units_x64).0;
+            ctx.accounts.swap_pool_state_account.current_units_inflow_x64 = (current_units_inflow_x64 - units_x64).into();
         }
         else if !current_units_inflow_x64.is_zero() {    // NOTE: Optimisation carried over from EVM. Does it also work in Solana? (i.e. not writing to a state variable unless strictly required)
-            ctx.accounts.swap_pool_state_account.current_units_inflow_x64 = [0, 0, 0, 0];            
+            ctx.accounts.swap_pool_state_account.current_units_inflow_x64 = U256Limbs::default();            
         }
 
         emit!(OutSwapEvent {
@@ -629,14 +893,15 @@ pub mod swap_pool_amplified {
             target_withdrawer: destination,
             target_chain: chain,
 
-            escrow_nonce,
+            swap_hash,
+            swap_id,
         
             deposited_asset_mint: ctx.accounts.input_asset_mint.key(),
             depositor_asset_wallet: ctx.accounts.input_asset_wallet.key(),
         
             deposited_asset_amount: amount,
             withdrawn_pool_units_x64: units_x64.0,
-            fees: 0,
+            fees: pool_fee,
         });
 
         Ok(())
@@ -644,9 +909,11 @@ pub mod swap_pool_amplified {
 
     pub fn out_swap_ack(
         ctx: Context<OutSwapAck>,
-        escrow_nonce: u32          // Used in context
+        swap_id: [u8; 32]           // Used in context
     ) -> Result<()> {
 
+        // NOTE: the swap escrow is closed once the instruction completes (see the 'OutSwapAck' context), with its rent returned
+        // to the swap_escrow_rent_payer. This also guarantees that the escrow cannot be released more than once.
         let escrowed_amount = ctx.accounts.swap_escrow.amount;
         let escrowed_asset_index = ctx.accounts.swap_escrow.asset_index as usize;
 
@@ -655,7 +922,8 @@ pub mod swap_pool_amplified {
 
         emit!(OutSwapAckEvent {
             swap_pool: ctx.accounts.swap_pool_state_account.key(),
-            escrow_nonce
+            swap_hash: ctx.accounts.swap_escrow.swap_hash,
+            swap_id
         });
 
         Ok(())
@@ -663,14 +931,36 @@ pub mod swap_pool_amplified {
 
     pub fn out_swap_timeout(
         ctx: Context<OutSwapTimeout>,
-        escrow_nonce: u32,          // Used in context
+        swap_id: [u8; 32]           // Used in context
     ) -> Result<()> {
 
+        // NOTE: the swap escrow is closed once the instruction completes (see the 'OutSwapTimeout' context), with its rent returned
+        // to the swap_escrow_rent_payer. This also guarantees that the escrow cannot be released more than once.
         let escrowed_amount = ctx.accounts.swap_escrow.amount;
         let escrowed_asset_index = ctx.accounts.swap_escrow.asset_index as usize;
 
         ctx.accounts.swap_pool_state_account.escrowed_assets[escrowed_asset_index] = 
             ctx.accounts.swap_pool_state_account.escrowed_assets[escrowed_asset_index].checked_sub(escrowed_amount).unwrap();
+
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+        ctx.accounts.swap_pool_state_account.update_amplification(current_timestamp)?;
+
+        // The units of the swap never left the pool
+        ctx.accounts.swap_pool_state_account.update_unit_tracker(U256::from(ctx.accounts.swap_escrow.units_x64), false)?;
+
+        // Revert the security limit increase of the escrowed assets, as they leave the pool.
+        // NOTE: the decrease is computed with the current pool balance (as on EVM), hence it may not match exactly the increase
+        // of the out swap. It saturates at 0 so that the timeout (i.e. the refund) can never be blocked.
+        ctx.accounts.swap_pool_state_account.max_units_inflow_x64 = 
+            U256::from(ctx.accounts.swap_pool_state_account.max_units_inflow_x64)
+                .saturating_sub(mul_x64(
+                    U256::from(ctx.accounts.swap_pool_state_account.units_inflow_amplification_x64),
+                    ctx.accounts.swap_pool_state_account.get_units_inflow_capacity(
+                        ctx.accounts.swap_pool_asset_wallet.amount - escrowed_amount,   // Subtraction is safe, as the escrowed assets are held by the pool wallet
+                        ctx.accounts.swap_pool_asset_wallet.amount,                     // NOTE: this amount is from BEFORE the asset transfer
+                        escrowed_asset_index
+                    )?
+                ).map_err(ErrorCode::from)?).into();
         
         // Transfer tokens to fallback_wallet
         token_utils::transfer_tokens_using_pda_authority(  //TODO use transfer_checked?
@@ -688,7 +978,8 @@ pub mod swap_pool_amplified {
 
         emit!(OutSwapTimeoutEvent {
             swap_pool: ctx.accounts.swap_pool_state_account.key(),
-            escrow_nonce
+            swap_hash: ctx.accounts.swap_escrow.swap_hash,
+            swap_id
         });
 
         Ok(())
@@ -697,11 +988,14 @@ pub mod swap_pool_amplified {
     pub fn in_swap(
         ctx: Context<InSwap>,
         output_asset_index: u8,
-        units_x64: [u64; 4],
+        units_x64: U256Limbs,
         min_output: u64
-    ) -> Result<()> {   //TODO! set units_x64 to U256 (add serialisation/deserialisation to type)
+    ) -> Result<()> {
         // assert sender is chain_interface
 
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+        ctx.accounts.swap_pool_state_account.update_amplification(current_timestamp)?;
+
         let to_asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(
             &ctx.accounts.output_asset_mint.key()
         ).unwrap();
@@ -711,36 +1005,33 @@ pub mod swap_pool_amplified {
         }
 
         let output_balance = calculation_helpers::in_swap(
-            U256(units_x64),
+            U256::from(units_x64),
             U256::from(
                 ctx.accounts.swap_pool_output_asset_wallet.amount
                     .checked_sub(ctx.accounts.swap_pool_state_account.escrowed_assets[to_asset_index]).unwrap()
             ),
             U256::from(ctx.accounts.swap_pool_state_account.pool_assets_weights[output_asset_index as usize]),
             U256(ctx.accounts.swap_pool_state_account.amplification_x64)
-        )?.as_u64();      // U256 to u64 will panic if overflow
+        ).map_err(ErrorCode::from)?.as_u64();      // U256 to u64 will panic if overflow
 
         if output_balance < min_output {
             return Err(error!(ErrorCode::SwapMinYieldNotFulfilled))
         }
 
-        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
-        ctx.accounts.swap_pool_state_account.update_units_inflow(U256(units_x64), current_timestamp)?;
+        ctx.accounts.swap_pool_state_account.update_units_inflow(U256::from(units_x64), current_timestamp)?;
 
-        // TODO verify
-        ctx.accounts.swap_pool_state_account.unit_tracker_x64 = ctx.accounts.swap_pool_state_account.unit_tracker_x64
-            .checked_sub(U256(units_x64).as_u128().try_into().unwrap()).unwrap();
+        ctx.accounts.swap_pool_state_account.update_unit_tracker(U256::from(units_x64), false)?;
 
         ctx.accounts.swap_pool_state_account.max_units_inflow_x64 = 
-            U256(ctx.accounts.swap_pool_state_account.max_units_inflow_x64)
+            U256::from(ctx.accounts.swap_pool_state_account.max_units_inflow_x64)
                 .checked_sub(mul_x64(
-                    U256(ctx.accounts.swap_pool_state_account.units_inflow_amplification_x64),
+                    U256::from(ctx.accounts.swap_pool_state_account.units_inflow_amplification_x64),
                     ctx.accounts.swap_pool_state_account.get_units_inflow_capacity(
                         ctx.accounts.swap_pool_output_asset_wallet.amount,           // NOTE: this amount is from BEFORE the asset transfer
                         ctx.accounts.swap_pool_output_asset_wallet.amount - output_balance,
                         to_asset_index
                     )?
-                ).unwrap()).unwrap().0;
+                ).map_err(ErrorCode::from)?).unwrap().into();
         //TODO! unitsDiff       line 888
 
         // Transfer output assets to the user
@@ -763,7 +1054,7 @@ pub mod swap_pool_amplified {
             withdrawn_asset_mint: ctx.accounts.output_asset_mint.key(),
             withdrawer_asset_wallet: ctx.accounts.output_asset_wallet.key(),
         
-            deposited_pool_units_x64: units_x64,
+            deposited_pool_units_x64: units_x64.limbs,
             withdrawn_asset_amount: output_balance,
             fees: 0
         });
@@ -772,84 +1063,258 @@ pub mod swap_pool_amplified {
     }
 
 
-
-    // Liquidity Swaps **********************************************************************************************************
-
-    pub fn out_liquidity_swap(
-        ctx: Context<OutLiquiditySwap>,
-        chain: u64,
-        target_pool: Pubkey,
-        destination: Pubkey,
-        pool_tokens_amount: u64
+    pub fn underwrite_asset(
+        ctx: Context<UnderwriteAsset>,
+        underwrite_id: [u8; 32],
+        output_asset_index: u8,
+        units_x64: U256Limbs,
+        min_output: u64
     ) -> Result<()> {
 
-        let swap_pool_state_account = &mut ctx.accounts.swap_pool_state_account;
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+        ctx.accounts.swap_pool_state_account.update_amplification(current_timestamp)?;
 
-        // Compute the total amount of liquidity units being transferred
-        let mut out_liquidity_units_x64 = U256::from(0);
-    
-        for asset_index in 0..NUMASSETS {
+        let to_asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(
+            &ctx.accounts.output_asset_mint.key()
+        ).unwrap();
 
-            if swap_pool_state_account.pool_assets_mints[asset_index].eq(&Pubkey::default()) {
-                break;
-            }
+        if output_asset_index as usize != to_asset_index {
+            return Err(error!(ErrorCode::InvalidAssetMintAccount));
+        }
 
-            let asset_eq_balance = swap_pool_state_account.pool_assets_eq_balances[asset_index];
+        let output_balance = calculation_helpers::in_swap(
+            U256::from(units_x64),
+            U256::from(
+                ctx.accounts.swap_pool_output_asset_wallet.amount
+                    .checked_sub(ctx.accounts.swap_pool_state_account.escrowed_assets[to_asset_index]).unwrap()
+            ),
+            U256::from(ctx.accounts.swap_pool_state_account.pool_assets_weights[output_asset_index as usize]),
+            U256(ctx.accounts.swap_pool_state_account.amplification_x64)
+        ).map_err(ErrorCode::from)?.as_u64();      // U256 to u64 will panic if overflow
 
-            let pool_tokens_for_asset = pool_tokens_amount
-                .checked_mul(asset_eq_balance).unwrap()
-                .checked_div(ctx.accounts.swap_pool_token_mint.supply).unwrap();
+        if output_balance < min_output {
+            return Err(error!(ErrorCode::SwapMinYieldNotFulfilled))
+        }
 
-            let liquidity_for_asset_x64 = calculation_helpers::calc_out_liquidity_swap_x64(
-                U256::from(pool_tokens_for_asset),
-                U256::from(asset_eq_balance),
-                U256::from(swap_pool_state_account.pool_assets_weights[asset_index]),
-                U256(swap_pool_state_account.amplification_x64)
-            )?;
-            
-            out_liquidity_units_x64 = out_liquidity_units_x64.checked_add(liquidity_for_asset_x64).unwrap();
+        ctx.accounts.swap_pool_state_account.update_units_inflow(U256::from(units_x64), current_timestamp)?;
 
-            swap_pool_state_account.pool_assets_eq_balances[asset_index] = asset_eq_balance.checked_sub(pool_tokens_for_asset).unwrap();
+        ctx.accounts.swap_pool_state_account.update_unit_tracker(U256::from(units_x64), false)?;
 
-        }
+        ctx.accounts.swap_pool_state_account.max_units_inflow_x64 = 
+            U256::from(ctx.accounts.swap_pool_state_account.max_units_inflow_x64)
+                .checked_sub(mul_x64(
+                    U256::from(ctx.accounts.swap_pool_state_account.units_inflow_amplification_x64),
+                    ctx.accounts.swap_pool_state_account.get_units_inflow_capacity(
+                        ctx.accounts.swap_pool_output_asset_wallet.amount,           // NOTE: the purchased amount is escrowed rather than transferred out
+                        ctx.accounts.swap_pool_output_asset_wallet.amount - output_balance,
+                        to_asset_index
+                    )?
+                ).map_err(ErrorCode::from)?).unwrap().into();
 
-        // Burn pool tokens
-        ctx.accounts.burn_pool_tokens_of_sender(pool_tokens_amount)?;
+        // The purchased assets are not transferred out of the pool, but rather reserved until the underwrite is either
+        // released to the underwriter (once the swap arrives) or deleted (once it expires).
+        ctx.accounts.swap_pool_state_account.escrowed_assets[to_asset_index] = 
+            ctx.accounts.swap_pool_state_account.escrowed_assets[to_asset_index].checked_add(output_balance).unwrap();
 
-        // Perform cross chain swap
-        let ccsi_program = ctx.accounts.ibc_interface_program.to_account_info(); // Check is the same as the stored program?
-        let ccsi_swap_accounts = interface_accounts::CrossChainSwap {
-            swap_pool_authority: ctx.accounts.swap_pool_authority.to_account_info(),
-            interface_state_account: ctx.accounts.interface_state_account.to_account_info(),
-            connection_state_account: ctx.accounts.connection_state_account.to_account_info(),
-            polymerase_sender_program: ctx.accounts.polymerase_sender_program.to_account_info(),
-            polymerase_ibc_data: ctx.accounts.polymerase_ibc_data.to_account_info(),
-            polymerase_ibc_data_account_payer: ctx.accounts.polymerase_ibc_data_account_payer.to_account_info(),
-            system_program: ctx.accounts.system_program.to_account_info()
-        };
+        // Return the purchased amount to the interface, which collects it from the underwriter
+        set_return_data(&output_balance.to_le_bytes());
 
-        let signer: &[&[&[u8]]] = &[&[
-            ctx.accounts.swap_pool_state_account.to_account_info().key.as_ref(),
-            &POOL_AUTHORITY_SEED[..],
-            &[ctx.accounts.swap_pool_state_account.authority_bump]   // PDA bump
-        ]];
+        emit!(UnderwriteAssetEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            underwrite_id,
+            output_asset_mint: ctx.accounts.output_asset_mint.key(),
+            units_x64: units_x64.limbs,
+            output_amount: output_balance
+        });
 
-        let ccsi_swap_ctx = CpiContext::new_with_signer(
-            ccsi_program,
-            ccsi_swap_accounts,
-            signer
-        );
+        Ok(())
+    }
 
-        ibc_interface::cpi::cross_chain_liquidity_swap(
-            ccsi_swap_ctx,
+    pub fn release_underwrite_asset(
+        ctx: Context<ReleaseUnderwriteAsset>,
+        underwrite_id: [u8; 32],
+        output_asset_index: u8,
+        amount: u64
+    ) -> Result<()> {
+
+        let to_asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(
+            &ctx.accounts.output_asset_mint.key()
+        ).unwrap();
+
+        if output_asset_index as usize != to_asset_index {
+            return Err(error!(ErrorCode::InvalidAssetMintAccount));
+        }
+
+        ctx.accounts.swap_pool_state_account.escrowed_assets[to_asset_index] = 
+            ctx.accounts.swap_pool_state_account.escrowed_assets[to_asset_index].checked_sub(amount).unwrap();
+
+        // Repay the underwriter
+        token_utils::transfer_tokens_using_pda_authority(  //TODO use transfer_checked?
+            amount,
+            ctx.accounts.swap_pool_output_asset_wallet.to_account_info(),
+            ctx.accounts.refund_wallet.to_account_info(),
+            ctx.accounts.swap_pool_authority.to_account_info(),
+            &[
+                &ctx.accounts.swap_pool_state_account.key().to_bytes(),  // SwapPool state account
+                &POOL_AUTHORITY_SEED[..],                                // Pool authority seed
+                &[ctx.accounts.swap_pool_state_account.authority_bump]   // PDA bump
+            ],
+            ctx.accounts.token_program.to_account_info()
+        );
+
+        emit!(ReleaseUnderwriteAssetEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            underwrite_id,
+            refund_wallet: ctx.accounts.refund_wallet.key(),
+            amount
+        });
+
+        Ok(())
+    }
+
+    pub fn delete_underwrite_asset(
+        ctx: Context<DeleteUnderwriteAsset>,
+        underwrite_id: [u8; 32],
+        output_asset_index: u8,
+        amount: u64,
+        units_x64: U256Limbs
+    ) -> Result<()> {
+
+        // The reserved assets are returned to the pool (they never left the pool wallet)
+        ctx.accounts.swap_pool_state_account.escrowed_assets[output_asset_index as usize] = 
+            ctx.accounts.swap_pool_state_account.escrowed_assets[output_asset_index as usize].checked_sub(amount).unwrap();
+
+        // Return the underwritten units to the unit tracker, as the swap has not been completed
+        ctx.accounts.swap_pool_state_account.update_unit_tracker(U256::from(units_x64), true)?;
+
+        emit!(DeleteUnderwriteAssetEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            underwrite_id,
+            amount
+        });
+
+        Ok(())
+    }
+
+
+
+    // Liquidity Swaps **********************************************************************************************************
+
+    pub fn out_liquidity_swap(
+        ctx: Context<OutLiquiditySwap>,
+        chain: u64,
+        target_pool: Pubkey,
+        destination: Pubkey,
+        pool_tokens_amount: u64,
+        swap_hash: [u8; 32],        // Used in context
+        fallback_wallet: Pubkey,
+        timeout_slot: u64,          // Slot at which the swap times out (0 for no timeout)
+        timeout_timestamp: u64      // Unix timestamp at which the swap times out (0 for no timeout)
+    ) -> Result<()> {
+
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+        ctx.accounts.swap_pool_state_account.update_amplification(current_timestamp)?;
+
+        // Verify the swap hash used to derive the liquidity escrow matches the swap parameters
+        let expected_swap_hash = LiquidityEscrow::compute_liquidity_swap_hash(
+            &ctx.accounts.swap_pool_state_account.key(),
+            chain,
+            &target_pool,
+            &destination,
+            pool_tokens_amount,
+            &fallback_wallet,
+            &ctx.accounts.polymerase_ibc_data.key()
+        );
+
+        if swap_hash != expected_swap_hash {
+            return Err(error!(ErrorCode::InvalidSwapHash));
+        }
+
+        let swap_pool_state_account = &mut ctx.accounts.swap_pool_state_account;
+
+        // Compute the total amount of liquidity units being transferred
+        let mut out_liquidity_units_x64 = U256::from(0);
+
+        // The eq balance deltas are stored on the escrow, so that they can be restored exactly should the swap time out
+        let mut eq_balances_deltas = [0u64; MAX_ASSETS];
+    
+        for asset_index in 0..swap_pool_state_account.pool_assets_mints.len() {
+
+            let asset_eq_balance = swap_pool_state_account.pool_assets_eq_balances[asset_index];
+
+            let pool_tokens_for_asset = pool_tokens_amount
+                .checked_mul(asset_eq_balance).unwrap()
+                .checked_div(ctx.accounts.swap_pool_token_mint.supply).unwrap();
+
+            let liquidity_for_asset_x64 = calculation_helpers::calc_out_liquidity_swap_x64(
+                U256::from(pool_tokens_for_asset),
+                U256::from(asset_eq_balance),
+                U256::from(swap_pool_state_account.pool_assets_weights[asset_index]),
+                U256(swap_pool_state_account.amplification_x64)
+            ).map_err(ErrorCode::from)?;
+            
+            out_liquidity_units_x64 = out_liquidity_units_x64.checked_add(liquidity_for_asset_x64).unwrap();
+
+            swap_pool_state_account.pool_assets_eq_balances[asset_index] = asset_eq_balance.checked_sub(pool_tokens_for_asset).unwrap();
+            eq_balances_deltas[asset_index] = pool_tokens_for_asset;
+
+        }
+
+        // Burn pool tokens
+        ctx.accounts.burn_pool_tokens_of_sender(pool_tokens_amount)?;
+
+        // Perform cross chain swap
+        let ccsi_program = ctx.accounts.ibc_interface_program.to_account_info(); // Check is the same as the stored program?
+        let ccsi_swap_accounts = interface_accounts::CrossChainSwap {
+            swap_pool_authority: ctx.accounts.swap_pool_authority.to_account_info(),
+            interface_state_account: ctx.accounts.interface_state_account.to_account_info(),
+            connection_state_account: ctx.accounts.connection_state_account.to_account_info(),
+            polymerase_sender_program: ctx.accounts.polymerase_sender_program.to_account_info(),
+            polymerase_ibc_data: ctx.accounts.polymerase_ibc_data.to_account_info(),
+            polymerase_ibc_data_account_payer: ctx.accounts.polymerase_ibc_data_account_payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info()
+        };
+
+        let signer: &[&[&[u8]]] = &[&[
+            ctx.accounts.swap_pool_state_account.to_account_info().key.as_ref(),
+            &POOL_AUTHORITY_SEED[..],
+            &[ctx.accounts.swap_pool_state_account.authority_bump]   // PDA bump
+        ]];
+
+        let ccsi_swap_ctx = CpiContext::new_with_signer(
+            ccsi_program,
+            ccsi_swap_accounts,
+            signer
+        );
+
+        ibc_interface::cpi::cross_chain_liquidity_swap(
+            ccsi_swap_ctx,
             chain,
-            false,
             ctx.accounts.swap_pool_state_account.key(),
             target_pool,
             destination,
-            out_liquidity_units_x64.0
+            out_liquidity_units_x64.0,
+            pool_tokens_amount,
+            timeout_slot,
+            timeout_timestamp
         ).unwrap();
 
+        // The swap id identifies the swap acknowledgement/timeout (derived from the message, see 'LiquiditySwapPayload::swap_id')
+        let swap_id = get_interface_swap_id(&ctx.accounts.ibc_interface_program.key())?;
+
+        // Escrow the pool tokens
+        // NOTE: the pool tokens have already been burnt. They are minted back to the fallback wallet should the swap time out.
+        ctx.accounts.liquidity_escrow.swap_hash                   = swap_hash;
+        ctx.accounts.liquidity_escrow.swap_id                     = swap_id;
+        ctx.accounts.liquidity_escrow.pool_token_amount           = pool_tokens_amount;
+        ctx.accounts.liquidity_escrow.eq_balances_deltas          = eq_balances_deltas;
+        ctx.accounts.liquidity_escrow.fallback_wallet             = fallback_wallet;
+        ctx.accounts.liquidity_escrow.liquidity_escrow_rent_payer = ctx.accounts.liquidity_escrow_rent_payer.key();
+        ctx.accounts.liquidity_escrow.bump                        = ctx.bumps.get("liquidity_escrow").unwrap().to_owned();
+
+        ctx.accounts.swap_pool_state_account.escrowed_pool_tokens =
+            ctx.accounts.swap_pool_state_account.escrowed_pool_tokens.checked_add(pool_tokens_amount).unwrap();
+
         // Correct the routing security limit. (To increase the maximum allowed daily volume)
         if ctx.accounts.swap_pool_state_account.current_liquidity_inflow > pool_tokens_amount {
             ctx.accounts.swap_pool_state_account.current_liquidity_inflow -= pool_tokens_amount
@@ -868,6 +1333,8 @@ pub mod swap_pool_amplified {
             pool_token_amount: pool_tokens_amount,
             liquidity_units_x64: out_liquidity_units_x64.0,
             fees: 0,
+            swap_hash,
+            swap_id
         });
 
 
@@ -877,9 +1344,12 @@ pub mod swap_pool_amplified {
 
     pub fn in_liquidity_swap<'a, 'b, 'c, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, InLiquiditySwap<'info>>,
-        liquidity_units_x64: [u64; 4]
+        liquidity_units_x64: U256Limbs
     ) -> Result<()> {
 
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+        ctx.accounts.swap_pool_state_account.update_amplification(current_timestamp)?;
+
         let total_pool_tokens;
         {
             let swap_pool_state_account = &mut ctx.accounts.swap_pool_state_account;
@@ -888,20 +1358,17 @@ pub mod swap_pool_amplified {
             let mut aggregate_weight_x64 = U256::from(0);
             let one_minus_amp_x64 = ONE_X64.checked_sub(amplification).unwrap();
         
-            for asset_index in 0..NUMASSETS {
-
-                if swap_pool_state_account.pool_assets_mints[asset_index].eq(&Pubkey::default()) {
-                    break;
-                }
+            for asset_index in 0..swap_pool_state_account.pool_assets_mints.len() {
 
                 let asset_eq_balance = swap_pool_state_account.pool_assets_eq_balances[asset_index];
 
                 aggregate_weight_x64 = aggregate_weight_x64.checked_add(
+                    // NOTE: rounded up, as a larger aggregate weight yields less pool tokens
                     U256::from(swap_pool_state_account.pool_assets_weights[asset_index]).checked_mul(
-                        pow_x64(
+                        pow_x64_up(
                             U256::from(asset_eq_balance).shl(64), // No overflow guaranteed, going from u64 to u256
                             one_minus_amp_x64
-                        ).unwrap()
+                        ).map_err(ErrorCode::from)?
                     ).unwrap()
                 ).unwrap();
                 
@@ -909,11 +1376,11 @@ pub mod swap_pool_amplified {
 
             // Compute the 'received' pool tokens corresponding to the first asset of the pool
             let asset_0_pool_tokens = calculation_helpers::calc_in_liquidity_swap(
-                U256(liquidity_units_x64),
+                U256::from(liquidity_units_x64),
                 U256::from(swap_pool_state_account.pool_assets_eq_balances[0]),
                 aggregate_weight_x64,
                 amplification
-            )?;
+            ).map_err(ErrorCode::from)?;
 
             // Compute the total pool tokens 'received' from the ones corresponding to the first asset
             let pool_tokens_supply = U256::from(ctx.accounts.swap_pool_token_mint.supply);
@@ -926,11 +1393,7 @@ pub mod swap_pool_amplified {
                 asset_0_pool_tokens.as_u64()
             ).unwrap();
 
-            for asset_index in 1..NUMASSETS {
-
-                if swap_pool_state_account.pool_assets_mints[asset_index].eq(&Pubkey::default()) {
-                    break;
-                }
+            for asset_index in 1..swap_pool_state_account.pool_assets_mints.len() {
 
                 let asset_eq_balance = swap_pool_state_account.pool_assets_eq_balances[asset_index];
 
@@ -945,7 +1408,6 @@ pub mod swap_pool_amplified {
         }
 
         // Verify and update the security limit
-        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
         ctx.accounts.swap_pool_state_account.update_liquidity_units_inflow(
             total_pool_tokens.as_u64(),
             ctx.accounts.swap_pool_token_mint.supply,
@@ -961,7 +1423,7 @@ pub mod swap_pool_amplified {
             target_pool_token_wallet: ctx.accounts.destination_pool_token_wallet.key(),
         
             pool_token_amount: total_pool_tokens.as_u64(),
-            liquidity_units_x64: liquidity_units_x64,
+            liquidity_units_x64: liquidity_units_x64.limbs,
             fees: 0
         });
 
@@ -970,6 +1432,66 @@ pub mod swap_pool_amplified {
 
 
 
+    pub fn out_liquidity_swap_ack(
+        ctx: Context<OutLiquiditySwapAck>,
+        swap_id: [u8; 32]           // Used in context
+    ) -> Result<()> {
+
+        // NOTE: the liquidity escrow is closed once the instruction completes (see the 'OutLiquiditySwapAck' context), with its
+        // rent returned to the liquidity_escrow_rent_payer. This also guarantees that the escrow cannot be released more than once.
+
+        ctx.accounts.swap_pool_state_account.escrowed_pool_tokens = ctx.accounts.swap_pool_state_account.escrowed_pool_tokens
+            .checked_sub(ctx.accounts.liquidity_escrow.pool_token_amount).unwrap();
+
+        emit!(OutLiquiditySwapAckEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            swap_hash: ctx.accounts.liquidity_escrow.swap_hash,
+            swap_id
+        });
+
+        Ok(())
+    }
+
+
+    pub fn out_liquidity_swap_timeout(
+        ctx: Context<OutLiquiditySwapTimeout>,
+        swap_id: [u8; 32]           // Used in context
+    ) -> Result<()> {
+
+        // NOTE: the liquidity escrow is closed once the instruction completes (see the 'OutLiquiditySwapTimeout' context), with its
+        // rent returned to the liquidity_escrow_rent_payer. This also guarantees that the escrow cannot be released more than once.
+
+        let escrowed_pool_tokens = ctx.accounts.liquidity_escrow.pool_token_amount;
+
+        ctx.accounts.swap_pool_state_account.escrowed_pool_tokens =
+            ctx.accounts.swap_pool_state_account.escrowed_pool_tokens.checked_sub(escrowed_pool_tokens).unwrap();
+
+        // Restore the eq balances removed by the out_liquidity_swap. The recorded deltas are used rather than recomputing them
+        // from the pool token supply, as the latter may have changed (or be zero if all the remaining liquidity was withdrawn).
+        let eq_balances_deltas = ctx.accounts.liquidity_escrow.eq_balances_deltas;
+        let swap_pool_state_account = &mut ctx.accounts.swap_pool_state_account;
+
+        for asset_index in 0..swap_pool_state_account.pool_assets_mints.len() {
+
+            swap_pool_state_account.pool_assets_eq_balances[asset_index] = swap_pool_state_account.pool_assets_eq_balances[asset_index]
+                .checked_add(eq_balances_deltas[asset_index]).unwrap();
+
+        }
+
+        // Mint the escrowed pool tokens back to the fallback wallet
+        ctx.accounts.mint_pool_tokens_for_fallback_wallet(escrowed_pool_tokens)?;
+
+        emit!(OutLiquiditySwapTimeoutEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            swap_hash: ctx.accounts.liquidity_escrow.swap_hash,
+            swap_id
+        });
+
+        Ok(())
+    }
+
+
+
     // Fees *********************************************************************************************************************
 
     pub fn distribute_fees(
@@ -978,107 +1500,221 @@ pub mod swap_pool_amplified {
 
         let swap_pool_state_account = &mut ctx.accounts.swap_pool_state_account;
 
-        let amplification_x64 = U256(swap_pool_state_account.amplification_x64);
-        let one_minus_amp_x64 = ONE_X64.checked_sub(amplification_x64).unwrap();
+        // 1 Account given per asset: the swap pool asset wallet
+        let asset_balances = get_pool_asset_balances(swap_pool_state_account, ctx.remaining_accounts)?;
 
-        let mut aggregate_weight_x64 = U256::from(0);
-        let mut calc_outstanding_units_x64: i128 = 0;
-    
-        for asset_index in 0..NUMASSETS {
+        // NOTE: fails if the balances do not hold any fees
+        swap_pool_state_account.pool_assets_eq_balances = calculation_helpers::calc_fee_distribution_eq_balances(
+            swap_pool_state_account.get_unit_tracker_x64(),
+            &asset_balances,
+            &swap_pool_state_account.pool_assets_eq_balances,
+            &swap_pool_state_account.pool_assets_weights,
+            ctx.accounts.swap_pool_token_mint.supply,
+            U256(swap_pool_state_account.amplification_x64)
+        ).map_err(ErrorCode::from)?;
 
-            if swap_pool_state_account.pool_assets_mints[asset_index].eq(&Pubkey::default()) {
-                break;
-            }
+        // TODO event
 
-            let swap_pool_asset_wallet = &ctx.remaining_accounts[asset_index];  // Verified in 'create_swap_pool_asset_wallet' step
-            
-            // Make sure the swap_pool_asset_wallet provided matches the expected pda
-            let seeds: &[&[u8]] = &[
-                &swap_pool_state_account.key().to_bytes(),
-                &swap_pool_state_account.pool_assets_mints[asset_index].key().to_bytes(),
-                POOL_ASSET_WALLET_SEED,
-                &[swap_pool_state_account.wallets_bumps[asset_index]]
-            ];
+        Ok(())
+    }
 
-            let expected_swap_pool_asset_wallet  = Pubkey::create_program_address(seeds, &ID).unwrap();
+    pub fn set_pool_fee(
+        ctx: Context<SetPoolFee>,
+        pool_fee_x64: u64
+    ) -> Result<()> {
 
-            if swap_pool_asset_wallet.key() != expected_swap_pool_asset_wallet {
-                return Err(error!(ErrorCode::InvalidSwapPoolAssetWalletAccount));
-            }
-    
-            let swap_pool_asset_wallet_as_token_account = TokenAccount::try_deserialize(&mut &swap_pool_asset_wallet.try_borrow_data()?[..])?;    
+        ctx.accounts.swap_pool_state_account.pool_fee_x64 = pool_fee_x64;
 
-            let asset_eq_balance = swap_pool_state_account.pool_assets_eq_balances[asset_index];
-            let asset_balance    = swap_pool_asset_wallet_as_token_account.amount;
-            
-            if asset_balance > asset_eq_balance {
-                calc_outstanding_units_x64 = calc_outstanding_units_x64.checked_add(
-                    calculation_helpers::calc_out_liquidity_swap_x64(
-                        U256::from(asset_balance - asset_eq_balance),
-                        U256::from(asset_eq_balance),
-                        U256::from(swap_pool_state_account.pool_assets_weights[asset_index]),
-                        one_minus_amp_x64
-                    )?.try_into().unwrap()
-                ).unwrap();
-            }
-            else {
-                calc_outstanding_units_x64 = calc_outstanding_units_x64.checked_sub(
-                    calculation_helpers::calc_out_liquidity_swap_x64(
-                        U256::from(asset_eq_balance - asset_balance),
-                        U256::from(asset_balance),
-                        U256::from(swap_pool_state_account.pool_assets_weights[asset_index]),
-                        one_minus_amp_x64
-                    )?.try_into().unwrap()
-                ).unwrap();
-            }
+        emit!(SetPoolFeeEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            pool_fee_x64
+        });
 
-            aggregate_weight_x64 = aggregate_weight_x64.checked_add(
-                U256::from(swap_pool_state_account.pool_assets_weights[asset_index]).checked_mul(
-                    pow_x64(
-                        U256::from(asset_eq_balance).shl(64), // No overflow guaranteed, going from u64 to u256
-                        one_minus_amp_x64
-                    ).unwrap()
-                ).unwrap()
-            ).unwrap();
-            
+        Ok(())
+    }
+
+    pub fn set_governance_fee(
+        ctx: Context<SetGovernanceFee>,
+        governance_fee_x64: u64
+    ) -> Result<()> {
+
+        if governance_fee_x64 > MAX_GOVERNANCE_FEE_X64 {
+            return Err(error!(ErrorCode::InvalidGovernanceFee));
         }
 
-        calc_outstanding_units_x64 = swap_pool_state_account.unit_tracker_x64.checked_sub(calc_outstanding_units_x64).unwrap();
+        ctx.accounts.swap_pool_state_account.governance_fee_x64 = governance_fee_x64;
 
-        // Compute the pool tokens corresponding to the first asset of the pool
-        let asset_0_pool_tokens = calculation_helpers::calc_in_liquidity_swap(
-            U256::from(calc_outstanding_units_x64),
-            U256::from(swap_pool_state_account.pool_assets_eq_balances[0]),
-            aggregate_weight_x64,
-            amplification_x64
-        )?;
+        emit!(SetGovernanceFeeEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            governance_fee_x64
+        });
 
-        // Compute the total pool tokens from the ones corresponding to the first asset
-        let pool_tokens_supply = U256::from(ctx.accounts.swap_pool_token_mint.supply);
-        let total_pool_tokens = asset_0_pool_tokens
-            .checked_mul(pool_tokens_supply).unwrap()
-            .checked_div(U256::from(swap_pool_state_account.pool_assets_eq_balances[0])).unwrap();
+        Ok(())
+    }
 
-        // Update the eq balances
-        swap_pool_state_account.pool_assets_eq_balances[0] = swap_pool_state_account.pool_assets_eq_balances[0].checked_add(
-            asset_0_pool_tokens.as_u64()
-        ).unwrap();
+    pub fn set_fee_administrator(
+        ctx: Context<SetFeeAdministrator>,
+        fee_administrator: Pubkey
+    ) -> Result<()> {
 
-        for asset_index in 1..NUMASSETS {
+        ctx.accounts.swap_pool_state_account.fee_administrator = fee_administrator;
 
-            if swap_pool_state_account.pool_assets_mints[asset_index].eq(&Pubkey::default()) {
-                break;
-            }
+        emit!(SetFeeAdministratorEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            fee_administrator
+        });
 
-            let asset_eq_balance = swap_pool_state_account.pool_assets_eq_balances[asset_index];
+        Ok(())
+    }
 
-            swap_pool_state_account.pool_assets_eq_balances[asset_index] = asset_eq_balance.checked_add(
-                asset_eq_balance.checked_mul(total_pool_tokens.as_u64()).unwrap()
-            ).unwrap();
+    pub fn set_governance_fee_destination(
+        ctx: Context<SetGovernanceFeeDestination>,
+        governance_fee_destination: Pubkey
+    ) -> Result<()> {
+
+        ctx.accounts.swap_pool_state_account.governance_fee_destination = governance_fee_destination;
+
+        emit!(SetGovernanceFeeDestinationEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            governance_fee_destination
+        });
+
+        Ok(())
+    }
+
+
+
+    // Governance ***************************************************************************************************************
+
+    pub fn set_max_units_inflow(
+        ctx: Context<SetMaxUnitsInflow>,
+        max_units_inflow_x64: U256Limbs
+    ) -> Result<()> {
+
+        ctx.accounts.swap_pool_state_account.max_units_inflow_x64 = max_units_inflow_x64;
+
+        emit!(SetMaxUnitsInflowEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            max_units_inflow_x64: max_units_inflow_x64.limbs
+        });
+
+        Ok(())
+    }
 
+    /// Moves the amplification linearly from the current one to 1/'target_k', reaching it at 'target_timestamp'. The
+    /// amplification of local-only pools is moved towards the target whenever the pool is used (and on update_amplification).
+    /// The amplification of cross chain pools is only moved by update_amplification, as the unit tracker must be updated
+    /// with the pool balances every time the amplification changes.
+    pub fn set_amplification(
+        ctx: Context<SetAmplification>,
+        target_k: u64,
+        target_timestamp: u64
+    ) -> Result<()> {
+
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+
+        if target_timestamp < current_timestamp + MIN_ADJUSTMENT_TIME || target_timestamp > current_timestamp + MAX_ADJUSTMENT_TIME {
+            return Err(error!(ErrorCode::InvalidAdjustmentTargetTimestamp));
         }
 
-        // TODO event
+        let swap_pool_state_account = &mut ctx.accounts.swap_pool_state_account;
+
+        // Apply any ongoing adjustment, as the new one starts from the current amplification
+        // NOTE: for cross chain pools, the new adjustment starts from the amplification set by the last update_amplification
+        swap_pool_state_account.update_amplification(current_timestamp)?;
+
+        // The amplification must be < 1, and cannot change by more than a factor of 2 to protect the liquidity providers
+        if target_k <= 1 {
+            return Err(error!(ErrorCode::InvalidTargetAmplification));
+        }
+
+        let current_amplification_x64 = U256(swap_pool_state_account.amplification_x64);
+        let target_amplification_x64 = ONE_X64 / target_k;
+
+        if target_amplification_x64 > current_amplification_x64 * 2 || target_amplification_x64 * 2 < current_amplification_x64 {
+            return Err(error!(ErrorCode::InvalidTargetAmplification));
+        }
+
+        swap_pool_state_account.target_amplification_x64                = target_amplification_x64.0;
+        swap_pool_state_account.amplification_adjustment_target_timestamp = target_timestamp;
+        swap_pool_state_account.amplification_adjustment_last_timestamp   = current_timestamp;
+
+        emit!(SetAmplificationEvent {
+            swap_pool: swap_pool_state_account.key(),
+            target_amplification_x64: target_amplification_x64.0,
+            target_timestamp
+        });
+
+        Ok(())
+    }
+
+    /// Moves the amplification towards the target set by set_amplification. Can be called by anyone.
+    /// For cross chain pools, the swap pool asset wallets must be given (in asset order) as remaining accounts, as the unit
+    /// tracker and the units inflow security limit are updated with the amplification.
+    pub fn update_amplification(
+        ctx: Context<UpdateAmplification>
+    ) -> Result<()> {
+
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+
+        if ctx.accounts.swap_pool_state_account.local_only {
+            return ctx.accounts.swap_pool_state_account.update_amplification(current_timestamp);
+        }
+
+        let asset_balances = get_pool_asset_balances(&ctx.accounts.swap_pool_state_account, ctx.remaining_accounts)?;
+
+        ctx.accounts.swap_pool_state_account.update_cross_chain_amplification(current_timestamp, &asset_balances)?;
+
+        Ok(())
+    }
+
+    /// Pausing blocks deposits, swaps, liquidity swaps and underwrites. Withdrawals and the acknowledgement/timeout of
+    /// pending swaps are still allowed, so that users can always recover their assets.
+    pub fn set_paused(
+        ctx: Context<SetPaused>,
+        paused: bool
+    ) -> Result<()> {
+
+        ctx.accounts.swap_pool_state_account.paused = paused;
+
+        emit!(SetPausedEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            paused
+        });
+
+        Ok(())
+    }
+
+    /// First step of the dao_authority handover. The new authority must accept it with accept_dao_authority. Setting
+    /// the pending authority to the default pubkey cancels an ongoing handover.
+    pub fn transfer_dao_authority(
+        ctx: Context<TransferDaoAuthority>,
+        pending_dao_authority: Pubkey
+    ) -> Result<()> {
+
+        ctx.accounts.swap_pool_state_account.pending_dao_authority = pending_dao_authority;
+
+        emit!(TransferDaoAuthorityEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            pending_dao_authority
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_dao_authority(
+        ctx: Context<AcceptDaoAuthority>
+    ) -> Result<()> {
+
+        let swap_pool_state_account = &mut ctx.accounts.swap_pool_state_account;
+
+        swap_pool_state_account.dao_authority         = swap_pool_state_account.pending_dao_authority;
+        swap_pool_state_account.pending_dao_authority = Pubkey::default();
+
+        emit!(AcceptDaoAuthorityEvent {
+            swap_pool: swap_pool_state_account.key(),
+            dao_authority: swap_pool_state_account.dao_authority
+        });
 
         Ok(())
     }
@@ -1086,13 +1722,61 @@ pub mod swap_pool_amplified {
 }
 
 
+// Reads the balances of the pool asset wallets, given in asset order
+fn get_pool_asset_balances(
+    swap_pool_state_account: &Account<SwapPoolState>,
+    swap_pool_asset_wallets: &[AccountInfo]
+) -> Result<Vec<u64>> {
+
+    let asset_count = swap_pool_state_account.pool_assets_mints.len();
+    if swap_pool_asset_wallets.len() != asset_count {
+        return Err(error!(ErrorCode::InvalidRemainingAccountsCount));
+    }
+
+    let mut asset_balances: Vec<u64> = Vec::with_capacity(asset_count);
+    for (asset_index, swap_pool_asset_wallet) in swap_pool_asset_wallets.iter().enumerate() {
+
+        // Make sure the swap_pool_asset_wallet provided matches the expected pda
+        let seeds: &[&[u8]] = &[
+            &swap_pool_state_account.key().to_bytes(),
+            &swap_pool_state_account.pool_assets_mints[asset_index].key().to_bytes(),
+            POOL_ASSET_WALLET_SEED,
+            &[swap_pool_state_account.wallets_bumps[asset_index]]
+        ];
+
+        let expected_swap_pool_asset_wallet  = Pubkey::create_program_address(seeds, &ID).unwrap();
+
+        if swap_pool_asset_wallet.key() != expected_swap_pool_asset_wallet {
+            return Err(error!(ErrorCode::InvalidSwapPoolAssetWalletAccount));
+        }
+
+        let swap_pool_asset_wallet_as_token_account = TokenAccount::try_deserialize(&mut &swap_pool_asset_wallet.try_borrow_data()?[..])?;
+
+        asset_balances.push(swap_pool_asset_wallet_as_token_account.amount);
+    }
+
+    Ok(asset_balances)
+}
+
+// Reads the id of the swap message sent by the IBC interface (returned by cross_chain_swap and cross_chain_liquidity_swap)
+fn get_interface_swap_id(ibc_interface_program: &Pubkey) -> Result<[u8; 32]> {
+    match get_return_data() {
+        Some((program_id, data)) if program_id.eq(ibc_interface_program) && data.len() == 32 => {
+            Ok(data[..].try_into().unwrap())
+        },
+        _ => Err(error!(ErrorCode::InvalidIBCInterfaceReturnData))
+    }
+}
+
+
 
 #[derive(Accounts)]
+#[instruction(k: u64, initial_asset_balances: Vec<u64>)]
 pub struct Initialize<'info> {
     #[account(mut)]
     pub setup_master: Signer<'info>,
     pub dao_authority: Signer<'info>,
-    #[account(init, payer = setup_master, space = SwapPoolState::LEN)]  // NOTE: LEN includes discriminator
+    #[account(init, payer = setup_master, space = SwapPoolState::space(initial_asset_balances.len()))]  // NOTE: space includes discriminator
     pub swap_pool_state_account: Account<'info, SwapPoolState>,
 
     // Pool token accounts
@@ -1235,7 +1919,8 @@ pub struct CreateConnection<'info> {
         constraint =
             swap_pool_state_account.setup_master == authority.key() || 
             swap_pool_state_account.dao_authority == authority.key()
-            @ ErrorCode::InvalidAuthority
+            @ ErrorCode::InvalidAuthority,
+        constraint = !swap_pool_state_account.local_only @ ErrorCode::LocalOnlyPool
     )]
     pub swap_pool_state_account: Account<'info, SwapPoolState>,
 
@@ -1243,15 +1928,39 @@ pub struct CreateConnection<'info> {
     pub ibc_interface_program: Program<'info, IbcInterface>,
     /// CHECK: Safe, as we are passing it directly to the IBCInterface
     pub swap_pool_authority: UncheckedAccount<'info>,
-    #[account(mut)]
-    pub rent_payer: Signer<'info>,
+    #[account(mut)]
+    pub rent_payer: Signer<'info>,
+    /// CHECK: Safe, as we are passing it directly to the IBCInterface
+    pub interface_state_account: UncheckedAccount<'info>,
+    #[account(mut)]
+    /// CHECK: Safe, as we are passing it directly to the IBCInterface
+    pub connection_state_account: UncheckedAccount<'info>,
+    /// CHECK: Safe, as we are passing it directly to the IBCInterface
+    pub system_program: UncheckedAccount<'info>,
+}
+
+
+#[derive(Accounts)]
+pub struct SetConnectionEnabled<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        constraint =
+            swap_pool_state_account.setup_master == authority.key() || 
+            swap_pool_state_account.dao_authority == authority.key()
+            @ ErrorCode::InvalidAuthority,
+        constraint = !swap_pool_state_account.local_only @ ErrorCode::LocalOnlyPool
+    )]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+
+    // Accounts for IBCInterface.setConnectionEnabled()
+    pub ibc_interface_program: Program<'info, IbcInterface>,
+    /// CHECK: Safe, as we are passing it directly to the IBCInterface
+    pub swap_pool_authority: UncheckedAccount<'info>,
     /// CHECK: Safe, as we are passing it directly to the IBCInterface
     pub interface_state_account: UncheckedAccount<'info>,
     #[account(mut)]
     /// CHECK: Safe, as we are passing it directly to the IBCInterface
-    pub connection_state_account: UncheckedAccount<'info>,
-    /// CHECK: Safe, as we are passing it directly to the IBCInterface
-    pub system_program: UncheckedAccount<'info>,
+    pub connection_state_account: UncheckedAccount<'info>
 }
 
 
@@ -1275,10 +1984,56 @@ pub struct FinishSetup<'info> {
 }
 
 
+#[derive(Accounts)]
+pub struct CancelSetup<'info> {
+    // ! Make sure the provided setup_master matches the one saved in swap_pool_state_account (reset once the setup is finished)
+    #[account(mut, has_one = setup_master @ ErrorCode::InvalidSetupAuthority, close = setup_master)]
+    pub swap_pool_state_account: Box<Account<'info, SwapPoolState>>,
+    #[account(mut)]
+    pub setup_master: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            &swap_pool_state_account.key().to_bytes(),
+            POOL_TOKEN_MINT_SEED
+        ],
+        bump = swap_pool_state_account.token_mint_bump
+    )]
+    pub swap_pool_token_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut)]
+    pub depositor_pool_token_wallet_authority: Signer<'info>,      // Receives the rent of the depositor_pool_token_wallet
+    #[account(
+        mut,
+        token::mint = swap_pool_token_mint,
+        token::authority = depositor_pool_token_wallet_authority
+    )]
+    pub depositor_pool_token_wallet: Account<'info, TokenAccount>, // The token wallet holding the pool tokens minted on initialize (closed)
+
+    #[account(
+        seeds = [
+            &swap_pool_state_account.key().to_bytes(),
+            POOL_AUTHORITY_SEED
+        ],
+        bump = swap_pool_state_account.authority_bump
+    )]
+    /// CHECK: Safe, as we are not reading from the account.
+    pub swap_pool_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>
+}
+
+
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
-    #[account(mut)]
+    // NOTE: deposits are only allowed once the setup is finished, so that the setup cannot be cancelled with deposited assets
+    #[account(
+        mut,
+        constraint = swap_pool_state_account.setup_master == Pubkey::default() @ ErrorCode::SetupNotFinished,
+        constraint = !swap_pool_state_account.paused @ ErrorCode::PoolPaused
+    )]
     pub swap_pool_state_account: Account<'info, SwapPoolState>,
 
     #[account(mut)]
@@ -1367,7 +2122,7 @@ impl<'info> Withdraw<'info> {
 
 #[derive(Accounts)]
 pub struct LocalSwap<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = !swap_pool_state_account.paused @ ErrorCode::PoolPaused)]
     pub swap_pool_state_account: Box<Account<'info, SwapPoolState>>,
 
     // Input asset
@@ -1406,6 +2161,14 @@ pub struct LocalSwap<'info> {
     )]
     pub swap_pool_output_asset_wallet: Account<'info, TokenAccount>,
 
+    // Governance fee wallet
+    #[account(
+        mut,
+        constraint = governance_fee_wallet.owner == swap_pool_state_account.governance_fee_destination @ ErrorCode::InvalidGovernanceFeeWalletAccount,
+        constraint = governance_fee_wallet.mint == input_asset_mint.key() @ ErrorCode::InvalidGovernanceFeeWalletAccount
+    )]
+    pub governance_fee_wallet: Box<Account<'info, TokenAccount>>,
+
     // Asset wallet authority
     #[account(
         seeds = [
@@ -1428,11 +2191,15 @@ pub struct LocalSwap<'info> {
     destination: Pubkey,
     amount: u64,
     min_output: [u64; 4],
-    escrow_nonce: u32
+    swap_hash: [u8; 32]
 )]
 pub struct OutSwap<'info> {
 
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = !swap_pool_state_account.paused @ ErrorCode::PoolPaused,
+        constraint = !swap_pool_state_account.local_only @ ErrorCode::LocalOnlyPool
+    )]
     pub swap_pool_state_account: Box<Account<'info, SwapPoolState>>,
 
     // Input asset
@@ -1453,6 +2220,14 @@ pub struct OutSwap<'info> {
     )]
     pub swap_pool_input_asset_wallet: Account<'info, TokenAccount>,
 
+    // Governance fee wallet
+    #[account(
+        mut,
+        constraint = governance_fee_wallet.owner == swap_pool_state_account.governance_fee_destination @ ErrorCode::InvalidGovernanceFeeWalletAccount,
+        constraint = governance_fee_wallet.mint == input_asset_mint.key() @ ErrorCode::InvalidGovernanceFeeWalletAccount
+    )]
+    pub governance_fee_wallet: Box<Account<'info, TokenAccount>>,
+
     pub token_program: Program<'info, Token>,
 
     // Escrow account
@@ -1462,7 +2237,7 @@ pub struct OutSwap<'info> {
         space = SwapEscrow::LEN,
         seeds = [
             &swap_pool_state_account.key().to_bytes().as_ref(),
-            &escrow_nonce.to_be_bytes().as_ref(),
+            swap_hash.as_ref(),
             &POOL_ESCROW_SEED,
         ],
         bump
@@ -1507,7 +2282,7 @@ pub struct OutSwap<'info> {
 
 #[derive(Accounts)]
 #[instruction(
-    escrow_nonce: u32
+    swap_id: [u8; 32]
 )]
 pub struct OutSwapAck<'info> {
 
@@ -1519,9 +2294,10 @@ pub struct OutSwapAck<'info> {
         mut,
         close = swap_escrow_rent_payer,
         has_one = swap_escrow_rent_payer @ ErrorCode::InvalidSwapEscrowRentPayerAccount,
+        constraint = swap_escrow.swap_id == swap_id @ ErrorCode::InvalidSwapId,    // ! The escrow must belong to the acknowledged/timed out swap
         seeds = [
             &swap_pool_state_account.key().to_bytes().as_ref(),
-            &escrow_nonce.to_be_bytes().as_ref(),
+            swap_escrow.swap_hash.as_ref(),
             &POOL_ESCROW_SEED
         ],
         bump = swap_escrow.bump
@@ -1543,7 +2319,7 @@ pub struct OutSwapAck<'info> {
 
 #[derive(Accounts)]
 #[instruction(
-    escrow_nonce: u32
+    swap_id: [u8; 32]
 )]
 pub struct OutSwapTimeout<'info> {
 
@@ -1586,9 +2362,10 @@ pub struct OutSwapTimeout<'info> {
         close = swap_escrow_rent_payer,
         has_one = swap_escrow_rent_payer @ ErrorCode::InvalidSwapEscrowRentPayerAccount,
         has_one = fallback_wallet @ ErrorCode::InvalidFallbackWalletAccount,
+        constraint = swap_escrow.swap_id == swap_id @ ErrorCode::InvalidSwapId,    // ! The escrow must belong to the acknowledged/timed out swap
         seeds = [
             &swap_pool_state_account.key().to_bytes().as_ref(),
-            &escrow_nonce.to_be_bytes().as_ref(),
+            swap_escrow.swap_hash.as_ref(),
             &POOL_ESCROW_SEED
         ],
         bump = swap_escrow.bump
@@ -1609,7 +2386,14 @@ pub struct OutSwapTimeout<'info> {
 
 #[derive(Accounts)]
 pub struct InSwap<'info> {
-    #[account(mut, has_one = ibc_interface @ ErrorCode::InvalidIBCInterfaceAccount)]
+    // NOTE: the ibc_interface is verified with a raw constraint (rather than has_one, which Anchor always evaluates first)
+    // so that local-only pools fail with LocalOnlyPool
+    #[account(
+        mut,
+        constraint = !swap_pool_state_account.local_only @ ErrorCode::LocalOnlyPool,
+        constraint = !swap_pool_state_account.paused @ ErrorCode::PoolPaused,
+        constraint = swap_pool_state_account.ibc_interface == ibc_interface.key() @ ErrorCode::InvalidIBCInterfaceAccount
+    )]
     pub swap_pool_state_account: Box<Account<'info, SwapPoolState>>,
 
     // Output asset
@@ -1649,9 +2433,104 @@ pub struct InSwap<'info> {
 
 
 #[derive(Accounts)]
-pub struct OutLiquiditySwap<'info> {
+pub struct UnderwriteAsset<'info> {
+    // NOTE: the ibc_interface is verified with a raw constraint (rather than has_one, which Anchor always evaluates first)
+    // so that local-only pools fail with LocalOnlyPool
+    #[account(
+        mut,
+        constraint = !swap_pool_state_account.local_only @ ErrorCode::LocalOnlyPool,
+        constraint = !swap_pool_state_account.paused @ ErrorCode::PoolPaused,
+        constraint = swap_pool_state_account.ibc_interface == ibc_interface.key() @ ErrorCode::InvalidIBCInterfaceAccount
+    )]
+    pub swap_pool_state_account: Box<Account<'info, SwapPoolState>>,
+
+    // Output asset
+    #[account()]
+    pub output_asset_mint: Box<Account<'info, Mint>>,
+    #[account(
+        seeds = [
+            &swap_pool_state_account.key().to_bytes(),
+            &output_asset_mint.key().to_bytes(),
+            POOL_ASSET_WALLET_SEED
+        ],
+        bump = swap_pool_state_account.wallets_bumps[
+            swap_pool_state_account.get_asset_index(&output_asset_mint.key()).unwrap()
+        ]
+    )]
+    pub swap_pool_output_asset_wallet: Account<'info, TokenAccount>,
+
+    // Chain Interface
+    pub ibc_interface: Signer<'info>
+}
+
+
+#[derive(Accounts)]
+pub struct ReleaseUnderwriteAsset<'info> {
+    #[account(mut, has_one = ibc_interface @ ErrorCode::InvalidIBCInterfaceAccount)]
+    pub swap_pool_state_account: Box<Account<'info, SwapPoolState>>,
 
+    // Output asset
+    #[account()]
+    pub output_asset_mint: Box<Account<'info, Mint>>,
     #[account(mut)]
+    pub refund_wallet: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [
+            &swap_pool_state_account.key().to_bytes(),
+            &output_asset_mint.key().to_bytes(),
+            POOL_ASSET_WALLET_SEED
+        ],
+        bump = swap_pool_state_account.wallets_bumps[
+            swap_pool_state_account.get_asset_index(&output_asset_mint.key()).unwrap()
+        ]
+    )]
+    pub swap_pool_output_asset_wallet: Account<'info, TokenAccount>,
+
+    // Asset wallet authority
+    #[account(
+        seeds = [
+            &swap_pool_state_account.key().to_bytes(),
+            POOL_AUTHORITY_SEED
+        ],
+        bump = swap_pool_state_account.authority_bump
+    )]
+    /// CHECK: Safe, as we are not reading from the account.
+    pub swap_pool_authority: UncheckedAccount<'info>,
+
+    // Chain Interface
+    pub ibc_interface: Signer<'info>,
+
+    pub token_program: Program<'info, Token>
+}
+
+
+#[derive(Accounts)]
+pub struct DeleteUnderwriteAsset<'info> {
+    #[account(mut, has_one = ibc_interface @ ErrorCode::InvalidIBCInterfaceAccount)]
+    pub swap_pool_state_account: Box<Account<'info, SwapPoolState>>,
+
+    // Chain Interface
+    pub ibc_interface: Signer<'info>
+}
+
+
+#[derive(Accounts)]
+#[instruction(
+    chain: u64,
+    target_pool: Pubkey,
+    destination: Pubkey,
+    pool_tokens_amount: u64,
+    swap_hash: [u8; 32]
+)]
+pub struct OutLiquiditySwap<'info> {
+
+    #[account(
+        mut,
+        constraint = swap_pool_state_account.setup_master == Pubkey::default() @ ErrorCode::SetupNotFinished,
+        constraint = !swap_pool_state_account.paused @ ErrorCode::PoolPaused,
+        constraint = !swap_pool_state_account.local_only @ ErrorCode::LocalOnlyPool
+    )]
     pub swap_pool_state_account: Box<Account<'info, SwapPoolState>>,
 
     // Pool tokens
@@ -1670,6 +2549,22 @@ pub struct OutLiquiditySwap<'info> {
 
     pub token_program: Program<'info, Token>,
 
+    // Escrow account
+    #[account(
+        init,
+        payer = liquidity_escrow_rent_payer,
+        space = LiquidityEscrow::LEN,
+        seeds = [
+            &swap_pool_state_account.key().to_bytes().as_ref(),
+            swap_hash.as_ref(),
+            &POOL_LIQUIDITY_ESCROW_SEED,
+        ],
+        bump
+    )]
+    pub liquidity_escrow: Account<'info, LiquidityEscrow>,
+    #[account(mut)]
+    pub liquidity_escrow_rent_payer: Signer<'info>,
+
     pub ibc_interface_program: Program<'info, IbcInterface>,
 
     /// CHECK: Safe, as we are not reading from the account
@@ -1697,8 +2592,8 @@ pub struct OutLiquiditySwap<'info> {
     pub polymerase_ibc_data: Signer<'info>,
     #[account(mut)]
     pub polymerase_ibc_data_account_payer: Signer<'info>,    //TODO rename
-    /// CHECK: Safe, as we are not reading from the account
-    pub system_program: UncheckedAccount<'info> // Intentionally not Program<'info, System>, as we do not want to run any checks on the account (and hence minimize gas fees)
+
+    pub system_program: Program<'info, System>
 
 }
 
@@ -1728,8 +2623,139 @@ impl<'info> OutLiquiditySwap<'info> {
 
 
 #[derive(Accounts)]
-pub struct InLiquiditySwap<'info> {
+#[instruction(
+    swap_id: [u8; 32]
+)]
+pub struct OutLiquiditySwapAck<'info> {
+
     #[account(mut, has_one = ibc_interface @ ErrorCode::InvalidIBCInterfaceAccount)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+
+    // Escrow account
+    #[account(
+        mut,
+        close = liquidity_escrow_rent_payer,
+        has_one = liquidity_escrow_rent_payer @ ErrorCode::InvalidLiquidityEscrowRentPayerAccount,
+        constraint = liquidity_escrow.swap_id == swap_id @ ErrorCode::InvalidSwapId,    // ! The escrow must belong to the acknowledged/timed out swap
+        seeds = [
+            &swap_pool_state_account.key().to_bytes().as_ref(),
+            liquidity_escrow.swap_hash.as_ref(),
+            &POOL_LIQUIDITY_ESCROW_SEED
+        ],
+        bump = liquidity_escrow.bump
+    )]
+    pub liquidity_escrow: Account<'info, LiquidityEscrow>,
+
+    #[account(mut)]
+    /// CHECK: Must match the one saved in liquidity_escrow
+    pub liquidity_escrow_rent_payer: UncheckedAccount<'info>,
+
+    // Chain Interface
+    pub ibc_interface: Signer<'info>,
+
+    pub system_program: Program<'info, System> 
+
+}
+
+
+
+#[derive(Accounts)]
+#[instruction(
+    swap_id: [u8; 32]
+)]
+pub struct OutLiquiditySwapTimeout<'info> {
+
+    #[account(mut, has_one = ibc_interface @ ErrorCode::InvalidIBCInterfaceAccount)]
+    pub swap_pool_state_account: Box<Account<'info, SwapPoolState>>,
+
+    // Pool tokens
+    #[account(
+        mut,
+        seeds = [
+            &swap_pool_state_account.key().to_bytes(),
+            POOL_TOKEN_MINT_SEED
+        ],
+        bump = swap_pool_state_account.token_mint_bump
+    )]
+    pub swap_pool_token_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub fallback_wallet: Account<'info, TokenAccount>,
+
+    /// CHECK: Safe, as we are not reading from the account
+    #[account(
+        seeds = [
+            &swap_pool_state_account.key().to_bytes(),
+            POOL_AUTHORITY_SEED
+        ],
+        bump = swap_pool_state_account.authority_bump
+    )]
+    pub swap_pool_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    // Escrow account
+    #[account(
+        mut,
+        close = liquidity_escrow_rent_payer,
+        has_one = liquidity_escrow_rent_payer @ ErrorCode::InvalidLiquidityEscrowRentPayerAccount,
+        has_one = fallback_wallet @ ErrorCode::InvalidFallbackWalletAccount,
+        constraint = liquidity_escrow.swap_id == swap_id @ ErrorCode::InvalidSwapId,    // ! The escrow must belong to the acknowledged/timed out swap
+        seeds = [
+            &swap_pool_state_account.key().to_bytes().as_ref(),
+            liquidity_escrow.swap_hash.as_ref(),
+            &POOL_LIQUIDITY_ESCROW_SEED
+        ],
+        bump = liquidity_escrow.bump
+    )]
+    pub liquidity_escrow: Account<'info, LiquidityEscrow>,
+
+    #[account(mut)]
+    /// CHECK: Must match the one saved in liquidity_escrow
+    pub liquidity_escrow_rent_payer: UncheckedAccount<'info>,
+
+    // Chain Interface
+    pub ibc_interface: Signer<'info>,
+
+    pub system_program: Program<'info, System> 
+
+}
+
+impl<'info> OutLiquiditySwapTimeout<'info> {
+    
+    pub fn mint_pool_tokens_for_fallback_wallet(
+        &self,
+        amount: u64,
+    ) -> Result<()> {
+        token_utils::mint_tokens_using_pda_authority(
+            amount,    
+            self.fallback_wallet.to_account_info(),
+            self.swap_pool_token_mint.to_account_info(),
+            self.swap_pool_authority.to_account_info(),
+            &[
+                    &self.swap_pool_state_account.key().to_bytes(),             // SwapPool state account
+                    &POOL_AUTHORITY_SEED[..],                                   // Pool authority seed
+                    &[self.swap_pool_state_account.authority_bump.to_owned()]   // PDA bump
+                ],
+            self.token_program.to_account_info()
+        );
+
+        Ok(())
+    }
+}
+
+
+
+#[derive(Accounts)]
+pub struct InLiquiditySwap<'info> {
+    // NOTE: the ibc_interface is verified with a raw constraint (rather than has_one, which Anchor always evaluates first)
+    // so that local-only pools fail with LocalOnlyPool
+    #[account(
+        mut,
+        constraint = !swap_pool_state_account.local_only @ ErrorCode::LocalOnlyPool,
+        constraint = swap_pool_state_account.setup_master == Pubkey::default() @ ErrorCode::SetupNotFinished,
+        constraint = !swap_pool_state_account.paused @ ErrorCode::PoolPaused,
+        constraint = swap_pool_state_account.ibc_interface == ibc_interface.key() @ ErrorCode::InvalidIBCInterfaceAccount
+    )]
     pub swap_pool_state_account: Box<Account<'info, SwapPoolState>>,
 
     #[account(
@@ -1788,6 +2814,7 @@ impl<'info> InLiquiditySwap<'info> {
 
 #[derive(Accounts)]
 pub struct DistributeFees<'info> {
+    #[account(mut)]
     pub swap_pool_state_account: Box<Account<'info, SwapPoolState>>,
 
     #[account(
@@ -1803,54 +2830,187 @@ pub struct DistributeFees<'info> {
 
 
 
+#[derive(Accounts)]
+pub struct SetPoolFee<'info> {
+    #[account(mut, has_one = fee_administrator @ ErrorCode::InvalidFeeAdministrator)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+    pub fee_administrator: Signer<'info>,
+}
+
+
+#[derive(Accounts)]
+pub struct SetGovernanceFee<'info> {
+    #[account(mut, has_one = dao_authority @ ErrorCode::InvalidAuthority)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+    pub dao_authority: Signer<'info>,
+}
+
+
+#[derive(Accounts)]
+pub struct SetFeeAdministrator<'info> {
+    #[account(mut, has_one = dao_authority @ ErrorCode::InvalidAuthority)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+    pub dao_authority: Signer<'info>,
+}
+
+
+#[derive(Accounts)]
+pub struct SetGovernanceFeeDestination<'info> {
+    #[account(mut, has_one = dao_authority @ ErrorCode::InvalidAuthority)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+    pub dao_authority: Signer<'info>,
+}
+
+
+#[derive(Accounts)]
+pub struct SetMaxUnitsInflow<'info> {
+    #[account(
+        mut,
+        has_one = dao_authority @ ErrorCode::InvalidAuthority,
+        constraint = !swap_pool_state_account.local_only @ ErrorCode::LocalOnlyPool
+    )]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+    pub dao_authority: Signer<'info>,
+}
+
+
+#[derive(Accounts)]
+pub struct SetAmplification<'info> {
+    #[account(
+        mut,
+        has_one = dao_authority @ ErrorCode::InvalidAuthority
+    )]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+    pub dao_authority: Signer<'info>,
+}
+
+
+#[derive(Accounts)]
+pub struct UpdateAmplification<'info> {
+    #[account(mut)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+}
+
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(mut, has_one = dao_authority @ ErrorCode::InvalidAuthority)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+    pub dao_authority: Signer<'info>,
+}
+
+
+#[derive(Accounts)]
+pub struct TransferDaoAuthority<'info> {
+    #[account(mut, has_one = dao_authority @ ErrorCode::InvalidAuthority)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+    pub dao_authority: Signer<'info>,
+}
+
+
+#[derive(Accounts)]
+pub struct AcceptDaoAuthority<'info> {
+    #[account(mut, has_one = pending_dao_authority @ ErrorCode::InvalidAuthority)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+    pub pending_dao_authority: Signer<'info>,
+}
+
+
+
+/// U256 value (see shared_lib::u256::U256) as its little-endian u64 limbs. Defined within the program so that it can be
+/// described by the IDL (Anchor 0.25 cannot describe the U256 type of the shared library). Same layout as `[u64; 4]`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct U256Limbs {
+    pub limbs: [u64; 4]
+}
+
+impl From<U256> for U256Limbs {
+    fn from(value: U256) -> Self {
+        U256Limbs { limbs: value.0 }
+    }
+}
+
+impl From<U256Limbs> for U256 {
+    fn from(value: U256Limbs) -> Self {
+        U256(value.limbs)
+    }
+}
+
+
 #[account]
 pub struct SwapPoolState {
     pub setup_master: Pubkey,
-    pub dao_authority: Pubkey,     // TODO to be replaced/checked once the DAO structure gets finalized
+    pub dao_authority: Pubkey,
+    pub pending_dao_authority: Pubkey,  // Set by the dao_authority to hand over the authority, until accepted
+    pub paused: bool,
     pub ibc_interface: Pubkey,
-    pub pool_assets_mints: [Pubkey; NUMASSETS],
-    pub pool_assets_weights: [u64; NUMASSETS],
-    pub pool_assets_eq_balances: [u64; NUMASSETS],
+    pub local_only: bool,               // Set on finish_setup, disables the cross chain instructions and the security limit
+    pub pool_assets_mints: Vec<Pubkey>,
+    pub pool_assets_weights: Vec<u64>,
+    pub pool_assets_eq_balances: Vec<u64>,
     pub amplification_x64: [u64; 4],
+    pub target_amplification_x64: [u64; 4],
+    pub amplification_adjustment_target_timestamp: u64,   // 0 if no adjustment is in progress
+    pub amplification_adjustment_last_timestamp: u64,
+
+    pub escrowed_assets: Vec<u64>,
+    pub escrowed_pool_tokens: u64,      // Pool tokens of the pending liquidity swaps (burnt, minted back if the swaps time out)
 
-    pub escrowed_assets: [u64; NUMASSETS],
+    pub fee_administrator: Pubkey,
+    pub pool_fee_x64: u64,
+    pub governance_fee_x64: u64,
+    pub governance_fee_destination: Pubkey,
 
-    pub max_units_inflow_x64: [u64; 4],
-    pub units_inflow_amplification_x64: [u64; 4],
-    pub current_units_inflow_x64: [u64; 4],
+    pub max_units_inflow_x64: U256Limbs,
+    pub units_inflow_amplification_x64: U256Limbs,
+    pub current_units_inflow_x64: U256Limbs,
     pub current_units_inflow_timestamp: u64,
 
     pub current_liquidity_inflow: u64,
     pub current_liquidity_inflow_timestamp: u64,
 
-    pub unit_tracker_x64: i128,
+    // NOTE: I256 limbs (two's complement, see shared_lib::i256)
+    pub unit_tracker_x64: [u64; 4],
 
     pub authority_bump: u8,
     pub token_mint_bump: u8,
-    pub wallets_bumps: [u8; NUMASSETS]
+    pub wallets_bumps: Vec<u8>
 }
 
 impl SwapPoolState {
-    pub const LEN: usize = 
-        8               // discriminator
-        + 32            // setup_master
-        + 32            // dao_authority
-        + 32            // ibc_interface
-        + 32*NUMASSETS  // pool_assets_mints
-        + 8*NUMASSETS   // pool_assets_weights
-        + 8*NUMASSETS   // pool_assets_eq_balances
-        + 32            // amplification_x64
-        + 8*NUMASSETS   // escrowed_assets
-        + 8*4           // max_units_inflow_x64
-        + 8*4           // units_inflow_amplification_x64
-        + 8*4           // current_units_inflow_x64
-        + 8             // current_units_inflow_timestamp
-        + 8             // current_liquidity_inflow
-        + 8             // current_liquidity_inflow_timestamp
-        + 4*4           // unit_tracker_x64
-        + 1             // authority_bump
-        + 1             // token_mint_bump
-        + NUMASSETS;    // wallets_bumps
+    pub fn space(asset_count: usize) -> usize {
+        // NOTE: Vec fields are serialized with a 4 byte length prefix
+        8                           // discriminator
+            + 32                    // setup_master
+            + 32                    // dao_authority
+            + 32                    // pending_dao_authority
+            + 1                     // paused
+            + 32                    // ibc_interface
+            + 1                     // local_only
+            + 4 + 32*asset_count    // pool_assets_mints
+            + 4 + 8*asset_count     // pool_assets_weights
+            + 4 + 8*asset_count     // pool_assets_eq_balances
+            + 32                    // amplification_x64
+            + 32                    // target_amplification_x64
+            + 8                     // amplification_adjustment_target_timestamp
+            + 8                     // amplification_adjustment_last_timestamp
+            + 4 + 8*asset_count     // escrowed_assets
+            + 8                     // escrowed_pool_tokens
+            + 32                    // fee_administrator
+            + 8                     // pool_fee_x64
+            + 8                     // governance_fee_x64
+            + 32                    // governance_fee_destination
+            + 8*4                   // max_units_inflow_x64
+            + 8*4                   // units_inflow_amplification_x64
+            + 8*4                   // current_units_inflow_x64
+            + 8                     // current_units_inflow_timestamp
+            + 8                     // current_liquidity_inflow
+            + 8                     // current_liquidity_inflow_timestamp
+            + 8*4                   // unit_tracker_x64
+            + 1                     // authority_bump
+            + 1                     // token_mint_bump
+            + 4 + asset_count       // wallets_bumps
+    }
 
 
     pub fn get_asset_index(&self, asset: &Pubkey) -> Option<usize> {
@@ -1858,13 +3018,149 @@ impl SwapPoolState {
     }
 
 
+    /// Moves the amplification of local-only pools towards the target amplification set by set_amplification. Must be
+    /// called before using the amplification.
+    /// NOTE: does nothing for cross chain pools, whose amplification is only moved by update_cross_chain_amplification.
+    pub fn update_amplification(
+        &mut self,
+        current_timestamp: u64
+    ) -> Result<()> {
+
+        if !self.local_only {
+            return Ok(());
+        }
+
+        self.move_amplification(current_timestamp)?;
+
+        Ok(())
+    }
+
+
+    /// Moves the amplification of a cross chain pool towards the target amplification set by set_amplification, and
+    /// updates the values derived from it:
+    /// - The unit tracker is moved by the change of the units implied by the pool balances (sum of WA * (At^(1-k) - A0^(1-k))),
+    ///   so that the units which are not accounted for by the unit tracker (i.e. the pool fees) are kept.
+    /// - The units inflow amplification and the units inflow limit are recomputed as on initialize, using the eq balances.
+    pub fn update_cross_chain_amplification(
+        &mut self,
+        current_timestamp: u64,
+        asset_balances: &[u64]
+    ) -> Result<()> {
+
+        let previous_amplification_x64 = U256(self.amplification_x64);
+
+        if !self.move_amplification(current_timestamp)? {
+            return Ok(());
+        }
+
+        let amplification_x64 = U256(self.amplification_x64);
+
+        let previous_outstanding_units_x64 = calculation_helpers::calc_outstanding_units_x64(
+            asset_balances,
+            &self.pool_assets_eq_balances,
+            &self.pool_assets_weights,
+            previous_amplification_x64
+        ).map_err(ErrorCode::from)?;
+
+        let outstanding_units_x64 = calculation_helpers::calc_outstanding_units_x64(
+            asset_balances,
+            &self.pool_assets_eq_balances,
+            &self.pool_assets_weights,
+            amplification_x64
+        ).map_err(ErrorCode::from)?;
+
+        let unit_tracker_x64 = self.get_unit_tracker_x64()
+            .checked_sub(previous_outstanding_units_x64)
+            .and_then(|unit_tracker_x64| unit_tracker_x64.checked_add(outstanding_units_x64))
+            .map_err(ErrorCode::from)?;
+
+        self.unit_tracker_x64 = unit_tracker_x64.0.0;
+
+        let (units_inflow_amplification_x64, max_units_inflow_x64) = calculation_helpers::calc_max_units_inflow_x64(
+            &self.pool_assets_weights,
+            &self.pool_assets_eq_balances,
+            amplification_x64
+        ).map_err(ErrorCode::from)?;
+
+        self.units_inflow_amplification_x64 = units_inflow_amplification_x64.into();
+        self.max_units_inflow_x64           = max_units_inflow_x64.into();
+
+        Ok(())
+    }
+
+
+    /// Moves the amplification towards the target amplification. Returns whether the amplification has been moved.
+    fn move_amplification(
+        &mut self,
+        current_timestamp: u64
+    ) -> Result<bool> {
+
+        let target_timestamp = self.amplification_adjustment_target_timestamp;
+        let last_timestamp   = self.amplification_adjustment_last_timestamp;
+
+        // Nothing to do if there is no adjustment in progress, or if it has already been updated at this time
+        if target_timestamp == 0 || current_timestamp <= last_timestamp {
+            return Ok(false);
+        }
+
+        // NOTE: target_timestamp > last_timestamp is guaranteed, as the adjustment is cleared once the target is reached
+        let amplification_x64 = calculation_helpers::calc_amplification_adjustment(
+            U256(self.amplification_x64),
+            U256(self.target_amplification_x64),
+            current_timestamp - last_timestamp,
+            target_timestamp - last_timestamp
+        ).map_err(ErrorCode::from)?;
+
+        self.amplification_x64 = amplification_x64.0;
+
+        // Finish the adjustment once the target amplification is reached
+        if current_timestamp >= target_timestamp {
+            self.amplification_adjustment_target_timestamp = 0;
+        }
+
+        self.amplification_adjustment_last_timestamp = current_timestamp;
+
+        Ok(true)
+    }
+
+
+    pub fn get_unit_tracker_x64(&self) -> I256 {
+        I256(U256(self.unit_tracker_x64))
+    }
+
+
+    /// Track the units that have been sent (increase) or received (decrease) by the pool.
+    pub fn update_unit_tracker(
+        &mut self,
+        units_x64: U256,
+        increase: bool
+    ) -> Result<()> {
+
+        let units_x64 = I256::from_unsigned(units_x64).map_err(ErrorCode::from)?;
+
+        let unit_tracker_x64 = match increase {
+            true  => self.get_unit_tracker_x64().checked_add(units_x64),
+            false => self.get_unit_tracker_x64().checked_sub(units_x64)
+        }.map_err(ErrorCode::from)?;
+
+        self.unit_tracker_x64 = unit_tracker_x64.0.0;
+
+        Ok(())
+    }
+
+
     pub fn update_units_inflow(
         &mut self,
         units_inflow_x64: U256,
         current_timestamp: u64
     ) -> Result<()> {
 
-        let max_units_inflow_x64 = U256(self.max_units_inflow_x64);
+        // No security limit is maintained for local-only pools
+        if self.local_only {
+            return Ok(());
+        }
+
+        let max_units_inflow_x64 = U256::from(self.max_units_inflow_x64);
 
         // If more time has passed since the last update than DECAYRATE, the current inflow state does not matter (it has fully decayed)
         if current_timestamp > self.current_units_inflow_timestamp + DECAYRATE {
@@ -1872,14 +3168,14 @@ impl SwapPoolState {
                 return Err(error!(ErrorCode::SwapLimitExceeded));
             }
 
-            self.current_units_inflow_x64       = units_inflow_x64.0;
+            self.current_units_inflow_x64       = units_inflow_x64.into();
             self.current_units_inflow_timestamp = current_timestamp;
 
             return Ok(());
         }
 
         // Compute how much inflow has decayed since last update
-        let current_units_inflow_x64 = U256(self.current_units_inflow_x64);
+        let current_units_inflow_x64 = U256::from(self.current_units_inflow_x64);
 
         let decayed_inflow = max_units_inflow_x64.checked_mul(
             U256::from(current_timestamp.checked_sub(self.current_units_inflow_timestamp).unwrap())  // TODO checked_sub required?
@@ -1891,7 +3187,7 @@ impl SwapPoolState {
                 return Err(error!(ErrorCode::SwapLimitExceeded));
             }
 
-            self.current_units_inflow_x64 = units_inflow_x64.0;
+            self.current_units_inflow_x64 = units_inflow_x64.into();
         }
         // If some of the current inflow still matters
         else {
@@ -1901,7 +3197,7 @@ impl SwapPoolState {
                 return Err(error!(ErrorCode::SwapLimitExceeded));
             }
 
-            self.current_units_inflow_x64 = new_net_units_inflow_x64.0;
+            self.current_units_inflow_x64 = new_net_units_inflow_x64.into();
         }
 
         self.current_units_inflow_timestamp = current_timestamp;
@@ -1917,6 +3213,11 @@ impl SwapPoolState {
         current_timestamp: u64
     ) -> Result<()> {
 
+        // No security limit is maintained for local-only pools
+        if self.local_only {
+            return Ok(());
+        }
+
         // Allows 1/3 of the pool to be drained through liquidity swaps
         let max_pool_tokens_flow = current_pool_token_supply / 2;
 
@@ -1976,8 +3277,8 @@ impl SwapPoolState {
         if old_balance < new_balance {
             return Ok(
                 U256::from(self.pool_assets_weights[asset_index]).checked_mul(
-                    pow_x64(U256::from(new_balance).shl(64), one_minus_amp).unwrap().checked_sub(
-                        pow_x64(U256::from(old_balance).shl(64), one_minus_amp).unwrap()
+                    pow_x64(U256::from(new_balance).shl(64), one_minus_amp).map_err(ErrorCode::from)?.checked_sub(
+                        pow_x64(U256::from(old_balance).shl(64), one_minus_amp).map_err(ErrorCode::from)?
                     ).unwrap()
                 ).unwrap()
             );
@@ -1985,8 +3286,8 @@ impl SwapPoolState {
 
         Ok(
             U256::from(self.pool_assets_weights[asset_index]).checked_mul(
-                pow_x64(U256::from(old_balance).shl(64), one_minus_amp).unwrap().checked_sub(
-                    pow_x64(U256::from(new_balance).shl(64), one_minus_amp).unwrap()
+                pow_x64(U256::from(old_balance).shl(64), one_minus_amp).map_err(ErrorCode::from)?.checked_sub(
+                    pow_x64(U256::from(new_balance).shl(64), one_minus_amp).map_err(ErrorCode::from)?
                 ).unwrap()
             ).unwrap()
         )
@@ -1997,8 +3298,11 @@ impl SwapPoolState {
 
 #[account]
 pub struct SwapEscrow {
+    pub swap_hash: [u8; 32],        // Used to derive the escrow account
+    pub swap_id: [u8; 32],          // Id of the swap message, to match its acknowledgement/timeout with the escrow
     pub amount: u64,
     pub asset_index: u8,
+    pub units_x64: U256Limbs,       // Units sent, returned to the unit tracker if the swap times out
     pub fallback_wallet: Pubkey,
     pub swap_escrow_rent_payer: Pubkey,
     pub bump: u8,
@@ -2007,24 +3311,110 @@ pub struct SwapEscrow {
 impl SwapEscrow {
     pub const LEN: usize = 
         8       // discriminator
+        + 32    // swap_hash
+        + 32    // swap_id
         + 8     // amount
         + 1     // asset_index
+        + 8*4   // units_x64
         + 32    // fallback_wallet
         + 32    // rent_receiver
         + 1;    // bump
+
+    // The swap hash is used to derive the escrow account of a cross chain swap. The swap acknowledgement/timeout is matched with
+    // its escrow via the swap id instead (derived from the IBC payload like on EVM, and saved on the escrow).
+    // NOTE: unlike the swap id, the swap hash cannot depend on the swapped units nor the slot, as the escrow address must be known
+    // before submitting the transaction. The (newly created) polymerase_ibc_data account is used to guarantee uniqueness instead.
+    pub fn compute_swap_hash(
+        swap_pool: &Pubkey,
+        chain: u64,
+        target_pool: &Pubkey,
+        to_asset_index: u8,
+        destination: &Pubkey,
+        amount: u64,
+        min_output: &[u64; 4],
+        input_asset_mint: &Pubkey,
+        fallback_wallet: &Pubkey,
+        polymerase_ibc_data: &Pubkey
+    ) -> [u8; 32] {
+        hashv(&[
+            &swap_pool.to_bytes(),
+            &chain.to_be_bytes(),
+            &target_pool.to_bytes(),
+            &[to_asset_index],
+            &destination.to_bytes(),
+            &amount.to_be_bytes(),
+            &min_output.iter().flat_map(|el| el.to_be_bytes()).collect::<Vec<u8>>(),
+            &input_asset_mint.to_bytes(),
+            &fallback_wallet.to_bytes(),
+            &polymerase_ibc_data.to_bytes()
+        ]).to_bytes()
+    }
+}
+
+
+
+#[account]
+pub struct LiquidityEscrow {
+    pub swap_hash: [u8; 32],        // Used to derive the escrow account
+    pub swap_id: [u8; 32],          // Id of the swap message, to match its acknowledgement/timeout with the escrow
+    pub pool_token_amount: u64,
+    pub eq_balances_deltas: [u64; MAX_ASSETS],
+    pub fallback_wallet: Pubkey,
+    pub liquidity_escrow_rent_payer: Pubkey,
+    pub bump: u8,
+}
+
+impl LiquidityEscrow {
+    pub const LEN: usize = 
+        8       // discriminator
+        + 32    // swap_hash
+        + 32    // swap_id
+        + 8     // pool_token_amount
+        + 8*MAX_ASSETS  // eq_balances_deltas
+        + 32    // fallback_wallet
+        + 32    // liquidity_escrow_rent_payer
+        + 1;    // bump
+
+    // Equivalent to SwapEscrow::compute_swap_hash, for liquidity swaps.
+    pub fn compute_liquidity_swap_hash(
+        swap_pool: &Pubkey,
+        chain: u64,
+        target_pool: &Pubkey,
+        destination: &Pubkey,
+        pool_tokens_amount: u64,
+        fallback_wallet: &Pubkey,
+        polymerase_ibc_data: &Pubkey
+    ) -> [u8; 32] {
+        hashv(&[
+            &swap_pool.to_bytes(),
+            &chain.to_be_bytes(),
+            &target_pool.to_bytes(),
+            &destination.to_bytes(),
+            &pool_tokens_amount.to_be_bytes(),
+            &fallback_wallet.to_bytes(),
+            &polymerase_ibc_data.to_bytes()
+        ]).to_bytes()
+    }
 }
 
 
 // Events ***********************************************************************************************************************
 
+#[event]
+pub struct CancelSetupEvent {
+    swap_pool: Pubkey,
+    returned_asset_amounts: Vec<u64>,
+    burnt_pool_token_amount: u64
+}
+
 // TODO add assets weights to events?
 // TODO add asset mints to deposit event?
 #[event]
 pub struct DepositEvent {
     swap_pool: Pubkey,
 
-    deposited_asset_amounts: [u64; NUMASSETS],
-    depositor_asset_wallets: [Pubkey; NUMASSETS],
+    deposited_asset_amounts: Vec<u64>,
+    depositor_asset_wallets: Vec<Pubkey>,
 
     withdrawn_pool_token_amount: u64,
     depositor_pool_token_wallet: Pubkey
@@ -2035,8 +3425,8 @@ pub struct DepositEvent {
 pub struct WithdrawEvent {
     swap_pool: Pubkey,
 
-    withdrawn_asset_amounts: [u64; NUMASSETS],
-    withdrawer_asset_wallets: [Pubkey; NUMASSETS],
+    withdrawn_asset_amounts: Vec<u64>,
+    withdrawer_asset_wallets: Vec<Pubkey>,
 
     burnt_pool_token_amount: u64,
     withdrawer_pool_token_wallet: Pubkey
@@ -2078,7 +3468,8 @@ pub struct OutSwapEvent {
     target_withdrawer: Pubkey,
     target_chain: u64,
 
-    escrow_nonce: u32,
+    swap_hash: [u8; 32],
+    swap_id: [u8; 32],
 
     deposited_asset_mint: Pubkey,
     depositor_asset_wallet: Pubkey,
@@ -2091,13 +3482,15 @@ pub struct OutSwapEvent {
 #[event]
 pub struct OutSwapAckEvent {
     swap_pool: Pubkey,
-    escrow_nonce: u32
+    swap_hash: [u8; 32],
+    swap_id: [u8; 32]
 }
 
 #[event]
 pub struct OutSwapTimeoutEvent {
     swap_pool: Pubkey,
-    escrow_nonce: u32
+    swap_hash: [u8; 32],
+    swap_id: [u8; 32]
 }
 
 #[event]
@@ -2126,6 +3519,104 @@ pub struct OutLiquiditySwapEvent {
     pool_token_amount: u64,
     liquidity_units_x64: [u64; 4],
     fees: u64,
+
+    swap_hash: [u8; 32],
+    swap_id: [u8; 32],
+}
+
+#[event]
+pub struct OutLiquiditySwapAckEvent {
+    swap_pool: Pubkey,
+    swap_hash: [u8; 32],
+    swap_id: [u8; 32]
+}
+
+#[event]
+pub struct OutLiquiditySwapTimeoutEvent {
+    swap_pool: Pubkey,
+    swap_hash: [u8; 32],
+    swap_id: [u8; 32]
+}
+
+#[event]
+pub struct UnderwriteAssetEvent {
+    swap_pool: Pubkey,
+    underwrite_id: [u8; 32],
+    output_asset_mint: Pubkey,
+    units_x64: [u64; 4],
+    output_amount: u64
+}
+
+#[event]
+pub struct ReleaseUnderwriteAssetEvent {
+    swap_pool: Pubkey,
+    underwrite_id: [u8; 32],
+    refund_wallet: Pubkey,
+    amount: u64
+}
+
+#[event]
+pub struct DeleteUnderwriteAssetEvent {
+    swap_pool: Pubkey,
+    underwrite_id: [u8; 32],
+    amount: u64
+}
+
+
+
+#[event]
+pub struct SetPoolFeeEvent {
+    swap_pool: Pubkey,
+    pool_fee_x64: u64
+}
+
+#[event]
+pub struct SetGovernanceFeeEvent {
+    swap_pool: Pubkey,
+    governance_fee_x64: u64
+}
+
+#[event]
+pub struct SetFeeAdministratorEvent {
+    swap_pool: Pubkey,
+    fee_administrator: Pubkey
+}
+
+#[event]
+pub struct SetGovernanceFeeDestinationEvent {
+    swap_pool: Pubkey,
+    governance_fee_destination: Pubkey
+}
+
+#[event]
+pub struct SetMaxUnitsInflowEvent {
+    swap_pool: Pubkey,
+    max_units_inflow_x64: [u64; 4]
+}
+
+#[event]
+pub struct SetAmplificationEvent {
+    swap_pool: Pubkey,
+    target_amplification_x64: [u64; 4],
+    target_timestamp: u64
+}
+
+#[event]
+pub struct SetPausedEvent {
+    swap_pool: Pubkey,
+    paused: bool
+}
+
+#[event]
+pub struct TransferDaoAuthorityEvent {
+    swap_pool: Pubkey,
+    pending_dao_authority: Pubkey
+}
+
+#[event]
+pub struct AcceptDaoAuthorityEvent {
+    swap_pool: Pubkey,
+    dao_authority: Pubkey
 }
 
 
@@ -2144,9 +3635,15 @@ pub enum ErrorCode {
     
     #[msg("There must be at least one asset with a non-zero balance to create the pool.")]
     NoAssetsProvided,
+
+    #[msg("All initial asset balances must be non-zero.")]
+    InvalidAssetBalance,
     
     #[msg("The swap interface has not been linked to the pool.")]
-    SwapInterfaceNotLinked, //TODO allow fully local pools?
+    SwapInterfaceNotLinked,
+
+    #[msg("The pool setup has not been finished.")]
+    SetupNotFinished,
     
     
     // Swaps
@@ -2184,4 +3681,82 @@ pub enum ErrorCode {
     
     #[msg("The provided IBC state account does not match the expected one.")]
     InvalidIBCInterfaceAccount,
+
+
+    // Fees
+    #[msg("The governance fee exceeds the maximum allowed.")]
+    InvalidGovernanceFee,
+
+    #[msg("The fee administrator does not match the expected one.")]
+    InvalidFeeAdministrator,
+
+    #[msg("The governance fee wallet is not owned by the governance fee destination or does not hold the swapped asset.")]
+    InvalidGovernanceFeeWalletAccount,
+
+
+    // Assets
+    #[msg("The count of assets provided is invalid.")]
+    InvalidAssetCount,
+
+    // Swap escrows
+    #[msg("The provided swap hash does not match the swap parameters.")]
+    InvalidSwapHash,
+
+    #[msg("The provided escrow does not belong to the acknowledged or timed out swap.")]
+    InvalidSwapId,
+
+    #[msg("The IBC interface did not return the id of the sent swap.")]
+    InvalidIBCInterfaceReturnData,
+
+    #[msg("The provided liquidity escrow rent payer does not match with the original payer of the escrow account rent.")]
+    InvalidLiquidityEscrowRentPayerAccount,
+
+    // Math
+    #[msg("Arithmetic overflow.")]
+    MathOverflow,
+
+    #[msg("Arithmetic underflow.")]
+    MathUnderflow,
+
+    #[msg("Division by zero.")]
+    MathDivisionByZero,
+
+    #[msg("Math function evaluated outside of its domain.")]
+    MathDomainError,
+
+    // Governance
+    #[msg("The pool is paused.")]
+    PoolPaused,
+
+    #[msg("The adjustment target timestamp must be between 7 and 365 days in the future.")]
+    InvalidAdjustmentTargetTimestamp,
+
+    #[msg("The target weights must be non-zero, and within a factor of 10 of the current weights.")]   // Unused in this program, here so that error codes match between amplified/non-amplified programs
+    InvalidTargetWeight,
+
+    // Setup cancellation
+    #[msg("The pool has pending swap escrows.")]
+    PendingSwapEscrows,
+
+    #[msg("The provided pool token wallet does not hold the whole pool token supply.")]
+    PoolTokensOutstanding,
+
+    // Local-only pools
+    #[msg("The pool is a local-only pool, cross chain functionality is disabled.")]
+    LocalOnlyPool,
+
+    // Amplification adjustments (amplified pools only, hence last so that error codes match between the pool programs)
+    #[msg("The target amplification must be less than 1, and within a factor of 2 of the current amplification.")]
+    InvalidTargetAmplification,
+}
+
+impl From<MathError> for ErrorCode {
+    fn from(err: MathError) -> Self {
+        match err {
+            MathError::Overflow       => ErrorCode::MathOverflow,
+            MathError::Underflow      => ErrorCode::MathUnderflow,
+            MathError::DivisionByZero => ErrorCode::MathDivisionByZero,
+            MathError::DomainError    => ErrorCode::MathDomainError,
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,354 @@
+#[cfg(test)]
+mod test_calculation_helpers {
+    use std::ops::Shl;
+
+    use rug::Float;
+    use rug::ops::Pow;
+
+    use shared_lib::u256::U256;
+    use shared_lib::i256::I256;
+    use shared_lib::fixed_point_math_x64::pow_x64_up;
+    use shared_lib::test::test_common::test_common::*;
+
+    use crate::calculation_helpers::*;
+
+    const SAMPLE_COUNT: usize = 20000;
+
+    // Maximum number of samples that may be skipped because the computation fails (i.e. too many units for the target pool
+    // or overflows for large amplifications, ~2.5% of the samples)
+    const MAX_SKIPPED_SAMPLES: usize = SAMPLE_COUNT / 20;
+
+    const PRECISION: u32 = 256;
+
+    const ONE_X64: U256 = U256([0, 1, 0, 0]);
+
+    // Samples are taken from [start, end)
+    fn rand_range(start: u64, end: u64) -> u64 {
+        start + rand::random::<u64>() % (end - start)
+    }
+
+    // Samples a balance, a weight and an input amount. The input share (input/balance) is sampled log-uniformly
+    // from [10^-6, 1), so that both small and large swaps are covered
+    fn sample_asset() -> (u64, u64, u64) {
+        let balance = rand_range(1_000, 1 << 60);
+        let weight  = rand_range(1, 1 << 16);
+        let input   = ((balance as f64) * 10_f64.powf(-6. * rand::random::<f64>())) as u64;
+
+        (balance, weight, input.max(1))
+    }
+
+    // Samples an amplification within (0, 1) (x64)
+    fn sample_amplification_x64() -> U256 {
+        U256::from(rand_range(1 << 56, u64::MAX - (1 << 56)))
+    }
+
+    fn to_float(val: u64) -> Float {
+        Float::with_val(PRECISION, val)
+    }
+
+    fn uint_to_high_precision_float(val: U256) -> Float {
+        uint_x64_to_high_precision_float(&val.shl(64u8))
+    }
+
+    // Exact integrals **********************************************************************************************************
+
+    /// WA * ((At + x)^(1-k) - At^(1-k))
+    fn target_out_swap(input: u64, source_balance: u64, source_weight: u64, one_minus_amp: &Float) -> Float {
+        to_float(source_weight) * (
+            (to_float(source_balance) + input).pow(one_minus_amp) - to_float(source_balance).pow(one_minus_amp)
+        )
+    }
+
+    /// Bt * (1 - (I/(I - U))^(-1/(1-k))), with I = WB * Bt^(1-k)
+    fn target_in_swap(units: &Float, target_balance: u64, target_weight: u64, one_minus_amp: &Float) -> Float {
+        let intermediate = to_float(target_weight) * to_float(target_balance).pow(one_minus_amp);
+        let base = intermediate.clone() / (intermediate - units);
+
+        to_float(target_balance) * (1 - base.pow(-(1 / one_minus_amp.clone())))
+    }
+
+
+
+    // Swaps ********************************************************************************************************************
+    // The swaps must never return more than the exact integrals (i.e. round in favour of the pool).
+
+    #[test]
+    fn test_out_swap() {
+
+        for _ in 0..SAMPLE_COUNT {
+            let (balance, weight, input) = sample_asset();
+            let amplification_x64 = sample_amplification_x64();
+            let one_minus_amp = uint_x64_to_high_precision_float(&(ONE_X64 - amplification_x64));
+
+            let units = uint_x64_to_high_precision_float(
+                &out_swap_x64(U256::from(input), U256::from(balance), U256::from(weight), amplification_x64).unwrap()
+            );
+            let target = target_out_swap(input, balance, weight, &one_minus_amp);
+
+            // Both powers are computed with a relative error smaller than 2^-17
+            let error_bound = to_float(weight) * (to_float(balance) + input).pow(&one_minus_amp) * Float::with_val(PRECISION, 2).pow(-16);
+
+            assert!(
+                units <= target,
+                "balance: {}, weight: {}, input: {}, amplification_x64: {}", balance, weight, input, amplification_x64
+            );
+            assert!(
+                target - units <= error_bound,
+                "balance: {}, weight: {}, input: {}, amplification_x64: {}", balance, weight, input, amplification_x64
+            );
+        }
+    }
+
+    #[test]
+    fn test_in_swap() {
+
+        let mut skipped = 0;
+        for _ in 0..SAMPLE_COUNT {
+            let (source_balance, source_weight, input) = sample_asset();
+            let (target_balance, target_weight, _) = sample_asset();
+            let amplification_x64 = sample_amplification_x64();
+            let one_minus_amp = uint_x64_to_high_precision_float(&(ONE_X64 - amplification_x64));
+
+            let units_x64 = match out_swap_x64(U256::from(input), U256::from(source_balance), U256::from(source_weight), amplification_x64) {
+                Ok(units_x64) => units_x64,
+                Err(_) => { skipped += 1; continue }
+            };
+
+            // NOTE: skip the swaps which cannot be computed (i.e. too many units for the target pool)
+            let output = match in_swap(units_x64, U256::from(target_balance), U256::from(target_weight), amplification_x64) {
+                Ok(output) => uint_to_high_precision_float(output),
+                Err(_) => { skipped += 1; continue }
+            };
+            let target = target_in_swap(&uint_x64_to_high_precision_float(&units_x64), target_balance, target_weight, &one_minus_amp);
+
+            // NOTE: the target may be NaN if the units exceed the target pool capacity while the rounded intermediate does not
+            if target.is_nan() { continue }
+
+            assert!(
+                output <= target,
+                "units_x64: {}, balance: {}, weight: {}, amplification_x64: {}", units_x64, target_balance, target_weight, amplification_x64
+            );
+        }
+
+        assert!(skipped <= MAX_SKIPPED_SAMPLES, "skipped samples: {}", skipped);
+    }
+
+    #[test]
+    fn test_full_swap() {
+
+        let mut skipped = 0;
+        for _ in 0..SAMPLE_COUNT {
+            let (source_balance, source_weight, input) = sample_asset();
+            let (target_balance, target_weight, _) = sample_asset();
+            let amplification_x64 = sample_amplification_x64();
+            let one_minus_amp = uint_x64_to_high_precision_float(&(ONE_X64 - amplification_x64));
+
+            let output = match full_swap(
+                U256::from(input),
+                U256::from(source_balance),
+                U256::from(source_weight),
+                U256::from(target_balance),
+                U256::from(target_weight),
+                amplification_x64
+            ) {
+                Ok(output) => output,
+                Err(_) => { skipped += 1; continue }
+            };
+
+            // full_swap must match exactly the cross chain swaps (i.e. out_swap_x64 followed by in_swap)
+            let units_x64 = out_swap_x64(U256::from(input), U256::from(source_balance), U256::from(source_weight), amplification_x64).unwrap();
+            assert_eq!(
+                in_swap(units_x64, U256::from(target_balance), U256::from(target_weight), amplification_x64),
+                Ok(output),
+                "source balance: {}, source weight: {}, target balance: {}, target weight: {}, input: {}, amplification_x64: {}",
+                source_balance, source_weight, target_balance, target_weight, input, amplification_x64
+            );
+
+            let target_units = target_out_swap(input, source_balance, source_weight, &one_minus_amp);
+            let target = target_in_swap(&target_units, target_balance, target_weight, &one_minus_amp);
+            if target.is_nan() { continue }
+
+            assert!(
+                uint_to_high_precision_float(output) <= target,
+                "source balance: {}, source weight: {}, target balance: {}, target weight: {}, input: {}, amplification_x64: {}",
+                source_balance, source_weight, target_balance, target_weight, input, amplification_x64
+            );
+        }
+
+        assert!(skipped <= MAX_SKIPPED_SAMPLES, "skipped samples: {}", skipped);
+    }
+
+
+
+    // Liquidity swaps **********************************************************************************************************
+
+    #[test]
+    fn test_liquidity_swaps() {
+
+        let mut skipped = 0;
+        for _ in 0..SAMPLE_COUNT {
+            let (eq_balance, weight, liquidity) = sample_asset();
+            if liquidity >= eq_balance { continue }
+            let amplification_x64 = sample_amplification_x64();
+            let one_minus_amp = uint_x64_to_high_precision_float(&(ONE_X64 - amplification_x64));
+
+            // WA * (A0^(1-k) - (A0 - x)^(1-k))
+            let units_x64 = calc_out_liquidity_swap_x64(
+                U256::from(liquidity),
+                U256::from(eq_balance),
+                U256::from(weight),
+                amplification_x64
+            ).unwrap();
+            let target_units = to_float(weight) * (
+                to_float(eq_balance).pow(&one_minus_amp) - to_float(eq_balance - liquidity).pow(&one_minus_amp)
+            );
+
+            let units = uint_x64_to_high_precision_float(&units_x64);
+            assert!(
+                units <= target_units,
+                "eq balance: {}, weight: {}, liquidity: {}, amplification_x64: {}", eq_balance, weight, liquidity, amplification_x64
+            );
+
+            // B0 * (((W_SUM + U)/W_SUM)^(1/(1-k)) - 1), with W_SUM computed as in_liquidity_swap does
+            let aggregate_weight_x64 = U256::from(weight) * pow_x64_up(U256::from(eq_balance).shl(64u8), ONE_X64 - amplification_x64).unwrap();
+
+            // NOTE: skip the swaps which cannot be computed (i.e. the output overflows for large amplifications)
+            let output = match calc_in_liquidity_swap(units_x64, U256::from(eq_balance), aggregate_weight_x64, amplification_x64) {
+                Ok(output) => uint_to_high_precision_float(output),
+                Err(_) => { skipped += 1; continue }
+            };
+            let aggregate_weight = uint_x64_to_high_precision_float(&aggregate_weight_x64);
+            let target_output = to_float(eq_balance) * (
+                ((aggregate_weight.clone() + units) / aggregate_weight).pow(1 / one_minus_amp) - 1
+            );
+
+            assert!(
+                output <= target_output,
+                "eq balance: {}, weight: {}, units_x64: {}, amplification_x64: {}", eq_balance, weight, units_x64, amplification_x64
+            );
+        }
+
+        assert!(skipped <= MAX_SKIPPED_SAMPLES, "skipped samples: {}", skipped);
+    }
+
+
+
+    // Fee distribution *********************************************************************************************************
+
+    #[test]
+    fn test_outstanding_units() {
+
+        let eq_balances = [1_000_000_000, 2_000_000_000];
+        let weights = [1, 2];
+
+        for amplification_x64 in [ONE_X64 / 4, ONE_X64 / 2, ONE_X64 / 4 * 3] {
+
+            // Balances at equilibrium do not imply any units
+            assert_eq!(
+                calc_outstanding_units_x64(&eq_balances, &eq_balances, &weights, amplification_x64),
+                Ok(I256::ZERO)
+            );
+
+            // Every balance contributes WA * (At^(1-k) - A0^(1-k)), which is negative if the balance is below its eq balance
+            let balances = [eq_balances[0] + 1_000_000, eq_balances[1] - 3_000_000];
+
+            let surplus_units_x64 = calc_out_liquidity_swap_x64(
+                U256::from(1_000_000), U256::from(balances[0]), U256::from(weights[0]), amplification_x64
+            ).unwrap();
+            let deficit_units_x64 = calc_out_liquidity_swap_x64(
+                U256::from(3_000_000), U256::from(eq_balances[1]), U256::from(weights[1]), amplification_x64
+            ).unwrap();
+
+            assert_eq!(
+                calc_outstanding_units_x64(&balances, &eq_balances, &weights, amplification_x64),
+                I256::from_unsigned(surplus_units_x64).unwrap().checked_sub(I256::from_unsigned(deficit_units_x64).unwrap())
+            );
+            assert!(surplus_units_x64 < deficit_units_x64);
+        }
+    }
+
+    #[test]
+    fn test_fee_distribution() {
+
+        let mut skipped = 0;
+        for _ in 0..SAMPLE_COUNT {
+            let amplification_x64 = sample_amplification_x64();
+            let one_minus_amp = uint_x64_to_high_precision_float(&(ONE_X64 - amplification_x64));
+
+            // The pool holds up to 1% of its eq balances as fees, none of which are accounted for by the unit tracker
+            let (eq_balance_0, weight_0, _) = sample_asset();
+            let (eq_balance_1, weight_1, _) = sample_asset();
+            let eq_balances = [eq_balance_0, eq_balance_1];
+            let weights = [weight_0, weight_1];
+            let balances = eq_balances.map(|eq_balance| eq_balance + rand_range(0, eq_balance / 100 + 1));
+            let pool_tokens_supply = rand_range(1_000, 1 << 60);
+
+            // NOTE: skip the distributions which cannot be computed (i.e. the output overflows for large amplifications)
+            let new_eq_balances = match calc_fee_distribution_eq_balances(
+                I256::ZERO,
+                &balances,
+                &eq_balances,
+                &weights,
+                pool_tokens_supply,
+                amplification_x64
+            ) {
+                Ok(new_eq_balances) => new_eq_balances,
+                Err(_) => { skipped += 1; continue }
+            };
+
+            // Fees: sum of WA * (At^(1-k) - A0^(1-k))
+            let fee_units = (0..2).fold(Float::with_val(PRECISION, 0), |acc, i| {
+                acc + to_float(weights[i]) * (to_float(balances[i]).pow(&one_minus_amp) - to_float(eq_balances[i]).pow(&one_minus_amp))
+            });
+
+            // Valued as an in liquidity swap of the first asset, with W_SUM computed as calc_fee_distribution_eq_balances does
+            let aggregate_weight = uint_x64_to_high_precision_float(&(0..2).fold(U256::zero(), |acc, i| {
+                acc + U256::from(weights[i]) * pow_x64_up(U256::from(eq_balances[i]).shl(64u8), ONE_X64 - amplification_x64).unwrap()
+            }));
+            let asset_0_amount = to_float(eq_balances[0]) * (
+                ((aggregate_weight.clone() + fee_units) / aggregate_weight).pow(1 / one_minus_amp.clone()) - 1
+            );
+
+            // Every eq balance grows by the same share, and never by more than the fees held by the pool
+            for i in 0..2 {
+                let target = to_float(eq_balances[i]) * (1 + asset_0_amount.clone() / eq_balances[i]);
+
+                assert!(
+                    new_eq_balances[i] >= eq_balances[i] && to_float(new_eq_balances[i]) <= target,
+                    "balances: {:?}, eq balances: {:?}, weights: {:?}, amplification_x64: {}", balances, eq_balances, weights, amplification_x64
+                );
+            }
+        }
+
+        assert!(skipped <= MAX_SKIPPED_SAMPLES, "skipped samples: {}", skipped);
+    }
+
+    #[test]
+    fn test_fee_distribution_without_fees() {
+
+        let amplification_x64 = ONE_X64 / 2;
+        let eq_balances = [1_000_000_000, 2_000_000_000];
+        let weights = [1, 2];
+
+        // Balances at equilibrium: there is nothing to distribute
+        assert_eq!(
+            calc_fee_distribution_eq_balances(I256::ZERO, &eq_balances, &eq_balances, &weights, 1_000_000, amplification_x64),
+            Ok(eq_balances.to_vec())
+        );
+
+        let units_x64 = out_swap_x64(U256::from(1_000_000), U256::from(eq_balances[0]), U256::from(weights[0]), amplification_x64).unwrap();
+
+        // The distribution fails if the unit tracker exceeds the units held by the pool
+        assert!(
+            calc_fee_distribution_eq_balances(
+                I256::from_unsigned(units_x64).unwrap(),
+                &eq_balances,
+                &eq_balances,
+                &weights,
+                1_000_000,
+                amplification_x64
+            ).is_err()
+        );
+    }
+
+}
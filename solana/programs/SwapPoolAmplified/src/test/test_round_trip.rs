@@ -0,0 +1,183 @@
+#[cfg(test)]
+mod test_round_trip {
+    use std::ops::Shl;
+
+    use shared_lib::u256::U256;
+    use shared_lib::fixed_point_math_x64::pow_x64_up;
+
+    use crate::calculation_helpers::*;
+
+    const SAMPLE_COUNT: usize = 20000;
+
+    const ONE_X64: U256 = U256([0, 1, 0, 0]);
+
+    // Samples are taken from [start, end)
+    fn rand_range(start: u64, end: u64) -> u64 {
+        start + rand::random::<u64>() % (end - start)
+    }
+
+    // Samples a balance, a weight and an input amount (of up to 'max_input_share' times the balance)
+    fn sample_asset(max_input_share: u64) -> (u64, u64, u64) {
+        let balance = rand_range(1_000, 1 << 60);
+        let weight  = rand_range(1, 1 << 16);
+        let input   = rand_range(1, balance / max_input_share + 2);
+
+        (balance, weight, input)
+    }
+
+    // Samples an amplification within (0, 1) (x64)
+    fn sample_amplification_x64() -> U256 {
+        U256::from(rand_range(1 << 56, u64::MAX - (1 << 56)))
+    }
+
+
+
+    // Local round trips ********************************************************************************************************
+    // Swapping assets in and immediately swapping them back out must never return more than what was put in.
+
+    #[test]
+    fn test_out_in_swap_round_trip() {
+
+        for _ in 0..SAMPLE_COUNT {
+            let (balance, weight, input) = sample_asset(1);
+            let amplification_x64 = sample_amplification_x64();
+
+            let units_x64 = out_swap_x64(U256::from(input), U256::from(balance), U256::from(weight), amplification_x64).unwrap();
+
+            let output = in_swap(units_x64, U256::from(balance) + input, U256::from(weight), amplification_x64).unwrap();
+
+            assert!(
+                output <= U256::from(input),
+                "balance: {}, weight: {}, input: {}, amplification_x64: {}", balance, weight, input, amplification_x64
+            );
+        }
+    }
+
+    #[test]
+    fn test_full_swap_round_trip() {
+
+        for _ in 0..SAMPLE_COUNT {
+            let (source_balance, source_weight, input) = sample_asset(1);
+            let (target_balance, target_weight, _) = sample_asset(1);
+            let amplification_x64 = sample_amplification_x64();
+
+            // NOTE: skip the swaps which cannot be computed (e.g. too large input compared to the pool balance)
+            let output = match full_swap(
+                U256::from(input),
+                U256::from(source_balance),
+                U256::from(source_weight),
+                U256::from(target_balance),
+                U256::from(target_weight),
+                amplification_x64
+            ) {
+                Ok(output) => output,
+                Err(_) => continue
+            };
+
+            if output.is_zero() { continue }
+
+            let round_trip_output = match full_swap(
+                output,
+                U256::from(target_balance) - output,
+                U256::from(target_weight),
+                U256::from(source_balance) + input,
+                U256::from(source_weight),
+                amplification_x64
+            ) {
+                Ok(round_trip_output) => round_trip_output,
+                Err(_) => continue
+            };
+
+            assert!(
+                round_trip_output <= U256::from(input),
+                "source balance: {}, source weight: {}, target balance: {}, target weight: {}, input: {}, amplification_x64: {}",
+                source_balance, source_weight, target_balance, target_weight, input, amplification_x64
+            );
+        }
+    }
+
+    #[test]
+    fn test_liquidity_swap_round_trip() {
+
+        for _ in 0..SAMPLE_COUNT {
+            let (eq_balance, weight, liquidity) = sample_asset(2);
+            if liquidity >= eq_balance { continue }
+            let amplification_x64 = sample_amplification_x64();
+
+            let units_x64 = calc_out_liquidity_swap_x64(
+                U256::from(liquidity),
+                U256::from(eq_balance),
+                U256::from(weight),
+                amplification_x64
+            ).unwrap();
+
+            // Aggregate weight of the pool once the liquidity has been withdrawn (single asset pool), computed as in_liquidity_swap does
+            let aggregate_weight_x64 = U256::from(weight) * pow_x64_up(
+                U256::from(eq_balance - liquidity).shl(64),
+                ONE_X64 - amplification_x64
+            ).unwrap();
+
+            let output = calc_in_liquidity_swap(
+                units_x64,
+                U256::from(eq_balance - liquidity),
+                aggregate_weight_x64,
+                amplification_x64
+            ).unwrap();
+
+            assert!(
+                output <= U256::from(liquidity),
+                "eq balance: {}, weight: {}, liquidity: {}, amplification_x64: {}", eq_balance, weight, liquidity, amplification_x64
+            );
+        }
+    }
+
+
+    // Deposit round trips ******************************************************************************************************
+    // Depositing and immediately withdrawing the same pool tokens must never return more than what was put in.
+    // NOTE: the computations mirror the ones of the 'deposit' and 'withdraw' instructions.
+
+    #[test]
+    fn test_deposit_withdraw_round_trip() {
+
+        for _ in 0..SAMPLE_COUNT {
+            let pool_tokens_supply = rand_range(1_000, 1 << 40);
+            let asset_eq_balance   = rand_range(1_000, 1 << 40);
+            let asset_balance      = rand_range(asset_eq_balance / 2 + 1, asset_eq_balance * 2);
+            let pool_tokens        = rand_range(1, pool_tokens_supply);
+
+            // Deposit
+            let deposit_pool_tokens_for_asset = pool_tokens * asset_eq_balance / pool_tokens_supply;
+            let deposited_amount = calc_asset_amount_for_pool_tokens_up(
+                deposit_pool_tokens_for_asset,
+                asset_balance,
+                asset_eq_balance
+            ).unwrap();
+
+            // The deposited amount must cover the exact (non rounded) value of the pool tokens
+            assert!(
+                deposited_amount as u128 * asset_eq_balance as u128 >= asset_balance as u128 * deposit_pool_tokens_for_asset as u128,
+                "pool tokens supply: {}, eq balance: {}, balance: {}, pool tokens: {}",
+                pool_tokens_supply, asset_eq_balance, asset_balance, pool_tokens
+            );
+
+            let new_asset_eq_balance   = asset_eq_balance + deposit_pool_tokens_for_asset;
+            let new_asset_balance      = asset_balance + deposited_amount;
+            let new_pool_tokens_supply = pool_tokens_supply + pool_tokens;
+
+            // Withdraw
+            let withdraw_pool_tokens_for_asset = pool_tokens * new_asset_eq_balance / new_pool_tokens_supply;
+            let withdrawn_amount = calc_asset_amount_for_pool_tokens(
+                withdraw_pool_tokens_for_asset,
+                new_asset_balance,
+                new_asset_eq_balance
+            ).unwrap();
+
+            assert!(
+                withdrawn_amount <= deposited_amount,
+                "pool tokens supply: {}, eq balance: {}, balance: {}, pool tokens: {}",
+                pool_tokens_supply, asset_eq_balance, asset_balance, pool_tokens
+            );
+        }
+    }
+
+}
@@ -1,17 +1,25 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke_signed, hash::hash};
 
 declare_id!("4Zo7npJHcpHhnj9dUXAY2ZzpuZT75AFn1z3uD6WqgBKu");
 
+// Seed of the PDA that signs the packets (received packets and acknowledgement/timeout results) forwarded to the dapps
+pub const POLYMERASE_AUTHORITY_SEED: &[u8] = b"polymeraseAuthority";
+
+// Seed of the PDA that stores the relayer allowed to deliver packets and packet results
+pub const RELAYER_CONFIG_SEED: &[u8] = b"relayerConfig";
+
 #[program]
 pub mod polymerase_sender {
     use super::*;
 
-    // Create account for IBC packet data
+    // Create account for IBC packet data. A timeout value of 0 is interpreted as no timeout.
     pub fn send_ibc_packet(
         ctx: Context<SendIbcPacket>,
         channel_id: [u8; 32],
         payload: Vec<u8>,
         timeout_block_height: u64,
+        timeout_timestamp: u64,
     ) -> Result<()> {
 
         if payload.len() > MAX_PAYLOAD_LENGTH {
@@ -28,15 +36,152 @@ pub mod polymerase_sender {
         ibc_data.channel_id           = channel_id;
         ibc_data.payload              = payload;
         ibc_data.timeout_block_height = timeout_block_height;
+        ibc_data.timeout_timestamp    = timeout_timestamp;
+
+        Ok(())
+    }
+
+    // Create the relayer config (upgrade authority of the program only). The signer becomes the admin of the config,
+    // which is the only one allowed to change the relayer afterwards.
+    // NOTE: the relayer config is a singleton, hence this must be called once when deploying the program.
+    pub fn initialize_relayer_config(
+        ctx: Context<InitializeRelayerConfig>,
+        relayer: Pubkey
+    ) -> Result<()> {
+
+        let relayer_config = &mut ctx.accounts.relayer_config;
+
+        relayer_config.admin   = ctx.accounts.admin.key();
+        relayer_config.relayer = relayer;
 
         Ok(())
     }
 
-    // TODO: make sure only only the relayer can call this!!!
-    // Close Ibc data account. 
+    // Change the relayer allowed to deliver packets and packet results
+    pub fn set_relayer(
+        ctx: Context<SetRelayer>,
+        relayer: Pubkey
+    ) -> Result<()> {
+
+        ctx.accounts.relayer_config.relayer = relayer;
+
+        Ok(())
+    }
+
+    // Close Ibc data account (relayer only).
     pub fn close_account(_ctx: Context<CloseAccount>) -> Result<()> {
         Ok(())
     }
+
+    // Forward an ibc packet received from a remote chain to the dapp (relayer only), together with the (local)
+    // channel on which the packet arrived, so that the dapp can verify the packet origin. Packets which have reached
+    // their timeout are rejected, so that they can be safely timed out on the source chain.
+    pub fn on_recv_packet<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ForwardPacket<'info>>,
+        channel_id: [u8; 32],
+        timeout_block_height: u64,
+        timeout_timestamp: u64,
+        data: Vec<u8>
+    ) -> Result<()> {
+
+        let clock = Clock::get()?;
+
+        if timeout_block_height != 0 && clock.slot >= timeout_block_height {
+            return Err(Errors::PacketTimedOut.into());
+        }
+
+        if timeout_timestamp != 0 && clock.unix_timestamp >= timeout_timestamp as i64 {
+            return Err(Errors::PacketTimedOut.into());
+        }
+
+        forward_packet(
+            &ctx.accounts.dapp_program,
+            &ctx.accounts.polymerase_authority,
+            *ctx.bumps.get("polymerase_authority").unwrap(),
+            ctx.remaining_accounts,
+            "on_recv_packet",
+            (data, channel_id).try_to_vec().unwrap()
+        )
+    }
+
+    // Forward the acknowledgement of an ibc packet to the dapp (relayer only). The ibc data account of the packet
+    // is closed, so that each packet result can only be delivered once.
+    pub fn on_acknowledgement_packet<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ForwardPacketResult<'info>>,
+        data: Vec<u8>
+    ) -> Result<()> {
+        forward_packet(
+            &ctx.accounts.dapp_program,
+            &ctx.accounts.polymerase_authority,
+            *ctx.bumps.get("polymerase_authority").unwrap(),
+            ctx.remaining_accounts,
+            "on_acknowledgement_packet",
+            data.try_to_vec().unwrap()
+        )
+    }
+
+    // Forward the timeout of an ibc packet to the dapp (relayer only). The ibc data account of the packet is closed,
+    // so that each packet result can only be delivered once.
+    pub fn on_timeout_packet<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ForwardPacketResult<'info>>,
+        data: Vec<u8>
+    ) -> Result<()> {
+        forward_packet(
+            &ctx.accounts.dapp_program,
+            &ctx.accounts.polymerase_authority,
+            *ctx.bumps.get("polymerase_authority").unwrap(),
+            ctx.remaining_accounts,
+            "on_timeout_packet",
+            data.try_to_vec().unwrap()
+        )
+    }
+}
+
+// Invoke the given callback of the dapp with the provided (serialized) arguments. The dapp accounts are taken from the
+// remaining accounts (in order), with the polymerase_authority PDA signing the invocation.
+fn forward_packet<'info>(
+    dapp_program: &UncheckedAccount<'info>,
+    polymerase_authority: &UncheckedAccount<'info>,
+    polymerase_authority_bump: u8,
+    remaining_accounts: &[AccountInfo<'info>],
+    callback: &str,
+    mut args: Vec<u8>
+) -> Result<()> {
+
+    // Full CPI data (sighash + arguments)
+    let mut data_vec = hash(format!("global:{}", callback).as_bytes()).to_bytes()[..8].to_vec();
+    data_vec.append(&mut args);
+
+    let polymerase_authority_key = polymerase_authority.key();
+
+    let meta_accounts: Vec<AccountMeta> = remaining_accounts.iter().map(|a| AccountMeta {
+        pubkey: a.key(),
+        is_signer: a.is_signer || a.key().eq(&polymerase_authority_key),
+        is_writable: a.is_writable,
+    }).collect();
+
+    let instruction = Instruction {
+        data: data_vec,
+        program_id: dapp_program.key(),
+        accounts: meta_accounts,
+    };
+
+    let mut accounts: Vec<AccountInfo> = vec![
+        polymerase_authority.to_account_info(),
+        dapp_program.to_account_info(),
+    ];
+    accounts.extend_from_slice(remaining_accounts);
+
+    invoke_signed(
+        &instruction,
+        &accounts,
+        &[&[
+            POLYMERASE_AUTHORITY_SEED,
+            &[polymerase_authority_bump]
+        ]]
+    ).unwrap();
+
+    Ok(())
 }
 
 #[derive(Accounts)]
@@ -54,7 +199,41 @@ pub struct SendIbcPacket<'info> {
     pub system_program: Program<'info, System>,
 }
 
-// TODO: test that only the relayer can call this!!!
+#[derive(Accounts)]
+pub struct InitializeRelayerConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = RelayerConfig::LEN,
+        seeds = [RELAYER_CONFIG_SEED],
+        bump
+    )]
+    pub relayer_config: Account<'info, RelayerConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ Errors::Unauthorized
+    )]
+    pub program: Program<'info, crate::program::PolymeraseSender>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ Errors::Unauthorized  // ! Only the upgrade authority of the program
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRelayer<'info> {
+    #[account(
+        mut,
+        seeds = [RELAYER_CONFIG_SEED],
+        bump,
+        has_one = admin @ Errors::Unauthorized
+    )]
+    pub relayer_config: Account<'info, RelayerConfig>,
+    pub admin: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct CloseAccount<'info> {
     #[account(
@@ -66,19 +245,97 @@ pub struct CloseAccount<'info> {
     #[account(mut)]
     /// CHECK: must match the one saved in ibc_data
     pub payer: AccountInfo<'info>,
+    #[account(
+        seeds = [RELAYER_CONFIG_SEED],
+        bump,
+        constraint = relayer_config.relayer == authority.key() @ Errors::Unauthorized  // ! Only the registered relayer
+    )]
+    pub relayer_config: Account<'info, RelayerConfig>,
     #[account(mut)]
     pub authority: Signer<'info>, // relayer
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct ForwardPacket<'info> {
+    /// CHECK: The program to which the packet is forwarded
+    #[account(executable)]
+    pub dapp_program: UncheckedAccount<'info>,
+    /// CHECK: Safe, as we are not reading from the account. Signs the forwarded invocation.
+    #[account(
+        seeds = [POLYMERASE_AUTHORITY_SEED],
+        bump
+    )]
+    pub polymerase_authority: UncheckedAccount<'info>,
+    #[account(
+        seeds = [RELAYER_CONFIG_SEED],
+        bump,
+        constraint = relayer_config.relayer == authority.key() @ Errors::Unauthorized  // ! Only the registered relayer
+    )]
+    pub relayer_config: Account<'info, RelayerConfig>,
+    #[account()]
+    pub authority: Signer<'info>, // relayer
+}
+
+#[derive(Accounts)]
+#[instruction(data: Vec<u8>)]
+pub struct ForwardPacketResult<'info> {
+    /// CHECK: The program to which the packet result is forwarded
+    #[account(executable)]
+    pub dapp_program: UncheckedAccount<'info>,
+    /// CHECK: Safe, as we are not reading from the account. Signs the forwarded invocation.
+    #[account(
+        seeds = [POLYMERASE_AUTHORITY_SEED],
+        bump
+    )]
+    pub polymerase_authority: UncheckedAccount<'info>,
+    #[account(
+        seeds = [RELAYER_CONFIG_SEED],
+        bump,
+        constraint = relayer_config.relayer == authority.key() @ Errors::Unauthorized  // ! Only the registered relayer
+    )]
+    pub relayer_config: Account<'info, RelayerConfig>,
+    // The ibc data account created when the packet was sent. Closed once the result is forwarded.
+    #[account(
+        mut,
+        close = payer,
+        has_one = sender @ Errors::InvalidPacketSender,
+        has_one = payer,
+        constraint = ibc_data.payload == data @ Errors::InvalidPacketData                  // ! The forwarded data must be the packet sent by the dapp
+    )]
+    pub ibc_data: Account<'info, IbcData>,
+    /// CHECK: Must match the sender saved in ibc_data, and be owned by the dapp program
+    #[account(
+        owner = dapp_program.key() @ Errors::InvalidPacketSender                           // ! The packet must have been sent by the dapp
+    )]
+    pub sender: UncheckedAccount<'info>,
+    #[account(mut)]
+    /// CHECK: must match the one saved in ibc_data
+    pub payer: UncheckedAccount<'info>,
+    #[account()]
+    pub authority: Signer<'info>, // relayer
+}
+
 // Accounts ****************************************************************************************
 
+#[account]
+pub struct RelayerConfig {
+    pub admin: Pubkey,             // 32 bytes
+    pub relayer: Pubkey,           // 32 bytes
+}
+
+impl RelayerConfig {
+    const LEN: usize = DISCRIMINATOR_LENGTH
+        + PUBLIC_KEY_LENGTH                         // admin
+        + PUBLIC_KEY_LENGTH;                        // relayer
+}
+
 const DISCRIMINATOR_LENGTH        : usize = 8;
 const PUBLIC_KEY_LENGTH           : usize = 32;
 const CHANNEL_ID_LENGTH           : usize = 32;
 const VECTOR_LENGTH_PREFIX        : usize = 4;   // stores the size of the vector.
 const MAX_PAYLOAD_LENGTH          : usize = 512; // max length
-const TIMEOUT_BLOCK_HEIGHT_LENGTH : usize = 8;
+const TIMEOUT_LENGTH              : usize = 8;
 
 #[account]
 pub struct IbcData {
@@ -87,6 +344,7 @@ pub struct IbcData {
     pub channel_id: [u8; 32],      // 32 bytes
     pub payload: Vec<u8>,          // 1024 bytes (arbitrary)
     pub timeout_block_height: u64, // 8 bytes
+    pub timeout_timestamp: u64,    // 8 bytes
 }
 
 impl IbcData {
@@ -95,11 +353,20 @@ impl IbcData {
         + PUBLIC_KEY_LENGTH                         // payer
         + CHANNEL_ID_LENGTH                         // channel id
         + VECTOR_LENGTH_PREFIX + MAX_PAYLOAD_LENGTH // payload
-        + TIMEOUT_BLOCK_HEIGHT_LENGTH;              // timeout block height
+        + TIMEOUT_LENGTH                            // timeout block height
+        + TIMEOUT_LENGTH;                           // timeout timestamp
 }
 
 #[error_code]
 pub enum Errors {
     #[msg("The provided data should be 512 chars long maximum")]
     DataTooLong,
+    #[msg("The signer is not authorized to perform this action")]
+    Unauthorized,
+    #[msg("The provided data does not match the sent packet")]
+    InvalidPacketData,
+    #[msg("The packet has not been sent by the dapp")]
+    InvalidPacketSender,
+    #[msg("The packet has reached its timeout")]
+    PacketTimedOut,
 }
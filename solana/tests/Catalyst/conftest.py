@@ -20,6 +20,7 @@ import os
 sys.path.insert(1, os.path.join(sys.path[0], '../../../simulator'))
 
 from utils.common_utils import SimplePoolContext, UserWallet, create_mints, create_users, setup_simple_pool_test_env
+from utils.swap_interface_utils import set_polymerase_relayer
 
 # Global parameters *************************************************************************************************************
 
@@ -69,6 +70,20 @@ async def swap_pool_amplified_program(workspace: WorkspaceType) -> Program:
     return program
 
 
+@fixture(scope="module")
+async def router_program(workspace: WorkspaceType) -> Program:
+    program = workspace["catalyst_router"]
+    program.provider.opts = TxOpts(skip_preflight=DEFAULT_SKIP_PREFLIGHT)
+    return program
+
+
+@fixture(scope="module")
+async def factory_program(workspace: WorkspaceType) -> Program:
+    program = workspace["catalyst_factory"]
+    program.provider.opts = TxOpts(skip_preflight=DEFAULT_SKIP_PREFLIGHT)
+    return program
+
+
 @fixture(scope="module")
 async def swap_interface_program(workspace: WorkspaceType) -> Program:
     program = workspace["ibc_interface"]
@@ -164,6 +179,22 @@ async def polymerase_emulator_setup_master_keypair(provider: Provider) -> Keypai
     return setup_master
 
 
+@fixture(scope="module")
+async def polymerase_relayer_keypair(provider: Provider, polymerase_sender_program: Program) -> Keypair:
+
+    relayer = Keypair()
+    airdrop_result = await provider.connection.request_airdrop(
+        relayer.public_key, 1000000000000
+    )
+
+    await confirm_transaction(provider, airdrop_result.value)
+
+    # Register the relayer on the polymerase endpoint (only the registered relayer can deliver packets)
+    await set_polymerase_relayer(polymerase_sender_program, relayer.public_key)
+
+    return relayer
+
+
 
 @fixture(scope="module")
 async def mints(
@@ -8,13 +8,39 @@ from anchorpy import Context, Program, Provider
 
 from conftest import create_mints, create_users
 from utils.account_utils import get_swap_pool_authority
-from utils.swap_interface_utils import initialize_swap_interface_state, register_swap_interface_polymerase_port
+from utils.swap_interface_utils import get_relayer_config, initialize_polymerase_relayer_config, initialize_swap_interface_state, register_swap_interface_polymerase_port
 from utils.verify_utils import verify_catalyst_configuration
-from utils.transaction_utils import confirm_transaction
+from utils.transaction_utils import confirm_transaction, is_program_error
 from utils.swap_pool_utils import POOL_MAX_ASSET_COUNT, finish_swap_pool_setup, initialize_swap_pool_state, link_swap_interface_to_swap_pool
 from utils.token_utils import fund_accounts
 
 
+async def test_polymerase_relayer_config_unauthorized_initializer(
+    polymerase_sender_program: Program,
+    provider: Provider,
+    generic_payer: Keypair
+):
+    """
+        Only the upgrade authority of the polymerase endpoint can create the (singleton) relayer config
+        NOTE: must run before the relayer config is created by the polymerase_relayer_keypair fixture
+    """
+
+    relayer_config = get_relayer_config(polymerase_sender_program.program_id)[0]
+    assert (await provider.connection.get_account_info(relayer_config)).value is None
+
+    with pytest.raises(Exception) as exception_info:
+        await initialize_polymerase_relayer_config(
+            polymerase_sender_program,
+            generic_payer.public_key,
+            generic_payer
+        )
+
+    assert is_program_error(exception_info.value, polymerase_sender_program, "Unauthorized")
+
+    # The relayer config must not have been created
+    assert (await provider.connection.get_account_info(relayer_config)).value is None
+
+
 @given(asset_count = st.integers(min_value=0, max_value=POOL_MAX_ASSET_COUNT+1))
 async def test_create_swap_pool_asset_count(
     swap_pool_program: Program,
@@ -108,6 +134,7 @@ async def test_create_swap_pool_asset_count(
 
     # Finish setup
     rpc_result = await swap_pool_program.rpc["finish_setup"](
+        False,  # local_only
         ctx=Context(
             accounts={
                 "setup_master": swap_pool_setup_master_keypair.public_key,
@@ -132,6 +159,57 @@ async def test_create_swap_pool_asset_count(
 
 
 
+@pytest.mark.parametrize("assets_balances, assets_weights, expected_error", [
+    ([10000, 0], [1, 1], "InvalidAssetBalance"),
+    ([10000, 10000], [1, 0], "InvalidAssetWeight"),
+])
+async def test_create_swap_pool_zero_balance_or_weight(
+    swap_pool_program: Program,
+    swap_pool_setup_master_keypair: Keypair,
+    provider: Provider,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    assets_balances: list[int],
+    assets_weights: list[int],
+    expected_error: str
+):
+    """
+        A SwapPool cannot be created with a zero initial balance or a zero weight
+    """
+
+    swap_pool_state_keypair = Keypair()
+    swap_pool_authority     = get_swap_pool_authority(swap_pool_program.program_id, swap_pool_state_keypair.public_key)[0]
+
+    assets    = await create_mints(provider, mint_authority, len(assets_balances))
+    depositor = (await create_users(provider, assets, generic_payer, 1))[0]
+
+    await fund_accounts(
+        provider                 = provider,
+        mints                    = assets,
+        mints_authority          = mint_authority,
+        token_accounts           = depositor.token_accounts,
+        balance                  = [10000 for _ in assets],
+        delegate_authority       = swap_pool_authority,
+        delegate_balance         = [10000 for _ in assets],
+        token_accounts_authority = depositor.user_keypair
+    )
+
+    with pytest.raises(Exception) as exception_info:
+        await initialize_swap_pool_state(
+            swap_pool_program                     = swap_pool_program,
+            swap_pool_setup_master_keypair        = swap_pool_setup_master_keypair,
+            amplification                         = None,
+            assets_weights                        = assets_weights,
+            assets_balances                       = assets_balances,
+            assets_mints                          = assets,
+            deposit_wallets                       = depositor.token_accounts,
+            depositor_pool_token_wallet_authority = depositor.user_keypair.public_key,
+            swap_pool_state_keypair               = swap_pool_state_keypair
+        )
+
+    assert is_program_error(exception_info.value, swap_pool_program, expected_error)
+
+
 async def test_add_same_asset_twice(
     swap_pool_program: Program,
     swap_pool_setup_master_keypair: Keypair,
@@ -1,14 +1,19 @@
+import asyncio
+from dataclasses import dataclass
+import pytest
 from solana.keypair import Keypair
+from solana.publickey import PublicKey
 from solana.transaction import AccountMeta
 from solana.system_program import SYS_PROGRAM_ID
 
 from anchorpy import Context, Program
 from conftest import FactoryFixture, generic_payer
-from utils.common_utils import SOLANA_CHAIN_ID
+from utils.common_utils import SOLANA_CHAIN_ID, SimplePoolContext
 
-from utils.swap_pool_utils import perform_cross_chain_swap, perform_local_swap
-from utils.transaction_utils import TxEventListener, confirm_transaction
-from utils.token_utils import get_account_info, mint_to
+from utils.swap_interface_utils import DEFAULT_CHANNEL_ID, compute_underwrite_id, deliver_ibc_packet, deliver_ibc_packet_result, get_ibc_result_accounts, get_polymerase_authority, get_relayer_config, get_underwrite_state_account
+from utils.swap_pool_utils import PerformCrossChainSwapResult, perform_cross_chain_swap, perform_local_swap, set_connection_enabled
+from utils.transaction_utils import TxEventListener, confirm_transaction, is_program_error
+from utils.token_utils import create_token_account, get_account_info, mint_to
 from utils.verify_utils import u256_array_to_int, verify_catalyst_state, verify_in_swap_event
 from spl.token.constants import TOKEN_PROGRAM_ID
 
@@ -201,7 +206,7 @@ async def test_self_cross_chain_swap(
     swap_interface_program: Program,
     polymerase_emulator_setup_master_keypair: Keypair,
     polymerase_sender_program: Program,
-    polymerase_receiver_program: Program,
+    polymerase_relayer_keypair: Keypair,
     mint_authority: Keypair,
     generic_payer: Keypair,
     simple_pool_context_factory: FactoryFixture,
@@ -269,11 +274,8 @@ async def test_self_cross_chain_swap(
     )
 
 
-    target_chain_id   = SOLANA_CHAIN_ID
-    target_pool_id    = swap_interface_state
-    target_program_id = swap_interface_program.program_id
-
-    source_swap_id    = 0
+    target_chain_id = SOLANA_CHAIN_ID
+    target_pool_id  = swap_interface_state
 
     cross_chain_swap_result = await perform_cross_chain_swap(
         swapper_input_asset_balance,
@@ -284,7 +286,6 @@ async def test_self_cross_chain_swap(
         target_pool_id,
         swapper_output_asset_index,
         swapper_output_asset_wallet,
-        source_swap_id,  # Escrow nonce
         generic_payer,
         swap_pool_program,
         swap_pool_state,
@@ -300,7 +301,7 @@ async def test_self_cross_chain_swap(
     sim_out_swap_units = catalyst_simulator.out_swap(
         swapper_input_asset,
         swapper_input_asset_balance,
-        source_swap_id
+        cross_chain_swap_result.swap_hash
     )
 
     await verify_catalyst_state(swap_pool_program, swap_pool_state, catalyst_simulator)
@@ -317,38 +318,54 @@ async def test_self_cross_chain_swap(
     # Fetch payload
     data = await polymerase_sender_program.account["IbcData"].fetch(cross_chain_swap_result.ibc_data)
 
-    authority = Keypair() #not used for now
+    # The underwrite state account must always be provided (the swap has not been underwritten)
+    underwrite_state = get_underwrite_state_account(
+        swap_interface_program.program_id,
+        swap_interface_state,
+        compute_underwrite_id(
+            swap_pool_state,
+            swapper_output_asset_index,
+            cross_chain_swap_result.out_swap_event.data.withdrawnPoolUnitsX64,
+            0,
+            swapper_output_asset_wallet,
+            cross_chain_swap_result.swap_id
+        )
+    )[0]
+
+    on_ibc_invocation_remaining_accounts = [
+        AccountMeta(swap_interface_state,          is_signer=False, is_writable=False ), # interface_state_account
+        AccountMeta(swap_pool_state,               is_signer=False, is_writable=True  ), # swap_pool
+        AccountMeta(swap_pool_program.program_id,  is_signer=False, is_writable=False ), # swap_pool_program
+        AccountMeta(TOKEN_PROGRAM_ID,              is_signer=False, is_writable=False ), # token_program
+        AccountMeta(connection_state,              is_signer=False, is_writable=False ), # connection_state_account
+        AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
+        AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
+        AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+        AccountMeta(swap_pool_authority,           is_signer=False, is_writable=False ), # swap_pool_authority
+        AccountMeta(underwrite_state,              is_signer=False, is_writable=True  ), # underwrite_state_account
+    ]
+
+    # A truncated payload must be rejected (and not make the program panic)
+    with pytest.raises(Exception) as exception_info:
+        await deliver_ibc_packet(
+            data.payload[:-1],
+            port_registration,
+            swap_interface_program,
+            on_ibc_invocation_remaining_accounts,
+            polymerase_sender_program,
+            polymerase_relayer_keypair
+        )
+
+    assert is_program_error(exception_info.value, swap_interface_program, "InvalidPayload")
 
     async with TxEventListener("InSwapEvent") as ev_listener:
-        rpc_result = await polymerase_receiver_program.rpc["on_receive"](
-            swap_interface_state,
-            "",
-            "",
-            "",
-            "",
-            1,
+        rpc_result = await deliver_ibc_packet(
             data.payload,
-            0,
-            0,
-            ctx=Context(
-                accounts={
-                    "port_registration": port_registration,
-                    "dapp_program": target_program_id,
-                    "authority": authority.public_key
-                },
-                remaining_accounts=[
-                    AccountMeta(swap_interface_state,          is_signer=False, is_writable=False ), # interface_state_account
-                    AccountMeta(swap_pool_state,               is_signer=False, is_writable=True  ), # swap_pool
-                    AccountMeta(swap_pool_program.program_id,  is_signer=False, is_writable=False ), # swap_pool_program
-                    AccountMeta(TOKEN_PROGRAM_ID,              is_signer=False, is_writable=False ), # token_program
-                    AccountMeta(connection_state,              is_signer=False, is_writable=False ), # connection_state_account
-                    AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
-                    AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
-                    AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
-                    AccountMeta(swap_pool_authority,           is_signer=False, is_writable=False ), # swap_pool_authority
-                ],
-                signers=[authority]
-            )
+            port_registration,
+            swap_interface_program,
+            on_ibc_invocation_remaining_accounts,
+            polymerase_sender_program,
+            polymerase_relayer_keypair
         )
         await confirm_transaction(provider, rpc_result)
 
@@ -390,30 +407,35 @@ async def test_self_cross_chain_swap(
 
     # Step 3: Trigger ibc ack on source chain (simulates polymer chain endpoint contract)
     swap_escrow = cross_chain_swap_result.swap_escrow
-    ack_result = await swap_interface_program.rpc["on_acknowledgement_packet"](
-        data.payload,
-        ctx=Context(
-            accounts={
-                "port_registration": port_registration,
-                "interface_state_account": swap_interface_state,
-                "swap_pool": swap_pool_state,
-                "swap_pool_program": swap_pool_program.program_id,
-                "swap_pool_authority": swap_pool_authority,
-                "token_program": TOKEN_PROGRAM_ID,
-                "system_program": SYS_PROGRAM_ID
-            },
-            remaining_accounts=[
-                AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
-                AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
-                AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
-                AccountMeta(swap_escrow,                   is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
-                AccountMeta(generic_payer.public_key,      is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
-            ]
-        )
+
+    swap_escrow_rent          = (await provider.connection.get_account_info(swap_escrow)).value.lamports
+    rent_payer_balance_before = (await provider.connection.get_balance(generic_payer.public_key)).value
+
+    await deliver_ibc_packet_result(
+        "on_acknowledgement_packet",
+        cross_chain_swap_result.ibc_data,
+        port_registration,
+        swap_interface_program,
+        swap_interface_state,
+        swap_pool_state,
+        swap_pool_program.program_id,
+        swap_pool_authority,
+        [
+            AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
+            AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
+            AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+            AccountMeta(swap_escrow,                   is_signer=False, is_writable=True  ), # swap_escrow
+            AccountMeta(generic_payer.public_key,      is_signer=False, is_writable=True  ), # swap_escrow_rent_payer
+        ],
+        polymerase_sender_program,
+        polymerase_relayer_keypair
     )
-    await confirm_transaction(provider, ack_result)
 
-    catalyst_simulator.out_swap_ack(source_swap_id)
+    # The swap escrow must be closed, and its rent refunded to the original rent payer
+    assert (await provider.connection.get_account_info(swap_escrow)).value is None
+    assert (await provider.connection.get_balance(generic_payer.public_key)).value == rent_payer_balance_before + swap_escrow_rent
+
+    catalyst_simulator.out_swap_ack(cross_chain_swap_result.swap_hash)
 
     await verify_catalyst_state(swap_pool_program, swap_pool_state, catalyst_simulator)
 
@@ -439,7 +461,7 @@ async def test_self_cross_chain_swap_timeout(
     swap_interface_program: Program,
     polymerase_emulator_setup_master_keypair: Keypair,
     polymerase_sender_program: Program,
-    polymerase_receiver_program: Program,
+    polymerase_relayer_keypair: Keypair,
     mint_authority: Keypair,
     generic_payer: Keypair,
     simple_pool_context_factory: FactoryFixture,
@@ -507,11 +529,10 @@ async def test_self_cross_chain_swap_timeout(
     )
 
 
-    target_chain_id   = SOLANA_CHAIN_ID
-    target_pool_id    = swap_interface_state
-    target_program_id = swap_interface_program.program_id
+    swap_pool_state_before_swap = await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)
 
-    source_swap_id    = 0
+    target_chain_id = SOLANA_CHAIN_ID
+    target_pool_id  = swap_interface_state
 
     cross_chain_swap_result = await perform_cross_chain_swap(
         swapper_input_asset_balance,
@@ -522,7 +543,6 @@ async def test_self_cross_chain_swap_timeout(
         target_pool_id,
         swapper_output_asset_index,
         swapper_output_asset_wallet,
-        source_swap_id,  # Escrow nonce
         generic_payer,
         swap_pool_program,
         swap_pool_state,
@@ -532,13 +552,16 @@ async def test_self_cross_chain_swap_timeout(
         swap_interface_state,
         connection_state,
         polymerase_sender_program,
-        polymerase_emulator_setup_master_keypair # Payer, can be anyone
+        polymerase_emulator_setup_master_keypair, # Payer, can be anyone
+        timeout_timestamp=1                       # Already expired
     )
 
+    swap_pool_state_after_swap = await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)
+
     sim_out_swap_units = catalyst_simulator.out_swap(
         swapper_input_asset,
         swapper_input_asset_balance,
-        source_swap_id
+        cross_chain_swap_result.swap_hash
     )
 
     await verify_catalyst_state(swap_pool_program, swap_pool_state, catalyst_simulator)
@@ -549,11 +572,48 @@ async def test_self_cross_chain_swap_timeout(
 
 
 
-    # Skip Step 2: Do not perform cross-chain swap execute => receive => in_swap
+    # Step 2: Cross-chain swap execute => receive => in_swap must be rejected, as the message has timed out
 
     # Fetch payload
     data = await polymerase_sender_program.account["IbcData"].fetch(cross_chain_swap_result.ibc_data)
 
+    underwrite_state = get_underwrite_state_account(
+        swap_interface_program.program_id,
+        swap_interface_state,
+        compute_underwrite_id(
+            swap_pool_state,
+            swapper_output_asset_index,
+            cross_chain_swap_result.out_swap_event.data.withdrawnPoolUnitsX64,
+            0,
+            swapper_output_asset_wallet,
+            cross_chain_swap_result.swap_id
+        )
+    )[0]
+
+    with pytest.raises(Exception) as exception_info:
+        await deliver_ibc_packet(
+            data.payload,
+            port_registration,
+            swap_interface_program,
+            [
+                AccountMeta(swap_interface_state,          is_signer=False, is_writable=False ), # interface_state_account
+                AccountMeta(swap_pool_state,               is_signer=False, is_writable=True  ), # swap_pool
+                AccountMeta(swap_pool_program.program_id,  is_signer=False, is_writable=False ), # swap_pool_program
+                AccountMeta(TOKEN_PROGRAM_ID,              is_signer=False, is_writable=False ), # token_program
+                AccountMeta(connection_state,              is_signer=False, is_writable=False ), # connection_state_account
+                AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
+                AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
+                AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                AccountMeta(swap_pool_authority,           is_signer=False, is_writable=False ), # swap_pool_authority
+                AccountMeta(underwrite_state,              is_signer=False, is_writable=True  ), # underwrite_state_account
+            ],
+            polymerase_sender_program,
+            polymerase_relayer_keypair,
+            timeout_block_height = data.timeout_block_height,
+            timeout_timestamp    = data.timeout_timestamp
+        )
+    assert is_program_error(exception_info.value, polymerase_sender_program, "PacketTimedOut")
+
     # Compare the current state of the output asset destination wallet
     swapper_output_account_info_after_out_swap = await get_account_info(
         provider,
@@ -572,30 +632,42 @@ async def test_self_cross_chain_swap_timeout(
 
     # Step 3: Trigger ibc timeout on source chain (simulates polymer chain endpoint contract)
     swap_escrow = cross_chain_swap_result.swap_escrow
-    ack_result = await swap_interface_program.rpc["on_timeout_packet"](
-        data.payload,
-        ctx=Context(
-            accounts={
-                "port_registration": port_registration,
-                "interface_state_account": swap_interface_state,
-                "swap_pool": swap_pool_state,
-                "swap_pool_program": swap_pool_program.program_id,
-                "swap_pool_authority": swap_pool_authority,
-                "token_program": TOKEN_PROGRAM_ID,
-                "system_program": SYS_PROGRAM_ID
-            },
-            remaining_accounts=[
-                AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
-                AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
-                AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
-                AccountMeta(swap_escrow,                   is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
-                AccountMeta(generic_payer.public_key,      is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
-            ]
-        )
+
+    swap_escrow_rent          = (await provider.connection.get_account_info(swap_escrow)).value.lamports
+    rent_payer_balance_before = (await provider.connection.get_balance(generic_payer.public_key)).value
+
+    await deliver_ibc_packet_result(
+        "on_timeout_packet",
+        cross_chain_swap_result.ibc_data,
+        port_registration,
+        swap_interface_program,
+        swap_interface_state,
+        swap_pool_state,
+        swap_pool_program.program_id,
+        swap_pool_authority,
+        [
+            AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
+            AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
+            AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+            AccountMeta(swap_escrow,                   is_signer=False, is_writable=True  ), # swap_escrow
+            AccountMeta(generic_payer.public_key,      is_signer=False, is_writable=True  ), # swap_escrow_rent_payer
+        ],
+        polymerase_sender_program,
+        polymerase_relayer_keypair
     )
-    await confirm_transaction(provider, ack_result)
 
-    catalyst_simulator.out_swap_timeout(source_swap_id)
+    # The swap escrow must be closed, and its rent refunded to the original rent payer
+    assert (await provider.connection.get_account_info(swap_escrow)).value is None
+    assert (await provider.connection.get_balance(generic_payer.public_key)).value == rent_payer_balance_before + swap_escrow_rent
+
+    # The amplified pool must return the units of the swap to the unit tracker, and revert the security limit increase
+    swap_pool_state_after_timeout = await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)
+    if hasattr(swap_pool_state_after_timeout, "unit_tracker_x64"):
+        assert swap_pool_state_after_timeout.unit_tracker_x64 == swap_pool_state_before_swap.unit_tracker_x64
+        assert u256_array_to_int(swap_pool_state_after_timeout.max_units_inflow_x64.limbs) < \
+            u256_array_to_int(swap_pool_state_after_swap.max_units_inflow_x64.limbs)
+
+    catalyst_simulator.out_swap_timeout(cross_chain_swap_result.swap_hash)
 
     await verify_catalyst_state(swap_pool_program, swap_pool_state, catalyst_simulator)
 
@@ -610,3 +682,1023 @@ async def test_self_cross_chain_swap_timeout(
     after_timeout_balance  = swapper_output_account_info_after_swap_timeout.amount
 
     assert before_balance == after_timeout_balance   # Timeout must return assets
+
+
+async def test_self_cross_chain_swap_underwrite(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    polymerase_relayer_keypair: Keypair,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    # Define test parameters
+    provider = swap_pool_program.provider
+
+    user_count           = 2
+    asset_count          = 2
+    pool_assets_weights  = [1, 1]
+    pool_assets_balances = [1000, 3000]
+    pool_amplification   = None
+
+    spc = await simple_pool_context_factory(
+        user_count,
+        asset_count,
+        pool_assets_balances,
+        pool_assets_weights,
+        pool_amplification,
+        True
+    )
+
+    swap_pool_state     = spc.swap_pool_state
+    swap_pool_authority = spc.swap_pool_authority
+    swap_pool_assets    = spc.swap_pool_assets
+    users               = spc.users
+
+    # Swap config
+    swapper                     = users[1]
+    swapper_input_asset_index   = 0
+    swapper_input_asset         = swap_pool_assets[swapper_input_asset_index]
+    swapper_input_asset_wallet  = swapper.token_accounts[swapper_input_asset_index]
+    swapper_input_asset_balance = 500
+    swapper_output_asset_index  = 1
+    swapper_output_asset        = swap_pool_assets[swapper_output_asset_index]
+    swapper_output_asset_wallet = swapper.token_accounts[swapper_output_asset_index]
+
+    initialize_swap_pool_state_result = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result
+    swap_pool_input_asset_wallet      = initialize_swap_pool_state_result.swap_pool_asset_wallets[swapper_input_asset_index]
+    swap_pool_output_asset_wallet     = initialize_swap_pool_state_result.swap_pool_asset_wallets[swapper_output_asset_index]
+
+    swap_interface_state              = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration                 = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    # Underwriter config
+    underwriter                      = generic_payer
+    underwriter_output_asset_balance = 3000
+    underwriter_output_asset_wallet  = await create_token_account(
+        provider,
+        swapper_output_asset,
+        underwriter.public_key,
+        generic_payer
+    )
+
+
+    # Fund swapper with tokens for the swap, and the underwriter with tokens to underwrite the swap
+    await mint_to(
+        provider,
+        swapper_input_asset_wallet,
+        swapper_input_asset,
+        mint_authority,
+        swapper_input_asset_balance
+    )
+
+    await mint_to(
+        provider,
+        underwriter_output_asset_wallet,
+        swapper_output_asset,
+        mint_authority,
+        underwriter_output_asset_balance
+    )
+
+
+    # Step 1: Perform cross-chain swap Outswap => CrossChainSwap => CallMultichain
+    cross_chain_swap_result = await perform_cross_chain_swap(
+        swapper_input_asset_balance,
+        swapper_input_asset,
+        swapper_input_asset_wallet,
+        swapper.user_keypair,
+        SOLANA_CHAIN_ID,
+        swap_interface_state,
+        swapper_output_asset_index,
+        swapper_output_asset_wallet,
+        generic_payer,
+        swap_pool_program,
+        swap_pool_state,
+        swap_pool_input_asset_wallet,
+        swap_pool_authority,
+        swap_interface_program,
+        swap_interface_state,
+        connection_state,
+        polymerase_sender_program,
+        polymerase_emulator_setup_master_keypair # Payer, can be anyone
+    )
+
+    units_x64 = cross_chain_swap_result.out_swap_event.data.withdrawnPoolUnitsX64
+
+    underwrite_id = compute_underwrite_id(
+        swap_pool_state,
+        swapper_output_asset_index,
+        units_x64,
+        0,
+        swapper_output_asset_wallet,
+        cross_chain_swap_result.swap_id
+    )
+    underwrite_state = get_underwrite_state_account(
+        swap_interface_program.program_id,
+        swap_interface_state,
+        underwrite_id
+    )[0]
+
+
+    # Step 2: Underwrite the swap before the packet arrives
+    swapper_output_balance_before_underwrite = (await get_account_info(provider, swapper_output_asset, swapper_output_asset_wallet)).amount
+    escrowed_assets_before_underwrite        = (await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)).escrowed_assets
+
+    async with TxEventListener("SwapUnderwrittenEvent") as ev_listener:
+        tx = await swap_interface_program.rpc["underwrite"](
+            underwrite_id,
+            swapper_output_asset_index,
+            units_x64,
+            0,
+            swapper_output_asset_wallet,
+            cross_chain_swap_result.swap_id,
+            ctx=Context(
+                accounts={
+                    "underwriter": underwriter.public_key,
+                    "interface_state_account": swap_interface_state,
+                    "swap_pool": swap_pool_state,
+                    "swap_pool_program": swap_pool_program.program_id,
+                    "underwrite_state_account": underwrite_state,
+                    "output_asset_mint": swapper_output_asset,
+                    "swap_pool_output_asset_wallet": swap_pool_output_asset_wallet,
+                    "underwriter_asset_wallet": underwriter_output_asset_wallet,
+                    "destination_asset_wallet": swapper_output_asset_wallet,
+                    "token_program": TOKEN_PROGRAM_ID,
+                    "system_program": SYS_PROGRAM_ID
+                },
+                signers=[underwriter]
+            )
+        )
+        await confirm_transaction(provider, tx)
+
+        underwritten_amount = (await ev_listener.get_events(swap_interface_program))[0].data.amount
+
+    assert underwritten_amount > 0
+
+    # The swapper must have received the underwritten amount, which must be reserved on the pool
+    swapper_output_balance_after_underwrite = (await get_account_info(provider, swapper_output_asset, swapper_output_asset_wallet)).amount
+    escrowed_assets_after_underwrite        = (await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)).escrowed_assets
+
+    assert swapper_output_balance_after_underwrite == swapper_output_balance_before_underwrite + underwritten_amount
+    assert escrowed_assets_after_underwrite[swapper_output_asset_index] == escrowed_assets_before_underwrite[swapper_output_asset_index] + underwritten_amount
+    assert (await get_account_info(provider, swapper_output_asset, underwriter_output_asset_wallet)).amount == underwriter_output_asset_balance - underwritten_amount
+
+
+    # Step 3: Perform cross-chain swap execute => receive => release the underwrite
+    data = await polymerase_sender_program.account["IbcData"].fetch(cross_chain_swap_result.ibc_data)
+
+    async with TxEventListener("FulfillUnderwriteEvent") as ev_listener:
+        rpc_result = await deliver_ibc_packet(
+            data.payload,
+            port_registration,
+            swap_interface_program,
+            [
+                AccountMeta(swap_interface_state,            is_signer=False, is_writable=False ), # interface_state_account
+                AccountMeta(swap_pool_state,                 is_signer=False, is_writable=True  ), # swap_pool
+                AccountMeta(swap_pool_program.program_id,    is_signer=False, is_writable=False ), # swap_pool_program
+                AccountMeta(TOKEN_PROGRAM_ID,                is_signer=False, is_writable=False ), # token_program
+                AccountMeta(connection_state,                is_signer=False, is_writable=False ), # connection_state_account
+                AccountMeta(swapper_output_asset,            is_signer=False, is_writable=False ), # output_asset_mint
+                AccountMeta(swapper_output_asset_wallet,     is_signer=False, is_writable=True  ), # output_asset_wallet
+                AccountMeta(swap_pool_output_asset_wallet,   is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                AccountMeta(swap_pool_authority,             is_signer=False, is_writable=False ), # swap_pool_authority
+                AccountMeta(underwrite_state,                is_signer=False, is_writable=True  ), # underwrite_state_account
+                AccountMeta(underwriter_output_asset_wallet, is_signer=False, is_writable=True  ), # refund_wallet
+                AccountMeta(underwriter.public_key,          is_signer=False, is_writable=True  ), # underwriter
+            ],
+            polymerase_sender_program,
+            polymerase_relayer_keypair
+        )
+        await confirm_transaction(provider, rpc_result)
+
+        fulfill_underwrite_event = (await ev_listener.get_events(swap_interface_program))[0]
+        assert bytes(fulfill_underwrite_event.data.underwriteId) == underwrite_id
+        assert fulfill_underwrite_event.data.amount              == underwritten_amount
+
+    # The underwriter must have been repaid, and the swapper must not have received the swap output a second time
+    assert (await get_account_info(provider, swapper_output_asset, underwriter_output_asset_wallet)).amount == underwriter_output_asset_balance
+    assert (await get_account_info(provider, swapper_output_asset, swapper_output_asset_wallet)).amount == swapper_output_balance_after_underwrite
+
+    escrowed_assets_after_fulfill = (await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)).escrowed_assets
+    assert escrowed_assets_after_fulfill[swapper_output_asset_index] == escrowed_assets_before_underwrite[swapper_output_asset_index]
+
+    # The underwrite state must be closed
+    assert (await provider.connection.get_account_info(underwrite_state)).value is None
+
+
+@dataclass
+class UnderwrittenSwapContext:
+    spc: SimplePoolContext
+    swapper_output_asset: PublicKey
+    swapper_output_asset_wallet: PublicKey
+    swap_pool_output_asset_wallet: PublicKey
+    swap_interface_state: PublicKey
+    connection_state: PublicKey
+    cross_chain_swap_result: PerformCrossChainSwapResult
+    underwriter: Keypair
+    underwriter_output_asset_wallet: PublicKey
+    underwrite_id: bytes
+    underwrite_state: PublicKey
+    underwritten_amount: int
+    escrowed_assets_before_underwrite: list[int]
+
+
+async def perform_underwritten_self_cross_chain_swap(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    swap_interface_setup_master_keypair: Keypair,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+    underwrite_expiry_slots: int | None = None
+) -> UnderwrittenSwapContext:
+
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(2, 2, [1000, 3000], [1, 1], None, True)
+
+    swapper                       = spc.users[1]
+    swapper_input_asset           = spc.swap_pool_assets[0]
+    swapper_input_asset_wallet    = swapper.token_accounts[0]
+    swapper_input_asset_balance   = 500
+    swapper_output_asset_index    = 1
+    swapper_output_asset          = spc.swap_pool_assets[swapper_output_asset_index]
+    swapper_output_asset_wallet   = swapper.token_accounts[swapper_output_asset_index]
+
+    swap_pool_asset_wallets       = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swap_pool_output_asset_wallet = swap_pool_asset_wallets[swapper_output_asset_index]
+    swap_interface_state          = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    if underwrite_expiry_slots is not None:
+        tx = await swap_interface_program.rpc["set_underwrite_expiry_slots"](
+            underwrite_expiry_slots,
+            ctx=Context(
+                accounts={
+                    "interface_state_account": swap_interface_state,
+                    "configurator": swap_interface_setup_master_keypair.public_key
+                },
+                signers=[swap_interface_setup_master_keypair]
+            )
+        )
+        await confirm_transaction(provider, tx)
+
+    # Underwriter config
+    underwriter                     = generic_payer
+    underwriter_output_asset_wallet = await create_token_account(provider, swapper_output_asset, underwriter.public_key, generic_payer)
+
+    await mint_to(provider, swapper_input_asset_wallet, swapper_input_asset, mint_authority, swapper_input_asset_balance)
+    await mint_to(provider, underwriter_output_asset_wallet, swapper_output_asset, mint_authority, 3000)
+
+    # Perform the cross-chain swap Outswap => CrossChainSwap => CallMultichain
+    cross_chain_swap_result = await perform_cross_chain_swap(
+        swapper_input_asset_balance,
+        swapper_input_asset,
+        swapper_input_asset_wallet,
+        swapper.user_keypair,
+        SOLANA_CHAIN_ID,
+        swap_interface_state,
+        swapper_output_asset_index,
+        swapper_output_asset_wallet,
+        generic_payer,
+        swap_pool_program,
+        spc.swap_pool_state,
+        swap_pool_asset_wallets[0],
+        spc.swap_pool_authority,
+        swap_interface_program,
+        swap_interface_state,
+        connection_state,
+        polymerase_sender_program,
+        polymerase_emulator_setup_master_keypair # Payer, can be anyone
+    )
+
+    units_x64 = cross_chain_swap_result.out_swap_event.data.withdrawnPoolUnitsX64
+
+    underwrite_id = compute_underwrite_id(
+        spc.swap_pool_state,
+        swapper_output_asset_index,
+        units_x64,
+        0,
+        swapper_output_asset_wallet,
+        cross_chain_swap_result.swap_id
+    )
+    underwrite_state = get_underwrite_state_account(swap_interface_program.program_id, swap_interface_state, underwrite_id)[0]
+
+    escrowed_assets_before_underwrite = (await swap_pool_program.account["SwapPoolState"].fetch(spc.swap_pool_state)).escrowed_assets
+
+    # Underwrite the swap before the packet arrives
+    async with TxEventListener("SwapUnderwrittenEvent") as ev_listener:
+        tx = await swap_interface_program.rpc["underwrite"](
+            underwrite_id,
+            swapper_output_asset_index,
+            units_x64,
+            0,
+            swapper_output_asset_wallet,
+            cross_chain_swap_result.swap_id,
+            ctx=Context(
+                accounts={
+                    "underwriter": underwriter.public_key,
+                    "interface_state_account": swap_interface_state,
+                    "swap_pool": spc.swap_pool_state,
+                    "swap_pool_program": swap_pool_program.program_id,
+                    "underwrite_state_account": underwrite_state,
+                    "output_asset_mint": swapper_output_asset,
+                    "swap_pool_output_asset_wallet": swap_pool_output_asset_wallet,
+                    "underwriter_asset_wallet": underwriter_output_asset_wallet,
+                    "destination_asset_wallet": swapper_output_asset_wallet,
+                    "token_program": TOKEN_PROGRAM_ID,
+                    "system_program": SYS_PROGRAM_ID
+                },
+                signers=[underwriter]
+            )
+        )
+        await confirm_transaction(provider, tx)
+
+        underwritten_amount = (await ev_listener.get_events(swap_interface_program))[0].data.amount
+
+    return UnderwrittenSwapContext(
+        spc,
+        swapper_output_asset,
+        swapper_output_asset_wallet,
+        swap_pool_output_asset_wallet,
+        swap_interface_state,
+        connection_state,
+        cross_chain_swap_result,
+        underwriter,
+        underwriter_output_asset_wallet,
+        underwrite_id,
+        underwrite_state,
+        underwritten_amount,
+        escrowed_assets_before_underwrite
+    )
+
+
+async def expire_underwrite(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    usc: UnderwrittenSwapContext,
+    caller: Keypair
+) -> None:
+    tx = await swap_interface_program.rpc["expire_underwrite"](
+        usc.underwrite_id,
+        ctx=Context(
+            accounts={
+                "caller": caller.public_key,
+                "interface_state_account": usc.swap_interface_state,
+                "swap_pool": usc.spc.swap_pool_state,
+                "swap_pool_program": swap_pool_program.program_id,
+                "underwrite_state_account": usc.underwrite_state,
+                "underwriter": usc.underwriter.public_key,
+                "system_program": SYS_PROGRAM_ID
+            },
+            signers=[caller]
+        )
+    )
+    await confirm_transaction(swap_interface_program.provider, tx)
+
+
+async def verify_underwrite_expired(
+    swap_pool_program: Program,
+    usc: UnderwrittenSwapContext
+) -> None:
+    provider = swap_pool_program.provider
+
+    # The underwrite state must be closed, and the assets reserved on the pool freed. The underwriter is not repaid.
+    assert (await provider.connection.get_account_info(usc.underwrite_state)).value is None
+
+    escrowed_assets = (await swap_pool_program.account["SwapPoolState"].fetch(usc.spc.swap_pool_state)).escrowed_assets
+    assert escrowed_assets == usc.escrowed_assets_before_underwrite
+
+    assert (await get_account_info(provider, usc.swapper_output_asset, usc.underwriter_output_asset_wallet)).amount == 3000 - usc.underwritten_amount
+
+
+async def test_self_cross_chain_swap_underwrite_expire_early(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    swap_interface_setup_master_keypair: Keypair,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    usc = await perform_underwritten_self_cross_chain_swap(
+        swap_pool_program,
+        swap_interface_program,
+        swap_interface_setup_master_keypair,
+        polymerase_emulator_setup_master_keypair,
+        polymerase_sender_program,
+        mint_authority,
+        generic_payer,
+        simple_pool_context_factory
+    )
+
+    # Third parties cannot expire the underwrite before its expiry
+    with pytest.raises(Exception):
+        await expire_underwrite(swap_pool_program, swap_interface_program, usc, Keypair())
+
+    assert (await swap_pool_program.provider.connection.get_account_info(usc.underwrite_state)).value is not None
+
+    # The underwriter can expire the underwrite at any time
+    async with TxEventListener("ExpireUnderwriteEvent") as ev_listener:
+        await expire_underwrite(swap_pool_program, swap_interface_program, usc, usc.underwriter)
+
+        expire_underwrite_event = (await ev_listener.get_events(swap_interface_program))[0]
+        assert bytes(expire_underwrite_event.data.underwriteId) == usc.underwrite_id
+        assert expire_underwrite_event.data.expirer             == usc.underwriter.public_key
+        assert expire_underwrite_event.data.amount              == usc.underwritten_amount
+
+    await verify_underwrite_expired(swap_pool_program, usc)
+
+
+async def test_self_cross_chain_swap_underwrite_expire_after_expiry(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    swap_interface_setup_master_keypair: Keypair,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    polymerase_relayer_keypair: Keypair,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    provider = swap_pool_program.provider
+
+    usc = await perform_underwritten_self_cross_chain_swap(
+        swap_pool_program,
+        swap_interface_program,
+        swap_interface_setup_master_keypair,
+        polymerase_emulator_setup_master_keypair,
+        polymerase_sender_program,
+        mint_authority,
+        generic_payer,
+        simple_pool_context_factory,
+        underwrite_expiry_slots=2
+    )
+
+    # Wait for the underwrite to expire
+    expiry = (await swap_interface_program.account["UnderwriteState"].fetch(usc.underwrite_state)).expiry
+    while (await provider.connection.get_slot()).value <= expiry:
+        await asyncio.sleep(0.4)
+
+    # Anyone can expire the underwrite once the expiry has passed
+    await expire_underwrite(swap_pool_program, swap_interface_program, usc, Keypair())
+
+    await verify_underwrite_expired(swap_pool_program, usc)
+
+
+    # A swap arriving after the underwrite has expired is executed as an ordinary swap
+    swapper_output_balance_before_swap = (await get_account_info(provider, usc.swapper_output_asset, usc.swapper_output_asset_wallet)).amount
+
+    data = await polymerase_sender_program.account["IbcData"].fetch(usc.cross_chain_swap_result.ibc_data)
+
+    async with TxEventListener("InSwapEvent") as ev_listener:
+        rpc_result = await deliver_ibc_packet(
+            data.payload,
+            usc.spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port,
+            swap_interface_program,
+            [
+                AccountMeta(usc.swap_interface_state,          is_signer=False, is_writable=False ), # interface_state_account
+                AccountMeta(usc.spc.swap_pool_state,           is_signer=False, is_writable=True  ), # swap_pool
+                AccountMeta(swap_pool_program.program_id,      is_signer=False, is_writable=False ), # swap_pool_program
+                AccountMeta(TOKEN_PROGRAM_ID,                  is_signer=False, is_writable=False ), # token_program
+                AccountMeta(usc.connection_state,              is_signer=False, is_writable=False ), # connection_state_account
+                AccountMeta(usc.swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
+                AccountMeta(usc.swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
+                AccountMeta(usc.swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                AccountMeta(usc.spc.swap_pool_authority,       is_signer=False, is_writable=False ), # swap_pool_authority
+                AccountMeta(usc.underwrite_state,              is_signer=False, is_writable=True  ), # underwrite_state_account
+            ],
+            polymerase_sender_program,
+            polymerase_relayer_keypair
+        )
+        await confirm_transaction(provider, rpc_result)
+
+        in_swap_event = (await ev_listener.get_events(swap_pool_program))[0]
+
+    swapper_output_balance_after_swap = (await get_account_info(provider, usc.swapper_output_asset, usc.swapper_output_asset_wallet)).amount
+    assert swapper_output_balance_after_swap - swapper_output_balance_before_swap == in_swap_event.data.withdrawnAssetAmount
+    assert in_swap_event.data.withdrawnAssetAmount > 0
+
+    # The underwriter must not have been repaid
+    assert (await get_account_info(provider, usc.swapper_output_asset, usc.underwriter_output_asset_wallet)).amount == 3000 - usc.underwritten_amount
+
+
+async def test_self_cross_chain_swap_disabled_connection(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    polymerase_relayer_keypair: Keypair,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    # Define test parameters
+    provider = swap_pool_program.provider
+
+    user_count           = 2
+    asset_count          = 2
+    pool_assets_weights  = [1, 1]
+    pool_assets_balances = [1000, 3000]
+    pool_amplification   = None
+
+    spc = await simple_pool_context_factory(
+        user_count,
+        asset_count,
+        pool_assets_balances,
+        pool_assets_weights,
+        pool_amplification,
+        True
+    )
+
+    swap_pool_state     = spc.swap_pool_state
+    swap_pool_authority = spc.swap_pool_authority
+    swap_pool_assets    = spc.swap_pool_assets
+    users               = spc.users
+
+    # Swap config
+    swapper                     = users[1]
+    swapper_input_asset_index   = 0
+    swapper_input_asset         = swap_pool_assets[swapper_input_asset_index]
+    swapper_input_asset_wallet  = swapper.token_accounts[swapper_input_asset_index]
+    swapper_input_asset_balance = 500
+    swapper_output_asset_index  = 0
+    swapper_output_asset        = swap_pool_assets[swapper_output_asset_index]
+    swapper_output_asset_wallet = swapper.token_accounts[swapper_output_asset_index]
+
+    initialize_swap_pool_state_result = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result
+    swap_pool_input_asset_wallet      = initialize_swap_pool_state_result.swap_pool_asset_wallets[swapper_input_asset_index]
+    swap_pool_output_asset_wallet     = initialize_swap_pool_state_result.swap_pool_asset_wallets[swapper_output_asset_index]
+
+    swap_interface_state              = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration                 = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+
+    # Fund swapper with tokens for the swap
+    await mint_to(
+        provider,
+        swapper_input_asset_wallet,
+        swapper_input_asset,
+        mint_authority,
+        swapper_input_asset_balance
+    )
+
+
+    # Step 1: Perform cross-chain swap Outswap => CrossChainSwap => CallMultichain
+    cross_chain_swap_result = await perform_cross_chain_swap(
+        swapper_input_asset_balance,
+        swapper_input_asset,
+        swapper_input_asset_wallet,
+        swapper.user_keypair,
+        SOLANA_CHAIN_ID,
+        swap_interface_state,
+        swapper_output_asset_index,
+        swapper_output_asset_wallet,
+        generic_payer,
+        swap_pool_program,
+        swap_pool_state,
+        swap_pool_input_asset_wallet,
+        swap_pool_authority,
+        swap_interface_program,
+        swap_interface_state,
+        connection_state,
+        polymerase_sender_program,
+        polymerase_emulator_setup_master_keypair # Payer, can be anyone
+    )
+
+
+    # Step 2: Disable the connection
+    await set_connection_enabled(
+        SOLANA_CHAIN_ID,
+        swap_interface_state,
+        False,
+        swap_pool_program,
+        swap_pool_state,
+        initialize_swap_pool_state_result.dao_authority_keypair,
+        swap_interface_program,
+        swap_interface_state
+    )
+
+    assert (await swap_interface_program.account["ConnectionState"].fetch(connection_state)).enabled == False
+
+
+    # Step 3: Cross-chain swap execute => receive => in_swap must be rejected, as the connection is disabled
+    data = await polymerase_sender_program.account["IbcData"].fetch(cross_chain_swap_result.ibc_data)
+
+    underwrite_state = get_underwrite_state_account(
+        swap_interface_program.program_id,
+        swap_interface_state,
+        compute_underwrite_id(
+            swap_pool_state,
+            swapper_output_asset_index,
+            cross_chain_swap_result.out_swap_event.data.withdrawnPoolUnitsX64,
+            0,
+            swapper_output_asset_wallet,
+            cross_chain_swap_result.swap_id
+        )
+    )[0]
+
+    with pytest.raises(Exception):
+        await deliver_ibc_packet(
+            data.payload,
+            port_registration,
+            swap_interface_program,
+            [
+                AccountMeta(swap_interface_state,          is_signer=False, is_writable=False ), # interface_state_account
+                AccountMeta(swap_pool_state,               is_signer=False, is_writable=True  ), # swap_pool
+                AccountMeta(swap_pool_program.program_id,  is_signer=False, is_writable=False ), # swap_pool_program
+                AccountMeta(TOKEN_PROGRAM_ID,              is_signer=False, is_writable=False ), # token_program
+                AccountMeta(connection_state,              is_signer=False, is_writable=False ), # connection_state_account
+                AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
+                AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
+                AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                AccountMeta(swap_pool_authority,           is_signer=False, is_writable=False ), # swap_pool_authority
+                AccountMeta(underwrite_state,              is_signer=False, is_writable=True  ), # underwrite_state_account
+            ],
+            polymerase_sender_program,
+            polymerase_relayer_keypair
+        )
+
+
+async def test_self_cross_chain_swap_unauthorised_ibc_result(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    polymerase_relayer_keypair: Keypair,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    # Define test parameters
+    provider = swap_pool_program.provider
+
+    user_count           = 2
+    asset_count          = 2
+    pool_assets_weights  = [1, 1]
+    pool_assets_balances = [1000, 3000]
+    pool_amplification   = None
+
+    spc = await simple_pool_context_factory(
+        user_count,
+        asset_count,
+        pool_assets_balances,
+        pool_assets_weights,
+        pool_amplification,
+        True
+    )
+
+    swap_pool_state     = spc.swap_pool_state
+    swap_pool_authority = spc.swap_pool_authority
+    swap_pool_assets    = spc.swap_pool_assets
+    users               = spc.users
+
+    # Swap config
+    swapper                     = users[1]
+    swapper_input_asset_index   = 0
+    swapper_input_asset         = swap_pool_assets[swapper_input_asset_index]
+    swapper_input_asset_wallet  = swapper.token_accounts[swapper_input_asset_index]
+    swapper_input_asset_balance = 500
+    swapper_output_asset_index  = 0
+    swapper_output_asset        = swap_pool_assets[swapper_output_asset_index]
+    swapper_output_asset_wallet = swapper.token_accounts[swapper_output_asset_index]
+
+    initialize_swap_pool_state_result = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result
+    swap_pool_input_asset_wallet      = initialize_swap_pool_state_result.swap_pool_asset_wallets[swapper_input_asset_index]
+    swap_pool_output_asset_wallet     = initialize_swap_pool_state_result.swap_pool_asset_wallets[swapper_output_asset_index]
+
+    swap_interface_state              = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration                 = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+
+    # Fund swapper with tokens for the swap
+    await mint_to(
+        provider,
+        swapper_input_asset_wallet,
+        swapper_input_asset,
+        mint_authority,
+        swapper_input_asset_balance
+    )
+
+
+    # Step 1: Perform cross-chain swap Outswap => CrossChainSwap => CallMultichain
+    cross_chain_swap_result = await perform_cross_chain_swap(
+        swapper_input_asset_balance,
+        swapper_input_asset,
+        swapper_input_asset_wallet,
+        swapper.user_keypair,
+        SOLANA_CHAIN_ID,
+        swap_interface_state,
+        swapper_output_asset_index,
+        swapper_output_asset_wallet,
+        generic_payer,
+        swap_pool_program,
+        swap_pool_state,
+        swap_pool_input_asset_wallet,
+        swap_pool_authority,
+        swap_interface_program,
+        swap_interface_state,
+        connection_state,
+        polymerase_sender_program,
+        polymerase_emulator_setup_master_keypair # Payer, can be anyone
+    )
+
+    data        = await polymerase_sender_program.account["IbcData"].fetch(cross_chain_swap_result.ibc_data)
+    swap_escrow = cross_chain_swap_result.swap_escrow
+
+    ibc_result_remaining_accounts = [
+        AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
+        AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
+        AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+        AccountMeta(swap_escrow,                   is_signer=False, is_writable=True  ), # swap_escrow
+        AccountMeta(generic_payer.public_key,      is_signer=False, is_writable=True  ), # swap_escrow_rent_payer
+    ]
+
+
+    # Step 2: Trigger the ibc ack/timeout directly on the interface (i.e. bypassing the polymerase endpoint) => must fail
+    unauthorised_caller = Keypair()
+
+    for callback in ["on_acknowledgement_packet", "on_timeout_packet"]:
+        with pytest.raises(Exception):
+            await swap_interface_program.rpc[callback](
+                data.payload,
+                ctx=Context(
+                    accounts={
+                        "port_registration": port_registration,
+                        "polymerase_authority": unauthorised_caller.public_key,
+                        "interface_state_account": swap_interface_state,
+                        "swap_pool": swap_pool_state,
+                        "swap_pool_program": swap_pool_program.program_id,
+                        "swap_pool_authority": swap_pool_authority,
+                        "token_program": TOKEN_PROGRAM_ID,
+                        "system_program": SYS_PROGRAM_ID
+                    },
+                    remaining_accounts=ibc_result_remaining_accounts,
+                    signers=[unauthorised_caller]
+                )
+            )
+
+    # The swap escrow must not have been released
+    assert (await provider.connection.get_account_info(swap_escrow)).value is not None
+
+
+    # Step 3: Trigger the ibc ack through the polymerase endpoint with an unregistered relayer, or with data which does not
+    # match the sent packet => must fail
+    polymerase_authority = get_polymerase_authority(polymerase_sender_program.program_id)[0]
+
+    for relayer_keypair, payload in [(unauthorised_caller, data.payload), (polymerase_relayer_keypair, bytes(len(data.payload)))]:
+        with pytest.raises(Exception):
+            await polymerase_sender_program.rpc["on_acknowledgement_packet"](
+                payload,
+                ctx=Context(
+                    accounts={
+                        "dapp_program": swap_interface_program.program_id,
+                        "polymerase_authority": polymerase_authority,
+                        "relayer_config": get_relayer_config(polymerase_sender_program.program_id)[0],
+                        "ibc_data": cross_chain_swap_result.ibc_data,
+                        "sender": data.sender,
+                        "payer": data.payer,
+                        "authority": relayer_keypair.public_key
+                    },
+                    remaining_accounts=get_ibc_result_accounts(
+                        port_registration,
+                        polymerase_authority,
+                        swap_interface_state,
+                        swap_pool_state,
+                        swap_pool_program.program_id,
+                        swap_pool_authority
+                    ) + ibc_result_remaining_accounts,
+                    signers=[relayer_keypair]
+                )
+            )
+
+    assert (await provider.connection.get_account_info(swap_escrow)).value is not None
+
+
+    # Step 4: Trigger the ibc ack through the registered polymerase endpoint => must succeed
+    await deliver_ibc_packet_result(
+        "on_acknowledgement_packet",
+        cross_chain_swap_result.ibc_data,
+        port_registration,
+        swap_interface_program,
+        swap_interface_state,
+        swap_pool_state,
+        swap_pool_program.program_id,
+        swap_pool_authority,
+        ibc_result_remaining_accounts,
+        polymerase_sender_program,
+        polymerase_relayer_keypair
+    )
+
+    assert (await provider.connection.get_account_info(swap_escrow)).value is None
+
+    # The ibc data account must have been closed (i.e. the ack cannot be delivered again)
+    assert (await provider.connection.get_account_info(cross_chain_swap_result.ibc_data)).value is None
+
+
+async def test_self_cross_chain_swap_unauthorised_recv_packet(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    polymerase_relayer_keypair: Keypair,
+    polymerase_receiver_program: Program,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    # Define test parameters
+    provider = swap_pool_program.provider
+
+    user_count           = 2
+    asset_count          = 2
+    pool_assets_weights  = [1, 1]
+    pool_assets_balances = [1000, 3000]
+    pool_amplification   = None
+
+    spc = await simple_pool_context_factory(
+        user_count,
+        asset_count,
+        pool_assets_balances,
+        pool_assets_weights,
+        pool_amplification,
+        True
+    )
+
+    swap_pool_state     = spc.swap_pool_state
+    swap_pool_authority = spc.swap_pool_authority
+    swap_pool_assets    = spc.swap_pool_assets
+    users               = spc.users
+
+    # Swap config
+    swapper                     = users[1]
+    swapper_input_asset_index   = 0
+    swapper_input_asset         = swap_pool_assets[swapper_input_asset_index]
+    swapper_input_asset_wallet  = swapper.token_accounts[swapper_input_asset_index]
+    swapper_input_asset_balance = 500
+    swapper_output_asset_index  = 0
+    swapper_output_asset        = swap_pool_assets[swapper_output_asset_index]
+    swapper_output_asset_wallet = swapper.token_accounts[swapper_output_asset_index]
+
+    initialize_swap_pool_state_result = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result
+    swap_pool_input_asset_wallet      = initialize_swap_pool_state_result.swap_pool_asset_wallets[swapper_input_asset_index]
+    swap_pool_output_asset_wallet     = initialize_swap_pool_state_result.swap_pool_asset_wallets[swapper_output_asset_index]
+
+    swap_interface_state              = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration                 = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+
+    # Fund swapper with tokens for the swap
+    await mint_to(
+        provider,
+        swapper_input_asset_wallet,
+        swapper_input_asset,
+        mint_authority,
+        swapper_input_asset_balance
+    )
+
+
+    # Step 1: Perform cross-chain swap Outswap => CrossChainSwap => CallMultichain
+    cross_chain_swap_result = await perform_cross_chain_swap(
+        swapper_input_asset_balance,
+        swapper_input_asset,
+        swapper_input_asset_wallet,
+        swapper.user_keypair,
+        SOLANA_CHAIN_ID,
+        swap_interface_state,
+        swapper_output_asset_index,
+        swapper_output_asset_wallet,
+        generic_payer,
+        swap_pool_program,
+        swap_pool_state,
+        swap_pool_input_asset_wallet,
+        swap_pool_authority,
+        swap_interface_program,
+        swap_interface_state,
+        connection_state,
+        polymerase_sender_program,
+        polymerase_emulator_setup_master_keypair # Payer, can be anyone
+    )
+
+    data = await polymerase_sender_program.account["IbcData"].fetch(cross_chain_swap_result.ibc_data)
+    # The underwrite state account must always be provided (the swap has not been underwritten)
+    underwrite_state = get_underwrite_state_account(
+        swap_interface_program.program_id,
+        swap_interface_state,
+        compute_underwrite_id(
+            swap_pool_state,
+            swapper_output_asset_index,
+            cross_chain_swap_result.out_swap_event.data.withdrawnPoolUnitsX64,
+            0,
+            swapper_output_asset_wallet,
+            cross_chain_swap_result.swap_id
+        )
+    )[0]
+
+    on_ibc_invocation_remaining_accounts = [
+        AccountMeta(swap_interface_state,          is_signer=False, is_writable=False ), # interface_state_account
+        AccountMeta(swap_pool_state,               is_signer=False, is_writable=True  ), # swap_pool
+        AccountMeta(swap_pool_program.program_id,  is_signer=False, is_writable=False ), # swap_pool_program
+        AccountMeta(TOKEN_PROGRAM_ID,              is_signer=False, is_writable=False ), # token_program
+        AccountMeta(connection_state,              is_signer=False, is_writable=False ), # connection_state_account
+        AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
+        AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
+        AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+        AccountMeta(swap_pool_authority,           is_signer=False, is_writable=False ), # swap_pool_authority
+        AccountMeta(underwrite_state,              is_signer=False, is_writable=True  ), # underwrite_state_account
+    ]
+
+    swapper_output_balance_before_swap = (await get_account_info(provider, swapper_output_asset, swapper_output_asset_wallet)).amount
+
+
+    # Step 2: Deliver the packet directly on the interface with a forged polymerase authority => must fail
+    unauthorised_caller = Keypair()
+
+    with pytest.raises(Exception):
+        await swap_interface_program.rpc["on_recv_packet"](
+            data.payload,
+            list(DEFAULT_CHANNEL_ID),
+            ctx=Context(
+                accounts={
+                    "port_registration": port_registration,
+                    "polymerase_authority": unauthorised_caller.public_key,
+                    "interface_state_account": swap_interface_state,
+                    "swap_pool": swap_pool_state,
+                    "swap_pool_program": swap_pool_program.program_id,
+                    "token_program": TOKEN_PROGRAM_ID,
+                    "connection_state_account": connection_state
+                },
+                remaining_accounts=on_ibc_invocation_remaining_accounts[5:],
+                signers=[unauthorised_caller]
+            )
+        )
+
+
+    # Step 3: Deliver the packet through the polymerase receiver (which is not the registered polymerase endpoint) => must fail
+    with pytest.raises(Exception):
+        await polymerase_receiver_program.rpc["on_receive"](
+            swap_interface_state,
+            "",
+            "",
+            "",
+            "",
+            1,
+            data.payload,
+            0,
+            0,
+            ctx=Context(
+                accounts={
+                    "port_registration": port_registration,
+                    "dapp_program": swap_interface_program.program_id,
+                    "authority": unauthorised_caller.public_key
+                },
+                remaining_accounts=on_ibc_invocation_remaining_accounts,
+                signers=[unauthorised_caller]
+            )
+        )
+
+    # Step 4: Deliver the packet through the registered polymerase endpoint, but with an unregistered relayer => must fail
+    with pytest.raises(Exception):
+        await deliver_ibc_packet(
+            data.payload,
+            port_registration,
+            swap_interface_program,
+            on_ibc_invocation_remaining_accounts,
+            polymerase_sender_program,
+            unauthorised_caller
+        )
+
+    # The swapper must not have received any assets
+    assert (await get_account_info(provider, swapper_output_asset, swapper_output_asset_wallet)).amount == swapper_output_balance_before_swap
+
+
+    # Step 5: Deliver the packet through the registered polymerase endpoint => must succeed
+    await deliver_ibc_packet(
+        data.payload,
+        port_registration,
+        swap_interface_program,
+        on_ibc_invocation_remaining_accounts,
+        polymerase_sender_program,
+        polymerase_relayer_keypair
+    )
+
+    assert (await get_account_info(provider, swapper_output_asset, swapper_output_asset_wallet)).amount > swapper_output_balance_before_swap
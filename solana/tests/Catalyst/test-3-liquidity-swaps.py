@@ -1,12 +1,15 @@
+import pytest
 from solana.keypair import Keypair
 from solana.transaction import AccountMeta
 
 from anchorpy import Context, Program
 from conftest import FactoryFixture
-from utils.swap_pool_utils import create_connection
+from utils.account_utils import get_swap_pool_liquidity_escrow
+from utils.swap_interface_utils import deliver_ibc_packet, deliver_ibc_packet_result
+from utils.swap_pool_utils import compute_liquidity_swap_hash, create_connection
 from utils.common_utils import SOLANA_CHAIN_ID
-from utils.transaction_utils import TxEventListener, confirm_transaction
-from utils.token_utils import approve, get_account_info
+from utils.transaction_utils import TxEventListener, confirm_transaction, is_program_error
+from utils.token_utils import approve, get_account_info, get_mint_info
 from utils.verify_utils import verify_catalyst_state, verify_in_liquidity_swap_event, verify_out_liquidity_swap_event
 from spl.token.constants import TOKEN_PROGRAM_ID
 
@@ -18,7 +21,7 @@ async def test_liquidity_swap_with_self(
     swap_interface_program: Program,
     polymerase_emulator_setup_master_keypair: Keypair,
     polymerase_sender_program: Program,
-    polymerase_receiver_program: Program,
+    polymerase_relayer_keypair: Keypair,
     simple_pool_context_factory: FactoryFixture
 ):
     # Define test parameters
@@ -61,10 +64,9 @@ async def test_liquidity_swap_with_self(
 
 
     # Perform cross-chain liquidity swap (first part): OutLiquiditySwap => CrossChainLiquiditySwap => CallMultichain
-    swap_amount       = 100000
-    target_chain_id   = SOLANA_CHAIN_ID
-    target_pool_id    = swap_interface_state
-    target_program_id = swap_interface_program.program_id
+    swap_amount     = 100000
+    target_chain_id = SOLANA_CHAIN_ID
+    target_pool_id  = swap_interface_state
 
     sim_liquidity_units_x64 = catalyst_simulator.out_liquidity_swap(
         swap_amount,
@@ -87,6 +89,17 @@ async def test_liquidity_swap_with_self(
 
     polymerase_ibc_data_account_keypair = Keypair() 
 
+    swap_hash = compute_liquidity_swap_hash(
+        swap_pool_state,
+        target_chain_id,
+        target_pool_id,
+        liquidity_provider_pool_token_wallet,
+        swap_amount,
+        liquidity_provider_pool_token_wallet,
+        polymerase_ibc_data_account_keypair.public_key
+    )
+    liquidity_escrow = get_swap_pool_liquidity_escrow(swap_pool_program.program_id, swap_pool_state, swap_hash)[0]
+
     # OutLiquiditySwapEvent
     async with TxEventListener("OutLiquiditySwapEvent") as ev_listener:
         tx = await swap_pool_program.rpc["out_liquidity_swap"](
@@ -94,12 +107,18 @@ async def test_liquidity_swap_with_self(
             target_pool_id,
             liquidity_provider_pool_token_wallet,
             swap_amount,
+            swap_hash,
+            liquidity_provider_pool_token_wallet,
+            0, # timeout_slot
+            0, # timeout_timestamp
             ctx=Context(
                 accounts={
                     "swap_pool_state_account": swap_pool_state,
                     "liquidity_provider_pool_token_wallet": liquidity_provider_pool_token_wallet,
                     "swap_pool_token_mint": swap_pool_token_mint,
                     "token_program": TOKEN_PROGRAM_ID,
+                    "liquidity_escrow": liquidity_escrow,
+                    "liquidity_escrow_rent_payer": polymerase_emulator_setup_master_keypair.public_key,
                     "ibc_interface_program": swap_interface_program.program_id,
                     "swap_pool_authority": swap_pool_authority,
                     "interface_state_account": swap_interface_state,
@@ -126,6 +145,7 @@ async def test_liquidity_swap_with_self(
             pool_token_mint          = swap_pool_token_mint,
             source_pool_token_wallet = liquidity_provider_pool_token_wallet,
             pool_token_amount        = swap_amount,
+            swap_hash                = swap_hash,
             liquidity_units_x64      = sim_liquidity_units_x64,
         )
 
@@ -153,37 +173,23 @@ async def test_liquidity_swap_with_self(
     # Fetch payload
     data = await polymerase_sender_program.account["IbcData"].fetch(polymerase_ibc_data_account_keypair.public_key)
 
-    authority = Keypair() #not used for now
-
     async with TxEventListener("InLiquiditySwapEvent") as ev_listener:
-        rpc_result = await polymerase_receiver_program.rpc["on_receive"](
-            swap_interface_state,
-            "",
-            "",
-            "",
-            "",
-            1,
+        rpc_result = await deliver_ibc_packet(
             data.payload,
-            0,
-            0,
-            ctx=Context(
-                accounts={
-                    "port_registration": port_registration,
-                    "dapp_program": target_program_id,
-                    "authority": authority.public_key
-                },
-                remaining_accounts=[
-                    AccountMeta(swap_interface_state,          is_signer=False, is_writable=False ), # interface_state_account
-                    AccountMeta(swap_pool_state,               is_signer=False, is_writable=True  ), # swap_pool
-                    AccountMeta(swap_pool_program.program_id,  is_signer=False, is_writable=False ), # swap_pool_program
-                    AccountMeta(TOKEN_PROGRAM_ID,              is_signer=False, is_writable=False ), # token_program
-                    AccountMeta(connection_state,              is_signer=False, is_writable=False ), # connection_state_account
-                    AccountMeta(swap_pool_token_mint,          is_signer=False, is_writable=True ), # output_asset_mint
-                    AccountMeta(liquidity_provider_pool_token_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
-                    AccountMeta(swap_pool_authority,           is_signer=False, is_writable=False ), # swap_pool_authority
-                ],
-                signers=[authority]
-            )
+            port_registration,
+            swap_interface_program,
+            [
+                AccountMeta(swap_interface_state,          is_signer=False, is_writable=False ), # interface_state_account
+                AccountMeta(swap_pool_state,               is_signer=False, is_writable=True  ), # swap_pool
+                AccountMeta(swap_pool_program.program_id,  is_signer=False, is_writable=False ), # swap_pool_program
+                AccountMeta(TOKEN_PROGRAM_ID,              is_signer=False, is_writable=False ), # token_program
+                AccountMeta(connection_state,              is_signer=False, is_writable=False ), # connection_state_account
+                AccountMeta(swap_pool_token_mint,          is_signer=False, is_writable=True ), # output_asset_mint
+                AccountMeta(liquidity_provider_pool_token_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
+                AccountMeta(swap_pool_authority,           is_signer=False, is_writable=False ), # swap_pool_authority
+            ],
+            polymerase_sender_program,
+            polymerase_relayer_keypair
         )
         await confirm_transaction(provider, rpc_result)
 
@@ -227,7 +233,7 @@ async def test_liquidity_swap(
     swap_interface_program: Program,
     polymerase_emulator_setup_master_keypair: Keypair,
     polymerase_sender_program: Program,
-    polymerase_receiver_program: Program,
+    polymerase_relayer_keypair: Keypair,
     generic_payer: Keypair,
     simple_pool_context_factory: FactoryFixture
 ):
@@ -298,6 +304,8 @@ async def test_liquidity_swap(
     # Connect Pool 1 and Pool 2
     target_chain_id   = SOLANA_CHAIN_ID
     target_program_id = swap_interface_program.program_id
+    channel_id_1      = bytes([1]*32)
+    channel_id_2      = bytes([2]*32)
 
     create_connection_result_1 = await create_connection(
         target_chain_id        = target_chain_id,
@@ -308,10 +316,16 @@ async def test_liquidity_swap(
         authority_keypair      = dao_authority_keypair_1,
         swap_interface_program = swap_interface_program,
         swap_interface_state   = swap_interface_state_1,
-        rent_payer_keypair     = generic_payer
+        rent_payer_keypair     = generic_payer,
+        channel_id             = channel_id_1
     )
     connection_state_1 = create_connection_result_1.connection_state
 
+    connection_state_1_data = await swap_interface_program.account["ConnectionState"].fetch(connection_state_1)
+    assert connection_state_1_data.connected_interface_program == target_program_id
+    assert connection_state_1_data.connected_chain             == target_chain_id
+    assert bytes(connection_state_1_data.channel_id)           == channel_id_1
+
     create_connection_result_2 = await create_connection(
         target_chain_id        = target_chain_id,
         target_pool_id         = swap_interface_state_1,
@@ -321,10 +335,16 @@ async def test_liquidity_swap(
         authority_keypair      = dao_authority_keypair_2,
         swap_interface_program = swap_interface_program,
         swap_interface_state   = swap_interface_state_2,
-        rent_payer_keypair     = generic_payer
+        rent_payer_keypair     = generic_payer,
+        channel_id             = channel_id_2
     )
     connection_state_2 = create_connection_result_2.connection_state
 
+    connection_state_2_data = await swap_interface_program.account["ConnectionState"].fetch(connection_state_2)
+    assert connection_state_2_data.connected_interface_program == target_program_id
+    assert connection_state_2_data.connected_chain             == target_chain_id
+    assert bytes(connection_state_2_data.channel_id)           == channel_id_2
+
 
     # Perform cross-chain liquidity swap (first part): OutLiquiditySwap => CrossChainLiquiditySwap => CallMultichain
     swap_amount    = 100000
@@ -351,6 +371,17 @@ async def test_liquidity_swap(
 
     polymerase_ibc_data_account_keypair = Keypair() 
 
+    swap_hash = compute_liquidity_swap_hash(
+        swap_pool_state_1,
+        target_chain_id,
+        target_pool_id,
+        liquidity_provider_pool_token_wallet_2,
+        swap_amount,
+        liquidity_provider_pool_token_wallet_1,
+        polymerase_ibc_data_account_keypair.public_key
+    )
+    liquidity_escrow = get_swap_pool_liquidity_escrow(swap_pool_program.program_id, swap_pool_state_1, swap_hash)[0]
+
     # OutLiquiditySwapEvent
     async with TxEventListener("OutLiquiditySwapEvent") as ev_listener:
         tx = await swap_pool_program.rpc["out_liquidity_swap"](
@@ -358,12 +389,18 @@ async def test_liquidity_swap(
             target_pool_id,
             liquidity_provider_pool_token_wallet_2,
             swap_amount,
+            swap_hash,
+            liquidity_provider_pool_token_wallet_1,
+            0, # timeout_slot
+            0, # timeout_timestamp
             ctx=Context(
                 accounts={
                     "swap_pool_state_account": swap_pool_state_1,
                     "liquidity_provider_pool_token_wallet": liquidity_provider_pool_token_wallet_1,
                     "swap_pool_token_mint": swap_pool_token_mint_1,
                     "token_program": TOKEN_PROGRAM_ID,
+                    "liquidity_escrow": liquidity_escrow,
+                    "liquidity_escrow_rent_payer": polymerase_emulator_setup_master_keypair.public_key,
                     "ibc_interface_program": swap_interface_program.program_id,
                     "swap_pool_authority": swap_pool_authority_1,
                     "interface_state_account": swap_interface_state_1,
@@ -390,6 +427,7 @@ async def test_liquidity_swap(
             pool_token_mint          = swap_pool_token_mint_1,
             source_pool_token_wallet = liquidity_provider_pool_token_wallet_1,
             pool_token_amount        = swap_amount,
+            swap_hash                = swap_hash,
             liquidity_units_x64      = sim_liquidity_units_x64,
         )
 
@@ -417,37 +455,43 @@ async def test_liquidity_swap(
     # Fetch payload
     data = await polymerase_sender_program.account["IbcData"].fetch(polymerase_ibc_data_account_keypair.public_key)
 
-    authority = Keypair() #not used for now
+    # The packet must be routed on the channel of the pool 1 => pool 2 connection
+    assert bytes(data.channel_id) == channel_id_1
+
+    on_ibc_invocation_accounts = [
+        AccountMeta(swap_interface_state_2,                  is_signer=False, is_writable=False ), # interface_state_account
+        AccountMeta(swap_pool_state_2,                       is_signer=False, is_writable=True  ), # swap_pool
+        AccountMeta(swap_pool_program.program_id,            is_signer=False, is_writable=False ), # swap_pool_program
+        AccountMeta(TOKEN_PROGRAM_ID,                        is_signer=False, is_writable=False ), # token_program
+        AccountMeta(connection_state_2,                      is_signer=False, is_writable=False ), # connection_state_account
+        AccountMeta(swap_pool_token_mint_2,                  is_signer=False, is_writable=True ),  # output_asset_mint
+        AccountMeta(liquidity_provider_pool_token_wallet_2,  is_signer=False, is_writable=True  ), # output_asset_wallet
+        AccountMeta(swap_pool_authority_2,                   is_signer=False, is_writable=False ), # swap_pool_authority
+    ]
+
+    # The packet must be rejected if it is not received on the channel of the pool 2 => pool 1 connection
+    with pytest.raises(Exception) as exception_info:
+        await deliver_ibc_packet(
+            data.payload,
+            port_registration_2,
+            swap_interface_program,
+            on_ibc_invocation_accounts,
+            polymerase_sender_program,
+            polymerase_relayer_keypair,
+            channel_id = channel_id_1
+        )
+
+    assert is_program_error(exception_info.value, swap_interface_program, "InvalidChannel")
 
     async with TxEventListener("InLiquiditySwapEvent") as ev_listener:
-        rpc_result = await polymerase_receiver_program.rpc["on_receive"](
-            swap_interface_state_2,
-            "",
-            "",
-            "",
-            "",
-            1,
+        rpc_result = await deliver_ibc_packet(
             data.payload,
-            0,
-            0,
-            ctx=Context(
-                accounts={
-                    "port_registration": port_registration_2,
-                    "dapp_program": target_program_id,
-                    "authority": authority.public_key
-                },
-                remaining_accounts=[
-                    AccountMeta(swap_interface_state_2,                  is_signer=False, is_writable=False ), # interface_state_account
-                    AccountMeta(swap_pool_state_2,                       is_signer=False, is_writable=True  ), # swap_pool
-                    AccountMeta(swap_pool_program.program_id,            is_signer=False, is_writable=False ), # swap_pool_program
-                    AccountMeta(TOKEN_PROGRAM_ID,                        is_signer=False, is_writable=False ), # token_program
-                    AccountMeta(connection_state_2,                      is_signer=False, is_writable=False ), # connection_state_account
-                    AccountMeta(swap_pool_token_mint_2,                  is_signer=False, is_writable=True ),  # output_asset_mint
-                    AccountMeta(liquidity_provider_pool_token_wallet_2,  is_signer=False, is_writable=True  ), # output_asset_wallet
-                    AccountMeta(swap_pool_authority_2,                   is_signer=False, is_writable=False ), # swap_pool_authority
-                ],
-                signers=[authority]
-            )
+            port_registration_2,
+            swap_interface_program,
+            on_ibc_invocation_accounts,
+            polymerase_sender_program,
+            polymerase_relayer_keypair,
+            channel_id = channel_id_2
         )
         await confirm_transaction(provider, rpc_result)
 
@@ -482,3 +526,264 @@ async def test_liquidity_swap(
     assert liq_provider_account_info_after_swap.amount > liq_provider_account_info_mid_swap.amount
 
     assert (liq_provider_account_info_after_swap.amount - liq_provider_account_info_mid_swap.amount)/swap_amount > 0.95 #TODO add better test
+
+
+async def test_liquidity_swap_timeout(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    polymerase_relayer_keypair: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(1, 2, [100000000, 300000000], [1, 1], None, True)
+
+    swap_pool_state     = spc.swap_pool_state
+    swap_pool_authority = spc.swap_pool_authority
+    liquidity_provider  = spc.users[0]
+
+    initialize_swap_pool_state_result    = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result
+    swap_interface_state                 = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration                    = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+    swap_pool_token_mint                 = initialize_swap_pool_state_result.swap_pool_token_mint
+    liquidity_provider_pool_token_wallet = initialize_swap_pool_state_result.depositor_pool_token_wallet_keypair.public_key
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    pool_tokens_supply = (await get_account_info(provider, swap_pool_token_mint, liquidity_provider_pool_token_wallet)).amount
+    swap_amount        = pool_tokens_supply // 2
+
+    eq_balances_before_swap = (await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)).pool_assets_eq_balances
+
+
+    # Step 1: Perform the out liquidity swap, with the liquidity provider wallet as the fallback wallet
+    await approve(provider, liquidity_provider_pool_token_wallet, liquidity_provider.user_keypair, swap_pool_authority, swap_amount)
+
+    polymerase_ibc_data_account_keypair = Keypair()
+
+    swap_hash = compute_liquidity_swap_hash(
+        swap_pool_state,
+        SOLANA_CHAIN_ID,
+        swap_interface_state,
+        liquidity_provider_pool_token_wallet,
+        swap_amount,
+        liquidity_provider_pool_token_wallet,
+        polymerase_ibc_data_account_keypair.public_key
+    )
+    liquidity_escrow = get_swap_pool_liquidity_escrow(swap_pool_program.program_id, swap_pool_state, swap_hash)[0]
+
+    tx = await swap_pool_program.rpc["out_liquidity_swap"](
+        SOLANA_CHAIN_ID,
+        swap_interface_state,
+        liquidity_provider_pool_token_wallet,
+        swap_amount,
+        swap_hash,
+        liquidity_provider_pool_token_wallet,
+        0, # timeout_slot
+        0, # timeout_timestamp
+        ctx=Context(
+            accounts={
+                "swap_pool_state_account": swap_pool_state,
+                "liquidity_provider_pool_token_wallet": liquidity_provider_pool_token_wallet,
+                "swap_pool_token_mint": swap_pool_token_mint,
+                "token_program": TOKEN_PROGRAM_ID,
+                "liquidity_escrow": liquidity_escrow,
+                "liquidity_escrow_rent_payer": polymerase_emulator_setup_master_keypair.public_key,
+                "ibc_interface_program": swap_interface_program.program_id,
+                "swap_pool_authority": swap_pool_authority,
+                "interface_state_account": swap_interface_state,
+                "connection_state_account": connection_state,
+                "polymerase_sender_program": polymerase_sender_program.program_id,
+                "polymerase_ibc_data": polymerase_ibc_data_account_keypair.public_key,
+                "polymerase_ibc_data_account_payer": polymerase_emulator_setup_master_keypair.public_key,
+                "system_program": SYS_PROGRAM_ID
+            },
+            signers=[polymerase_emulator_setup_master_keypair, polymerase_ibc_data_account_keypair]
+        )
+    )
+    await confirm_transaction(provider, tx)
+
+
+    # Step 2: Withdraw all the remaining liquidity, so that the pool token supply is zero when the swap times out
+    remaining_pool_tokens = pool_tokens_supply - swap_amount
+
+    await approve(provider, liquidity_provider_pool_token_wallet, liquidity_provider.user_keypair, swap_pool_authority, remaining_pool_tokens)
+
+    withdraw_remaining_accounts: list[AccountMeta] = []
+    for mint, swap_pool_asset_wallet, withdrawer_asset_wallet in zip(
+        spc.swap_pool_assets,
+        initialize_swap_pool_state_result.swap_pool_asset_wallets,
+        liquidity_provider.token_accounts
+    ):
+        withdraw_remaining_accounts += [
+            AccountMeta(mint,                    is_signer=False, is_writable=False ), # asset mint
+            AccountMeta(swap_pool_asset_wallet,  is_signer=False, is_writable=True  ), # swap pool asset wallet
+            AccountMeta(withdrawer_asset_wallet, is_signer=False, is_writable=True  ), # withdrawer asset wallet
+        ]
+
+    tx = await swap_pool_program.rpc["withdraw"](
+        remaining_pool_tokens,
+        ctx=Context(
+            accounts={
+                "swap_pool_state_account": swap_pool_state,
+                "withdrawer_pool_token_wallet": liquidity_provider_pool_token_wallet,
+                "swap_pool_token_mint": swap_pool_token_mint,
+                "swap_pool_authority": swap_pool_authority,
+                "token_program": TOKEN_PROGRAM_ID
+            },
+            remaining_accounts=withdraw_remaining_accounts
+        )
+    )
+    await confirm_transaction(provider, tx)
+
+    assert (await get_mint_info(provider, swap_pool_token_mint)).supply == 0
+
+    eq_balances_before_timeout = (await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)).pool_assets_eq_balances
+
+
+    # Step 3: Trigger the ibc timeout on the source chain (simulates the polymer chain endpoint contract)
+    await deliver_ibc_packet_result(
+        "on_timeout_packet",
+        polymerase_ibc_data_account_keypair.public_key,
+        port_registration,
+        swap_interface_program,
+        swap_interface_state,
+        swap_pool_state,
+        swap_pool_program.program_id,
+        swap_pool_authority,
+        [
+            AccountMeta(swap_pool_token_mint,                                 is_signer=False, is_writable=True ), # swap_pool_token_mint
+            AccountMeta(liquidity_provider_pool_token_wallet,                 is_signer=False, is_writable=True ), # fallback_wallet
+            AccountMeta(liquidity_escrow,                                     is_signer=False, is_writable=True ), # liquidity_escrow
+            AccountMeta(polymerase_emulator_setup_master_keypair.public_key,  is_signer=False, is_writable=True ), # liquidity_escrow_rent_payer
+        ],
+        polymerase_sender_program,
+        polymerase_relayer_keypair
+    )
+
+    # The escrowed pool tokens must be minted back to the fallback wallet, and the liquidity escrow closed
+    assert (await get_account_info(provider, swap_pool_token_mint, liquidity_provider_pool_token_wallet)).amount == swap_amount
+    assert (await get_mint_info(provider, swap_pool_token_mint)).supply == swap_amount
+    assert (await provider.connection.get_account_info(liquidity_escrow)).value is None
+
+    # The eq balances removed by the out liquidity swap must be restored exactly
+    eq_balances_after_timeout = (await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)).pool_assets_eq_balances
+
+    for eq_balance_before_swap, eq_balance_before_timeout, eq_balance_after_timeout in zip(
+        eq_balances_before_swap,
+        eq_balances_before_timeout,
+        eq_balances_after_timeout
+    ):
+        assert eq_balance_after_timeout == eq_balance_before_timeout + swap_amount * eq_balance_before_swap // pool_tokens_supply
+
+
+async def test_liquidity_swap_message_timed_out(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    polymerase_relayer_keypair: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(1, 2, [100000000, 300000000], [1, 1], None, True)
+
+    swap_pool_state     = spc.swap_pool_state
+    swap_pool_authority = spc.swap_pool_authority
+    liquidity_provider  = spc.users[0]
+
+    initialize_swap_pool_state_result    = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result
+    swap_interface_state                 = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration                    = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+    swap_pool_token_mint                 = initialize_swap_pool_state_result.swap_pool_token_mint
+    liquidity_provider_pool_token_wallet = initialize_swap_pool_state_result.depositor_pool_token_wallet_keypair.public_key
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    swap_amount = 100000
+
+
+    # Step 1: Perform an out liquidity swap which times out on the first slot (i.e. has already timed out)
+    await approve(provider, liquidity_provider_pool_token_wallet, liquidity_provider.user_keypair, swap_pool_authority, swap_amount)
+
+    polymerase_ibc_data_account_keypair = Keypair()
+
+    swap_hash = compute_liquidity_swap_hash(
+        swap_pool_state,
+        SOLANA_CHAIN_ID,
+        swap_interface_state,
+        liquidity_provider_pool_token_wallet,
+        swap_amount,
+        liquidity_provider_pool_token_wallet,
+        polymerase_ibc_data_account_keypair.public_key
+    )
+    liquidity_escrow = get_swap_pool_liquidity_escrow(swap_pool_program.program_id, swap_pool_state, swap_hash)[0]
+
+    tx = await swap_pool_program.rpc["out_liquidity_swap"](
+        SOLANA_CHAIN_ID,
+        swap_interface_state,
+        liquidity_provider_pool_token_wallet,
+        swap_amount,
+        swap_hash,
+        liquidity_provider_pool_token_wallet,
+        1, # timeout_slot
+        0, # timeout_timestamp
+        ctx=Context(
+            accounts={
+                "swap_pool_state_account": swap_pool_state,
+                "liquidity_provider_pool_token_wallet": liquidity_provider_pool_token_wallet,
+                "swap_pool_token_mint": swap_pool_token_mint,
+                "token_program": TOKEN_PROGRAM_ID,
+                "liquidity_escrow": liquidity_escrow,
+                "liquidity_escrow_rent_payer": polymerase_emulator_setup_master_keypair.public_key,
+                "ibc_interface_program": swap_interface_program.program_id,
+                "swap_pool_authority": swap_pool_authority,
+                "interface_state_account": swap_interface_state,
+                "connection_state_account": connection_state,
+                "polymerase_sender_program": polymerase_sender_program.program_id,
+                "polymerase_ibc_data": polymerase_ibc_data_account_keypair.public_key,
+                "polymerase_ibc_data_account_payer": polymerase_emulator_setup_master_keypair.public_key,
+                "system_program": SYS_PROGRAM_ID
+            },
+            signers=[polymerase_emulator_setup_master_keypair, polymerase_ibc_data_account_keypair]
+        )
+    )
+    await confirm_transaction(provider, tx)
+
+    liq_provider_balance_after_out_swap = (await get_account_info(provider, swap_pool_token_mint, liquidity_provider_pool_token_wallet)).amount
+
+
+    # Step 2: Cross-chain liquidity swap execute => receive => in_liquidity_swap must be rejected, as the message has timed out
+    data = await polymerase_sender_program.account["IbcData"].fetch(polymerase_ibc_data_account_keypair.public_key)
+
+    with pytest.raises(Exception) as exception_info:
+        rpc_result = await deliver_ibc_packet(
+            data.payload,
+            port_registration,
+            swap_interface_program,
+            [
+                AccountMeta(swap_interface_state,                   is_signer=False, is_writable=False ), # interface_state_account
+                AccountMeta(swap_pool_state,                        is_signer=False, is_writable=True  ), # swap_pool
+                AccountMeta(swap_pool_program.program_id,           is_signer=False, is_writable=False ), # swap_pool_program
+                AccountMeta(TOKEN_PROGRAM_ID,                       is_signer=False, is_writable=False ), # token_program
+                AccountMeta(connection_state,                       is_signer=False, is_writable=False ), # connection_state_account
+                AccountMeta(swap_pool_token_mint,                   is_signer=False, is_writable=True  ), # output_asset_mint
+                AccountMeta(liquidity_provider_pool_token_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
+                AccountMeta(swap_pool_authority,                    is_signer=False, is_writable=False ), # swap_pool_authority
+            ],
+            polymerase_sender_program,
+            polymerase_relayer_keypair,
+            timeout_block_height = data.timeout_block_height,
+            timeout_timestamp    = data.timeout_timestamp
+        )
+        await confirm_transaction(provider, rpc_result)
+
+    assert is_program_error(exception_info.value, polymerase_sender_program, "PacketTimedOut")
+
+    # No pool tokens must have been minted
+    assert (await get_account_info(provider, swap_pool_token_mint, liquidity_provider_pool_token_wallet)).amount == liq_provider_balance_after_out_swap
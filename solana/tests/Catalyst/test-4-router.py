@@ -0,0 +1,636 @@
+import pytest
+from solana.keypair import Keypair
+from solana.publickey import PublicKey
+from solana.transaction import AccountMeta
+from solana.system_program import SYS_PROGRAM_ID
+
+from anchorpy import Context, Program, Provider
+from conftest import FactoryFixture
+
+from utils.account_utils import get_swap_pool_escrow_wallet
+from utils.common_utils import SOLANA_CHAIN_ID
+from utils.swap_pool_utils import compute_swap_hash, get_or_create_governance_fee_wallet
+from utils.transaction_utils import TxEventListener, confirm_transaction, is_program_error
+from utils.token_utils import create_token_account, get_account_info, mint_to
+from utils.verify_utils import int_to_u256_array
+from spl.token.constants import TOKEN_PROGRAM_ID, WRAPPED_SOL_MINT
+
+
+WALLET_BALANCE = 2**64-1
+
+
+def local_swap_remaining_accounts(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_authority: PublicKey,
+    input_asset: PublicKey,
+    input_asset_wallet: PublicKey,
+    swap_pool_input_asset_wallet: PublicKey,
+    output_asset: PublicKey,
+    output_asset_wallet: PublicKey,
+    swap_pool_output_asset_wallet: PublicKey,
+    governance_fee_wallet: PublicKey
+) -> list[AccountMeta]:
+    return [
+        AccountMeta(swap_pool_program.program_id, is_signer=False, is_writable=False),
+        AccountMeta(swap_pool_state, is_signer=False, is_writable=True),
+        AccountMeta(input_asset, is_signer=False, is_writable=False),
+        AccountMeta(input_asset_wallet, is_signer=False, is_writable=True),
+        AccountMeta(swap_pool_input_asset_wallet, is_signer=False, is_writable=True),
+        AccountMeta(output_asset, is_signer=False, is_writable=False),
+        AccountMeta(output_asset_wallet, is_signer=False, is_writable=True),
+        AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True),
+        AccountMeta(governance_fee_wallet, is_signer=False, is_writable=True),
+        AccountMeta(swap_pool_authority, is_signer=False, is_writable=False)
+    ]
+
+
+def liquidity_remaining_accounts(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_authority: PublicKey,
+    swap_pool_token_mint: PublicKey,
+    user_pool_token_wallet: PublicKey,
+    assets: list[PublicKey],
+    swap_pool_asset_wallets: list[PublicKey],
+    user_asset_wallets: list[PublicKey]
+) -> list[AccountMeta]:
+    remaining_accounts = [
+        AccountMeta(swap_pool_program.program_id, is_signer=False, is_writable=False),
+        AccountMeta(swap_pool_state, is_signer=False, is_writable=True),
+        AccountMeta(user_pool_token_wallet, is_signer=False, is_writable=True),
+        AccountMeta(swap_pool_token_mint, is_signer=False, is_writable=True),
+        AccountMeta(swap_pool_authority, is_signer=False, is_writable=False)
+    ]
+
+    for asset, swap_pool_asset_wallet, user_asset_wallet in zip(assets, swap_pool_asset_wallets, user_asset_wallets):
+        remaining_accounts += [
+            AccountMeta(asset, is_signer=False, is_writable=False),
+            AccountMeta(swap_pool_asset_wallet, is_signer=False, is_writable=True),
+            AccountMeta(user_asset_wallet, is_signer=False, is_writable=True)
+        ]
+
+    return remaining_accounts
+
+
+async def fund_lamports(provider: Provider, wallet: PublicKey, lamports: int):
+    airdrop_result = await provider.connection.request_airdrop(wallet, lamports)
+    await confirm_transaction(provider, airdrop_result.value)
+
+
+async def test_router_multi_hop_local_swap(
+    router_program: Program,
+    swap_pool_program: Program,
+    mint_authority: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    # Define test parameters
+    provider = swap_pool_program.provider
+
+    user_count           = 2
+    asset_count          = 3
+    pool_assets_weights  = [1, 1, 1]
+    pool_assets_balances = [1000*10**10, 1000*10**10, 1000*10**10]
+    pool_amplification   = None
+
+    spc = await simple_pool_context_factory(
+        user_count,
+        asset_count,
+        pool_assets_balances,
+        pool_assets_weights,
+        pool_amplification,
+        False
+    )
+
+    swap_pool_state     = spc.swap_pool_state
+    swap_pool_authority = spc.swap_pool_authority
+    swap_pool_assets    = spc.swap_pool_assets
+    swap_pool_wallets   = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+
+    swapper      = spc.users[1]
+    swap_amount  = 5000000000
+
+    await mint_to(provider, swapper.token_accounts[0], swap_pool_assets[0], mint_authority, swap_amount)
+
+    governance_fee_wallets = [
+        await get_or_create_governance_fee_wallet(swap_pool_program, swap_pool_state, asset) for asset in swap_pool_assets
+    ]
+
+    # Route: asset 0 -> asset 1 -> asset 2. The second hop swaps the full output of the first one.
+    remaining_accounts = []
+    for from_index, to_index in [(0, 1), (1, 2)]:
+        remaining_accounts += local_swap_remaining_accounts(
+            swap_pool_program,
+            swap_pool_state,
+            swap_pool_authority,
+            swap_pool_assets[from_index],
+            swapper.token_accounts[from_index],
+            swap_pool_wallets[from_index],
+            swap_pool_assets[to_index],
+            swapper.token_accounts[to_index],
+            swap_pool_wallets[to_index],
+            governance_fee_wallets[from_index]
+        )
+
+    Command = router_program.type["Command"]
+    commands = [
+        Command.LocalSwap(amount=swap_amount, min_yield=0, approx=False),
+        Command.LocalSwap(amount=WALLET_BALANCE, min_yield=0, approx=False)
+    ]
+
+    accounts = {
+        "user": swapper.user_keypair.public_key,
+        "output_account": swapper.token_accounts[2],
+        "token_program": TOKEN_PROGRAM_ID,
+        "system_program": SYS_PROGRAM_ID
+    }
+
+    # The route must fail if the minimum output is not reached
+    with pytest.raises(Exception):
+        await router_program.rpc["execute"](
+            commands,
+            swap_amount,
+            ctx=Context(accounts=accounts, remaining_accounts=remaining_accounts, signers=[swapper.user_keypair])
+        )
+
+    tx = await router_program.rpc["execute"](
+        commands,
+        1,
+        ctx=Context(accounts=accounts, remaining_accounts=remaining_accounts, signers=[swapper.user_keypair])
+    )
+    await confirm_transaction(provider, tx)
+
+    input_wallet_info        = await get_account_info(provider, swap_pool_assets[0], swapper.token_accounts[0])
+    intermediate_wallet_info = await get_account_info(provider, swap_pool_assets[1], swapper.token_accounts[1])
+    output_wallet_info       = await get_account_info(provider, swap_pool_assets[2], swapper.token_accounts[2])
+
+    assert input_wallet_info.amount == 0
+    assert intermediate_wallet_info.amount == 0
+    assert 0 < output_wallet_info.amount < swap_amount
+
+    # No allowances must be left on the user wallets
+    assert input_wallet_info.delegate is None
+    assert intermediate_wallet_info.delegate is None
+
+
+
+async def test_router_out_swap(
+    router_program: Program,
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    polymerase_sender_program: Program,
+    mint_authority: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    # Define test parameters
+    provider = swap_pool_program.provider
+
+    user_count           = 2
+    asset_count          = 2
+    pool_assets_weights  = [1, 1]
+    pool_assets_balances = [1000*10**10, 1000*10**10]
+    pool_amplification   = None
+
+    spc = await simple_pool_context_factory(
+        user_count,
+        asset_count,
+        pool_assets_balances,
+        pool_assets_weights,
+        pool_amplification,
+        True
+    )
+
+    swap_pool_state      = spc.swap_pool_state
+    swap_pool_authority  = spc.swap_pool_authority
+    swap_pool_assets     = spc.swap_pool_assets
+    swap_pool_wallets    = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swap_interface_state = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    swapper      = spc.users[1]
+    swap_amount  = 5000000000
+
+    await mint_to(provider, swapper.token_accounts[0], swap_pool_assets[0], mint_authority, swap_amount)
+
+    # The user pays for the rent of the swap escrow and of the polymerase ibc data account
+    await fund_lamports(provider, swapper.user_keypair.public_key, 10**9)
+
+    governance_fee_wallet = await get_or_create_governance_fee_wallet(swap_pool_program, swap_pool_state, swap_pool_assets[0])
+
+    polymerase_ibc_data_keypair = Keypair()
+
+    target_chain_id    = SOLANA_CHAIN_ID
+    target_pool_id     = swap_interface_state
+    output_asset_index = 1
+    destination_wallet = swapper.token_accounts[1]
+    fallback_wallet    = swapper.token_accounts[0]
+
+    swap_hash = compute_swap_hash(
+        swap_pool_state,
+        target_chain_id,
+        target_pool_id,
+        output_asset_index,
+        destination_wallet,
+        swap_amount,
+        0,
+        swap_pool_assets[0],
+        fallback_wallet,
+        polymerase_ibc_data_keypair.public_key
+    )
+
+    swap_escrow = get_swap_pool_escrow_wallet(swap_pool_program.program_id, swap_pool_state, swap_hash)[0]
+
+    remaining_accounts = [
+        AccountMeta(swap_pool_program.program_id, is_signer=False, is_writable=False),
+        AccountMeta(swap_pool_state, is_signer=False, is_writable=True),
+        AccountMeta(swap_pool_assets[0], is_signer=False, is_writable=False),
+        AccountMeta(swapper.token_accounts[0], is_signer=False, is_writable=True),
+        AccountMeta(swap_pool_wallets[0], is_signer=False, is_writable=True),
+        AccountMeta(governance_fee_wallet, is_signer=False, is_writable=True),
+        AccountMeta(swap_escrow, is_signer=False, is_writable=True),
+        AccountMeta(swap_interface_program.program_id, is_signer=False, is_writable=False),
+        AccountMeta(swap_pool_authority, is_signer=False, is_writable=False),
+        AccountMeta(swap_interface_state, is_signer=False, is_writable=False),
+        AccountMeta(connection_state, is_signer=False, is_writable=False),
+        AccountMeta(polymerase_sender_program.program_id, is_signer=False, is_writable=False),
+        AccountMeta(polymerase_ibc_data_keypair.public_key, is_signer=True, is_writable=True)
+    ]
+
+    Command     = router_program.type["Command"]
+    OutSwapArgs = router_program.type["OutSwapArgs"]
+    commands = [
+        Command.OutSwap(OutSwapArgs(
+            chain=target_chain_id,
+            target_pool=target_pool_id,
+            to_asset_index=output_asset_index,
+            destination=destination_wallet,
+            amount=swap_amount,
+            min_output=int_to_u256_array(0),
+            swap_hash=list(swap_hash),
+            fallback_wallet=fallback_wallet,
+            timeout_slot=0,
+            timeout_timestamp=0,
+            approx_from=False
+        ))
+    ]
+
+    async with TxEventListener("OutSwapEvent") as ev_listener:
+        tx = await router_program.rpc["execute"](
+            commands,
+            0,
+            ctx=Context(
+                accounts={
+                    "user": swapper.user_keypair.public_key,
+                    "output_account": swapper.token_accounts[0],
+                    "token_program": TOKEN_PROGRAM_ID,
+                    "system_program": SYS_PROGRAM_ID
+                },
+                remaining_accounts=remaining_accounts,
+                signers=[swapper.user_keypair, polymerase_ibc_data_keypair]
+            )
+        )
+        await confirm_transaction(provider, tx)
+
+        out_swap_event = (await ev_listener.get_events(swap_pool_program))[0]
+
+    assert bytes(out_swap_event.data.swapHash) == swap_hash
+    assert out_swap_event.data.depositedAssetAmount == swap_amount
+
+    # The escrow must be created for the swap, with the rent paid by the user
+    swap_escrow_data = await swap_pool_program.account["SwapEscrow"].fetch(swap_escrow)
+    assert swap_escrow_data.fallback_wallet == fallback_wallet
+    assert swap_escrow_data.swap_escrow_rent_payer == swapper.user_keypair.public_key
+
+    input_wallet_info = await get_account_info(provider, swap_pool_assets[0], swapper.token_accounts[0])
+    assert input_wallet_info.amount == 0
+    assert input_wallet_info.delegate is None
+
+
+async def test_router_deposit(
+    router_program: Program,
+    swap_pool_program: Program,
+    mint_authority: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    # Define test parameters
+    provider = swap_pool_program.provider
+
+    user_count           = 2
+    asset_count          = 3
+    pool_assets_weights  = [1, 1, 1]
+    pool_assets_balances = [1000*10**10, 1000*10**10, 1000*10**10]
+    pool_amplification   = None
+
+    spc = await simple_pool_context_factory(
+        user_count,
+        asset_count,
+        pool_assets_balances,
+        pool_assets_weights,
+        pool_amplification,
+        False
+    )
+
+    initialize_swap_pool_state_result = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result
+    swap_pool_token_mint = initialize_swap_pool_state_result.swap_pool_token_mint
+    swap_pool_wallets    = initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swap_pool_assets     = spc.swap_pool_assets
+
+    depositor = spc.users[1]
+    deposited_pool_tokens = 5000
+    depositor_balance     = 10**10
+
+    # Fund the depositor without approving any allowance: the router must approve (and revoke) the allowances
+    for asset, wallet in zip(swap_pool_assets, depositor.token_accounts):
+        await mint_to(provider, wallet, asset, mint_authority, depositor_balance)
+
+    depositor_pool_token_wallet = await create_token_account(provider, swap_pool_token_mint, depositor.user_keypair.public_key)
+
+    remaining_accounts = liquidity_remaining_accounts(
+        swap_pool_program,
+        spc.swap_pool_state,
+        spc.swap_pool_authority,
+        swap_pool_token_mint,
+        depositor_pool_token_wallet,
+        swap_pool_assets,
+        swap_pool_wallets,
+        depositor.token_accounts[:asset_count]
+    )
+
+    Command = router_program.type["Command"]
+    tx = await router_program.rpc["execute"](
+        [Command.Deposit(pool_tokens_amount=deposited_pool_tokens, asset_count=asset_count)],
+        deposited_pool_tokens,
+        ctx=Context(
+            accounts={
+                "user": depositor.user_keypair.public_key,
+                "output_account": depositor_pool_token_wallet,
+                "token_program": TOKEN_PROGRAM_ID,
+                "system_program": SYS_PROGRAM_ID
+            },
+            remaining_accounts=remaining_accounts,
+            signers=[depositor.user_keypair]
+        )
+    )
+    await confirm_transaction(provider, tx)
+
+    pool_token_wallet_info = await get_account_info(provider, swap_pool_token_mint, depositor_pool_token_wallet)
+    assert pool_token_wallet_info.amount == deposited_pool_tokens
+
+    for asset, wallet in zip(swap_pool_assets, depositor.token_accounts):
+        wallet_info = await get_account_info(provider, asset, wallet)
+        assert 0 < depositor_balance - wallet_info.amount < depositor_balance
+
+        # The u64::MAX allowance given to the pool authority must have been revoked
+        assert wallet_info.delegate is None
+        assert wallet_info.delegated_amount == 0
+
+
+async def test_router_withdraw_wallet_balance(
+    router_program: Program,
+    swap_pool_program: Program,
+    mint_authority: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    # Define test parameters
+    provider = swap_pool_program.provider
+
+    user_count           = 2
+    asset_count          = 3
+    pool_assets_weights  = [1, 1, 1]
+    pool_assets_balances = [1000*10**10, 1000*10**10, 1000*10**10]
+    pool_amplification   = None
+
+    spc = await simple_pool_context_factory(
+        user_count,
+        asset_count,
+        pool_assets_balances,
+        pool_assets_weights,
+        pool_amplification,
+        False
+    )
+
+    initialize_swap_pool_state_result = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result
+    swap_pool_token_mint = initialize_swap_pool_state_result.swap_pool_token_mint
+    swap_pool_wallets    = initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swap_pool_assets     = spc.swap_pool_assets
+
+    withdrawer = spc.users[1]
+    deposited_pool_tokens = 5000
+
+    for asset, wallet in zip(swap_pool_assets, withdrawer.token_accounts):
+        await mint_to(provider, wallet, asset, mint_authority, 10**10)
+
+    withdrawer_pool_token_wallet = await create_token_account(provider, swap_pool_token_mint, withdrawer.user_keypair.public_key)
+
+    remaining_accounts = liquidity_remaining_accounts(
+        swap_pool_program,
+        spc.swap_pool_state,
+        spc.swap_pool_authority,
+        swap_pool_token_mint,
+        withdrawer_pool_token_wallet,
+        swap_pool_assets,
+        swap_pool_wallets,
+        withdrawer.token_accounts[:asset_count]
+    )
+
+    accounts = {
+        "user": withdrawer.user_keypair.public_key,
+        "output_account": withdrawer.token_accounts[0],
+        "token_program": TOKEN_PROGRAM_ID,
+        "system_program": SYS_PROGRAM_ID
+    }
+
+    Command = router_program.type["Command"]
+
+    # Get some pool tokens to withdraw
+    tx = await router_program.rpc["execute"](
+        [Command.Deposit(pool_tokens_amount=deposited_pool_tokens, asset_count=asset_count)],
+        0,
+        ctx=Context(accounts=accounts, remaining_accounts=remaining_accounts, signers=[withdrawer.user_keypair])
+    )
+    await confirm_transaction(provider, tx)
+
+    asset_wallet_infos_before = [
+        await get_account_info(provider, asset, wallet) for asset, wallet in zip(swap_pool_assets, withdrawer.token_accounts)
+    ]
+
+    # Withdraw the full pool token balance of the wallet
+    tx = await router_program.rpc["execute"](
+        [Command.Withdraw(pool_tokens_amount=WALLET_BALANCE, asset_count=asset_count)],
+        1,
+        ctx=Context(accounts=accounts, remaining_accounts=remaining_accounts, signers=[withdrawer.user_keypair])
+    )
+    await confirm_transaction(provider, tx)
+
+    pool_token_wallet_info = await get_account_info(provider, swap_pool_token_mint, withdrawer_pool_token_wallet)
+    assert pool_token_wallet_info.amount == 0
+    assert pool_token_wallet_info.delegate is None
+
+    for asset, wallet, wallet_info_before in zip(swap_pool_assets, withdrawer.token_accounts, asset_wallet_infos_before):
+        wallet_info = await get_account_info(provider, asset, wallet)
+        assert wallet_info.amount > wallet_info_before.amount
+
+
+async def test_router_wrap_sol(
+    router_program: Program,
+    generic_payer: Keypair
+):
+    provider = router_program.provider
+
+    user         = Keypair()
+    wrap_amount  = 10**9
+
+    await fund_lamports(provider, user.public_key, 2*wrap_amount)
+
+    wrapped_sol_wallet = await create_token_account(provider, WRAPPED_SOL_MINT, user.public_key, generic_payer)
+
+    Command = router_program.type["Command"]
+    tx = await router_program.rpc["execute"](
+        [Command.WrapSol(amount=wrap_amount)],
+        wrap_amount,
+        ctx=Context(
+            accounts={
+                "user": user.public_key,
+                "output_account": wrapped_sol_wallet,
+                "token_program": TOKEN_PROGRAM_ID,
+                "system_program": SYS_PROGRAM_ID
+            },
+            remaining_accounts=[AccountMeta(wrapped_sol_wallet, is_signer=False, is_writable=True)],
+            signers=[user]
+        )
+    )
+    await confirm_transaction(provider, tx)
+
+    wrapped_sol_wallet_info = await get_account_info(provider, WRAPPED_SOL_MINT, wrapped_sol_wallet)
+    assert wrapped_sol_wallet_info.amount == wrap_amount
+
+
+async def test_router_unwrap_sol(
+    router_program: Program,
+    generic_payer: Keypair
+):
+    provider = router_program.provider
+
+    user         = Keypair()
+    wrap_amount  = 10**9
+
+    await fund_lamports(provider, user.public_key, 2*wrap_amount)
+
+    wrapped_sol_wallet = await create_token_account(provider, WRAPPED_SOL_MINT, user.public_key, generic_payer)
+    wrapped_sol_wallet_accounts = [AccountMeta(wrapped_sol_wallet, is_signer=False, is_writable=True)]
+
+    Command = router_program.type["Command"]
+    tx = await router_program.rpc["execute"](
+        [Command.WrapSol(amount=wrap_amount)],
+        wrap_amount,
+        ctx=Context(
+            accounts={
+                "user": user.public_key,
+                "output_account": wrapped_sol_wallet,
+                "token_program": TOKEN_PROGRAM_ID,
+                "system_program": SYS_PROGRAM_ID
+            },
+            remaining_accounts=wrapped_sol_wallet_accounts,
+            signers=[user]
+        )
+    )
+    await confirm_transaction(provider, tx)
+
+    # Unwrapping closes the wallet, returning both the wrapped amount and the rent to the user
+    tx = await router_program.rpc["execute"](
+        [Command.UnwrapSol()],
+        wrap_amount,
+        ctx=Context(
+            accounts={
+                "user": user.public_key,
+                "output_account": user.public_key,
+                "token_program": TOKEN_PROGRAM_ID,
+                "system_program": SYS_PROGRAM_ID
+            },
+            remaining_accounts=wrapped_sol_wallet_accounts,
+            signers=[user]
+        )
+    )
+    await confirm_transaction(provider, tx)
+
+    assert (await provider.connection.get_account_info(wrapped_sol_wallet)).value is None
+
+
+async def test_router_invalid_remaining_accounts_count(
+    router_program: Program,
+    swap_pool_program: Program,
+    mint_authority: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    # Define test parameters
+    provider = swap_pool_program.provider
+
+    user_count           = 2
+    asset_count          = 2
+    pool_assets_weights  = [1, 1]
+    pool_assets_balances = [1000*10**10, 1000*10**10]
+    pool_amplification   = None
+
+    spc = await simple_pool_context_factory(
+        user_count,
+        asset_count,
+        pool_assets_balances,
+        pool_assets_weights,
+        pool_amplification,
+        False
+    )
+
+    swap_pool_assets  = spc.swap_pool_assets
+    swap_pool_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+
+    swapper      = spc.users[1]
+    swap_amount  = 5000000000
+
+    await mint_to(provider, swapper.token_accounts[0], swap_pool_assets[0], mint_authority, swap_amount)
+
+    governance_fee_wallet = await get_or_create_governance_fee_wallet(swap_pool_program, spc.swap_pool_state, swap_pool_assets[0])
+
+    local_swap_accounts = local_swap_remaining_accounts(
+        swap_pool_program,
+        spc.swap_pool_state,
+        spc.swap_pool_authority,
+        swap_pool_assets[0],
+        swapper.token_accounts[0],
+        swap_pool_wallets[0],
+        swap_pool_assets[1],
+        swapper.token_accounts[1],
+        swap_pool_wallets[1],
+        governance_fee_wallet
+    )
+
+    Command = router_program.type["Command"]
+    accounts = {
+        "user": swapper.user_keypair.public_key,
+        "output_account": swapper.token_accounts[1],
+        "token_program": TOKEN_PROGRAM_ID,
+        "system_program": SYS_PROGRAM_ID
+    }
+
+    # Missing accounts
+    with pytest.raises(Exception) as exception_info:
+        await router_program.rpc["execute"](
+            [Command.LocalSwap(amount=swap_amount, min_yield=0, approx=False)],
+            0,
+            ctx=Context(accounts=accounts, remaining_accounts=local_swap_accounts[:-1], signers=[swapper.user_keypair])
+        )
+    assert is_program_error(exception_info.value, router_program, "InvalidRemainingAccountsCount")
+
+    # Accounts left unused by the commands
+    with pytest.raises(Exception) as exception_info:
+        await router_program.rpc["execute"](
+            [Command.LocalSwap(amount=swap_amount, min_yield=0, approx=False)],
+            0,
+            ctx=Context(
+                accounts=accounts,
+                remaining_accounts=local_swap_accounts + [AccountMeta(governance_fee_wallet, is_signer=False, is_writable=False)],
+                signers=[swapper.user_keypair]
+            )
+        )
+    assert is_program_error(exception_info.value, router_program, "InvalidRemainingAccountsCount")
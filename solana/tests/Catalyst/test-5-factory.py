@@ -0,0 +1,214 @@
+import pytest
+from solana.keypair import Keypair
+from solana.publickey import PublicKey
+from solana.transaction import AccountMeta
+from solana.system_program import SYS_PROGRAM_ID
+from solana.sysvar import SYSVAR_RENT_PUBKEY
+
+from anchorpy import Context, Program
+
+from utils.account_utils import get_factory_pool, get_factory_pool_registration, get_swap_pool_asset_wallet, get_swap_pool_authority, get_swap_pool_token_mint
+from utils.common_utils import create_mints, create_users
+from utils.transaction_utils import confirm_transaction
+from utils.token_utils import fund_accounts
+from spl.token.constants import TOKEN_PROGRAM_ID
+
+
+DEFAULT_GOVERNANCE_FEE_X64 = 2**62     # 25%
+
+
+async def deploy_pool(
+    factory_program: Program,
+    factory_state: PublicKey,
+    template_program: PublicKey,
+    template_id: int,
+    salt: bytes,
+    setup_master_keypair: Keypair,
+    assets: list[PublicKey],
+    assets_balances: list[int],
+    assets_weights: list[int],
+    depositor_keypair: Keypair,
+    depositor_wallets: list[PublicKey],
+    swap_pool_state: PublicKey
+) -> None:
+
+    dao_authority_keypair               = Keypair()
+    depositor_pool_token_wallet_keypair = Keypair()
+
+    remaining_accounts: list[AccountMeta] = []
+    for asset, depositor_wallet in zip(assets, depositor_wallets):
+        remaining_accounts += [
+            AccountMeta(asset, is_signer=False, is_writable=False),
+            AccountMeta(get_swap_pool_asset_wallet(template_program, swap_pool_state, asset)[0], is_signer=False, is_writable=True),
+            AccountMeta(depositor_wallet, is_signer=False, is_writable=True)
+        ]
+
+    tx = await factory_program.rpc["deploy_pool"](
+        template_id,
+        list(salt),
+        1,                  # k
+        assets_balances,
+        assets_weights,
+        0,                  # pool fee
+        ctx=Context(
+            accounts={
+                "factory_state_account": factory_state,
+                "template_program": template_program,
+                "setup_master": setup_master_keypair.public_key,
+                "dao_authority": dao_authority_keypair.public_key,
+                "swap_pool_state_account": swap_pool_state,
+                "swap_pool_token_mint": get_swap_pool_token_mint(template_program, swap_pool_state)[0],
+                "depositor_pool_token_wallet_authority": depositor_keypair.public_key,
+                "depositor_pool_token_wallet": depositor_pool_token_wallet_keypair.public_key,
+                "swap_pool_authority": get_swap_pool_authority(template_program, swap_pool_state)[0],
+                "pool_registration": get_factory_pool_registration(factory_program.program_id, factory_state, swap_pool_state)[0],
+                "rent": SYSVAR_RENT_PUBKEY,
+                "token_program": TOKEN_PROGRAM_ID,
+                "system_program": SYS_PROGRAM_ID
+            },
+            remaining_accounts=remaining_accounts,
+            signers=[setup_master_keypair, dao_authority_keypair, depositor_pool_token_wallet_keypair]
+        )
+    )
+    await confirm_transaction(factory_program.provider, tx)
+
+
+async def test_factory_deploy_pool(
+    factory_program: Program,
+    swap_pool_program: Program,
+    swap_pool_setup_master_keypair: Keypair,
+    mint_authority: Keypair,
+    generic_payer: Keypair
+):
+    provider = factory_program.provider
+
+    factory_owner_keypair   = swap_pool_setup_master_keypair
+    factory_state_keypair   = Keypair()
+    factory_state           = factory_state_keypair.public_key
+    governance_fee_destination = Keypair().public_key
+
+    # Create the factory and register the SwapPool template
+    tx = await factory_program.rpc["initialize"](
+        DEFAULT_GOVERNANCE_FEE_X64,
+        governance_fee_destination,
+        ctx=Context(
+            accounts={
+                "owner": factory_owner_keypair.public_key,
+                "factory_state_account": factory_state,
+                "system_program": SYS_PROGRAM_ID
+            },
+            signers=[factory_owner_keypair, factory_state_keypair]
+        )
+    )
+    await confirm_transaction(provider, tx)
+
+    tx = await factory_program.rpc["add_template"](
+        swap_pool_program.program_id,
+        ctx=Context(
+            accounts={
+                "owner": factory_owner_keypair.public_key,
+                "factory_state_account": factory_state
+            },
+            signers=[factory_owner_keypair]
+        )
+    )
+    await confirm_transaction(provider, tx)
+
+    # Only the factory owner may add templates
+    not_owner_keypair = Keypair()
+    with pytest.raises(Exception):
+        await factory_program.rpc["add_template"](
+            swap_pool_program.program_id,
+            ctx=Context(
+                accounts={
+                    "owner": not_owner_keypair.public_key,
+                    "factory_state_account": factory_state
+                },
+                signers=[not_owner_keypair]
+            )
+        )
+
+    # Deploy a pool
+    template_id     = 0
+    salt            = bytes(range(32))
+    assets_balances = [1000*10**10, 1000*10**10]
+    assets_weights  = [1, 1]
+
+    assets    = await create_mints(provider, mint_authority, 2)
+    depositor = (await create_users(provider, assets, generic_payer, 1))[0]
+
+    swap_pool_state = get_factory_pool(
+        factory_program.program_id,
+        factory_state,
+        template_id,
+        assets,
+        swap_pool_setup_master_keypair.public_key,
+        salt
+    )[0]
+
+    await fund_accounts(
+        provider                 = provider,
+        mints                    = assets,
+        mints_authority          = mint_authority,
+        token_accounts           = depositor.token_accounts,
+        balance                  = [2*balance for balance in assets_balances],
+        delegate_authority       = get_swap_pool_authority(swap_pool_program.program_id, swap_pool_state)[0],
+        delegate_balance         = [2*balance for balance in assets_balances],
+        token_accounts_authority = depositor.user_keypair
+    )
+
+    # The pool must be deployed at the derived address
+    with pytest.raises(Exception):
+        await deploy_pool(
+            factory_program, factory_state, swap_pool_program.program_id, template_id, salt, swap_pool_setup_master_keypair,
+            assets, assets_balances, assets_weights, depositor.user_keypair, depositor.token_accounts,
+            Keypair().public_key
+        )
+
+    # The address is bound to its deployer: nobody else can deploy a pool at it
+    with pytest.raises(Exception):
+        await deploy_pool(
+            factory_program, factory_state, swap_pool_program.program_id, template_id, salt, generic_payer,
+            assets, assets_balances, assets_weights, depositor.user_keypair, depositor.token_accounts,
+            swap_pool_state
+        )
+
+    # And to the salt
+    with pytest.raises(Exception):
+        await deploy_pool(
+            factory_program, factory_state, swap_pool_program.program_id, template_id, bytes(32), swap_pool_setup_master_keypair,
+            assets, assets_balances, assets_weights, depositor.user_keypair, depositor.token_accounts,
+            swap_pool_state
+        )
+
+    await deploy_pool(
+        factory_program, factory_state, swap_pool_program.program_id, template_id, salt, swap_pool_setup_master_keypair,
+        assets, assets_balances, assets_weights, depositor.user_keypair, depositor.token_accounts,
+        swap_pool_state
+    )
+
+    # Verify the pool and its registration
+    swap_pool_state_data = await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)
+    assert swap_pool_state_data.pool_assets_mints == assets
+    assert swap_pool_state_data.governance_fee_x64 == DEFAULT_GOVERNANCE_FEE_X64
+    assert swap_pool_state_data.fee_administrator == factory_owner_keypair.public_key
+    assert swap_pool_state_data.governance_fee_destination == governance_fee_destination
+
+    pool_registration = get_factory_pool_registration(factory_program.program_id, factory_state, swap_pool_state)[0]
+    pool_registration_data = await factory_program.account["PoolRegistration"].fetch(pool_registration)
+    assert pool_registration_data.factory == factory_state
+    assert pool_registration_data.pool == swap_pool_state
+    assert pool_registration_data.template_program == swap_pool_program.program_id
+    assert pool_registration_data.deployer == swap_pool_setup_master_keypair.public_key
+    assert bytes(pool_registration_data.salt) == salt
+
+    factory_state_data = await factory_program.account["FactoryState"].fetch(factory_state)
+    assert factory_state_data.pool_count == 1
+
+    # The same pool cannot be deployed twice
+    with pytest.raises(Exception):
+        await deploy_pool(
+            factory_program, factory_state, swap_pool_program.program_id, template_id, salt, swap_pool_setup_master_keypair,
+            assets, assets_balances, assets_weights, depositor.user_keypair, depositor.token_accounts,
+            swap_pool_state
+        )
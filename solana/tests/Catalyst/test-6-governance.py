@@ -0,0 +1,252 @@
+import pytest
+from solana.keypair import Keypair
+from solana.publickey import PublicKey
+
+from anchorpy import Context, Program
+from conftest import FactoryFixture
+
+from utils.swap_pool_utils import perform_local_swap
+from utils.transaction_utils import confirm_transaction
+from utils.token_utils import mint_to
+from utils.verify_utils import int_to_u256_array, u256_array_to_int
+
+MIN_ADJUSTMENT_TIME = 60*60*24*7
+
+
+async def set_paused(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    dao_authority_keypair: Keypair,
+    paused: bool
+) -> None:
+    tx = await swap_pool_program.rpc["set_paused"](
+        paused,
+        ctx=Context(
+            accounts={
+                "swap_pool_state_account": swap_pool_state,
+                "dao_authority": dao_authority_keypair.public_key
+            },
+            signers=[dao_authority_keypair]
+        )
+    )
+    await confirm_transaction(swap_pool_program.provider, tx)
+
+
+async def set_weights(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    dao_authority_keypair: Keypair,
+    target_weights: list[int],
+    target_timestamp: int
+) -> None:
+    tx = await swap_pool_program.rpc["set_weights"](
+        target_weights,
+        target_timestamp,
+        ctx=Context(
+            accounts={
+                "swap_pool_state_account": swap_pool_state,
+                "dao_authority": dao_authority_keypair.public_key
+            },
+            signers=[dao_authority_keypair]
+        )
+    )
+    await confirm_transaction(swap_pool_program.provider, tx)
+
+
+async def get_chain_timestamp(provider) -> int:
+    slot = (await provider.connection.get_slot()).value
+    return (await provider.connection.get_block_time(slot)).value
+
+
+async def test_pause(
+    swap_pool_program: Program,
+    mint_authority: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(2, 2, [1000*10**10, 1000*10**10], [1, 1], None, False)
+
+    swap_pool_state       = spc.swap_pool_state
+    swap_pool_assets      = spc.swap_pool_assets
+    swap_pool_wallets     = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    dao_authority_keypair = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair
+    swapper               = spc.users[1]
+
+    await mint_to(provider, swapper.token_accounts[0], swap_pool_assets[0], mint_authority, 2000)
+
+    async def local_swap():
+        await perform_local_swap(
+            1000,
+            0,
+            False, # approx
+            swap_pool_assets[0],
+            swapper.token_accounts[0],
+            swapper.user_keypair,
+            swap_pool_assets[1],
+            swapper.token_accounts[1],
+            swap_pool_program,
+            swap_pool_state,
+            swap_pool_wallets[0],
+            swap_pool_wallets[1],
+            spc.swap_pool_authority
+        )
+
+    # Only the dao authority can pause the pool
+    with pytest.raises(Exception):
+        await set_paused(swap_pool_program, swap_pool_state, swapper.user_keypair, True)
+
+    await set_paused(swap_pool_program, swap_pool_state, dao_authority_keypair, True)
+    assert (await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)).paused
+
+    with pytest.raises(Exception):
+        await local_swap()
+
+    await set_paused(swap_pool_program, swap_pool_state, dao_authority_keypair, False)
+    await local_swap()
+
+
+async def test_dao_authority_handover(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture
+):
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(1, 2, [1000*10**10, 1000*10**10], [1, 1], None, False)
+
+    swap_pool_state           = spc.swap_pool_state
+    dao_authority_keypair     = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair
+    new_dao_authority_keypair = Keypair()
+
+    async def accept_dao_authority(authority_keypair: Keypair):
+        tx = await swap_pool_program.rpc["accept_dao_authority"](
+            ctx=Context(
+                accounts={
+                    "swap_pool_state_account": swap_pool_state,
+                    "pending_dao_authority": authority_keypair.public_key
+                },
+                signers=[authority_keypair]
+            )
+        )
+        await confirm_transaction(provider, tx)
+
+    tx = await swap_pool_program.rpc["transfer_dao_authority"](
+        new_dao_authority_keypair.public_key,
+        ctx=Context(
+            accounts={
+                "swap_pool_state_account": swap_pool_state,
+                "dao_authority": dao_authority_keypair.public_key
+            },
+            signers=[dao_authority_keypair]
+        )
+    )
+    await confirm_transaction(provider, tx)
+
+    # The authority does not change until the handover is accepted
+    swap_pool_state_data = await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)
+    assert swap_pool_state_data.dao_authority == dao_authority_keypair.public_key
+    assert swap_pool_state_data.pending_dao_authority == new_dao_authority_keypair.public_key
+
+    # Only the pending authority can accept the handover
+    with pytest.raises(Exception):
+        await accept_dao_authority(Keypair())
+
+    await accept_dao_authority(new_dao_authority_keypair)
+
+    swap_pool_state_data = await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)
+    assert swap_pool_state_data.dao_authority == new_dao_authority_keypair.public_key
+    assert swap_pool_state_data.pending_dao_authority == PublicKey(0)
+
+    # The previous authority has no rights anymore
+    with pytest.raises(Exception):
+        await set_paused(swap_pool_program, swap_pool_state, dao_authority_keypair, True)
+
+    await set_paused(swap_pool_program, swap_pool_state, new_dao_authority_keypair, True)
+
+
+async def test_set_weights(
+    swap_pool_program: Program,
+    mint_authority: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+        Start a weights adjustment, and verify that the security limit set by the governance is scaled (rather than
+        overwritten) as the weights move.
+    """
+    provider = swap_pool_program.provider
+
+    initial_weights = [10**9, 10**9]
+    target_weights  = [2*10**9, 4*10**9]
+
+    spc = await simple_pool_context_factory(2, 2, [1000*10**10, 1000*10**10], initial_weights, None, False)
+
+    swap_pool_state       = spc.swap_pool_state
+    swap_pool_assets      = spc.swap_pool_assets
+    swap_pool_wallets     = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    dao_authority_keypair = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair
+    swapper               = spc.users[1]
+
+    # Set a custom security limit
+    max_units_inflow_x64 = 12345 * 2**64
+    U256Limbs = swap_pool_program.type["U256Limbs"]
+    tx = await swap_pool_program.rpc["set_max_units_inflow"](
+        U256Limbs(limbs=int_to_u256_array(max_units_inflow_x64)),
+        ctx=Context(
+            accounts={
+                "swap_pool_state_account": swap_pool_state,
+                "dao_authority": dao_authority_keypair.public_key
+            },
+            signers=[dao_authority_keypair]
+        )
+    )
+    await confirm_transaction(provider, tx)
+
+    target_timestamp = await get_chain_timestamp(provider) + MIN_ADJUSTMENT_TIME + 60
+
+    # Only the dao authority can set the weights
+    with pytest.raises(Exception):
+        await set_weights(swap_pool_program, swap_pool_state, swapper.user_keypair, target_weights, target_timestamp)
+
+    # The adjustment must last at least MIN_ADJUSTMENT_TIME
+    with pytest.raises(Exception):
+        await set_weights(swap_pool_program, swap_pool_state, dao_authority_keypair, target_weights, target_timestamp - 120)
+
+    # The weights cannot change by more than a factor of 10
+    with pytest.raises(Exception):
+        await set_weights(swap_pool_program, swap_pool_state, dao_authority_keypair, [11*10**9, 10**9], target_timestamp)
+
+    await set_weights(swap_pool_program, swap_pool_state, dao_authority_keypair, target_weights, target_timestamp)
+
+    swap_pool_state_data = await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)
+    assert swap_pool_state_data.pool_assets_target_weights == target_weights
+    assert swap_pool_state_data.weights_adjustment_target_timestamp == target_timestamp
+    previous_weights = swap_pool_state_data.pool_assets_weights
+    assert u256_array_to_int(swap_pool_state_data.max_units_inflow_x64.limbs) == max_units_inflow_x64
+
+    # The weights are updated whenever the pool is used
+    await mint_to(provider, swapper.token_accounts[0], swap_pool_assets[0], mint_authority, 1000)
+    await perform_local_swap(
+        1000,
+        0,
+        False, # approx
+        swap_pool_assets[0],
+        swapper.token_accounts[0],
+        swapper.user_keypair,
+        swap_pool_assets[1],
+        swapper.token_accounts[1],
+        swap_pool_program,
+        swap_pool_state,
+        swap_pool_wallets[0],
+        swap_pool_wallets[1],
+        spc.swap_pool_authority
+    )
+
+    swap_pool_state_data = await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)
+    new_weights = swap_pool_state_data.pool_assets_weights
+
+    for previous_weight, new_weight, target_weight in zip(previous_weights, new_weights, target_weights):
+        assert previous_weight <= new_weight <= target_weight
+
+    # The security limit is scaled with the weights, not recomputed from them
+    assert u256_array_to_int(swap_pool_state_data.max_units_inflow_x64.limbs) == \
+        max_units_inflow_x64 * sum(new_weights) // sum(previous_weights)
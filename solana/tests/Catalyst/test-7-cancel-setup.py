@@ -0,0 +1,180 @@
+import pytest
+from solana.keypair import Keypair
+from solana.transaction import AccountMeta
+
+from anchorpy import Context, Program, Provider
+
+from conftest import create_mints, create_users
+from utils.account_utils import get_swap_pool_asset_wallet, get_swap_pool_authority
+from utils.swap_pool_utils import cancel_swap_pool_setup, finish_swap_pool_setup, initialize_swap_pool_state, link_swap_interface_to_swap_pool
+from utils.transaction_utils import is_program_error
+from utils.token_utils import fund_accounts, get_account_info
+from spl.token.constants import TOKEN_PROGRAM_ID
+
+
+async def test_cancel_setup(
+    swap_pool_program: Program,
+    swap_pool_setup_master_keypair: Keypair,
+    provider: Provider,
+    mint_authority: Keypair,
+    generic_payer: Keypair
+):
+    """
+        Cancel the setup of a SwapPool: the initial balances are returned and the pool accounts are closed
+    """
+
+    asset_count = 2
+
+    swap_pool_state_keypair = Keypair()
+    swap_pool_state         = swap_pool_state_keypair.public_key
+    swap_pool_authority     = get_swap_pool_authority(swap_pool_program.program_id, swap_pool_state)[0]
+
+    assets          = await create_mints(provider, mint_authority, asset_count)
+    assets_balances = [10000, 20000]
+
+    depositor               = (await create_users(provider, assets, generic_payer, 1))[0]
+    depositor_asset_wallets = depositor.token_accounts[:asset_count]
+
+    await fund_accounts(
+        provider                 = provider,
+        mints                    = assets,
+        mints_authority          = mint_authority,
+        token_accounts           = depositor_asset_wallets,
+        balance                  = assets_balances,
+        delegate_authority       = swap_pool_authority,
+        delegate_balance         = assets_balances,
+        token_accounts_authority = depositor.user_keypair
+    )
+
+    initialize_result = await initialize_swap_pool_state(
+        swap_pool_program                     = swap_pool_program,
+        swap_pool_setup_master_keypair        = swap_pool_setup_master_keypair,
+        amplification                         = None,
+        assets_weights                        = [1, 1],
+        assets_balances                       = assets_balances,
+        assets_mints                          = assets,
+        deposit_wallets                       = depositor_asset_wallets,
+        depositor_pool_token_wallet_authority = depositor.user_keypair.public_key,
+        swap_pool_state_keypair               = swap_pool_state_keypair
+    )
+
+    async def cancel_setup(setup_master_keypair: Keypair):
+        await cancel_swap_pool_setup(
+            swap_pool_program,
+            swap_pool_state,
+            setup_master_keypair,
+            initialize_result.swap_pool_token_mint,
+            initialize_result.depositor_pool_token_wallet_keypair.public_key,
+            depositor.user_keypair,
+            initialize_result.swap_pool_asset_wallets,
+            depositor_asset_wallets
+        )
+
+    # Deposits are rejected until the setup is finished
+    deposit_remaining_accounts: list[AccountMeta] = []
+    for asset, depositor_asset_wallet in zip(assets, depositor_asset_wallets):
+        deposit_remaining_accounts += [
+            AccountMeta(asset, is_signer=False, is_writable=False),
+            AccountMeta(get_swap_pool_asset_wallet(swap_pool_program.program_id, swap_pool_state, asset)[0], is_signer=False, is_writable=True),
+            AccountMeta(depositor_asset_wallet, is_signer=False, is_writable=True)
+        ]
+
+    with pytest.raises(Exception) as exception_info:
+        await swap_pool_program.rpc["deposit"](
+            1000,
+            ctx=Context(
+                accounts={
+                    "swap_pool_state_account": swap_pool_state,
+                    "depositor_pool_token_wallet": initialize_result.depositor_pool_token_wallet_keypair.public_key,
+                    "swap_pool_token_mint": initialize_result.swap_pool_token_mint,
+                    "swap_pool_authority": swap_pool_authority,
+                    "token_program": TOKEN_PROGRAM_ID
+                },
+                remaining_accounts=deposit_remaining_accounts
+            )
+        )
+    assert is_program_error(exception_info.value, swap_pool_program, "SetupNotFinished")
+
+    # Only the setup master can cancel the setup
+    with pytest.raises(Exception):
+        await cancel_setup(Keypair())
+
+    depositor_pool_token_wallet      = initialize_result.depositor_pool_token_wallet_keypair.public_key
+    depositor_pool_token_wallet_rent = (await provider.connection.get_account_info(depositor_pool_token_wallet)).value.lamports
+    depositor_balance_before         = (await provider.connection.get_balance(depositor.user_keypair.public_key)).value
+
+    await cancel_setup(swap_pool_setup_master_keypair)
+
+    # The initial balances are returned
+    for asset, depositor_asset_wallet, balance in zip(assets, depositor_asset_wallets, assets_balances):
+        assert (await get_account_info(provider, asset, depositor_asset_wallet)).amount == balance
+
+    # The pool state and asset wallets are closed
+    assert (await provider.connection.get_account_info(swap_pool_state)).value is None
+    for swap_pool_asset_wallet in initialize_result.swap_pool_asset_wallets:
+        assert (await provider.connection.get_account_info(swap_pool_asset_wallet)).value is None
+
+    # The depositor pool token wallet is closed, and its rent refunded to its authority
+    assert (await provider.connection.get_account_info(depositor_pool_token_wallet)).value is None
+    assert (await provider.connection.get_balance(depositor.user_keypair.public_key)).value == \
+        depositor_balance_before + depositor_pool_token_wallet_rent
+
+
+async def test_cancel_finished_setup(
+    swap_pool_program: Program,
+    swap_pool_setup_master_keypair: Keypair,
+    provider: Provider,
+    mint_authority: Keypair,
+    generic_payer: Keypair
+):
+    """
+        The setup cannot be cancelled once it is finished
+    """
+
+    swap_pool_state_keypair = Keypair()
+    swap_pool_state         = swap_pool_state_keypair.public_key
+    swap_pool_authority     = get_swap_pool_authority(swap_pool_program.program_id, swap_pool_state)[0]
+
+    assets          = await create_mints(provider, mint_authority, 1)
+    assets_balances = [10000]
+
+    depositor               = (await create_users(provider, assets, generic_payer, 1))[0]
+    depositor_asset_wallets = depositor.token_accounts[:1]
+
+    await fund_accounts(
+        provider                 = provider,
+        mints                    = assets,
+        mints_authority          = mint_authority,
+        token_accounts           = depositor_asset_wallets,
+        balance                  = assets_balances,
+        delegate_authority       = swap_pool_authority,
+        delegate_balance         = assets_balances,
+        token_accounts_authority = depositor.user_keypair
+    )
+
+    initialize_result = await initialize_swap_pool_state(
+        swap_pool_program                     = swap_pool_program,
+        swap_pool_setup_master_keypair        = swap_pool_setup_master_keypair,
+        amplification                         = None,
+        assets_weights                        = [1],
+        assets_balances                       = assets_balances,
+        assets_mints                          = assets,
+        deposit_wallets                       = depositor_asset_wallets,
+        depositor_pool_token_wallet_authority = depositor.user_keypair.public_key,
+        swap_pool_state_keypair               = swap_pool_state_keypair
+    )
+
+    await link_swap_interface_to_swap_pool(swap_pool_program, swap_pool_state, swap_pool_setup_master_keypair, Keypair().public_key)
+    await finish_swap_pool_setup(swap_pool_program, swap_pool_state, swap_pool_setup_master_keypair)
+
+    with pytest.raises(Exception):
+        await cancel_swap_pool_setup(
+            swap_pool_program,
+            swap_pool_state,
+            swap_pool_setup_master_keypair,
+            initialize_result.swap_pool_token_mint,
+            initialize_result.depositor_pool_token_wallet_keypair.public_key,
+            depositor.user_keypair,
+            initialize_result.swap_pool_asset_wallets,
+            depositor_asset_wallets
+        )
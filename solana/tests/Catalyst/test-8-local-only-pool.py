@@ -0,0 +1,84 @@
+import pytest
+from solana.keypair import Keypair
+from solana.publickey import PublicKey
+
+from anchorpy import Context, Program, Provider
+
+from conftest import create_mints, create_users
+from utils.account_utils import get_swap_pool_authority
+from utils.swap_pool_utils import finish_swap_pool_setup, initialize_swap_pool_state
+from utils.token_utils import fund_accounts
+from utils.transaction_utils import confirm_transaction
+
+
+async def test_local_only_pool(
+    swap_pool_program: Program,
+    swap_pool_setup_master_keypair: Keypair,
+    provider: Provider,
+    mint_authority: Keypair,
+    generic_payer: Keypair
+):
+    """
+        Create a local-only SwapPool: no swap interface is required, and the cross chain functionality is disabled
+    """
+
+    asset_count = 2
+
+    swap_pool_state_keypair = Keypair()
+    swap_pool_state         = swap_pool_state_keypair.public_key
+    swap_pool_authority     = get_swap_pool_authority(swap_pool_program.program_id, swap_pool_state)[0]
+
+    assets          = await create_mints(provider, mint_authority, asset_count)
+    assets_balances = [10000, 10000]
+
+    depositor               = (await create_users(provider, assets, generic_payer, 1))[0]
+    depositor_asset_wallets = depositor.token_accounts[:asset_count]
+
+    await fund_accounts(
+        provider                 = provider,
+        mints                    = assets,
+        mints_authority          = mint_authority,
+        token_accounts           = depositor_asset_wallets,
+        balance                  = assets_balances,
+        delegate_authority       = swap_pool_authority,
+        delegate_balance         = assets_balances,
+        token_accounts_authority = depositor.user_keypair
+    )
+
+    initialize_result = await initialize_swap_pool_state(
+        swap_pool_program                     = swap_pool_program,
+        swap_pool_setup_master_keypair        = swap_pool_setup_master_keypair,
+        amplification                         = None,
+        assets_weights                        = [1, 1],
+        assets_balances                       = assets_balances,
+        assets_mints                          = assets,
+        deposit_wallets                       = depositor_asset_wallets,
+        depositor_pool_token_wallet_authority = depositor.user_keypair.public_key,
+        swap_pool_state_keypair               = swap_pool_state_keypair
+    )
+
+    # The setup cannot be finished without a swap interface, unless the pool is local-only
+    with pytest.raises(Exception):
+        await finish_swap_pool_setup(swap_pool_program, swap_pool_state, swap_pool_setup_master_keypair)
+
+    await finish_swap_pool_setup(swap_pool_program, swap_pool_state, swap_pool_setup_master_keypair, local_only=True)
+
+    swap_pool_state_data = await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)
+    assert swap_pool_state_data.local_only
+    assert swap_pool_state_data.ibc_interface == PublicKey(0)
+    assert swap_pool_state_data.setup_master == PublicKey(0)
+
+    # The security limit is not used by local-only pools
+    dao_authority_keypair = initialize_result.dao_authority_keypair
+    with pytest.raises(Exception):
+        tx = await swap_pool_program.rpc["set_max_units_inflow"](
+            swap_pool_program.type["U256Limbs"](limbs=[0, 1, 0, 0]),
+            ctx=Context(
+                accounts={
+                    "swap_pool_state_account": swap_pool_state,
+                    "dao_authority": dao_authority_keypair.public_key
+                },
+                signers=[dao_authority_keypair]
+            )
+        )
+        await confirm_transaction(provider, tx)
@@ -0,0 +1,301 @@
+from dataclasses import dataclass
+import pytest
+from solana.keypair import Keypair
+from solana.publickey import PublicKey
+
+from anchorpy import Context, Program
+from conftest import FactoryFixture
+
+from utils.account_utils import get_swap_pool_authority
+from utils.common_utils import SOLANA_CHAIN_ID, UserWallet, create_mints, create_users
+from utils.swap_pool_utils import CreateAndSetupSwapPoolResult, create_and_setup_swap_pool, create_connection, get_or_create_governance_fee_wallet, perform_cross_chain_swap, perform_local_swap
+from utils.transaction_utils import confirm_transaction
+from utils.token_utils import fund_accounts, get_account_info, mint_to
+
+
+POOL_FEE_X64       = 2**64 // 100   # 1%
+GOVERNANCE_FEE_X64 = 2**64 // 4     # 25% of the pool fee
+
+
+@dataclass
+class FeePoolContext:
+    swap_pool_state: PublicKey
+    swap_pool_authority: PublicKey
+    swap_pool_assets: list[PublicKey]
+    swap_pool_asset_wallets: list[PublicKey]
+    users: list[UserWallet]
+    create_and_setup_swap_pool_result: CreateAndSetupSwapPoolResult
+    connection_state: PublicKey
+
+
+def calc_fee_amount(amount: int, fee_x64: int) -> int:
+    # Mirrors 'calculation_helpers::calc_fee_amount' (rounds down)
+    return (amount * fee_x64) >> 64
+
+
+@pytest.fixture(params=["swap_pool", "swap_pool_amplified"])
+def fee_pool_config(
+    request: pytest.FixtureRequest,
+    swap_pool_program: Program,
+    swap_pool_amplified_program: Program
+) -> tuple[Program, int | None]:
+    # (program, amplification)
+    return (swap_pool_program, None) if request.param == "swap_pool" else (swap_pool_amplified_program, 2)
+
+
+async def setup_fee_pool(
+    swap_pool_program: Program,
+    amplification: int | None,
+    swap_pool_setup_master_keypair: Keypair,
+    swap_interface_program: Program,
+    swap_interface_setup_master_keypair: Keypair,
+    polymerase_register_program: Program,
+    polymerase_sender_program: Program,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    pool_assets_balances: list[int] = [1000*10**10, 1000*10**10]
+) -> FeePoolContext:
+
+    provider = swap_pool_program.provider
+
+    swap_pool_assets = await create_mints(provider, mint_authority, len(pool_assets_balances))
+    users            = await create_users(provider, swap_pool_assets, generic_payer, 2)
+
+    # The initial deposit is taken by the pool authority, derive it from the (yet to be created) pool state account
+    swap_pool_state_keypair = Keypair()
+    swap_pool_authority     = get_swap_pool_authority(swap_pool_program.program_id, swap_pool_state_keypair.public_key)[0]
+
+    liquidity_provider = users[0]
+    await fund_accounts(
+        provider                 = provider,
+        mints                    = swap_pool_assets,
+        mints_authority          = mint_authority,
+        token_accounts           = liquidity_provider.token_accounts,
+        balance                  = pool_assets_balances,
+        delegate_authority       = swap_pool_authority,
+        delegate_balance         = pool_assets_balances,
+        token_accounts_authority = liquidity_provider.user_keypair
+    )
+
+    create_and_setup_swap_pool_result = await create_and_setup_swap_pool(
+        swap_pool_program,
+        swap_pool_setup_master_keypair,
+        amplification,
+        [1] * len(pool_assets_balances),
+        pool_assets_balances,
+        swap_pool_assets,
+        liquidity_provider.token_accounts,
+        liquidity_provider.user_keypair.public_key,
+        swap_interface_program,
+        swap_interface_setup_master_keypair,
+        polymerase_register_program,
+        polymerase_sender_program,
+        swap_pool_state_keypair=swap_pool_state_keypair
+    )
+
+    initialize_swap_pool_state_result = create_and_setup_swap_pool_result.initialize_swap_pool_state_result
+    swap_pool_state                   = initialize_swap_pool_state_result.swap_pool_state
+    dao_authority_keypair             = initialize_swap_pool_state_result.dao_authority_keypair
+    swap_interface_state              = create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+
+    # Connect the pool with itself
+    create_connection_result = await create_connection(
+        target_chain_id        = SOLANA_CHAIN_ID,
+        target_pool_id         = swap_interface_state,
+        target_program_id      = swap_interface_program.program_id,
+        swap_pool_program      = swap_pool_program,
+        swap_pool_state        = swap_pool_state,
+        authority_keypair      = dao_authority_keypair,
+        swap_interface_program = swap_interface_program,
+        swap_interface_state   = swap_interface_state,
+        rent_payer_keypair     = generic_payer
+    )
+
+    # Set the fees (the dao authority is the default fee administrator)
+    tx = await swap_pool_program.rpc["set_pool_fee"](
+        POOL_FEE_X64,
+        ctx=Context(
+            accounts={
+                "swap_pool_state_account": swap_pool_state,
+                "fee_administrator": dao_authority_keypair.public_key
+            },
+            signers=[dao_authority_keypair]
+        )
+    )
+    await confirm_transaction(provider, tx)
+
+    tx = await swap_pool_program.rpc["set_governance_fee"](
+        GOVERNANCE_FEE_X64,
+        ctx=Context(
+            accounts={
+                "swap_pool_state_account": swap_pool_state,
+                "dao_authority": dao_authority_keypair.public_key
+            },
+            signers=[dao_authority_keypair]
+        )
+    )
+    await confirm_transaction(provider, tx)
+
+    return FeePoolContext(
+        swap_pool_state,
+        swap_pool_authority,
+        swap_pool_assets,
+        initialize_swap_pool_state_result.swap_pool_asset_wallets,
+        users,
+        create_and_setup_swap_pool_result,
+        create_connection_result.connection_state
+    )
+
+
+async def test_governance_fee_wallet_is_reused(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture
+):
+    spc = await simple_pool_context_factory(1, 2, [1000*10**10, 1000*10**10], [1, 1], None, False)
+
+    governance_fee_wallet = await get_or_create_governance_fee_wallet(swap_pool_program, spc.swap_pool_state, spc.swap_pool_assets[0])
+
+    assert await get_or_create_governance_fee_wallet(swap_pool_program, spc.swap_pool_state, spc.swap_pool_assets[0]) == governance_fee_wallet
+
+
+async def test_local_swap_fees(
+    fee_pool_config: tuple[Program, int | None],
+    swap_pool_setup_master_keypair: Keypair,
+    swap_interface_program: Program,
+    swap_interface_setup_master_keypair: Keypair,
+    polymerase_register_program: Program,
+    polymerase_sender_program: Program,
+    mint_authority: Keypair,
+    generic_payer: Keypair
+):
+    swap_pool_program, amplification = fee_pool_config
+    provider = swap_pool_program.provider
+
+    fpc = await setup_fee_pool(
+        swap_pool_program,
+        amplification,
+        swap_pool_setup_master_keypair,
+        swap_interface_program,
+        swap_interface_setup_master_keypair,
+        polymerase_register_program,
+        polymerase_sender_program,
+        mint_authority,
+        generic_payer
+    )
+
+    swapper      = fpc.users[1]
+    input_amount = 10**9
+
+    await mint_to(provider, swapper.token_accounts[0], fpc.swap_pool_assets[0], mint_authority, input_amount)
+
+    governance_fee_wallet = await get_or_create_governance_fee_wallet(swap_pool_program, fpc.swap_pool_state, fpc.swap_pool_assets[0])
+
+    pool_input_balance_before     = (await get_account_info(provider, fpc.swap_pool_assets[0], fpc.swap_pool_asset_wallets[0])).amount
+    governance_fee_balance_before = (await get_account_info(provider, fpc.swap_pool_assets[0], governance_fee_wallet)).amount
+
+    local_swap_result = await perform_local_swap(
+        input_amount,
+        0,
+        False, # approx
+        fpc.swap_pool_assets[0],
+        swapper.token_accounts[0],
+        swapper.user_keypair,
+        fpc.swap_pool_assets[1],
+        swapper.token_accounts[1],
+        swap_pool_program,
+        fpc.swap_pool_state,
+        fpc.swap_pool_asset_wallets[0],
+        fpc.swap_pool_asset_wallets[1],
+        fpc.swap_pool_authority,
+        governance_fee_wallet=governance_fee_wallet
+    )
+
+    expected_pool_fee       = calc_fee_amount(input_amount, POOL_FEE_X64)
+    expected_governance_fee = calc_fee_amount(expected_pool_fee, GOVERNANCE_FEE_X64)
+    assert expected_governance_fee > 0
+
+    assert local_swap_result.local_swap_event.data.fees == expected_pool_fee
+
+    # The governance share of the pool fee is sent to the governance fee wallet, the rest remains in the pool
+    governance_fee_balance_after = (await get_account_info(provider, fpc.swap_pool_assets[0], governance_fee_wallet)).amount
+    assert governance_fee_balance_after - governance_fee_balance_before == expected_governance_fee
+
+    pool_input_balance_after = (await get_account_info(provider, fpc.swap_pool_assets[0], fpc.swap_pool_asset_wallets[0])).amount
+    assert pool_input_balance_after - pool_input_balance_before == input_amount - expected_governance_fee
+
+
+async def test_out_swap_fees(
+    fee_pool_config: tuple[Program, int | None],
+    swap_pool_setup_master_keypair: Keypair,
+    swap_interface_program: Program,
+    swap_interface_setup_master_keypair: Keypair,
+    polymerase_register_program: Program,
+    polymerase_sender_program: Program,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    mint_authority: Keypair,
+    generic_payer: Keypair
+):
+    swap_pool_program, amplification = fee_pool_config
+    provider = swap_pool_program.provider
+
+    fpc = await setup_fee_pool(
+        swap_pool_program,
+        amplification,
+        swap_pool_setup_master_keypair,
+        swap_interface_program,
+        swap_interface_setup_master_keypair,
+        polymerase_register_program,
+        polymerase_sender_program,
+        mint_authority,
+        generic_payer
+    )
+
+    swapper      = fpc.users[1]
+    input_amount = 10**9
+
+    swap_interface_state = fpc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+
+    await mint_to(provider, swapper.token_accounts[0], fpc.swap_pool_assets[0], mint_authority, input_amount)
+
+    governance_fee_wallet = await get_or_create_governance_fee_wallet(swap_pool_program, fpc.swap_pool_state, fpc.swap_pool_assets[0])
+
+    governance_fee_balance_before = (await get_account_info(provider, fpc.swap_pool_assets[0], governance_fee_wallet)).amount
+    escrowed_assets_before        = (await swap_pool_program.account["SwapPoolState"].fetch(fpc.swap_pool_state)).escrowed_assets
+
+    cross_chain_swap_result = await perform_cross_chain_swap(
+        input_amount,
+        fpc.swap_pool_assets[0],
+        swapper.token_accounts[0],
+        swapper.user_keypair,
+        SOLANA_CHAIN_ID,
+        swap_interface_state,
+        0,
+        swapper.token_accounts[0],
+        generic_payer,
+        swap_pool_program,
+        fpc.swap_pool_state,
+        fpc.swap_pool_asset_wallets[0],
+        fpc.swap_pool_authority,
+        swap_interface_program,
+        swap_interface_state,
+        fpc.connection_state,
+        polymerase_sender_program,
+        polymerase_emulator_setup_master_keypair, # Payer, can be anyone
+        governance_fee_wallet=governance_fee_wallet
+    )
+
+    expected_pool_fee       = calc_fee_amount(input_amount, POOL_FEE_X64)
+    expected_governance_fee = calc_fee_amount(expected_pool_fee, GOVERNANCE_FEE_X64)
+    assert expected_governance_fee > 0
+
+    assert cross_chain_swap_result.out_swap_event.data.fees == expected_pool_fee
+
+    governance_fee_balance_after = (await get_account_info(provider, fpc.swap_pool_assets[0], governance_fee_wallet)).amount
+    assert governance_fee_balance_after - governance_fee_balance_before == expected_governance_fee
+
+    # Only the input amount net of the pool fee is escrowed
+    swap_escrow = await swap_pool_program.account["SwapEscrow"].fetch(cross_chain_swap_result.swap_escrow)
+    assert swap_escrow.amount == input_amount - expected_pool_fee
+
+    escrowed_assets_after = (await swap_pool_program.account["SwapPoolState"].fetch(fpc.swap_pool_state)).escrowed_assets
+    assert escrowed_assets_after[0] - escrowed_assets_before[0] == input_amount - expected_pool_fee
@@ -1,5 +1,6 @@
 
 from functools import cache
+from hashlib import sha256
 from typing import Tuple
 from solana.publickey import PublicKey
 
@@ -8,6 +9,9 @@ POOL_TOKEN_MINT_SEED        : str = "poolMint"
 POOL_AUTHORITY              : str = "poolAuth"
 INTERFACE_SWAP_AUTHORITY    : str = "intSwapAuth"
 POOL_ESCROW_SEED            : str = "poolEscrow"
+POOL_LIQUIDITY_ESCROW_SEED  : str = "poolLiqEscrow"
+FACTORY_POOL_SEED           : str = "factoryPool"
+FACTORY_POOL_REGISTRATION_SEED : str = "factoryPoolReg"
 
 
 @cache
@@ -58,13 +62,68 @@ def get_swap_pool_token_mint(
 def get_swap_pool_escrow_wallet(
     swap_pool_program_id: PublicKey,
     swap_pool_state_pubkey: PublicKey,
-    escrow_nonce: int
+    swap_hash: bytes
 ) -> Tuple[PublicKey, int]:
     return PublicKey.find_program_address(
         [
             swap_pool_state_pubkey.__bytes__(),
-            escrow_nonce.to_bytes(4, 'big'),
+            swap_hash,
             POOL_ESCROW_SEED.encode('utf-8')
         ],
         swap_pool_program_id
-    )
\ No newline at end of file
+    )
+
+
+@cache
+def get_swap_pool_liquidity_escrow(
+    swap_pool_program_id: PublicKey,
+    swap_pool_state_pubkey: PublicKey,
+    swap_hash: bytes
+) -> Tuple[PublicKey, int]:
+    return PublicKey.find_program_address(
+        [
+            swap_pool_state_pubkey.__bytes__(),
+            swap_hash,
+            POOL_LIQUIDITY_ESCROW_SEED.encode('utf-8')
+        ],
+        swap_pool_program_id
+    )
+
+
+def get_factory_pool(
+    factory_program_id: PublicKey,
+    factory_state_pubkey: PublicKey,
+    template_id: int,
+    assets: list[PublicKey],
+    deployer: PublicKey,
+    salt: bytes
+) -> Tuple[PublicKey, int]:
+    assets_hash = sha256(b"".join(asset.__bytes__() for asset in assets)).digest()
+
+    return PublicKey.find_program_address(
+        [
+            factory_state_pubkey.__bytes__(),
+            bytes([template_id]),
+            assets_hash,
+            deployer.__bytes__(),
+            salt,
+            FACTORY_POOL_SEED.encode('utf-8')
+        ],
+        factory_program_id
+    )
+
+
+@cache
+def get_factory_pool_registration(
+    factory_program_id: PublicKey,
+    factory_state_pubkey: PublicKey,
+    pool: PublicKey
+) -> Tuple[PublicKey, int]:
+    return PublicKey.find_program_address(
+        [
+            factory_state_pubkey.__bytes__(),
+            pool.__bytes__(),
+            FACTORY_POOL_REGISTRATION_SEED.encode('utf-8')
+        ],
+        factory_program_id
+    )
@@ -1,17 +1,31 @@
 
-from typing import Tuple
+import hashlib
+from typing import List, Tuple
 from anchorpy import Context, Program
 from dataclasses import dataclass
 from solana.keypair import Keypair
 from solana.publickey import PublicKey
 from solana.system_program import SYS_PROGRAM_ID
+from solana.transaction import AccountMeta
 from solana.rpc.commitment import Commitment
+from spl.token.constants import TOKEN_PROGRAM_ID
 from solders.signature import Signature
 
 from utils.transaction_utils import confirm_transaction, DEFAULT_TX_COMMITMENT
 from utils.account_utils import get_swap_pool_authority
 
 
+UNDERWRITE_STATE_SEED: str = "underwrite"
+
+POLYMERASE_AUTHORITY_SEED: str = "polymeraseAuthority"
+
+RELAYER_CONFIG_SEED: str = "relayerConfig"
+
+BPF_LOADER_UPGRADEABLE_PROGRAM_ID: PublicKey = PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+
+DEFAULT_CHANNEL_ID: bytes = bytes([1]*32)
+
+
 # Result classes ****************************************************************************************************************
 
 @dataclass
@@ -119,3 +133,242 @@ def get_connection_state_account(
         ],
         swap_interface_program_id
     )
+
+
+def compute_underwrite_id(
+    swap_pool_state: PublicKey,
+    output_asset_index: int,
+    units_x64: List[int],
+    min_output: int,
+    destination_wallet: PublicKey,
+    swap_id: bytes
+) -> bytes:
+    # Must match UnderwriteState::compute_underwrite_id of the IBCInterface program
+    return hashlib.sha256(
+        swap_pool_state.__bytes__() +
+        output_asset_index.to_bytes(1, 'big') +
+        b''.join(limb.to_bytes(8, 'big') for limb in units_x64) +
+        min_output.to_bytes(8, 'big') +
+        destination_wallet.__bytes__() +
+        swap_id
+    ).digest()
+
+
+def get_underwrite_state_account(
+    swap_interface_program_id: PublicKey,
+    swap_interface_state: PublicKey,
+    underwrite_id: bytes
+) -> Tuple[PublicKey, int]:
+
+    return PublicKey.find_program_address(
+        [
+            swap_interface_state.__bytes__(),
+            underwrite_id,
+            UNDERWRITE_STATE_SEED.encode('utf-8')
+        ],
+        swap_interface_program_id
+    )
+
+
+def get_polymerase_authority(
+    polymerase_endpoint_program_id: PublicKey
+) -> Tuple[PublicKey, int]:
+
+    return PublicKey.find_program_address(
+        [
+            POLYMERASE_AUTHORITY_SEED.encode('utf-8')
+        ],
+        polymerase_endpoint_program_id
+    )
+
+
+def get_relayer_config(
+    polymerase_endpoint_program_id: PublicKey
+) -> Tuple[PublicKey, int]:
+
+    return PublicKey.find_program_address(
+        [
+            RELAYER_CONFIG_SEED.encode('utf-8')
+        ],
+        polymerase_endpoint_program_id
+    )
+
+
+def get_program_data(
+    program_id: PublicKey
+) -> Tuple[PublicKey, int]:
+
+    return PublicKey.find_program_address(
+        [
+            bytes(program_id)
+        ],
+        BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+    )
+
+
+async def initialize_polymerase_relayer_config(
+    polymerase_endpoint_program: Program,
+    relayer: PublicKey,
+    admin_keypair: Keypair,     # Must be the upgrade authority of the polymerase endpoint program
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    tx = await polymerase_endpoint_program.rpc["initialize_relayer_config"](
+        relayer,
+        ctx=Context(
+            accounts={
+                "relayer_config": get_relayer_config(polymerase_endpoint_program.program_id)[0],
+                "admin": admin_keypair.public_key,
+                "program": polymerase_endpoint_program.program_id,
+                "program_data": get_program_data(polymerase_endpoint_program.program_id)[0],
+                "system_program": SYS_PROGRAM_ID
+            },
+            signers=[admin_keypair]
+        )
+    )
+    await confirm_transaction(polymerase_endpoint_program.provider, tx, commitment=commitment)
+
+    return tx
+
+
+async def set_polymerase_relayer(
+    polymerase_endpoint_program: Program,
+    relayer: PublicKey,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+    # The provider wallet (which deploys the programs, i.e. the upgrade authority) is used as the admin of the
+    # relayer config (created on first use)
+
+    provider       = polymerase_endpoint_program.provider
+    admin_keypair  = provider.wallet.payer
+    relayer_config = get_relayer_config(polymerase_endpoint_program.program_id)[0]
+
+    if (await provider.connection.get_account_info(relayer_config)).value is None:
+        return await initialize_polymerase_relayer_config(
+            polymerase_endpoint_program,
+            relayer,
+            admin_keypair,
+            commitment=commitment
+        )
+    else:
+        tx = await polymerase_endpoint_program.rpc["set_relayer"](
+            relayer,
+            ctx=Context(
+                accounts={
+                    "relayer_config": relayer_config,
+                    "admin": admin_keypair.public_key
+                },
+                signers=[admin_keypair]
+            )
+        )
+    await confirm_transaction(provider, tx, commitment=commitment)
+
+    return tx
+
+
+def get_ibc_result_accounts(
+    port_registration: PublicKey,
+    polymerase_authority: PublicKey,
+    swap_interface_state: PublicKey,
+    swap_pool_state: PublicKey,
+    swap_pool_program_id: PublicKey,
+    swap_pool_authority: PublicKey
+) -> List[AccountMeta]:
+    # Accounts of the OnIBCResult context of the IBCInterface program (in order)
+    return [
+        AccountMeta(port_registration,    is_signer=False, is_writable=False ), # port_registration
+        AccountMeta(polymerase_authority, is_signer=False, is_writable=False ), # polymerase_authority (signed by the polymerase endpoint)
+        AccountMeta(swap_interface_state, is_signer=False, is_writable=False ), # interface_state_account
+        AccountMeta(swap_pool_state,      is_signer=False, is_writable=True  ), # swap_pool
+        AccountMeta(swap_pool_program_id, is_signer=False, is_writable=False ), # swap_pool_program
+        AccountMeta(swap_pool_authority,  is_signer=False, is_writable=False ), # swap_pool_authority
+        AccountMeta(TOKEN_PROGRAM_ID,     is_signer=False, is_writable=False ), # token_program
+        AccountMeta(SYS_PROGRAM_ID,       is_signer=False, is_writable=False ), # system_program
+    ]
+
+
+async def deliver_ibc_packet(
+    payload: bytes,
+    port_registration: PublicKey,
+    swap_interface_program: Program,
+    remaining_accounts: List[AccountMeta],    # Accounts of the OnIBCInvocation context following the polymerase_authority
+    polymerase_endpoint_program: Program,
+    relayer_keypair: Keypair,
+    channel_id: bytes = DEFAULT_CHANNEL_ID,     # Channel on which the packet is received
+    timeout_block_height: int = 0,              # Timeout of the packet (0 for no timeout)
+    timeout_timestamp: int = 0,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    polymerase_authority = get_polymerase_authority(polymerase_endpoint_program.program_id)[0]
+
+    tx = await polymerase_endpoint_program.rpc["on_recv_packet"](
+        list(channel_id),
+        timeout_block_height,
+        timeout_timestamp,
+        payload,
+        ctx=Context(
+            accounts={
+                "dapp_program": swap_interface_program.program_id,
+                "polymerase_authority": polymerase_authority,
+                "relayer_config": get_relayer_config(polymerase_endpoint_program.program_id)[0],
+                "authority": relayer_keypair.public_key
+            },
+            remaining_accounts=[
+                AccountMeta(port_registration,    is_signer=False, is_writable=False ), # port_registration
+                AccountMeta(polymerase_authority, is_signer=False, is_writable=False ), # polymerase_authority (signed by the polymerase endpoint)
+            ] + remaining_accounts,
+            signers=[relayer_keypair]
+        )
+    )
+    await confirm_transaction(polymerase_endpoint_program.provider, tx, commitment=commitment)
+
+    return tx
+
+
+async def deliver_ibc_packet_result(
+    callback: str,      # 'on_acknowledgement_packet' or 'on_timeout_packet'
+    ibc_data: PublicKey,
+    port_registration: PublicKey,
+    swap_interface_program: Program,
+    swap_interface_state: PublicKey,
+    swap_pool_state: PublicKey,
+    swap_pool_program_id: PublicKey,
+    swap_pool_authority: PublicKey,
+    remaining_accounts: List[AccountMeta],
+    polymerase_endpoint_program: Program,
+    relayer_keypair: Keypair,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    polymerase_authority = get_polymerase_authority(polymerase_endpoint_program.program_id)[0]
+
+    # The packet result is bound to the ibc data account of the sent packet (which gets closed)
+    ibc_data_account = await polymerase_endpoint_program.account["IbcData"].fetch(ibc_data)
+
+    tx = await polymerase_endpoint_program.rpc[callback](
+        ibc_data_account.payload,
+        ctx=Context(
+            accounts={
+                "dapp_program": swap_interface_program.program_id,
+                "polymerase_authority": polymerase_authority,
+                "relayer_config": get_relayer_config(polymerase_endpoint_program.program_id)[0],
+                "ibc_data": ibc_data,
+                "sender": ibc_data_account.sender,
+                "payer": ibc_data_account.payer,
+                "authority": relayer_keypair.public_key
+            },
+            remaining_accounts=get_ibc_result_accounts(
+                port_registration,
+                polymerase_authority,
+                swap_interface_state,
+                swap_pool_state,
+                swap_pool_program_id,
+                swap_pool_authority
+            ) + remaining_accounts,
+            signers=[relayer_keypair]
+        )
+    )
+    await confirm_transaction(polymerase_endpoint_program.provider, tx, commitment=commitment)
+
+    return tx
@@ -1,4 +1,5 @@
 
+import hashlib
 from dataclasses import dataclass
 from typing import Any, List
 from anchorpy import Context, Program
@@ -12,13 +13,13 @@ from solana.sysvar import SYSVAR_RENT_PUBKEY
 from solders.signature import Signature
 
 from utils.account_utils import get_swap_pool_asset_wallet, get_swap_pool_authority, get_swap_pool_escrow_wallet, get_swap_pool_token_mint
-from utils.swap_interface_utils import InitializeSwapInterfaceResult, RegisterSwapInterfacePolymerasePortResult, get_connection_state_account, initialize_swap_interface_state, register_swap_interface_polymerase_port
-from utils.token_utils import approve, create_token_account, mint_to
+from utils.swap_interface_utils import DEFAULT_CHANNEL_ID, InitializeSwapInterfaceResult, RegisterSwapInterfacePolymerasePortResult, get_connection_state_account, initialize_swap_interface_state, register_swap_interface_polymerase_port
+from utils.token_utils import approve, create_token_account, get_or_create_associated_token_account, mint_to
 from utils.transaction_utils import TxEventListener, confirm_transaction, DEFAULT_TX_COMMITMENT
 from utils.verify_utils import int_to_u256_array, verify_local_swap_event, verify_out_swap_event
 
 
-POOL_MAX_ASSET_COUNT  = 3
+POOL_MAX_ASSET_COUNT  = 16
 
 # Result classes ****************************************************************************************************************
 
@@ -64,6 +65,8 @@ class AddLiquidityToSwapPoolResult:
 class PerformCrossChainSwapResult:
     tx: Signature
     ibc_data: PublicKey
+    swap_hash: bytes
+    swap_id: bytes
     swap_escrow: PublicKey
     out_swap_event: Any
 
@@ -74,6 +77,76 @@ class PerformLocalSwapResult:
 
 # Utils *************************************************************************************************************************
 
+def compute_swap_hash(
+    swap_pool_state: PublicKey,
+    target_chain_id: int,
+    target_pool_id: PublicKey,
+    output_asset_index: int,
+    destination_wallet: PublicKey,
+    input_amount: int,
+    min_output: int,
+    input_asset: PublicKey,
+    fallback_wallet: PublicKey,
+    polymerase_ibc_data: PublicKey
+) -> bytes:
+    # Must match SwapEscrow::compute_swap_hash of the SwapPool program
+    return hashlib.sha256(
+        swap_pool_state.__bytes__() +
+        target_chain_id.to_bytes(8, 'big') +
+        target_pool_id.__bytes__() +
+        output_asset_index.to_bytes(1, 'big') +
+        destination_wallet.__bytes__() +
+        input_amount.to_bytes(8, 'big') +
+        b''.join(limb.to_bytes(8, 'big') for limb in int_to_u256_array(min_output)) +
+        input_asset.__bytes__() +
+        fallback_wallet.__bytes__() +
+        polymerase_ibc_data.__bytes__()
+    ).digest()
+
+
+def compute_liquidity_swap_hash(
+    swap_pool_state: PublicKey,
+    target_chain_id: int,
+    target_pool_id: PublicKey,
+    destination_wallet: PublicKey,
+    pool_token_amount: int,
+    fallback_wallet: PublicKey,
+    polymerase_ibc_data: PublicKey
+) -> bytes:
+    # Must match LiquidityEscrow::compute_liquidity_swap_hash of the SwapPool program
+    return hashlib.sha256(
+        swap_pool_state.__bytes__() +
+        target_chain_id.to_bytes(8, 'big') +
+        target_pool_id.__bytes__() +
+        destination_wallet.__bytes__() +
+        pool_token_amount.to_bytes(8, 'big') +
+        fallback_wallet.__bytes__() +
+        polymerase_ibc_data.__bytes__()
+    ).digest()
+
+
+def swap_pool_supports_approx(swap_pool_program: Program) -> bool:
+    # The amplified pool does not implement the 'approx' swap calculations
+    local_swap_instruction = next(ix for ix in swap_pool_program.idl.instructions if ix.name in ("local_swap", "localSwap"))
+    return any(arg.name == "approx" for arg in local_swap_instruction.args)
+
+
+async def get_or_create_governance_fee_wallet(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    asset_mint: PublicKey
+) -> PublicKey:
+
+    swap_pool_state_data = await swap_pool_program.account["SwapPoolState"].fetch(swap_pool_state)
+
+    # Use the associated token account of the governance fee destination, so that repeated calls return the same wallet
+    return await get_or_create_associated_token_account(
+        swap_pool_program.provider,
+        asset_mint,
+        swap_pool_state_data.governance_fee_destination
+    )
+
+
 async def initialize_swap_pool_state(
     swap_pool_program: Program,
     swap_pool_setup_master_keypair: Keypair,
@@ -88,6 +161,10 @@ async def initialize_swap_pool_state(
     swap_pool_token_mint: PublicKey | None = None,
     swap_pool_authority: PublicKey | None = None,
     dao_authority_keypair: Keypair | None = None,
+    pool_fee_x64: int = 0,
+    governance_fee_x64: int = 0,
+    fee_administrator: PublicKey | None = None,
+    governance_fee_destination: PublicKey | None = None,
     commitment: Commitment = DEFAULT_TX_COMMITMENT
 ) -> InitializeSwapPoolStateResult:
 
@@ -99,6 +176,9 @@ async def initialize_swap_pool_state(
     swap_pool_state_keypair = swap_pool_state_keypair or Keypair()
     dao_authority_keypair   = dao_authority_keypair or Keypair()
 
+    fee_administrator          = fee_administrator or dao_authority_keypair.public_key
+    governance_fee_destination = governance_fee_destination or dao_authority_keypair.public_key
+
     swap_pool_token_mint = swap_pool_token_mint or get_swap_pool_token_mint(
         swap_pool_program.program_id,
         swap_pool_state_keypair.public_key
@@ -145,8 +225,12 @@ async def initialize_swap_pool_state(
         ))
 
     args: list[Any] = [1] if amplification is None else [amplification]
-    args.append([(assets_balances[i] if i < mints_count else 0) for i in range(POOL_MAX_ASSET_COUNT)])
-    args.append([(assets_weights[i]  if i < mints_count else 0) for i in range(POOL_MAX_ASSET_COUNT)])
+    args.append(assets_balances)
+    args.append(assets_weights)
+    args.append(pool_fee_x64)
+    args.append(governance_fee_x64)
+    args.append(fee_administrator)
+    args.append(governance_fee_destination)
 
     tx = await swap_pool_program.rpc["initialize"](
         *args,
@@ -212,9 +296,11 @@ async def finish_swap_pool_setup(
     swap_pool_program: Program,
     swap_pool_state: PublicKey,
     setup_master_keypair: Keypair,
+    local_only: bool = False,
     commitment: Commitment = DEFAULT_TX_COMMITMENT
 ) -> FinishSwapPoolSetupResult:
     tx = await swap_pool_program.rpc["finish_setup"](
+        local_only,
         ctx=Context(
             accounts={
                 "setup_master": setup_master_keypair.public_key,
@@ -230,6 +316,47 @@ async def finish_swap_pool_setup(
     return FinishSwapPoolSetupResult(tx)
 
 
+async def cancel_swap_pool_setup(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    setup_master_keypair: Keypair,
+    swap_pool_token_mint: PublicKey,
+    depositor_pool_token_wallet: PublicKey,
+    depositor_pool_token_wallet_authority_keypair: Keypair,
+    swap_pool_asset_wallets: List[PublicKey],
+    destination_asset_wallets: List[PublicKey],
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    # 2 Accounts per asset: the swap pool asset wallet and the destination asset wallet
+    remaining_accounts: List[AccountMeta] = []
+    for swap_pool_asset_wallet, destination_asset_wallet in zip(swap_pool_asset_wallets, destination_asset_wallets):
+        remaining_accounts.append(AccountMeta(pubkey=swap_pool_asset_wallet, is_signer=False, is_writable=True))
+        remaining_accounts.append(AccountMeta(pubkey=destination_asset_wallet, is_signer=False, is_writable=True))
+
+    tx = await swap_pool_program.rpc["cancel_setup"](
+        ctx=Context(
+            accounts={
+                "swap_pool_state_account": swap_pool_state,
+                "setup_master": setup_master_keypair.public_key,
+                "swap_pool_token_mint": swap_pool_token_mint,
+                "depositor_pool_token_wallet_authority": depositor_pool_token_wallet_authority_keypair.public_key,
+                "depositor_pool_token_wallet": depositor_pool_token_wallet,
+                "swap_pool_authority": get_swap_pool_authority(swap_pool_program.program_id, swap_pool_state)[0],
+                "token_program": TOKEN_PROGRAM_ID
+            },
+            remaining_accounts=remaining_accounts,
+            signers=[
+                setup_master_keypair,
+                depositor_pool_token_wallet_authority_keypair
+            ]
+        )
+    )
+    await confirm_transaction(swap_pool_program.provider, tx, commitment=commitment)
+
+    return tx
+
+
 async def create_connection(
     target_chain_id: int,
     target_pool_id: PublicKey,
@@ -240,6 +367,7 @@ async def create_connection(
     swap_interface_program: Program,
     swap_interface_state: PublicKey,
     rent_payer_keypair: Keypair,
+    channel_id: bytes = DEFAULT_CHANNEL_ID,
     commitment: Commitment = DEFAULT_TX_COMMITMENT
 ) -> CreateConnectionResult:
 
@@ -259,6 +387,7 @@ async def create_connection(
         target_chain_id,
         target_pool_id,
         target_program_id,
+        list(channel_id),
         ctx=Context(
             accounts={
                 "authority"                : authority_keypair.public_key,
@@ -278,6 +407,51 @@ async def create_connection(
     return CreateConnectionResult(tx, connection_state)
 
 
+async def set_connection_enabled(
+    target_chain_id: int,
+    target_pool_id: PublicKey,
+    enabled: bool,
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    authority_keypair: Keypair, # Either setup_master or dao_authority
+    swap_interface_program: Program,
+    swap_interface_state: PublicKey,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    connection_state, _ = get_connection_state_account(
+        swap_interface_program.program_id,
+        swap_interface_state,
+        target_chain_id,
+        target_pool_id
+    )
+
+    swap_pool_authority = get_swap_pool_authority(
+        swap_pool_program.program_id,
+        swap_pool_state
+    )[0]
+
+    tx = await swap_pool_program.rpc["set_connection_enabled"](
+        target_chain_id,
+        target_pool_id,
+        enabled,
+        ctx=Context(
+            accounts={
+                "authority"                : authority_keypair.public_key,
+                "swap_pool_state_account"  : swap_pool_state,
+                "ibc_interface_program"    : swap_interface_program.program_id,
+                "swap_pool_authority"      : swap_pool_authority,
+                "interface_state_account"  : swap_interface_state,
+                "connection_state_account" : connection_state
+            },
+            signers=[authority_keypair]
+        )
+    )
+    await confirm_transaction(swap_interface_program.provider, tx, commitment=commitment)
+
+    return tx
+
+
 async def create_and_setup_swap_pool(
     swap_pool_program: Program,
     swap_pool_setup_master_keypair: Keypair,
@@ -425,9 +599,16 @@ async def perform_local_swap(
     swap_pool_input_asset_wallet: PublicKey,
     swap_pool_output_asset_wallet: PublicKey,
     swap_pool_authority: PublicKey,
+    governance_fee_wallet: PublicKey | None = None,
     commitment: Commitment = DEFAULT_TX_COMMITMENT
 ) -> PerformLocalSwapResult:
 
+    governance_fee_wallet = governance_fee_wallet or await get_or_create_governance_fee_wallet(
+        swap_pool_program,
+        swap_pool_state,
+        input_asset
+    )
+
     await approve(
         swap_pool_program.provider,
         source_wallet,
@@ -438,10 +619,12 @@ async def perform_local_swap(
     )
 
     async with TxEventListener("LocalSwapEvent") as ev_listener:
+        args: list[Any] = [input_amount, minimum_output_amount]
+        if swap_pool_supports_approx(swap_pool_program):
+            args.append(approx)
+
         tx = await swap_pool_program.rpc["local_swap"](
-            input_amount,
-            minimum_output_amount,
-            approx,
+            *args,
             ctx=Context(
                 accounts={
                     "swap_pool_state_account": swap_pool_state,
@@ -451,6 +634,7 @@ async def perform_local_swap(
                     "output_asset_mint": output_asset,
                     "output_asset_wallet": destination_wallet,
                     "swap_pool_output_asset_wallet": swap_pool_output_asset_wallet,
+                    "governance_fee_wallet": governance_fee_wallet,
                     "swap_pool_authority": swap_pool_authority,
                     "token_program": TOKEN_PROGRAM_ID
                 }
@@ -484,7 +668,6 @@ async def perform_cross_chain_swap(
     target_pool_id: PublicKey,
     output_asset_index: int,
     destination_wallet: PublicKey,
-    swap_escrow_nonce: int,
     swap_escrow_payer: Keypair,
     swap_pool_program: Program,
     swap_pool_state: PublicKey,
@@ -498,19 +681,41 @@ async def perform_cross_chain_swap(
     min_output: int = 0,
     fallback_wallet: PublicKey | None = None,
     approx_from: bool = False,
-    approx_to:bool = False,
+    governance_fee_wallet: PublicKey | None = None,
+    timeout_slot: int = 0,
+    timeout_timestamp: int = 0,
     commitment: Commitment = DEFAULT_TX_COMMITMENT
 ) -> PerformCrossChainSwapResult:
 
     # TODO polymerase accounts should be derived here
+    polymerase_ibc_data_account_keypair = Keypair() 
+
+    fallback_wallet = fallback_wallet or source_wallet
+
+    swap_hash = compute_swap_hash(
+        swap_pool_state,
+        target_chain_id,
+        target_pool_id,
+        output_asset_index,
+        destination_wallet,
+        input_amount,
+        min_output,
+        input_asset,
+        fallback_wallet,
+        polymerase_ibc_data_account_keypair.public_key
+    )
 
     swap_escrow_wallet = get_swap_pool_escrow_wallet(
         swap_pool_program.program_id,
         swap_pool_state,
-        swap_escrow_nonce
+        swap_hash
     )[0]
 
-    fallback_wallet = fallback_wallet or source_wallet
+    governance_fee_wallet = governance_fee_wallet or await get_or_create_governance_fee_wallet(
+        swap_pool_program,
+        swap_pool_state,
+        input_asset
+    )
 
     await approve(
         swap_pool_program.provider,
@@ -521,26 +726,31 @@ async def perform_cross_chain_swap(
         commitment=commitment
     )
 
-    polymerase_ibc_data_account_keypair = Keypair() 
-
     async with TxEventListener("OutSwapEvent") as ev_listener:
-        tx = await swap_pool_program.rpc["out_swap"](
+        args: list[Any] = [
             target_chain_id,
             target_pool_id,
             output_asset_index,
             destination_wallet,
             input_amount,
             int_to_u256_array(min_output),
-            swap_escrow_nonce,
+            list(swap_hash),
             fallback_wallet,
-            approx_from,
-            approx_to,
+            timeout_slot,
+            timeout_timestamp
+        ]
+        if swap_pool_supports_approx(swap_pool_program):
+            args += [approx_from]
+
+        tx = await swap_pool_program.rpc["out_swap"](
+            *args,
             ctx=Context(
                 accounts={
                     "swap_pool_state_account": swap_pool_state,
                     "input_asset_mint": input_asset,
                     "input_asset_wallet": source_wallet,
                     "swap_pool_input_asset_wallet": swap_pool_input_asset_wallet,
+                    "governance_fee_wallet": governance_fee_wallet,
                     "token_program": TOKEN_PROGRAM_ID,
                     "swap_escrow": swap_escrow_wallet,
                     "swap_escrow_rent_payer": swap_escrow_payer.public_key,
@@ -575,12 +785,14 @@ async def perform_cross_chain_swap(
             deposited_asset_mint    = input_asset,
             depositor_asset_wallet  = source_wallet,
             deposited_asset_amount  = input_amount,
-            source_swap_id          = swap_escrow_nonce,
+            swap_hash               = swap_hash,
         )
 
     return PerformCrossChainSwapResult(
         tx,
         polymerase_ibc_data_account_keypair.public_key,
+        swap_hash,
+        bytes(out_swap_event.data.swapId),
         swap_escrow_wallet,
         out_swap_event
     )
@@ -15,7 +15,9 @@ from spl.token.instructions import (
     burn as burn_instruction,
     BurnParams,
     approve as approve_instruction,
-    ApproveParams
+    ApproveParams,
+    create_associated_token_account,
+    get_associated_token_address
 )
 from anchorpy import Provider
 from spl.token.core import AccountInfo, MintInfo
@@ -120,6 +122,37 @@ async def create_token_account(
     return account.public_key
 
 
+async def get_or_create_associated_token_account(
+    provider: Provider,
+    mint: PublicKey,
+    owner: PublicKey,
+    payer: Keypair | None = None,
+    tx_opts: TxOpts = TxOpts(skip_preflight=DEFAULT_SKIP_PREFLIGHT),
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> PublicKey:
+
+    account = get_associated_token_address(owner, mint)
+
+    # Only create the account if it does not exist yet
+    if (await provider.connection.get_account_info(account, commitment)).value is not None:
+        return account
+
+    payer = payer or provider.wallet.payer
+
+    tx = Transaction().add(
+        create_associated_token_account(
+            payer=payer.public_key,
+            owner=owner,
+            mint=mint
+        )
+    )
+
+    tx_result = await provider.send(tx, [payer], opts=tx_opts)
+    await confirm_transaction(provider, tx_result, commitment)
+
+    return account
+
+
 async def mint_to(
     provider: Provider,
     wallet: PublicKey,
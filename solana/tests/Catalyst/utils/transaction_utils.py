@@ -32,6 +32,13 @@ async def confirm_transaction(provider: Provider, tx: Signature, commitment: Com
     return confirmation
 
 
+def is_program_error(error: Exception, program: Program, error_name: str) -> bool:
+    # Anchor errors are reported either with their code (e.g. anchorpy's ProgramError) or as a hex 'custom program error' (e.g. by
+    # the preflight simulation, when raised within a cpi)
+    code = next(idl_error.code for idl_error in program.idl.errors if idl_error.name == error_name)
+    return str(code) in str(error) or hex(code) in str(error)
+
+
 class TxEventListener():
 
     ws: SolanaWsClientProtocol
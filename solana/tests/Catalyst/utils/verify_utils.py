@@ -52,41 +52,35 @@ async def verify_catalyst_configuration(
 
 
     # Check asset mints and weights
-    assert len(expected_pool_assets_mints) <= len(swap_pool_state_data.pool_assets_mints), \
-        f"Unexpected Catalyst configuration: too many expected pool assets mints provided, \
-        maximum {len(swap_pool_state_data.pool_assets_mints)} assets allowed."
+    assert len(expected_pool_assets_mints) == len(swap_pool_state_data.pool_assets_mints), \
+        f"Unexpected Catalyst configuration: asset count mismatch \
+        ({len(swap_pool_state_data.pool_assets_mints)} set, expected {len(expected_pool_assets_mints)})."
     
     for i, asset_mint in enumerate(swap_pool_state_data.pool_assets_mints):
 
-        if i < len(expected_pool_assets_mints):
-            # Asset mint
-            assert asset_mint == expected_pool_assets_mints[i], \
-                f"Unexpected Catalyst configuration: unexpected asset mint at position {i}  \
-                ({swap_pool_state_data.pool_assets_mints[i]} set, expected {expected_pool_assets_mints[i]})."
-
-            # Asset weight
-            assert swap_pool_state_data.pool_assets_weights[i] == expected_pool_assets_weights[i], \
-                f"Unexpected Catalyst configuration: asset weight mismatch for asset {i}  \
-                ({swap_pool_state_data.pool_assets_weights[i]} set, expected {expected_pool_assets_weights[i]})."
-            
-            # Check pool asset wallet exists
-            expected_asset_wallet, expected_asset_wallet_bump = get_swap_pool_asset_wallet(swap_pool_program.program_id, swap_pool_state, asset_mint)
-    
-            assert swap_pool_state_data.wallets_bumps[i] == expected_asset_wallet_bump, \
-                f"Unexpected Catalyst configuration: swap pool asset wallet bump mismatch \
-                    ({swap_pool_state_data.wallets_bumps[i]} set, expected {expected_asset_wallet_bump})."
-
-            await verify_token_wallet(
-                swap_pool_program.provider,
-                expected_asset_wallet,
-                asset_mint,
-                expected_swap_pool_authority
-            )
+        # Asset mint
+        assert asset_mint == expected_pool_assets_mints[i], \
+            f"Unexpected Catalyst configuration: unexpected asset mint at position {i}  \
+            ({swap_pool_state_data.pool_assets_mints[i]} set, expected {expected_pool_assets_mints[i]})."
+
+        # Asset weight
+        assert swap_pool_state_data.pool_assets_weights[i] == expected_pool_assets_weights[i], \
+            f"Unexpected Catalyst configuration: asset weight mismatch for asset {i}  \
+            ({swap_pool_state_data.pool_assets_weights[i]} set, expected {expected_pool_assets_weights[i]})."
         
-        else:
-            assert asset_mint == DEFAULT_PUBLIC_KEY, \
-                f"Unexpected Catalyst configuration: non-empty asset mint found at position {i}  \
-                ({swap_pool_state_data.pool_assets_mints[i]} set, expected {DEFAULT_PUBLIC_KEY})."
+        # Check pool asset wallet exists
+        expected_asset_wallet, expected_asset_wallet_bump = get_swap_pool_asset_wallet(swap_pool_program.program_id, swap_pool_state, asset_mint)
+
+        assert swap_pool_state_data.wallets_bumps[i] == expected_asset_wallet_bump, \
+            f"Unexpected Catalyst configuration: swap pool asset wallet bump mismatch \
+                ({swap_pool_state_data.wallets_bumps[i]} set, expected {expected_asset_wallet_bump})."
+
+        await verify_token_wallet(
+            swap_pool_program.provider,
+            expected_asset_wallet,
+            asset_mint,
+            expected_swap_pool_authority
+        )
 
 
     # Check pool token mint exists
@@ -255,7 +249,7 @@ def verify_out_swap_event(
     deposited_asset_mint     : PublicKey,
     depositor_asset_wallet   : PublicKey,
     deposited_asset_amount   : int,
-    source_swap_id           : int,
+    swap_hash                : bytes,
     withdrawn_pool_units_x64 : int | None = None
 ):
     assert out_swap_event.data.swapPool             == swap_pool
@@ -266,7 +260,7 @@ def verify_out_swap_event(
     assert out_swap_event.data.depositedAssetMint   == deposited_asset_mint
     assert out_swap_event.data.depositorAssetWallet == depositor_asset_wallet
     assert out_swap_event.data.depositedAssetAmount == deposited_asset_amount
-    assert out_swap_event.data.escrowNonce          == source_swap_id
+    assert bytes(out_swap_event.data.swapHash)      == swap_hash
 
     if withdrawn_pool_units_x64 is not None:
         assert u256_array_to_int(out_swap_event.data.withdrawnPoolUnitsX64) == withdrawn_pool_units_x64
@@ -300,6 +294,7 @@ def verify_out_liquidity_swap_event(
     pool_token_mint          : PublicKey,
     source_pool_token_wallet : PublicKey,
     pool_token_amount        : int,
+    swap_hash                : bytes,
     liquidity_units_x64      : int | None = None
 ):
     assert out_swap_event.data.swapPool              == swap_pool
@@ -310,6 +305,7 @@ def verify_out_liquidity_swap_event(
     assert out_swap_event.data.sourcePoolTokenWallet == source_pool_token_wallet
 
     assert out_swap_event.data.poolTokenAmount       == pool_token_amount
+    assert bytes(out_swap_event.data.swapHash)       == swap_hash
 
     if liquidity_units_x64 is not None:
         assert u256_array_to_int(out_swap_event.data.liquidityUnitsX64) == liquidity_units_x64
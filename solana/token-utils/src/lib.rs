@@ -127,6 +127,62 @@ pub fn burn_tokens_using_pda_authority<'info> (
 }
 
 
+pub fn close_token_account<'info> (
+    account       : AccountInfo<'info>,
+    destination   : AccountInfo<'info>,   // Receives the account lamports
+    authority     : AccountInfo<'info>,
+    token_program : AccountInfo<'info>,
+) {
+    anchor_lang::solana_program::program::invoke(
+        // Close account instruction (the account balance must be 0)
+        &spl_token::instruction::close_account(
+            &token_program.key(),
+            &account.key(),
+            &destination.key(),
+            &authority.key(),
+            &[]
+        ).unwrap(),
+
+        // Accounts
+        &[
+            account,
+            destination,
+            authority,
+        ],
+    ).unwrap();
+}
+
+
+pub fn close_token_account_using_pda_authority<'info> (
+    account             : AccountInfo<'info>,
+    destination         : AccountInfo<'info>,   // Receives the account lamports
+    pda_authority       : AccountInfo<'info>,
+    pda_authority_seeds : &[&[u8]],
+    token_program       : AccountInfo<'info>,
+) {
+    anchor_lang::solana_program::program::invoke_signed(
+        // Close account instruction (the account balance must be 0)
+        &spl_token::instruction::close_account(
+            &token_program.key(),
+            &account.key(),
+            &destination.key(),
+            &pda_authority.key(),
+            &[]
+        ).unwrap(),
+
+        // Accounts
+        &[
+            account,
+            destination,
+            pda_authority,
+        ],
+
+        // pda_authority seeds
+        &[pda_authority_seeds]
+    ).unwrap();
+}
+
+
 pub fn create_pda_token_account<'info> (
     account        : AccountInfo<'info>,
     account_seeds  : &[&[u8]],